@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE play_participants
+    DROP COLUMN IF EXISTS is_winner,
+    DROP COLUMN IF EXISTS score;
\ No newline at end of file
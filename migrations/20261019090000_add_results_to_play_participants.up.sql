@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE play_participants
+    ADD COLUMN score INTEGER,
+    ADD COLUMN is_winner BOOLEAN NOT NULL DEFAULT FALSE;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS players_normalized_name_key;
+ALTER TABLE players ADD CONSTRAINT players_name_key UNIQUE (name);
+
+DROP INDEX IF EXISTS games_normalized_name_key;
+ALTER TABLE games ADD CONSTRAINT games_name_creator_name_key UNIQUE (name, creator_name);
+
+DROP FUNCTION IF EXISTS normalize_name(TEXT);
\ No newline at end of file
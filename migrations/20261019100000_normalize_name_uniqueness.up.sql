@@ -0,0 +1,81 @@
+-- Add up migration script here
+-- Names are compared case-insensitively with surrounding whitespace trimmed and
+-- inner runs of whitespace collapsed. The unique indexes are built on the
+-- normalized expression rather than a stored column so `SELECT *` keeps
+-- matching the models. Only ASCII whitespace is collapsed and only ASCII
+-- letters are lowercased: `lower` and `\s` follow the database's encoding and
+-- collation, which could differ between servers, from the Rust
+-- `normalize_name` and after an OS upgrade changes the collation under the
+-- unique indexes.
+CREATE OR REPLACE FUNCTION normalize_name(name TEXT) RETURNS TEXT
+    LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+    RETURN translate(
+        regexp_replace(
+            regexp_replace(name, '^[ \t\n\v\f\r]+|[ \t\n\v\f\r]+$', '', 'g'),
+            '[ \t\n\v\f\r]+', ' ', 'g'
+        ),
+        'ABCDEFGHIJKLMNOPQRSTUVWXYZ',
+        'abcdefghijklmnopqrstuvwxyz'
+    );
+
+-- Rows that only differ in case or whitespace are merged into the oldest of
+-- them, the way POST /api/games/{id}/merge and POST /api/players/{id}/merge
+-- would: plays move over, and a play several of the players took part in
+-- keeps the result of the oldest of them.
+CREATE TEMPORARY TABLE merged_games AS
+SELECT id, first_value(id) OVER same_name AS target_id
+FROM games
+WINDOW same_name AS (
+    PARTITION BY normalize_name(name), normalize_name(creator_name)
+    ORDER BY created_at, id
+);
+
+UPDATE plays SET game_id = merged_games.target_id
+FROM merged_games
+WHERE plays.game_id = merged_games.id
+AND merged_games.id <> merged_games.target_id;
+
+DELETE FROM games
+WHERE id IN (SELECT id FROM merged_games WHERE id <> target_id);
+
+DROP TABLE merged_games;
+
+CREATE TEMPORARY TABLE merged_players AS
+SELECT
+    id,
+    first_value(id) OVER same_name AS target_id,
+    row_number() OVER same_name AS age,
+    bool_or(is_owner) OVER (PARTITION BY normalize_name(name)) AS is_owner
+FROM players
+WINDOW same_name AS (PARTITION BY normalize_name(name) ORDER BY created_at, id);
+
+DELETE FROM play_participants
+USING merged_players AS player, play_participants AS older, merged_players AS older_player
+WHERE play_participants.player_id = player.id
+AND older.play_id = play_participants.play_id
+AND older.player_id = older_player.id
+AND older_player.target_id = player.target_id
+AND older_player.age < player.age;
+
+UPDATE play_participants SET player_id = merged_players.target_id
+FROM merged_players
+WHERE play_participants.player_id = merged_players.id
+AND merged_players.id <> merged_players.target_id;
+
+DELETE FROM players
+WHERE id IN (SELECT id FROM merged_players WHERE id <> target_id);
+
+UPDATE players SET is_owner = true
+FROM merged_players
+WHERE players.id = merged_players.id
+AND merged_players.is_owner
+AND NOT players.is_owner;
+
+DROP TABLE merged_players;
+
+ALTER TABLE games DROP CONSTRAINT games_name_creator_name_key;
+CREATE UNIQUE INDEX games_normalized_name_key
+    ON games (normalize_name(name), normalize_name(creator_name));
+
+ALTER TABLE players DROP CONSTRAINT players_name_key;
+CREATE UNIQUE INDEX players_normalized_name_key ON players (normalize_name(name));
@@ -0,0 +1,19 @@
+-- Add down migration script here
+DELETE FROM plays WHERE deleted_at IS NOT NULL;
+DELETE FROM players WHERE deleted_at IS NOT NULL;
+DELETE FROM games WHERE deleted_at IS NOT NULL;
+
+DROP INDEX IF EXISTS uniq_single_owner;
+CREATE UNIQUE INDEX uniq_single_owner ON players ((is_owner))
+    WHERE is_owner = true;
+
+DROP INDEX IF EXISTS players_normalized_name_key;
+CREATE UNIQUE INDEX players_normalized_name_key ON players (normalize_name(name));
+
+DROP INDEX IF EXISTS games_normalized_name_key;
+CREATE UNIQUE INDEX games_normalized_name_key
+    ON games (normalize_name(name), normalize_name(creator_name));
+
+ALTER TABLE plays DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE players DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE games DROP COLUMN IF EXISTS deleted_at;
\ No newline at end of file
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- Deleting a game, player or play only stamps `deleted_at`; rows are removed
+-- for good when the trash is purged. Uniqueness only applies to live rows so a
+-- trashed name can be reused, which makes restoring it a conflict instead.
+ALTER TABLE games ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE players ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE plays ADD COLUMN deleted_at TIMESTAMPTZ;
+
+DROP INDEX games_normalized_name_key;
+CREATE UNIQUE INDEX games_normalized_name_key
+    ON games (normalize_name(name), normalize_name(creator_name))
+    WHERE deleted_at IS NULL;
+
+DROP INDEX players_normalized_name_key;
+CREATE UNIQUE INDEX players_normalized_name_key ON players (normalize_name(name))
+    WHERE deleted_at IS NULL;
+
+DROP INDEX uniq_single_owner;
+CREATE UNIQUE INDEX uniq_single_owner ON players ((is_owner))
+    WHERE is_owner = true AND deleted_at IS NULL;
\ No newline at end of file
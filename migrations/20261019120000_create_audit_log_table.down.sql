@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS play_participants_audit ON play_participants;
+DROP TRIGGER IF EXISTS plays_audit ON plays;
+DROP TRIGGER IF EXISTS players_audit ON players;
+DROP TRIGGER IF EXISTS games_audit ON games;
+DROP FUNCTION IF EXISTS audit_row_change();
+DROP TABLE IF EXISTS audit_log;
+DROP FUNCTION IF EXISTS audit_log_append_only();
\ No newline at end of file
@@ -0,0 +1,71 @@
+-- Add up migration script here
+-- Append-only change history. Rows are written by triggers so every insert,
+-- update and delete is recorded in the same transaction as the change itself.
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete', 'restore', 'purge')),
+    before JSONB,
+    after JSONB,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log (entity, entity_id, id);
+
+CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS TRIGGER
+    LANGUAGE plpgsql AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
+
+-- Soft deletes and restores show up as updates of `deleted_at` and are
+-- recorded as 'delete' and 'restore'; hard deleting a trashed row is a 'purge'.
+CREATE OR REPLACE FUNCTION audit_row_change() RETURNS TRIGGER
+    LANGUAGE plpgsql AS $$
+DECLARE
+    old_row JSONB := CASE WHEN TG_OP <> 'INSERT' THEN to_jsonb(OLD) END;
+    new_row JSONB := CASE WHEN TG_OP <> 'DELETE' THEN to_jsonb(NEW) END;
+    row_data JSONB := COALESCE(new_row, old_row);
+    row_action TEXT;
+    row_id TEXT;
+BEGIN
+    IF TG_OP = 'UPDATE' AND old_row = new_row THEN
+        RETURN NULL;
+    END IF;
+
+    row_action := CASE
+        WHEN TG_OP = 'INSERT' THEN 'create'
+        WHEN TG_OP = 'DELETE' AND old_row->>'deleted_at' IS NOT NULL THEN 'purge'
+        WHEN TG_OP = 'DELETE' THEN 'delete'
+        WHEN old_row->>'deleted_at' IS NULL AND new_row->>'deleted_at' IS NOT NULL THEN 'delete'
+        WHEN old_row->>'deleted_at' IS NOT NULL AND new_row->>'deleted_at' IS NULL THEN 'restore'
+        ELSE 'update'
+    END;
+
+    row_id := CASE
+        WHEN TG_TABLE_NAME = 'play_participants'
+            THEN (row_data->>'play_id') || '/' || (row_data->>'player_id')
+        ELSE row_data->>'id'
+    END;
+
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (TG_TABLE_NAME, row_id, row_action, old_row, new_row);
+
+    RETURN NULL;
+END;
+$$;
+
+CREATE TRIGGER games_audit AFTER INSERT OR UPDATE OR DELETE ON games
+    FOR EACH ROW EXECUTE FUNCTION audit_row_change();
+CREATE TRIGGER players_audit AFTER INSERT OR UPDATE OR DELETE ON players
+    FOR EACH ROW EXECUTE FUNCTION audit_row_change();
+CREATE TRIGGER plays_audit AFTER INSERT OR UPDATE OR DELETE ON plays
+    FOR EACH ROW EXECUTE FUNCTION audit_row_change();
+CREATE TRIGGER play_participants_audit AFTER INSERT OR UPDATE OR DELETE ON play_participants
+    FOR EACH ROW EXECUTE FUNCTION audit_row_change();
\ No newline at end of file
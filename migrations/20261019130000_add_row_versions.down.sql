@@ -0,0 +1,11 @@
+-- Add down migration script here
+DROP TRIGGER play_participants_version ON play_participants;
+DROP TRIGGER plays_version ON plays;
+DROP TRIGGER players_version ON players;
+DROP TRIGGER games_version ON games;
+DROP FUNCTION bump_row_version();
+
+ALTER TABLE play_participants DROP COLUMN version;
+ALTER TABLE plays DROP COLUMN version;
+ALTER TABLE players DROP COLUMN version;
+ALTER TABLE games DROP COLUMN version;
\ No newline at end of file
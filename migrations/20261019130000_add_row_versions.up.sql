@@ -0,0 +1,27 @@
+-- Add up migration script here
+-- Row versions for optimistic concurrency, exposed by the API as ETags. The
+-- trigger bumps the version on every update that changes a row, so merges,
+-- restores and reverts invalidate ETags just like PATCH does.
+ALTER TABLE games ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE players ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE plays ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE play_participants ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION bump_row_version() RETURNS TRIGGER
+    LANGUAGE plpgsql AS $$
+BEGIN
+    IF NEW IS DISTINCT FROM OLD THEN
+        NEW.version := OLD.version + 1;
+    END IF;
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER games_version BEFORE UPDATE ON games
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+CREATE TRIGGER players_version BEFORE UPDATE ON players
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+CREATE TRIGGER plays_version BEFORE UPDATE ON plays
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+CREATE TRIGGER play_participants_version BEFORE UPDATE ON play_participants
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
\ No newline at end of file
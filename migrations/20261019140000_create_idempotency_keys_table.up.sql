@@ -0,0 +1,15 @@
+-- Add up migration script here
+-- Responses of create requests sent with an Idempotency-Key, replayed when a
+-- client retries the same request. A row without a status is still running.
+CREATE TABLE idempotency_keys (
+    key TEXT NOT NULL,
+    path TEXT NOT NULL,
+    request_hash BYTEA NOT NULL,
+    status_code SMALLINT,
+    content_type TEXT,
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (key, path)
+);
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
\ No newline at end of file
@@ -0,0 +1,12 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS plays_bgg_play_id_key;
+DROP INDEX IF EXISTS players_bgg_username_key;
+DROP INDEX IF EXISTS games_bgg_id_key;
+
+ALTER TABLE plays
+    DROP COLUMN bgg_play_id,
+    DROP COLUMN comments,
+    DROP COLUMN length_minutes,
+    DROP COLUMN location;
+ALTER TABLE players DROP COLUMN bgg_username;
+ALTER TABLE games DROP COLUMN bgg_id;
\ No newline at end of file
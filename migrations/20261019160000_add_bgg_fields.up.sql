@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- What BoardGameGeek knows a game, a player and a play by, plus the play
+-- details its play history carries. A BGG play ID is unique among all plays,
+-- trashed ones included, so importing the same history twice adds nothing.
+ALTER TABLE games ADD COLUMN bgg_id INTEGER;
+ALTER TABLE players ADD COLUMN bgg_username TEXT;
+ALTER TABLE plays
+    ADD COLUMN location TEXT,
+    ADD COLUMN length_minutes INTEGER,
+    ADD COLUMN comments TEXT,
+    ADD COLUMN bgg_play_id BIGINT;
+
+CREATE UNIQUE INDEX games_bgg_id_key ON games (bgg_id) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX players_bgg_username_key ON players (lower(bgg_username))
+    WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX plays_bgg_play_id_key ON plays (bgg_play_id);
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Add down migration script here
+ALTER TABLE games
+    DROP COLUMN weight,
+    DROP COLUMN max_playtime_minutes,
+    DROP COLUMN min_playtime_minutes,
+    DROP COLUMN max_players,
+    DROP COLUMN min_players,
+    DROP COLUMN year_published;
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- Add up migration script here
+-- Game details BoardGameGeek keeps, filled in by importing its collection or
+-- thing XML. Weight is the average complexity rating of BGG users, 1 to 5.
+ALTER TABLE games
+    ADD COLUMN year_published INTEGER,
+    ADD COLUMN min_players INTEGER,
+    ADD COLUMN max_players INTEGER,
+    ADD COLUMN min_playtime_minutes INTEGER,
+    ADD COLUMN max_playtime_minutes INTEGER,
+    ADD COLUMN weight DOUBLE PRECISION;
\ No newline at end of file
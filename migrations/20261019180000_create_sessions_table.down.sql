@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS plays_session_id_idx;
+ALTER TABLE plays DROP COLUMN IF EXISTS session_id;
+DROP TABLE IF EXISTS session_attendees;
+DROP TABLE IF EXISTS sessions;
\ No newline at end of file
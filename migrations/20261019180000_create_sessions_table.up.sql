@@ -0,0 +1,33 @@
+-- Add up migration script here
+-- Game nights. A session is hosted by a player, attended by players and
+-- groups the plays of the evening. Deleting a session keeps its plays;
+-- purging a player drops their attendance and leaves the sessions they
+-- hosted without a host. Attendance is not audited on its own: it is part of
+-- the session and changing it bumps the session's version.
+CREATE TABLE sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    played_on DATE NOT NULL,
+    location TEXT,
+    host_id UUID REFERENCES players(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX sessions_played_on_idx ON sessions (played_on);
+
+CREATE TABLE session_attendees (
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    PRIMARY KEY (session_id, player_id)
+);
+
+CREATE INDEX session_attendees_player_id_idx ON session_attendees (player_id);
+
+ALTER TABLE plays ADD COLUMN session_id UUID REFERENCES sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX plays_session_id_idx ON plays (session_id);
+
+CREATE TRIGGER sessions_audit AFTER INSERT OR UPDATE OR DELETE ON sessions
+    FOR EACH ROW EXECUTE FUNCTION audit_row_change();
+CREATE TRIGGER sessions_version BEFORE UPDATE ON sessions
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS event_games;
+DROP TABLE IF EXISTS event_invitations;
+DROP TABLE IF EXISTS events;
\ No newline at end of file
@@ -0,0 +1,44 @@
+-- Add up migration script here
+-- Game nights being planned. An event invites players, who answer yes, no or
+-- maybe and may list games they want to bring. Once the night happened the
+-- event points at the session it turned into. Invitations and the games
+-- brought are not audited on their own: they are part of the event and
+-- changing them bumps the event's version. The games brought follow an
+-- invitation that moves to another player when players are merged.
+CREATE TABLE events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    scheduled_on DATE NOT NULL,
+    location TEXT,
+    host_id UUID REFERENCES players(id) ON DELETE SET NULL,
+    session_id UUID UNIQUE REFERENCES sessions(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX events_scheduled_on_idx ON events (scheduled_on);
+
+CREATE TABLE event_invitations (
+    event_id UUID NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    response TEXT CHECK (response IN ('yes', 'no', 'maybe')),
+    responded_at TIMESTAMPTZ,
+    PRIMARY KEY (event_id, player_id)
+);
+
+CREATE INDEX event_invitations_player_id_idx ON event_invitations (player_id);
+
+CREATE TABLE event_games (
+    event_id UUID NOT NULL,
+    player_id UUID NOT NULL,
+    game_id UUID NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    PRIMARY KEY (event_id, player_id, game_id),
+    FOREIGN KEY (event_id, player_id)
+        REFERENCES event_invitations(event_id, player_id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX event_games_game_id_idx ON event_games (game_id);
+
+CREATE TRIGGER events_audit AFTER INSERT OR UPDATE OR DELETE ON events
+    FOR EACH ROW EXECUTE FUNCTION audit_row_change();
+CREATE TRIGGER events_version BEFORE UPDATE ON events
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Who made a change. The application sets `bg.actor` to the X-Actor header of
+-- the request on every connection it takes from its pool, so the audit
+-- triggers pick it up without naming the column.
+ALTER TABLE audit_log
+    ADD COLUMN actor TEXT DEFAULT NULLIF(current_setting('bg.actor', true), '');
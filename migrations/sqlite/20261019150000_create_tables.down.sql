@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP TABLE idempotency_keys;
+DROP TABLE play_participants;
+DROP TABLE plays;
+DROP TABLE players;
+DROP TABLE games;
+DROP TABLE audit_log;
\ No newline at end of file
@@ -0,0 +1,367 @@
+-- Add up migration script here
+-- The SQLite schema, one migration for everything the Postgres migrations set
+-- up. IDs and timestamps are generated by the application, names are stored
+-- normalized next to the original for the unique indexes and row versions are
+-- bumped by the application's UPDATE statements. Timestamps are RFC 3339 text
+-- in UTC, which sorts chronologically.
+CREATE TABLE games (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    creator_name TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    normalized_name TEXT NOT NULL,
+    normalized_creator_name TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX games_normalized_name_key
+    ON games (normalized_name, normalized_creator_name)
+    WHERE deleted_at IS NULL;
+
+CREATE TABLE players (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    is_owner BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    normalized_name TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX players_normalized_name_key ON players (normalized_name)
+    WHERE deleted_at IS NULL;
+
+CREATE UNIQUE INDEX uniq_single_owner ON players (is_owner)
+    WHERE is_owner AND deleted_at IS NULL;
+
+CREATE TABLE plays (
+    id BLOB PRIMARY KEY NOT NULL,
+    game_id BLOB NOT NULL REFERENCES games(id) ON DELETE RESTRICT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX plays_game_id_idx ON plays (game_id);
+
+CREATE TABLE play_participants (
+    play_id BLOB NOT NULL REFERENCES plays(id) ON DELETE CASCADE,
+    player_id BLOB NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    score INTEGER,
+    is_winner BOOLEAN NOT NULL DEFAULT FALSE,
+    version INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (play_id, player_id)
+);
+
+CREATE INDEX play_participants_player_id_idx ON play_participants (player_id);
+
+-- Snapshots are stored as JSON text shaped like Postgres' to_jsonb of the row
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete', 'restore', 'purge')),
+    before TEXT,
+    after TEXT,
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log (entity, entity_id, id);
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER games_audit_insert AFTER INSERT ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_update AFTER UPDATE ON games
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.creator_name IS NOT NEW.creator_name
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_delete AFTER DELETE ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'games',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_insert AFTER INSERT ON players
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'players',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'is_owner', json(CASE WHEN NEW.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_update AFTER UPDATE ON players
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.is_owner IS NOT NEW.is_owner
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'players',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'is_owner', json(CASE WHEN OLD.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'is_owner', json(CASE WHEN NEW.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_delete AFTER DELETE ON players
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'players',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'is_owner', json(CASE WHEN OLD.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_insert AFTER INSERT ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_update AFTER UPDATE ON plays
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.game_id IS NOT NEW.game_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_delete AFTER DELETE ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'plays',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER play_participants_audit_insert AFTER INSERT ON play_participants
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'play_participants',
+        lower(substr(hex(NEW.play_id), 1, 8) || '-' || substr(hex(NEW.play_id), 9, 4) || '-' || substr(hex(NEW.play_id), 13, 4) || '-' || substr(hex(NEW.play_id), 17, 4) || '-' || substr(hex(NEW.play_id), 21)) || '/' || lower(substr(hex(NEW.player_id), 1, 8) || '-' || substr(hex(NEW.player_id), 9, 4) || '-' || substr(hex(NEW.player_id), 13, 4) || '-' || substr(hex(NEW.player_id), 17, 4) || '-' || substr(hex(NEW.player_id), 21)),
+        'create',
+        json_object(
+            'play_id', lower(substr(hex(NEW.play_id), 1, 8) || '-' || substr(hex(NEW.play_id), 9, 4) || '-' || substr(hex(NEW.play_id), 13, 4) || '-' || substr(hex(NEW.play_id), 17, 4) || '-' || substr(hex(NEW.play_id), 21)),
+            'player_id', lower(substr(hex(NEW.player_id), 1, 8) || '-' || substr(hex(NEW.player_id), 9, 4) || '-' || substr(hex(NEW.player_id), 13, 4) || '-' || substr(hex(NEW.player_id), 17, 4) || '-' || substr(hex(NEW.player_id), 21)),
+            'score', NEW.score,
+            'is_winner', json(CASE WHEN NEW.is_winner THEN 'true' ELSE 'false' END),
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER play_participants_audit_update AFTER UPDATE ON play_participants
+WHEN OLD.play_id IS NOT NEW.play_id
+    OR OLD.player_id IS NOT NEW.player_id
+    OR OLD.score IS NOT NEW.score
+    OR OLD.is_winner IS NOT NEW.is_winner
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'play_participants',
+        lower(substr(hex(NEW.play_id), 1, 8) || '-' || substr(hex(NEW.play_id), 9, 4) || '-' || substr(hex(NEW.play_id), 13, 4) || '-' || substr(hex(NEW.play_id), 17, 4) || '-' || substr(hex(NEW.play_id), 21)) || '/' || lower(substr(hex(NEW.player_id), 1, 8) || '-' || substr(hex(NEW.player_id), 9, 4) || '-' || substr(hex(NEW.player_id), 13, 4) || '-' || substr(hex(NEW.player_id), 17, 4) || '-' || substr(hex(NEW.player_id), 21)),
+        'update',
+        json_object(
+            'play_id', lower(substr(hex(OLD.play_id), 1, 8) || '-' || substr(hex(OLD.play_id), 9, 4) || '-' || substr(hex(OLD.play_id), 13, 4) || '-' || substr(hex(OLD.play_id), 17, 4) || '-' || substr(hex(OLD.play_id), 21)),
+            'player_id', lower(substr(hex(OLD.player_id), 1, 8) || '-' || substr(hex(OLD.player_id), 9, 4) || '-' || substr(hex(OLD.player_id), 13, 4) || '-' || substr(hex(OLD.player_id), 17, 4) || '-' || substr(hex(OLD.player_id), 21)),
+            'score', OLD.score,
+            'is_winner', json(CASE WHEN OLD.is_winner THEN 'true' ELSE 'false' END),
+            'version', OLD.version
+        ),
+        json_object(
+            'play_id', lower(substr(hex(NEW.play_id), 1, 8) || '-' || substr(hex(NEW.play_id), 9, 4) || '-' || substr(hex(NEW.play_id), 13, 4) || '-' || substr(hex(NEW.play_id), 17, 4) || '-' || substr(hex(NEW.play_id), 21)),
+            'player_id', lower(substr(hex(NEW.player_id), 1, 8) || '-' || substr(hex(NEW.player_id), 9, 4) || '-' || substr(hex(NEW.player_id), 13, 4) || '-' || substr(hex(NEW.player_id), 17, 4) || '-' || substr(hex(NEW.player_id), 21)),
+            'score', NEW.score,
+            'is_winner', json(CASE WHEN NEW.is_winner THEN 'true' ELSE 'false' END),
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER play_participants_audit_delete AFTER DELETE ON play_participants
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'play_participants',
+        lower(substr(hex(OLD.play_id), 1, 8) || '-' || substr(hex(OLD.play_id), 9, 4) || '-' || substr(hex(OLD.play_id), 13, 4) || '-' || substr(hex(OLD.play_id), 17, 4) || '-' || substr(hex(OLD.play_id), 21)) || '/' || lower(substr(hex(OLD.player_id), 1, 8) || '-' || substr(hex(OLD.player_id), 9, 4) || '-' || substr(hex(OLD.player_id), 13, 4) || '-' || substr(hex(OLD.player_id), 17, 4) || '-' || substr(hex(OLD.player_id), 21)),
+        'delete',
+        json_object(
+            'play_id', lower(substr(hex(OLD.play_id), 1, 8) || '-' || substr(hex(OLD.play_id), 9, 4) || '-' || substr(hex(OLD.play_id), 13, 4) || '-' || substr(hex(OLD.play_id), 17, 4) || '-' || substr(hex(OLD.play_id), 21)),
+            'player_id', lower(substr(hex(OLD.player_id), 1, 8) || '-' || substr(hex(OLD.player_id), 9, 4) || '-' || substr(hex(OLD.player_id), 13, 4) || '-' || substr(hex(OLD.player_id), 17, 4) || '-' || substr(hex(OLD.player_id), 21)),
+            'score', OLD.score,
+            'is_winner', json(CASE WHEN OLD.is_winner THEN 'true' ELSE 'false' END),
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TABLE idempotency_keys (
+    key TEXT NOT NULL,
+    path TEXT NOT NULL,
+    request_hash BLOB NOT NULL,
+    status_code INTEGER,
+    content_type TEXT,
+    response_body BLOB,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    PRIMARY KEY (key, path)
+);
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
\ No newline at end of file
@@ -0,0 +1,232 @@
+-- Add down migration script here
+DROP TRIGGER games_audit_insert;
+DROP TRIGGER games_audit_update;
+DROP TRIGGER games_audit_delete;
+DROP TRIGGER players_audit_insert;
+DROP TRIGGER players_audit_update;
+DROP TRIGGER players_audit_delete;
+DROP TRIGGER plays_audit_insert;
+DROP TRIGGER plays_audit_update;
+DROP TRIGGER plays_audit_delete;
+
+CREATE TRIGGER games_audit_insert AFTER INSERT ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_update AFTER UPDATE ON games
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.creator_name IS NOT NEW.creator_name
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_delete AFTER DELETE ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'games',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_insert AFTER INSERT ON players
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'players',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'is_owner', json(CASE WHEN NEW.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_update AFTER UPDATE ON players
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.is_owner IS NOT NEW.is_owner
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'players',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'is_owner', json(CASE WHEN OLD.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'is_owner', json(CASE WHEN NEW.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_delete AFTER DELETE ON players
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'players',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'is_owner', json(CASE WHEN OLD.is_owner THEN 'true' ELSE 'false' END),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_insert AFTER INSERT ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_update AFTER UPDATE ON plays
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.game_id IS NOT NEW.game_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_delete AFTER DELETE ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'plays',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+DROP INDEX plays_bgg_play_id_key;
+DROP INDEX players_bgg_username_key;
+DROP INDEX games_bgg_id_key;
+
+ALTER TABLE plays DROP COLUMN bgg_play_id;
+ALTER TABLE plays DROP COLUMN comments;
+ALTER TABLE plays DROP COLUMN length_minutes;
+ALTER TABLE plays DROP COLUMN location;
+ALTER TABLE players DROP COLUMN bgg_username;
+ALTER TABLE games DROP COLUMN bgg_id;
\ No newline at end of file
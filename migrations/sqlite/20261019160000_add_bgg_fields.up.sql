@@ -0,0 +1,266 @@
+-- Add up migration script here
+-- What BoardGameGeek knows a game, a player and a play by, plus the play
+-- details its play history carries, as in the Postgres migration. The audit
+-- triggers are recreated so their snapshots include the new columns.
+ALTER TABLE games ADD COLUMN bgg_id INTEGER;
+ALTER TABLE players ADD COLUMN bgg_username TEXT;
+ALTER TABLE plays ADD COLUMN location TEXT;
+ALTER TABLE plays ADD COLUMN length_minutes INTEGER;
+ALTER TABLE plays ADD COLUMN comments TEXT;
+ALTER TABLE plays ADD COLUMN bgg_play_id INTEGER;
+
+CREATE UNIQUE INDEX games_bgg_id_key ON games (bgg_id) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX players_bgg_username_key ON players (lower(bgg_username))
+    WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX plays_bgg_play_id_key ON plays (bgg_play_id);
+
+DROP TRIGGER games_audit_insert;
+DROP TRIGGER games_audit_update;
+DROP TRIGGER games_audit_delete;
+DROP TRIGGER players_audit_insert;
+DROP TRIGGER players_audit_update;
+DROP TRIGGER players_audit_delete;
+DROP TRIGGER plays_audit_insert;
+DROP TRIGGER plays_audit_update;
+DROP TRIGGER plays_audit_delete;
+
+CREATE TRIGGER games_audit_insert AFTER INSERT ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'bgg_id', NEW.bgg_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_update AFTER UPDATE ON games
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.creator_name IS NOT NEW.creator_name
+    OR OLD.bgg_id IS NOT NEW.bgg_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'bgg_id', OLD.bgg_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'bgg_id', NEW.bgg_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_delete AFTER DELETE ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'games',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'bgg_id', OLD.bgg_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_insert AFTER INSERT ON players
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'players',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'is_owner', json(CASE WHEN NEW.is_owner THEN 'true' ELSE 'false' END),
+            'bgg_username', NEW.bgg_username,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_update AFTER UPDATE ON players
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.is_owner IS NOT NEW.is_owner
+    OR OLD.bgg_username IS NOT NEW.bgg_username
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'players',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'is_owner', json(CASE WHEN OLD.is_owner THEN 'true' ELSE 'false' END),
+            'bgg_username', OLD.bgg_username,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'is_owner', json(CASE WHEN NEW.is_owner THEN 'true' ELSE 'false' END),
+            'bgg_username', NEW.bgg_username,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER players_audit_delete AFTER DELETE ON players
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'players',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'is_owner', json(CASE WHEN OLD.is_owner THEN 'true' ELSE 'false' END),
+            'bgg_username', OLD.bgg_username,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_insert AFTER INSERT ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'location', NEW.location,
+            'length_minutes', NEW.length_minutes,
+            'comments', NEW.comments,
+            'bgg_play_id', NEW.bgg_play_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_update AFTER UPDATE ON plays
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.game_id IS NOT NEW.game_id
+    OR OLD.location IS NOT NEW.location
+    OR OLD.length_minutes IS NOT NEW.length_minutes
+    OR OLD.comments IS NOT NEW.comments
+    OR OLD.bgg_play_id IS NOT NEW.bgg_play_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'location', OLD.location,
+            'length_minutes', OLD.length_minutes,
+            'comments', OLD.comments,
+            'bgg_play_id', OLD.bgg_play_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'location', NEW.location,
+            'length_minutes', NEW.length_minutes,
+            'comments', NEW.comments,
+            'bgg_play_id', NEW.bgg_play_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_delete AFTER DELETE ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'plays',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'location', OLD.location,
+            'length_minutes', OLD.length_minutes,
+            'comments', OLD.comments,
+            'bgg_play_id', OLD.bgg_play_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
\ No newline at end of file
@@ -0,0 +1,88 @@
+-- Add down migration script here
+DROP TRIGGER games_audit_insert;
+DROP TRIGGER games_audit_update;
+DROP TRIGGER games_audit_delete;
+
+CREATE TRIGGER games_audit_insert AFTER INSERT ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'bgg_id', NEW.bgg_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_update AFTER UPDATE ON games
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.creator_name IS NOT NEW.creator_name
+    OR OLD.bgg_id IS NOT NEW.bgg_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'bgg_id', OLD.bgg_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'bgg_id', NEW.bgg_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_delete AFTER DELETE ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'games',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'bgg_id', OLD.bgg_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+ALTER TABLE games DROP COLUMN weight;
+ALTER TABLE games DROP COLUMN max_playtime_minutes;
+ALTER TABLE games DROP COLUMN min_playtime_minutes;
+ALTER TABLE games DROP COLUMN max_players;
+ALTER TABLE games DROP COLUMN min_players;
+ALTER TABLE games DROP COLUMN year_published;
\ No newline at end of file
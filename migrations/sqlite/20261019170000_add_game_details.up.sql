@@ -0,0 +1,120 @@
+-- Add up migration script here
+-- Game details BoardGameGeek keeps, as in the Postgres migration. The audit
+-- triggers of games are recreated so their snapshots include them.
+ALTER TABLE games ADD COLUMN year_published INTEGER;
+ALTER TABLE games ADD COLUMN min_players INTEGER;
+ALTER TABLE games ADD COLUMN max_players INTEGER;
+ALTER TABLE games ADD COLUMN min_playtime_minutes INTEGER;
+ALTER TABLE games ADD COLUMN max_playtime_minutes INTEGER;
+ALTER TABLE games ADD COLUMN weight REAL;
+
+DROP TRIGGER games_audit_insert;
+DROP TRIGGER games_audit_update;
+DROP TRIGGER games_audit_delete;
+
+CREATE TRIGGER games_audit_insert AFTER INSERT ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'bgg_id', NEW.bgg_id,
+            'year_published', NEW.year_published,
+            'min_players', NEW.min_players,
+            'max_players', NEW.max_players,
+            'min_playtime_minutes', NEW.min_playtime_minutes,
+            'max_playtime_minutes', NEW.max_playtime_minutes,
+            'weight', NEW.weight,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_update AFTER UPDATE ON games
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.name IS NOT NEW.name
+    OR OLD.creator_name IS NOT NEW.creator_name
+    OR OLD.bgg_id IS NOT NEW.bgg_id
+    OR OLD.year_published IS NOT NEW.year_published
+    OR OLD.min_players IS NOT NEW.min_players
+    OR OLD.max_players IS NOT NEW.max_players
+    OR OLD.min_playtime_minutes IS NOT NEW.min_playtime_minutes
+    OR OLD.max_playtime_minutes IS NOT NEW.max_playtime_minutes
+    OR OLD.weight IS NOT NEW.weight
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'games',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'bgg_id', OLD.bgg_id,
+            'year_published', OLD.year_published,
+            'min_players', OLD.min_players,
+            'max_players', OLD.max_players,
+            'min_playtime_minutes', OLD.min_playtime_minutes,
+            'max_playtime_minutes', OLD.max_playtime_minutes,
+            'weight', OLD.weight,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'name', NEW.name,
+            'creator_name', NEW.creator_name,
+            'bgg_id', NEW.bgg_id,
+            'year_published', NEW.year_published,
+            'min_players', NEW.min_players,
+            'max_players', NEW.max_players,
+            'min_playtime_minutes', NEW.min_playtime_minutes,
+            'max_playtime_minutes', NEW.max_playtime_minutes,
+            'weight', NEW.weight,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER games_audit_delete AFTER DELETE ON games
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'games',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'name', OLD.name,
+            'creator_name', OLD.creator_name,
+            'bgg_id', OLD.bgg_id,
+            'year_published', OLD.year_published,
+            'min_players', OLD.min_players,
+            'max_players', OLD.max_players,
+            'min_playtime_minutes', OLD.min_playtime_minutes,
+            'max_playtime_minutes', OLD.max_playtime_minutes,
+            'weight', OLD.weight,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
\ No newline at end of file
@@ -0,0 +1,96 @@
+-- Add down migration script here
+DROP TRIGGER plays_audit_insert;
+DROP TRIGGER plays_audit_update;
+DROP TRIGGER plays_audit_delete;
+
+CREATE TRIGGER plays_audit_insert AFTER INSERT ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'location', NEW.location,
+            'length_minutes', NEW.length_minutes,
+            'comments', NEW.comments,
+            'bgg_play_id', NEW.bgg_play_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_update AFTER UPDATE ON plays
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.game_id IS NOT NEW.game_id
+    OR OLD.location IS NOT NEW.location
+    OR OLD.length_minutes IS NOT NEW.length_minutes
+    OR OLD.comments IS NOT NEW.comments
+    OR OLD.bgg_play_id IS NOT NEW.bgg_play_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'location', OLD.location,
+            'length_minutes', OLD.length_minutes,
+            'comments', OLD.comments,
+            'bgg_play_id', OLD.bgg_play_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'location', NEW.location,
+            'length_minutes', NEW.length_minutes,
+            'comments', NEW.comments,
+            'bgg_play_id', NEW.bgg_play_id,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_delete AFTER DELETE ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'plays',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'location', OLD.location,
+            'length_minutes', OLD.length_minutes,
+            'comments', OLD.comments,
+            'bgg_play_id', OLD.bgg_play_id,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+DROP INDEX plays_session_id_idx;
+ALTER TABLE plays DROP COLUMN session_id;
+DROP TABLE session_attendees;
+DROP TABLE sessions;
\ No newline at end of file
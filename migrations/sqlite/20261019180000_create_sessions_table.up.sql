@@ -0,0 +1,191 @@
+-- Add up migration script here
+-- Game nights, as in the Postgres migration. The application bumps the
+-- version of the plays a deleted session lets go of, and of a session whose
+-- attendance changes. The audit triggers of plays are recreated so their
+-- snapshots include the session.
+CREATE TABLE sessions (
+    id BLOB PRIMARY KEY NOT NULL,
+    played_on TEXT NOT NULL,
+    location TEXT,
+    host_id BLOB REFERENCES players(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX sessions_played_on_idx ON sessions (played_on);
+
+CREATE TABLE session_attendees (
+    session_id BLOB NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    player_id BLOB NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    PRIMARY KEY (session_id, player_id)
+);
+
+CREATE INDEX session_attendees_player_id_idx ON session_attendees (player_id);
+
+ALTER TABLE plays ADD COLUMN session_id BLOB REFERENCES sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX plays_session_id_idx ON plays (session_id);
+
+CREATE TRIGGER sessions_audit_insert AFTER INSERT ON sessions
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'sessions',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'played_on', NEW.played_on,
+            'location', NEW.location,
+            'host_id', CASE WHEN NEW.host_id IS NOT NULL THEN lower(substr(hex(NEW.host_id), 1, 8) || '-' || substr(hex(NEW.host_id), 9, 4) || '-' || substr(hex(NEW.host_id), 13, 4) || '-' || substr(hex(NEW.host_id), 17, 4) || '-' || substr(hex(NEW.host_id), 21)) END,
+            'created_at', NEW.created_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER sessions_audit_update AFTER UPDATE ON sessions
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.played_on IS NOT NEW.played_on
+    OR OLD.location IS NOT NEW.location
+    OR OLD.host_id IS NOT NEW.host_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'sessions',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'update',
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'played_on', OLD.played_on,
+            'location', OLD.location,
+            'host_id', CASE WHEN OLD.host_id IS NOT NULL THEN lower(substr(hex(OLD.host_id), 1, 8) || '-' || substr(hex(OLD.host_id), 9, 4) || '-' || substr(hex(OLD.host_id), 13, 4) || '-' || substr(hex(OLD.host_id), 17, 4) || '-' || substr(hex(OLD.host_id), 21)) END,
+            'created_at', OLD.created_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'played_on', NEW.played_on,
+            'location', NEW.location,
+            'host_id', CASE WHEN NEW.host_id IS NOT NULL THEN lower(substr(hex(NEW.host_id), 1, 8) || '-' || substr(hex(NEW.host_id), 9, 4) || '-' || substr(hex(NEW.host_id), 13, 4) || '-' || substr(hex(NEW.host_id), 17, 4) || '-' || substr(hex(NEW.host_id), 21)) END,
+            'created_at', NEW.created_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER sessions_audit_delete AFTER DELETE ON sessions
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'sessions',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        'delete',
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'played_on', OLD.played_on,
+            'location', OLD.location,
+            'host_id', CASE WHEN OLD.host_id IS NOT NULL THEN lower(substr(hex(OLD.host_id), 1, 8) || '-' || substr(hex(OLD.host_id), 9, 4) || '-' || substr(hex(OLD.host_id), 13, 4) || '-' || substr(hex(OLD.host_id), 17, 4) || '-' || substr(hex(OLD.host_id), 21)) END,
+            'created_at', OLD.created_at,
+            'version', OLD.version
+        )
+    );
+END;
+
+DROP TRIGGER plays_audit_insert;
+DROP TRIGGER plays_audit_update;
+DROP TRIGGER plays_audit_delete;
+
+CREATE TRIGGER plays_audit_insert AFTER INSERT ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'location', NEW.location,
+            'length_minutes', NEW.length_minutes,
+            'comments', NEW.comments,
+            'bgg_play_id', NEW.bgg_play_id,
+            'session_id', CASE WHEN NEW.session_id IS NOT NULL THEN lower(substr(hex(NEW.session_id), 1, 8) || '-' || substr(hex(NEW.session_id), 9, 4) || '-' || substr(hex(NEW.session_id), 13, 4) || '-' || substr(hex(NEW.session_id), 17, 4) || '-' || substr(hex(NEW.session_id), 21)) END,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_update AFTER UPDATE ON plays
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.game_id IS NOT NEW.game_id
+    OR OLD.location IS NOT NEW.location
+    OR OLD.length_minutes IS NOT NEW.length_minutes
+    OR OLD.comments IS NOT NEW.comments
+    OR OLD.bgg_play_id IS NOT NEW.bgg_play_id
+    OR OLD.session_id IS NOT NEW.session_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'plays',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'location', OLD.location,
+            'length_minutes', OLD.length_minutes,
+            'comments', OLD.comments,
+            'bgg_play_id', OLD.bgg_play_id,
+            'session_id', CASE WHEN OLD.session_id IS NOT NULL THEN lower(substr(hex(OLD.session_id), 1, 8) || '-' || substr(hex(OLD.session_id), 9, 4) || '-' || substr(hex(OLD.session_id), 13, 4) || '-' || substr(hex(OLD.session_id), 17, 4) || '-' || substr(hex(OLD.session_id), 21)) END,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'game_id', lower(substr(hex(NEW.game_id), 1, 8) || '-' || substr(hex(NEW.game_id), 9, 4) || '-' || substr(hex(NEW.game_id), 13, 4) || '-' || substr(hex(NEW.game_id), 17, 4) || '-' || substr(hex(NEW.game_id), 21)),
+            'location', NEW.location,
+            'length_minutes', NEW.length_minutes,
+            'comments', NEW.comments,
+            'bgg_play_id', NEW.bgg_play_id,
+            'session_id', CASE WHEN NEW.session_id IS NOT NULL THEN lower(substr(hex(NEW.session_id), 1, 8) || '-' || substr(hex(NEW.session_id), 9, 4) || '-' || substr(hex(NEW.session_id), 13, 4) || '-' || substr(hex(NEW.session_id), 17, 4) || '-' || substr(hex(NEW.session_id), 21)) END,
+            'created_at', NEW.created_at,
+            'deleted_at', NEW.deleted_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER plays_audit_delete AFTER DELETE ON plays
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'plays',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        CASE WHEN OLD.deleted_at IS NOT NULL THEN 'purge' ELSE 'delete' END,
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'game_id', lower(substr(hex(OLD.game_id), 1, 8) || '-' || substr(hex(OLD.game_id), 9, 4) || '-' || substr(hex(OLD.game_id), 13, 4) || '-' || substr(hex(OLD.game_id), 17, 4) || '-' || substr(hex(OLD.game_id), 21)),
+            'location', OLD.location,
+            'length_minutes', OLD.length_minutes,
+            'comments', OLD.comments,
+            'bgg_play_id', OLD.bgg_play_id,
+            'session_id', CASE WHEN OLD.session_id IS NOT NULL THEN lower(substr(hex(OLD.session_id), 1, 8) || '-' || substr(hex(OLD.session_id), 9, 4) || '-' || substr(hex(OLD.session_id), 13, 4) || '-' || substr(hex(OLD.session_id), 17, 4) || '-' || substr(hex(OLD.session_id), 21)) END,
+            'created_at', OLD.created_at,
+            'deleted_at', OLD.deleted_at,
+            'version', OLD.version
+        )
+    );
+END;
\ No newline at end of file
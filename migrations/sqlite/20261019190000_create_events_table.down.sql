@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE event_games;
+DROP TABLE event_invitations;
+DROP TABLE events;
\ No newline at end of file
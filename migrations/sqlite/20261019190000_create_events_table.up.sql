@@ -0,0 +1,108 @@
+-- Add up migration script here
+-- Planned game nights, as in the Postgres migration. The application bumps
+-- the version of an event whose invitations or games brought change.
+CREATE TABLE events (
+    id BLOB PRIMARY KEY NOT NULL,
+    scheduled_on TEXT NOT NULL,
+    location TEXT,
+    host_id BLOB REFERENCES players(id) ON DELETE SET NULL,
+    session_id BLOB UNIQUE REFERENCES sessions(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX events_scheduled_on_idx ON events (scheduled_on);
+
+CREATE TABLE event_invitations (
+    event_id BLOB NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    player_id BLOB NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    response TEXT CHECK (response IN ('yes', 'no', 'maybe')),
+    responded_at TEXT,
+    PRIMARY KEY (event_id, player_id)
+);
+
+CREATE INDEX event_invitations_player_id_idx ON event_invitations (player_id);
+
+CREATE TABLE event_games (
+    event_id BLOB NOT NULL,
+    player_id BLOB NOT NULL,
+    game_id BLOB NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    PRIMARY KEY (event_id, player_id, game_id),
+    FOREIGN KEY (event_id, player_id)
+        REFERENCES event_invitations(event_id, player_id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX event_games_game_id_idx ON event_games (game_id);
+
+CREATE TRIGGER events_audit_insert AFTER INSERT ON events
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, after)
+    VALUES (
+        'events',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'create',
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'scheduled_on', NEW.scheduled_on,
+            'location', NEW.location,
+            'host_id', CASE WHEN NEW.host_id IS NOT NULL THEN lower(substr(hex(NEW.host_id), 1, 8) || '-' || substr(hex(NEW.host_id), 9, 4) || '-' || substr(hex(NEW.host_id), 13, 4) || '-' || substr(hex(NEW.host_id), 17, 4) || '-' || substr(hex(NEW.host_id), 21)) END,
+            'session_id', CASE WHEN NEW.session_id IS NOT NULL THEN lower(substr(hex(NEW.session_id), 1, 8) || '-' || substr(hex(NEW.session_id), 9, 4) || '-' || substr(hex(NEW.session_id), 13, 4) || '-' || substr(hex(NEW.session_id), 17, 4) || '-' || substr(hex(NEW.session_id), 21)) END,
+            'created_at', NEW.created_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER events_audit_update AFTER UPDATE ON events
+WHEN OLD.id IS NOT NEW.id
+    OR OLD.scheduled_on IS NOT NEW.scheduled_on
+    OR OLD.location IS NOT NEW.location
+    OR OLD.host_id IS NOT NEW.host_id
+    OR OLD.session_id IS NOT NEW.session_id
+    OR OLD.created_at IS NOT NEW.created_at
+    OR OLD.version IS NOT NEW.version
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before, after)
+    VALUES (
+        'events',
+        lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+        'update',
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'scheduled_on', OLD.scheduled_on,
+            'location', OLD.location,
+            'host_id', CASE WHEN OLD.host_id IS NOT NULL THEN lower(substr(hex(OLD.host_id), 1, 8) || '-' || substr(hex(OLD.host_id), 9, 4) || '-' || substr(hex(OLD.host_id), 13, 4) || '-' || substr(hex(OLD.host_id), 17, 4) || '-' || substr(hex(OLD.host_id), 21)) END,
+            'session_id', CASE WHEN OLD.session_id IS NOT NULL THEN lower(substr(hex(OLD.session_id), 1, 8) || '-' || substr(hex(OLD.session_id), 9, 4) || '-' || substr(hex(OLD.session_id), 13, 4) || '-' || substr(hex(OLD.session_id), 17, 4) || '-' || substr(hex(OLD.session_id), 21)) END,
+            'created_at', OLD.created_at,
+            'version', OLD.version
+        ),
+        json_object(
+            'id', lower(substr(hex(NEW.id), 1, 8) || '-' || substr(hex(NEW.id), 9, 4) || '-' || substr(hex(NEW.id), 13, 4) || '-' || substr(hex(NEW.id), 17, 4) || '-' || substr(hex(NEW.id), 21)),
+            'scheduled_on', NEW.scheduled_on,
+            'location', NEW.location,
+            'host_id', CASE WHEN NEW.host_id IS NOT NULL THEN lower(substr(hex(NEW.host_id), 1, 8) || '-' || substr(hex(NEW.host_id), 9, 4) || '-' || substr(hex(NEW.host_id), 13, 4) || '-' || substr(hex(NEW.host_id), 17, 4) || '-' || substr(hex(NEW.host_id), 21)) END,
+            'session_id', CASE WHEN NEW.session_id IS NOT NULL THEN lower(substr(hex(NEW.session_id), 1, 8) || '-' || substr(hex(NEW.session_id), 9, 4) || '-' || substr(hex(NEW.session_id), 13, 4) || '-' || substr(hex(NEW.session_id), 17, 4) || '-' || substr(hex(NEW.session_id), 21)) END,
+            'created_at', NEW.created_at,
+            'version', NEW.version
+        )
+    );
+END;
+
+CREATE TRIGGER events_audit_delete AFTER DELETE ON events
+BEGIN
+    INSERT INTO audit_log (entity, entity_id, action, before)
+    VALUES (
+        'events',
+        lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+        'delete',
+        json_object(
+            'id', lower(substr(hex(OLD.id), 1, 8) || '-' || substr(hex(OLD.id), 9, 4) || '-' || substr(hex(OLD.id), 13, 4) || '-' || substr(hex(OLD.id), 17, 4) || '-' || substr(hex(OLD.id), 21)),
+            'scheduled_on', OLD.scheduled_on,
+            'location', OLD.location,
+            'host_id', CASE WHEN OLD.host_id IS NOT NULL THEN lower(substr(hex(OLD.host_id), 1, 8) || '-' || substr(hex(OLD.host_id), 9, 4) || '-' || substr(hex(OLD.host_id), 13, 4) || '-' || substr(hex(OLD.host_id), 17, 4) || '-' || substr(hex(OLD.host_id), 21)) END,
+            'session_id', CASE WHEN OLD.session_id IS NOT NULL THEN lower(substr(hex(OLD.session_id), 1, 8) || '-' || substr(hex(OLD.session_id), 9, 4) || '-' || substr(hex(OLD.session_id), 13, 4) || '-' || substr(hex(OLD.session_id), 17, 4) || '-' || substr(hex(OLD.session_id), 21)) END,
+            'created_at', OLD.created_at,
+            'version', OLD.version
+        )
+    );
+END;
\ No newline at end of file
@@ -0,0 +1,11 @@
+-- Add down migration script here
+DROP TRIGGER audit_log_actor;
+DROP TRIGGER audit_log_no_update;
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+ALTER TABLE audit_log DROP COLUMN actor;
+DROP TABLE audit_context;
@@ -0,0 +1,37 @@
+-- Add up migration script here
+-- Who made a change, as in the Postgres migration. SQLite has no settings to
+-- read in a column default, so the application keeps the actor of the request
+-- in the single row of `audit_context` and every new audit entry is stamped
+-- with it. Stamping is the one update the append-only trigger lets through.
+CREATE TABLE audit_context (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    actor TEXT
+);
+
+INSERT INTO audit_context (id) VALUES (1);
+
+ALTER TABLE audit_log ADD COLUMN actor TEXT;
+
+DROP TRIGGER audit_log_no_update;
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+WHEN NOT (
+    OLD.actor IS NULL
+    AND NEW.id = (SELECT max(id) FROM audit_log)
+    AND NEW.id IS OLD.id
+    AND NEW.entity IS OLD.entity
+    AND NEW.entity_id IS OLD.entity_id
+    AND NEW.action IS OLD.action
+    AND NEW.before IS OLD.before
+    AND NEW.after IS OLD.after
+    AND NEW.changed_at IS OLD.changed_at
+)
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER audit_log_actor AFTER INSERT ON audit_log
+WHEN (SELECT actor FROM audit_context) IS NOT NULL
+BEGIN
+    UPDATE audit_log SET actor = (SELECT actor FROM audit_context) WHERE id = NEW.id;
+END;
@@ -0,0 +1,52 @@
+//! Who makes a request, as told by its `X-Actor` header. The repositories hand
+//! it to the database with every connection they take from their pool, so the
+//! audit triggers record it next to each change.
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::response::ApiError;
+
+const X_ACTOR: &str = "x-actor";
+
+/// Longest actor we accept, in bytes
+const ACTOR_MAX_LEN: usize = 255;
+
+tokio::task_local! {
+    static ACTOR: Option<String>;
+}
+
+/// The actor of the request being served, if it named one. Work outside of a
+/// request, such as the background purges, has none.
+pub fn current() -> Option<String> {
+    ACTOR.try_with(Clone::clone).ok().flatten()
+}
+
+/// Runs `work` with `actor` as its actor
+pub async fn scope<F: Future>(actor: Option<String>, work: F) -> F::Output {
+    ACTOR.scope(actor, work).await
+}
+
+/// Middleware making the `X-Actor` header of a request its actor while it is
+/// served. Requests without the header are served without an actor.
+pub async fn actor(request: Request, next: Next) -> Response {
+    let actor = match request.headers().get(X_ACTOR).map(|actor| actor.to_str()) {
+        None => None,
+        Some(Ok(actor)) if !actor.trim().is_empty() && actor.len() <= ACTOR_MAX_LEN => {
+            Some(actor.trim().to_string())
+        }
+        Some(_) => {
+            return ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "X-Actor must be 1 to 255 visible ASCII characters",
+            )
+            .into_response();
+        }
+    };
+
+    scope(actor, next.run(request)).await
+}
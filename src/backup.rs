@@ -0,0 +1,860 @@
+//! Writing and restoring backup archives. An archive is written a chunk at a
+//! time, so it never has to be held as a whole. An import is planned against a
+//! snapshot of the stored rows, so a dry run reports exactly what the import
+//! would do; the planned rows are then written by the repository in one
+//! transaction.
+
+use std::collections::{HashMap, HashSet};
+
+use axum::body::Bytes;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        AuditLogModel, BackupTables, EventModel, GameModel, PlayModel, PlayParticipantModel,
+        PlayerModel, SessionModel,
+    },
+    repository::{attendees, invitations, normalize_name},
+    response::{ConflictReason, ImportConflict, ImportCounts, ImportReport},
+    schema::{ArchiveSchema, ConflictStrategy},
+};
+
+/// Size an archive chunk grows to before it is handed out
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// `archive` as JSON, the same as serializing it, in chunks of about
+/// `CHUNK_BYTES` that are only serialized as they are asked for
+pub fn write_archive(
+    archive: ArchiveSchema,
+) -> impl Iterator<Item = Result<Bytes, serde_json::Error>> + Send {
+    let head = serde_json::to_value(archive.exported_at).map(|exported_at| {
+        Bytes::from(format!(
+            "{{\"format\":{},\"version\":{},\"exported_at\":{},\"tables\":{{",
+            serde_json::Value::from(archive.format),
+            archive.version,
+            exported_at
+        ))
+    });
+    // Taken apart so a table added later cannot be left out
+    let BackupTables {
+        games,
+        players,
+        sessions,
+        events,
+        plays,
+        play_participants,
+        audit_log,
+    } = archive.tables;
+
+    std::iter::once(head)
+        .chain(write_table("games", games, ","))
+        .chain(write_table("players", players, ","))
+        .chain(write_table("sessions", sessions, ","))
+        .chain(write_table("events", events, ","))
+        .chain(write_table("plays", plays, ","))
+        .chain(write_table("play_participants", play_participants, ","))
+        .chain(write_table("audit_log", audit_log, "}}"))
+}
+
+/// `rows` as the `name` member of the tables object followed by `end`, in
+/// chunks of about `CHUNK_BYTES`
+fn write_table<T: Serialize + Send>(
+    name: &'static str,
+    rows: Vec<T>,
+    end: &'static str,
+) -> impl Iterator<Item = Result<Bytes, serde_json::Error>> + Send {
+    let mut rows = rows.into_iter().enumerate();
+    let mut chunk = format!("\"{}\":[", name).into_bytes();
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        for (i, row) in rows.by_ref() {
+            if i > 0 {
+                chunk.push(b',');
+            }
+            if let Err(e) = serde_json::to_writer(&mut chunk, &row) {
+                done = true;
+                return Some(Err(e));
+            }
+            if chunk.len() >= CHUNK_BYTES {
+                return Some(Ok(Bytes::from(std::mem::take(&mut chunk))));
+            }
+        }
+        done = true;
+        chunk.push(b']');
+        chunk.extend_from_slice(end.as_bytes());
+        Some(Ok(Bytes::from(std::mem::take(&mut chunk))))
+    })
+}
+
+/// What importing an archive comes down to
+pub struct ImportPlan {
+    pub report: ImportReport,
+    /// Rows to write, in the order they have to be written
+    pub rows: BackupTables,
+}
+
+/// An archived row pointing at a row that is neither archived nor stored
+#[derive(Debug)]
+pub struct MissingReference {
+    pub table: &'static str,
+    pub id: String,
+    pub field: &'static str,
+    pub reference: Uuid,
+}
+
+/// Works out how `archive` is merged into the `stored` rows.
+///
+/// Archived rows keep their IDs. A row whose ID is stored with different
+/// contents is a conflict, as is a live game or player whose name is taken by
+/// another live row; the archived row is then matched with that row and
+/// whatever points at it follows. With `Skip` the stored rows are kept, with
+/// `Overwrite` the archived ones replace them. An archived owner that would
+/// become a second owner is imported without ownership by `Skip` and takes it
+/// over with `Overwrite`. `Fail` plans like `Skip`; the caller is expected to
+/// refuse a plan with conflicts.
+pub fn plan_import(
+    stored: BackupTables,
+    archive: BackupTables,
+    strategy: ConflictStrategy,
+    dry_run: bool,
+) -> Result<ImportPlan, MissingReference> {
+    let mut planner = Planner {
+        strategy,
+        stored,
+        rows: BackupTables::default(),
+        game_ids: HashMap::new(),
+        player_ids: HashMap::new(),
+        report: ImportReport {
+            strategy,
+            dry_run,
+            games: ImportCounts::default(),
+            players: ImportCounts::default(),
+            sessions: ImportCounts::default(),
+            events: ImportCounts::default(),
+            plays: ImportCounts::default(),
+            play_participants: ImportCounts::default(),
+            audit_log: ImportCounts::default(),
+            conflicts: Vec::new(),
+        },
+    };
+
+    for game in archive.games {
+        planner.game(game);
+    }
+    for player in archive.players {
+        planner.player(player);
+    }
+    for session in archive.sessions {
+        planner.session(session)?;
+    }
+    for event in archive.events {
+        planner.event(event)?;
+    }
+    for play in archive.plays {
+        planner.play(play)?;
+    }
+    for participant in archive.play_participants {
+        planner.participant(participant)?;
+    }
+    planner.audit_log(archive.audit_log);
+
+    Ok(ImportPlan {
+        report: planner.report,
+        rows: planner.rows,
+    })
+}
+
+struct Planner {
+    strategy: ConflictStrategy,
+    /// The stored rows as they will be once the planned rows are written
+    stored: BackupTables,
+    rows: BackupTables,
+    /// Archived game and player IDs matched with a stored row by name
+    game_ids: HashMap<Uuid, Uuid>,
+    player_ids: HashMap<Uuid, Uuid>,
+    report: ImportReport,
+}
+
+/// Counts an archived row that has a stored counterpart, returning it when it
+/// is to be written over the stored one
+fn resolve<T>(
+    strategy: ConflictStrategy,
+    counts: &mut ImportCounts,
+    row: T,
+    same: bool,
+) -> Option<T> {
+    if same {
+        counts.unchanged += 1;
+        None
+    } else if strategy == ConflictStrategy::Overwrite {
+        counts.updated += 1;
+        Some(row)
+    } else {
+        counts.skipped += 1;
+        None
+    }
+}
+
+pub fn same_game(a: &GameModel, b: &GameModel) -> bool {
+    a.name == b.name
+        && a.creator_name == b.creator_name
+        && a.bgg_id == b.bgg_id
+        && a.year_published == b.year_published
+        && a.min_players == b.min_players
+        && a.max_players == b.max_players
+        && a.min_playtime_minutes == b.min_playtime_minutes
+        && a.max_playtime_minutes == b.max_playtime_minutes
+        && a.weight == b.weight
+        && a.created_at == b.created_at
+        && a.deleted_at == b.deleted_at
+}
+
+fn same_player(a: &PlayerModel, b: &PlayerModel) -> bool {
+    a.name == b.name
+        && a.is_owner == b.is_owner
+        && a.bgg_username == b.bgg_username
+        && a.created_at == b.created_at
+        && a.deleted_at == b.deleted_at
+}
+
+fn same_play(a: &PlayModel, b: &PlayModel) -> bool {
+    a.game_id == b.game_id
+        && a.location == b.location
+        && a.length_minutes == b.length_minutes
+        && a.comments == b.comments
+        && a.bgg_play_id == b.bgg_play_id
+        && a.session_id == b.session_id
+        && a.created_at == b.created_at
+        && a.deleted_at == b.deleted_at
+}
+
+fn same_session(a: &SessionModel, b: &SessionModel) -> bool {
+    a.played_on == b.played_on
+        && a.location == b.location
+        && a.host_id == b.host_id
+        && a.attendee_ids == b.attendee_ids
+        && a.created_at == b.created_at
+}
+
+fn same_event(a: &EventModel, b: &EventModel) -> bool {
+    a.scheduled_on == b.scheduled_on
+        && a.location == b.location
+        && a.host_id == b.host_id
+        && a.session_id == b.session_id
+        && a.invitations == b.invitations
+        && a.created_at == b.created_at
+}
+
+fn same_participant(a: &PlayParticipantModel, b: &PlayParticipantModel) -> bool {
+    a.score == b.score && a.is_winner == b.is_winner
+}
+
+/// What tells audit log entries apart, their IDs aside: an archive restored
+/// elsewhere has its entries logged under other IDs
+fn audit_key(entry: &AuditLogModel) -> String {
+    serde_json::json!([
+        entry.entity,
+        entry.entity_id,
+        entry.action,
+        entry.before,
+        entry.after,
+        entry.changed_at,
+        entry.actor,
+    ])
+    .to_string()
+}
+
+impl Planner {
+    fn conflict(
+        &mut self,
+        table: &'static str,
+        id: String,
+        existing_id: Uuid,
+        reason: ConflictReason,
+    ) {
+        self.report.conflicts.push(ImportConflict {
+            table,
+            id,
+            existing_id: existing_id.to_string(),
+            reason,
+        });
+    }
+
+    fn game(&mut self, mut row: GameModel) {
+        let games = &self.stored.games;
+        let same_id = games.iter().position(|game| game.id == row.id);
+        let same_name = if row.deleted_at.is_none() {
+            let name = normalize_name(&row.name);
+            let creator_name = normalize_name(&row.creator_name);
+            games.iter().position(|game| {
+                game.id != row.id
+                    && game.deleted_at.is_none()
+                    && normalize_name(&game.name) == name
+                    && normalize_name(&game.creator_name) == creator_name
+            })
+        } else {
+            None
+        };
+
+        let index = match (same_id, same_name) {
+            (None, None) => {
+                self.report.games.created += 1;
+                self.stored.games.push(row.clone());
+                self.rows.games.push(row);
+                return;
+            }
+            (_, Some(existing)) => {
+                let existing_id = games[existing].id;
+                self.conflict(
+                    "games",
+                    row.id.to_string(),
+                    existing_id,
+                    ConflictReason::Name,
+                );
+                if same_id.is_some() {
+                    // Taking over the stored row would leave two rows with this name
+                    self.report.games.skipped += 1;
+                    return;
+                }
+                self.game_ids.insert(row.id, existing_id);
+                row.id = existing_id;
+                existing
+            }
+            (Some(index), None) => {
+                if !same_game(&games[index], &row) {
+                    self.conflict("games", row.id.to_string(), row.id, ConflictReason::Id);
+                }
+                index
+            }
+        };
+
+        let same = same_game(&self.stored.games[index], &row);
+        if let Some(row) = resolve(self.strategy, &mut self.report.games, row, same) {
+            self.stored.games[index] = row.clone();
+            self.rows.games.push(row);
+        }
+    }
+
+    fn player(&mut self, mut row: PlayerModel) {
+        let players = &self.stored.players;
+        let same_id = players.iter().position(|player| player.id == row.id);
+        let same_name = if row.deleted_at.is_none() {
+            let name = normalize_name(&row.name);
+            players.iter().position(|player| {
+                player.id != row.id
+                    && player.deleted_at.is_none()
+                    && normalize_name(&player.name) == name
+            })
+        } else {
+            None
+        };
+
+        let index = match (same_id, same_name) {
+            (None, None) => None,
+            (_, Some(existing)) => {
+                let existing_id = players[existing].id;
+                self.conflict(
+                    "players",
+                    row.id.to_string(),
+                    existing_id,
+                    ConflictReason::Name,
+                );
+                if same_id.is_some() {
+                    self.report.players.skipped += 1;
+                    return;
+                }
+                self.player_ids.insert(row.id, existing_id);
+                row.id = existing_id;
+                Some(existing)
+            }
+            (Some(index), None) => {
+                if !same_player(&players[index], &row) {
+                    self.conflict("players", row.id.to_string(), row.id, ConflictReason::Id);
+                }
+                Some(index)
+            }
+        };
+
+        let write = match index {
+            Some(index) => {
+                let same = same_player(&self.stored.players[index], &row);
+                resolve(self.strategy, &mut self.report.players, row, same)
+            }
+            None => {
+                self.report.players.created += 1;
+                Some(row)
+            }
+        };
+        let Some(mut row) = write else {
+            return;
+        };
+
+        if row.is_owner && row.deleted_at.is_none() {
+            let owner = self.stored.players.iter().position(|player| {
+                player.id != row.id && player.is_owner && player.deleted_at.is_none()
+            });
+            if let Some(owner) = owner {
+                let owner_id = self.stored.players[owner].id;
+                self.conflict(
+                    "players",
+                    row.id.to_string(),
+                    owner_id,
+                    ConflictReason::Owner,
+                );
+                if self.strategy == ConflictStrategy::Overwrite {
+                    let previous_owner = &mut self.stored.players[owner];
+                    previous_owner.is_owner = false;
+                    self.rows.players.push(previous_owner.clone());
+                } else {
+                    row.is_owner = false;
+                }
+            }
+        }
+
+        match index {
+            Some(index) => self.stored.players[index] = row.clone(),
+            None => self.stored.players.push(row.clone()),
+        }
+        self.rows.players.push(row);
+    }
+
+    /// Sessions keep their IDs; the players they point at follow the players
+    /// matched by name
+    fn session(&mut self, mut row: SessionModel) -> Result<(), MissingReference> {
+        let player = |id| self.player_ids.get(&id).copied().unwrap_or(id);
+        row.host_id = row.host_id.map(player);
+        let listed: Vec<_> = row.attendee_ids.iter().copied().map(player).collect();
+        row.attendee_ids = attendees(row.host_id, &listed);
+
+        let references = row
+            .host_id
+            .map(|id| ("host_id", id))
+            .into_iter()
+            .chain(row.attendee_ids.iter().map(|id| ("attendee_ids", *id)));
+        for (field, reference) in references {
+            if !self.stored.players.iter().any(|p| p.id == reference) {
+                return Err(MissingReference {
+                    table: "sessions",
+                    id: row.id.to_string(),
+                    field,
+                    reference,
+                });
+            }
+        }
+
+        let sessions = &self.stored.sessions;
+        let Some(index) = sessions.iter().position(|session| session.id == row.id) else {
+            self.report.sessions.created += 1;
+            self.stored.sessions.push(row.clone());
+            self.rows.sessions.push(row);
+            return Ok(());
+        };
+
+        let same = same_session(&sessions[index], &row);
+        if !same {
+            self.conflict("sessions", row.id.to_string(), row.id, ConflictReason::Id);
+        }
+        if let Some(row) = resolve(self.strategy, &mut self.report.sessions, row, same) {
+            self.stored.sessions[index] = row.clone();
+            self.rows.sessions.push(row);
+        }
+        Ok(())
+    }
+
+    /// Events keep their IDs; the players and games they point at follow the
+    /// ones matched by name
+    fn event(&mut self, mut row: EventModel) -> Result<(), MissingReference> {
+        let player = |id| self.player_ids.get(&id).copied().unwrap_or(id);
+        let game = |id| self.game_ids.get(&id).copied().unwrap_or(id);
+        row.host_id = row.host_id.map(player);
+        let mut listed = Vec::new();
+        for invitation in &mut row.invitations {
+            invitation.player_id = player(invitation.player_id);
+            invitation.game_ids = invitation.game_ids.iter().copied().map(game).collect();
+            invitation.game_ids.sort();
+            invitation.game_ids.dedup();
+            listed.push(invitation.player_id);
+        }
+        row.invitations = invitations(&row.invitations, row.host_id, &listed);
+
+        let stored = &self.stored;
+        let players = row
+            .host_id
+            .map(|id| ("host_id", id))
+            .into_iter()
+            .chain(row.invitations.iter().map(|i| ("invitee_ids", i.player_id)))
+            .filter(|(_, id)| !stored.players.iter().any(|p| p.id == *id));
+        let games = row
+            .invitations
+            .iter()
+            .flat_map(|i| i.game_ids.iter().map(|id| ("game_ids", *id)))
+            .filter(|(_, id)| !stored.games.iter().any(|g| g.id == *id));
+        let session = row
+            .session_id
+            .map(|id| ("session_id", id))
+            .filter(|(_, id)| !stored.sessions.iter().any(|s| s.id == *id));
+        let missing = players.chain(games).chain(session).next();
+        if let Some((field, reference)) = missing {
+            return Err(MissingReference {
+                table: "events",
+                id: row.id.to_string(),
+                field,
+                reference,
+            });
+        }
+
+        let events = &self.stored.events;
+        let Some(index) = events.iter().position(|event| event.id == row.id) else {
+            self.report.events.created += 1;
+            self.stored.events.push(row.clone());
+            self.rows.events.push(row);
+            return Ok(());
+        };
+
+        let same = same_event(&events[index], &row);
+        if !same {
+            self.conflict("events", row.id.to_string(), row.id, ConflictReason::Id);
+        }
+        if let Some(row) = resolve(self.strategy, &mut self.report.events, row, same) {
+            self.stored.events[index] = row.clone();
+            self.rows.events.push(row);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mut row: PlayModel) -> Result<(), MissingReference> {
+        row.game_id = self
+            .game_ids
+            .get(&row.game_id)
+            .copied()
+            .unwrap_or(row.game_id);
+        let missing = if !self.stored.games.iter().any(|game| game.id == row.game_id) {
+            Some(("game_id", row.game_id))
+        } else {
+            row.session_id
+                .filter(|id| !self.stored.sessions.iter().any(|s| s.id == *id))
+                .map(|id| ("session_id", id))
+        };
+        if let Some((field, reference)) = missing {
+            return Err(MissingReference {
+                table: "plays",
+                id: row.id.to_string(),
+                field,
+                reference,
+            });
+        }
+
+        let Some(index) = self.stored.plays.iter().position(|play| play.id == row.id) else {
+            self.report.plays.created += 1;
+            self.stored.plays.push(row.clone());
+            self.rows.plays.push(row);
+            return Ok(());
+        };
+
+        let same = same_play(&self.stored.plays[index], &row);
+        if !same {
+            self.conflict("plays", row.id.to_string(), row.id, ConflictReason::Id);
+        }
+        if let Some(row) = resolve(self.strategy, &mut self.report.plays, row, same) {
+            self.stored.plays[index] = row.clone();
+            self.rows.plays.push(row);
+        }
+        Ok(())
+    }
+
+    fn participant(&mut self, mut row: PlayParticipantModel) -> Result<(), MissingReference> {
+        let id = format!("{}/{}", row.play_id, row.player_id);
+        row.player_id = self
+            .player_ids
+            .get(&row.player_id)
+            .copied()
+            .unwrap_or(row.player_id);
+
+        let missing = if !self.stored.plays.iter().any(|play| play.id == row.play_id) {
+            Some(("play_id", row.play_id))
+        } else if !self
+            .stored
+            .players
+            .iter()
+            .any(|player| player.id == row.player_id)
+        {
+            Some(("player_id", row.player_id))
+        } else {
+            None
+        };
+        if let Some((field, reference)) = missing {
+            return Err(MissingReference {
+                table: "play_participants",
+                id,
+                field,
+                reference,
+            });
+        }
+
+        let Some(index) = self
+            .stored
+            .play_participants
+            .iter()
+            .position(|participant| {
+                participant.play_id == row.play_id && participant.player_id == row.player_id
+            })
+        else {
+            self.report.play_participants.created += 1;
+            self.stored.play_participants.push(row.clone());
+            self.rows.play_participants.push(row);
+            return Ok(());
+        };
+
+        let same = same_participant(&self.stored.play_participants[index], &row);
+        if !same {
+            self.report.conflicts.push(ImportConflict {
+                table: "play_participants",
+                id,
+                existing_id: format!("{}/{}", row.play_id, row.player_id),
+                reason: ConflictReason::Id,
+            });
+        }
+        if let Some(row) = resolve(self.strategy, &mut self.report.play_participants, row, same) {
+            self.stored.play_participants[index] = row.clone();
+            self.rows.play_participants.push(row);
+        }
+        Ok(())
+    }
+
+    /// Appends the archived entries that are not logged yet. The log is
+    /// append-only, so entries never clash.
+    fn audit_log(&mut self, entries: Vec<AuditLogModel>) {
+        let logged: HashSet<_> = self.stored.audit_log.iter().map(audit_key).collect();
+        for entry in entries {
+            if logged.contains(&audit_key(&entry)) {
+                self.report.audit_log.unchanged += 1;
+            } else {
+                self.report.audit_log.created += 1;
+                self.rows.audit_log.push(entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn game(name: &str) -> GameModel {
+        GameModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            creator_name: "Klaus Teuber".to_string(),
+            bgg_id: None,
+            year_published: None,
+            min_players: None,
+            max_players: None,
+            min_playtime_minutes: None,
+            max_playtime_minutes: None,
+            weight: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    fn player(name: &str, is_owner: bool) -> PlayerModel {
+        PlayerModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            is_owner,
+            bgg_username: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    fn play(game_id: Uuid) -> PlayModel {
+        PlayModel {
+            id: Uuid::new_v4(),
+            game_id,
+            session_id: None,
+            location: None,
+            length_minutes: None,
+            comments: None,
+            bgg_play_id: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn a_written_archive_is_the_serialized_one() {
+        let games: Vec<_> = (0..1000).map(|i| game(&format!("Game {}", i))).collect();
+        let plays = games.iter().map(|game| play(game.id)).collect();
+        let archive = ArchiveSchema {
+            format: "bg-logger".to_string(),
+            version: 2,
+            exported_at: Utc::now(),
+            tables: BackupTables {
+                games,
+                players: vec![player("Ann", true)],
+                plays,
+                ..BackupTables::default()
+            },
+        };
+        let expected = serde_json::to_value(&archive).unwrap();
+
+        let chunks: Vec<_> = write_archive(archive).map(Result::unwrap).collect();
+
+        assert!(chunks.len() > 2);
+        let written: serde_json::Value = serde_json::from_slice(&chunks.concat()).unwrap();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn reimporting_an_archive_changes_nothing() {
+        let catan = game("Catan");
+        let tables = BackupTables {
+            plays: vec![play(catan.id)],
+            games: vec![catan],
+            players: vec![player("Ann", true)],
+            sessions: Vec::new(),
+            events: Vec::new(),
+            play_participants: Vec::new(),
+            audit_log: Vec::new(),
+        };
+
+        let plan = plan_import(tables.clone(), tables, ConflictStrategy::Fail, false).unwrap();
+
+        assert!(plan.report.conflicts.is_empty());
+        assert_eq!(plan.report.games.unchanged, 1);
+        assert_eq!(plan.report.players.unchanged, 1);
+        assert_eq!(plan.report.plays.unchanged, 1);
+        assert!(plan.rows.games.is_empty() && plan.rows.players.is_empty());
+        assert!(plan.rows.plays.is_empty());
+    }
+
+    #[test]
+    fn audit_log_entries_are_matched_without_their_ids() {
+        let catan = game("Catan");
+        let created = AuditLogModel {
+            id: 1,
+            entity: "games".to_string(),
+            entity_id: catan.id.to_string(),
+            action: "create".to_string(),
+            before: None,
+            after: Some(serde_json::to_value(&catan).unwrap()),
+            changed_at: catan.created_at,
+            actor: Some("ann".to_string()),
+        };
+        let trashed = AuditLogModel {
+            id: 2,
+            action: "delete".to_string(),
+            changed_at: Utc::now(),
+            ..created.clone()
+        };
+        let current = BackupTables {
+            audit_log: vec![AuditLogModel {
+                id: 7,
+                ..created.clone()
+            }],
+            ..Default::default()
+        };
+        let archive = BackupTables {
+            audit_log: vec![created, trashed],
+            ..Default::default()
+        };
+
+        let plan = plan_import(current, archive, ConflictStrategy::Fail, false).unwrap();
+
+        assert_eq!(plan.report.audit_log.unchanged, 1);
+        assert_eq!(plan.report.audit_log.created, 1);
+        assert_eq!(plan.rows.audit_log.len(), 1);
+        assert_eq!(plan.rows.audit_log[0].action, "delete");
+    }
+
+    #[test]
+    fn plays_follow_a_game_matched_by_name() {
+        let stored = game("Catan");
+        let archived = game(" catan ");
+        let archived_play = play(archived.id);
+        let archive = BackupTables {
+            games: vec![archived.clone()],
+            plays: vec![archived_play],
+            ..Default::default()
+        };
+        let current = BackupTables {
+            games: vec![stored.clone()],
+            ..Default::default()
+        };
+
+        let plan = plan_import(current, archive, ConflictStrategy::Skip, false).unwrap();
+
+        assert_eq!(plan.report.conflicts.len(), 1);
+        assert_eq!(plan.report.conflicts[0].reason, ConflictReason::Name);
+        assert_eq!(plan.report.games.skipped, 1);
+        assert!(plan.rows.games.is_empty());
+        assert_eq!(plan.rows.plays[0].game_id, stored.id);
+    }
+
+    #[test]
+    fn overwrite_hands_ownership_to_the_archived_owner() {
+        let stored_owner = player("Ann", true);
+        let archived_owner = player("Bob", true);
+        let current = BackupTables {
+            players: vec![stored_owner.clone()],
+            ..Default::default()
+        };
+        let archive = BackupTables {
+            players: vec![archived_owner.clone()],
+            ..Default::default()
+        };
+
+        let skipped = plan_import(
+            current.clone(),
+            archive.clone(),
+            ConflictStrategy::Skip,
+            false,
+        )
+        .unwrap();
+        assert_eq!(skipped.report.conflicts[0].reason, ConflictReason::Owner);
+        assert!(!skipped.rows.players[0].is_owner);
+
+        let overwritten =
+            plan_import(current, archive, ConflictStrategy::Overwrite, false).unwrap();
+        let owners: Vec<_> = overwritten
+            .rows
+            .players
+            .iter()
+            .map(|player| (player.id, player.is_owner))
+            .collect();
+        assert_eq!(
+            owners,
+            [(stored_owner.id, false), (archived_owner.id, true)]
+        );
+    }
+
+    #[test]
+    fn a_play_of_an_unknown_game_is_refused() {
+        let archive = BackupTables {
+            plays: vec![play(Uuid::new_v4())],
+            ..Default::default()
+        };
+
+        let missing = plan_import(
+            BackupTables::default(),
+            archive,
+            ConflictStrategy::Fail,
+            false,
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!((missing.table, missing.field), ("plays", "game_id"));
+    }
+}
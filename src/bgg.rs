@@ -0,0 +1,816 @@
+//! BoardGameGeek data read from files saved from its XML API, so importing
+//! needs no network access. A user's play history is what
+//! `https://boardgamegeek.com/xmlapi2/plays?username=...` returns, one file
+//! per page; game details come from `thing` or `collection` documents.
+
+use std::collections::HashSet;
+
+use chrono::{NaiveDate, Utc};
+use roxmltree::{Document, Node};
+use uuid::Uuid;
+
+use crate::{
+    backup::same_game,
+    model::{BackupTables, GameModel, PlayModel, PlayParticipantModel, PlayerModel},
+    repository::normalize_name,
+    response::{BggGamesImportData, BggPlaysImportData},
+    validation::{NAME_MAX_CHARS, SCORE_MAX, SCORE_MIN, normalize_text},
+};
+
+/// Creator of games created from BGG data, which does not name one
+pub const UNKNOWN_CREATOR: &str = "Unknown";
+
+/// A `<play>` of a BGG play history
+#[derive(Debug)]
+pub struct BggPlay {
+    pub id: i64,
+    pub date: NaiveDate,
+    /// How many times the play was logged at once
+    pub quantity: u32,
+    pub length_minutes: Option<i32>,
+    pub location: Option<String>,
+    pub comments: Option<String>,
+    /// The game's BGG object ID and name
+    pub object_id: i32,
+    pub name: String,
+    pub players: Vec<BggPlayer>,
+}
+
+#[derive(Debug)]
+pub struct BggPlayer {
+    pub username: Option<String>,
+    pub name: Option<String>,
+    /// Free text on BGG
+    pub score: Option<String>,
+    pub win: bool,
+}
+
+/// Text of an attribute, `None` when absent or blank
+fn attribute(node: Node, name: &str) -> Option<String> {
+    node.attribute(name)
+        .map(normalize_text)
+        .filter(|text| !text.is_empty())
+}
+
+/// Cuts a name down to the longest one we accept
+fn clip_name(name: &str) -> String {
+    name.chars().take(NAME_MAX_CHARS as usize).collect()
+}
+
+/// Reads the `<play>` elements of a `<plays>` document
+pub fn parse_plays(xml: &str) -> Result<Vec<BggPlay>, String> {
+    let document = Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("plays") {
+        return Err(format!(
+            "expected a <plays> document, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    root.children()
+        .filter(|node| node.has_tag_name("play"))
+        .map(|play| {
+            let id = play
+                .attribute("id")
+                .and_then(|id| id.parse().ok())
+                .ok_or("a <play> has no numeric id")?;
+            let date = play
+                .attribute("date")
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                .ok_or_else(|| format!("play {} has no valid date", id))?;
+            let item = play
+                .children()
+                .find(|node| node.has_tag_name("item"))
+                .ok_or_else(|| format!("play {} has no <item>", id))?;
+            let object_id = item
+                .attribute("objectid")
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| format!("the <item> of play {} has no numeric objectid", id))?;
+            let name = attribute(item, "name")
+                .ok_or_else(|| format!("the <item> of play {} has no name", id))?;
+
+            let comments = play
+                .children()
+                .find(|node| node.has_tag_name("comments"))
+                .and_then(|node| node.text())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(str::to_string);
+            let players = play
+                .children()
+                .filter(|node| node.has_tag_name("players"))
+                .flat_map(|players| players.children())
+                .filter(|node| node.has_tag_name("player"))
+                .map(|player| BggPlayer {
+                    username: attribute(player, "username"),
+                    name: attribute(player, "name"),
+                    score: attribute(player, "score"),
+                    win: player.attribute("win") == Some("1"),
+                })
+                .collect();
+
+            Ok(BggPlay {
+                id,
+                date,
+                quantity: play
+                    .attribute("quantity")
+                    .and_then(|quantity| quantity.parse().ok())
+                    .unwrap_or(1),
+                length_minutes: play
+                    .attribute("length")
+                    .and_then(|length| length.parse().ok())
+                    .filter(|&length| length > 0),
+                location: attribute(play, "location"),
+                comments,
+                object_id,
+                name: clip_name(&name),
+                players,
+            })
+        })
+        .collect()
+}
+
+/// A whole number score, if the free text is one within bounds
+pub fn parse_score(text: &str) -> Option<i32> {
+    let score = text.parse::<f64>().ok()?;
+    let in_range = (f64::from(SCORE_MIN)..=f64::from(SCORE_MAX)).contains(&score);
+    (score.fract() == 0.0 && in_range).then_some(score as i32)
+}
+
+/// New and updated rows to write and the report to answer with
+pub struct BggPlaysImport {
+    pub rows: BackupTables,
+    pub data: BggPlaysImportData,
+}
+
+/// Works out the rows importing `plays` adds to `stored`.
+///
+/// Plays whose BGG play ID is stored already, trashed ones included, are
+/// skipped. Games are matched by BGG object ID, then by name among the games
+/// without one; players by BGG username, then by name among the players
+/// without one. A game or player matched by name gets its BGG ID or username
+/// stored, anything not matched is created.
+pub fn plan_plays_import(plays: Vec<BggPlay>, stored: BackupTables) -> BggPlaysImport {
+    let mut importer = Importer {
+        imported: stored
+            .plays
+            .iter()
+            .filter_map(|play| play.bgg_play_id)
+            .collect(),
+        games: stored
+            .games
+            .into_iter()
+            .filter(|game| game.deleted_at.is_none())
+            .collect(),
+        players: stored
+            .players
+            .into_iter()
+            .filter(|player| player.deleted_at.is_none())
+            .collect(),
+        matched_games: HashSet::new(),
+        matched_players: HashSet::new(),
+        rows: BackupTables::default(),
+        data: BggPlaysImportData::default(),
+    };
+
+    for play in plays {
+        importer.play(play);
+    }
+
+    importer.data.games_matched = importer.matched_games.len();
+    importer.data.players_matched = importer.matched_players.len();
+    BggPlaysImport {
+        rows: importer.rows,
+        data: importer.data,
+    }
+}
+
+struct Importer {
+    /// BGG play IDs already stored or imported
+    imported: HashSet<i64>,
+    /// Live games and players, the ones this import creates included
+    games: Vec<GameModel>,
+    players: Vec<PlayerModel>,
+    /// Stored games and players the import used
+    matched_games: HashSet<Uuid>,
+    matched_players: HashSet<Uuid>,
+    rows: BackupTables,
+    data: BggPlaysImportData,
+}
+
+impl Importer {
+    fn play(&mut self, bgg_play: BggPlay) {
+        if !self.imported.insert(bgg_play.id) {
+            self.data.plays_skipped += 1;
+            return;
+        }
+        if bgg_play.quantity > 1 {
+            self.data.warnings.push(format!(
+                "play {} was logged {} times at once and is imported once",
+                bgg_play.id, bgg_play.quantity
+            ));
+        }
+
+        let play = PlayModel {
+            id: Uuid::new_v4(),
+            game_id: self.game(bgg_play.object_id, &bgg_play.name),
+            session_id: None,
+            location: bgg_play.location,
+            length_minutes: bgg_play.length_minutes,
+            comments: bgg_play.comments,
+            bgg_play_id: Some(bgg_play.id),
+            created_at: bgg_play.date.and_time(Default::default()).and_utc(),
+            deleted_at: None,
+            version: 1,
+        };
+
+        let mut participants = HashSet::new();
+        for bgg_player in bgg_play.players {
+            let Some(player_id) = self.player(bgg_play.id, &bgg_player) else {
+                continue;
+            };
+            if !participants.insert(player_id) {
+                self.data.warnings.push(format!(
+                    "play {} lists {} twice; only the first entry is imported",
+                    bgg_play.id,
+                    bgg_player.name.or(bgg_player.username).unwrap_or_default()
+                ));
+                continue;
+            }
+
+            let score = bgg_player.score.as_deref().and_then(|text| {
+                let score = parse_score(text);
+                if score.is_none() {
+                    self.data.warnings.push(format!(
+                        "play {}: score \"{}\" is not a whole number between {} and {} and is left out",
+                        bgg_play.id, text, SCORE_MIN, SCORE_MAX
+                    ));
+                }
+                score
+            });
+            self.rows.play_participants.push(PlayParticipantModel {
+                play_id: play.id,
+                player_id,
+                score,
+                is_winner: bgg_player.win,
+                version: 1,
+            });
+        }
+
+        self.data.plays_imported += 1;
+        self.rows.plays.push(play);
+    }
+
+    fn game(&mut self, object_id: i32, name: &str) -> Uuid {
+        if let Some(game) = self
+            .games
+            .iter()
+            .find(|game| game.bgg_id == Some(object_id))
+        {
+            self.matched_games.insert(game.id);
+            return game.id;
+        }
+
+        let key = normalize_name(name);
+        let mut same_name: Vec<_> = self
+            .games
+            .iter_mut()
+            .filter(|game| game.bgg_id.is_none() && normalize_name(&game.name) == key)
+            .collect();
+        same_name.sort_by_key(|game| game.created_at);
+        if let Some(game) = same_name.into_iter().next() {
+            game.bgg_id = Some(object_id);
+            self.matched_games.insert(game.id);
+            self.rows.games.push(game.clone());
+            return game.id;
+        }
+
+        // Another game of this name by an unknown creator is a different BGG item
+        let taken = self.games.iter().any(|game| {
+            normalize_name(&game.name) == key
+                && normalize_name(&game.creator_name) == normalize_name(UNKNOWN_CREATOR)
+        });
+        let creator_name = if taken {
+            format!("{} (BGG {})", UNKNOWN_CREATOR, object_id)
+        } else {
+            UNKNOWN_CREATOR.to_string()
+        };
+        let game = GameModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            creator_name,
+            bgg_id: Some(object_id),
+            year_published: None,
+            min_players: None,
+            max_players: None,
+            min_playtime_minutes: None,
+            max_playtime_minutes: None,
+            weight: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        self.games.push(game.clone());
+        self.rows.games.push(game.clone());
+        let id = game.id;
+        self.data.games_created.push(game);
+        id
+    }
+
+    fn player(&mut self, play_id: i64, bgg_player: &BggPlayer) -> Option<Uuid> {
+        let username = bgg_player.username.as_deref();
+        let Some(name) = bgg_player.name.as_deref().or(username).map(clip_name) else {
+            self.data.warnings.push(format!(
+                "play {} lists a player without name or username, who is left out",
+                play_id
+            ));
+            return None;
+        };
+
+        if let Some(username) = username
+            && let Some(player) = self.players.iter().find(|player| {
+                player
+                    .bgg_username
+                    .as_deref()
+                    .is_some_and(|stored| stored.eq_ignore_ascii_case(username))
+            })
+        {
+            self.matched_players.insert(player.id);
+            return Some(player.id);
+        }
+
+        let key = normalize_name(&name);
+        if let Some(player) = self
+            .players
+            .iter_mut()
+            .find(|player| player.bgg_username.is_none() && normalize_name(&player.name) == key)
+        {
+            if let Some(username) = username {
+                player.bgg_username = Some(username.to_string());
+                self.rows.players.push(player.clone());
+            }
+            self.matched_players.insert(player.id);
+            return Some(player.id);
+        }
+
+        // The name belongs to a player with another username
+        let name = match username {
+            Some(username) if self.name_taken(&name) => format!("{} ({})", name, username),
+            _ => name,
+        };
+        if self.name_taken(&name) {
+            self.data.warnings.push(format!(
+                "play {}: player {} clashes with an existing player and is left out",
+                play_id, name
+            ));
+            return None;
+        }
+
+        let player = PlayerModel {
+            id: Uuid::new_v4(),
+            name: clip_name(&name),
+            is_owner: false,
+            bgg_username: username.map(str::to_string),
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        self.players.push(player.clone());
+        self.rows.players.push(player.clone());
+        let id = player.id;
+        self.data.players_created.push(player);
+        Some(id)
+    }
+
+    fn name_taken(&self, name: &str) -> bool {
+        let key = normalize_name(name);
+        self.players
+            .iter()
+            .any(|player| normalize_name(&player.name) == key)
+    }
+}
+
+/// A game of a BGG `thing` or `collection` document. BGG writes 0 for
+/// values it does not know, which are read as absent.
+#[derive(Debug)]
+pub struct BggGame {
+    pub id: i32,
+    pub name: String,
+    pub year_published: Option<i32>,
+    pub min_players: Option<i32>,
+    pub max_players: Option<i32>,
+    pub min_playtime_minutes: Option<i32>,
+    pub max_playtime_minutes: Option<i32>,
+    /// Only in `thing` documents asked for with `stats=1`
+    pub weight: Option<f64>,
+    /// Only in `thing` documents
+    pub designers: Vec<String>,
+    /// False for collection items the user does not own, like wishlist
+    /// items and games owned before
+    pub owned: bool,
+}
+
+/// A positive number from an attribute
+fn positive<T: std::str::FromStr + Default + PartialOrd>(node: Node, name: &str) -> Option<T> {
+    node.attribute(name)
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|value| *value > T::default())
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+/// The `value` of a `thing` child element, like `<minplayers value="2"/>`
+fn thing_value<T: std::str::FromStr + Default + PartialOrd>(item: Node, tag: &str) -> Option<T> {
+    child(item, tag).and_then(|node| positive(node, "value"))
+}
+
+/// Reads the `<item>` elements of an `<items>` document, as returned by
+/// `https://boardgamegeek.com/xmlapi2/thing?id=...&stats=1` or
+/// `https://boardgamegeek.com/xmlapi2/collection?username=...&stats=1`
+pub fn parse_games(xml: &str) -> Result<Vec<BggGame>, String> {
+    let document = Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("items") {
+        return Err(format!(
+            "expected an <items> document, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    root.children()
+        .filter(|node| node.has_tag_name("item"))
+        .map(|item| {
+            // Collections refer to the game by `objectid`, `id` being the thing's own
+            if let Some(id) = item.attribute("objectid") {
+                let id = id
+                    .parse()
+                    .map_err(|_| "an <item> has no numeric objectid".to_string())?;
+                let name = child(item, "name")
+                    .and_then(|node| node.text())
+                    .map(normalize_text)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| format!("item {} has no name", id))?;
+                let stats = child(item, "stats");
+                let stat = |name| stats.and_then(|stats| positive(stats, name));
+
+                return Ok(BggGame {
+                    id,
+                    name: clip_name(&name),
+                    year_published: child(item, "yearpublished")
+                        .and_then(|node| node.text())
+                        .and_then(|year| year.trim().parse().ok())
+                        .filter(|&year| year > 0),
+                    min_players: stat("minplayers"),
+                    max_players: stat("maxplayers"),
+                    min_playtime_minutes: stat("minplaytime").or_else(|| stat("playingtime")),
+                    max_playtime_minutes: stat("maxplaytime").or_else(|| stat("playingtime")),
+                    weight: None,
+                    designers: Vec::new(),
+                    owned: child(item, "status").and_then(|node| node.attribute("own"))
+                        == Some("1"),
+                });
+            }
+
+            let id = item
+                .attribute("id")
+                .and_then(|id| id.parse().ok())
+                .ok_or("an <item> has no numeric id")?;
+            let names: Vec<_> = item
+                .children()
+                .filter(|node| node.has_tag_name("name"))
+                .collect();
+            let name = names
+                .iter()
+                .find(|node| node.attribute("type") == Some("primary"))
+                .or(names.first())
+                .and_then(|node| attribute(*node, "value"))
+                .ok_or_else(|| format!("item {} has no name", id))?;
+            let playing_time = thing_value(item, "playingtime");
+
+            Ok(BggGame {
+                id,
+                name: clip_name(&name),
+                year_published: thing_value(item, "yearpublished"),
+                min_players: thing_value(item, "minplayers"),
+                max_players: thing_value(item, "maxplayers"),
+                min_playtime_minutes: thing_value(item, "minplaytime").or(playing_time),
+                max_playtime_minutes: thing_value(item, "maxplaytime").or(playing_time),
+                weight: child(item, "statistics")
+                    .and_then(|node| child(node, "ratings"))
+                    .and_then(|node| thing_value(node, "averageweight")),
+                designers: item
+                    .children()
+                    .filter(|node| {
+                        node.has_tag_name("link")
+                            && node.attribute("type") == Some("boardgamedesigner")
+                    })
+                    .filter_map(|node| attribute(node, "value"))
+                    .collect(),
+                owned: true,
+            })
+        })
+        .collect()
+}
+
+/// New and updated games to write and the report to answer with
+pub struct BggGamesImport {
+    pub rows: BackupTables,
+    pub data: BggGamesImportData,
+}
+
+/// Works out the games importing `items` creates or updates in `stored`.
+///
+/// Games are matched by BGG ID, then by name among the live games without
+/// one. A matched game keeps its name and gets the BGG ID and every detail
+/// the file has; its designers become the creator unless another game of
+/// that name has them already. Items matching no game are created, by
+/// "Unknown" when the file names no designer. Collection items the user does
+/// not own are left out, so only owned games are suggested.
+pub fn plan_games_import(items: Vec<BggGame>, stored: BackupTables) -> BggGamesImport {
+    let mut games: Vec<_> = stored
+        .games
+        .into_iter()
+        .filter(|game| game.deleted_at.is_none())
+        .collect();
+    games.sort_by_key(|game| game.created_at);
+    let mut seen = HashSet::new();
+    let mut import = BggGamesImport {
+        rows: BackupTables::default(),
+        data: BggGamesImportData::default(),
+    };
+
+    for item in items {
+        if !item.owned {
+            import.data.items_not_owned += 1;
+            continue;
+        }
+        // A collection lists every copy owned
+        if !seen.insert(item.id) {
+            continue;
+        }
+        let creator = (!item.designers.is_empty()).then(|| clip_name(&item.designers.join(", ")));
+        let creator_taken = |games: &[GameModel], name: &str, creator: &str, id: Option<Uuid>| {
+            games.iter().any(|game| {
+                Some(game.id) != id
+                    && normalize_name(&game.name) == normalize_name(name)
+                    && normalize_name(&game.creator_name) == normalize_name(creator)
+            })
+        };
+
+        let key = normalize_name(&item.name);
+        let index = games
+            .iter()
+            .position(|game| game.bgg_id == Some(item.id))
+            .or_else(|| {
+                games
+                    .iter()
+                    .position(|game| game.bgg_id.is_none() && normalize_name(&game.name) == key)
+            });
+
+        let Some(index) = index else {
+            let creator_name = match creator {
+                Some(creator) if !creator_taken(&games, &item.name, &creator, None) => creator,
+                _ if !creator_taken(&games, &item.name, UNKNOWN_CREATOR, None) => {
+                    UNKNOWN_CREATOR.to_string()
+                }
+                _ => format!("{} (BGG {})", UNKNOWN_CREATOR, item.id),
+            };
+            let game = GameModel {
+                id: Uuid::new_v4(),
+                name: item.name,
+                creator_name,
+                bgg_id: Some(item.id),
+                year_published: item.year_published,
+                min_players: item.min_players,
+                max_players: item.max_players,
+                min_playtime_minutes: item.min_playtime_minutes,
+                max_playtime_minutes: item.max_playtime_minutes,
+                weight: item.weight,
+                created_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            };
+            games.push(game.clone());
+            import.rows.games.push(game.clone());
+            import.data.games_created.push(game);
+            continue;
+        };
+
+        let mut game = games[index].clone();
+        if let Some(creator) = creator
+            && normalize_name(&creator) != normalize_name(&game.creator_name)
+        {
+            if creator_taken(&games, &game.name, &creator, Some(game.id)) {
+                import.data.warnings.push(format!(
+                    "{} keeps creator {}: another game of that name is by {}",
+                    game.name, game.creator_name, creator
+                ));
+            } else {
+                game.creator_name = creator;
+            }
+        }
+        game.bgg_id = Some(item.id);
+        game.year_published = item.year_published.or(game.year_published);
+        game.min_players = item.min_players.or(game.min_players);
+        game.max_players = item.max_players.or(game.max_players);
+        game.min_playtime_minutes = item.min_playtime_minutes.or(game.min_playtime_minutes);
+        game.max_playtime_minutes = item.max_playtime_minutes.or(game.max_playtime_minutes);
+        game.weight = item.weight.or(game.weight);
+
+        if same_game(&game, &games[index]) {
+            import.data.games_unchanged += 1;
+            continue;
+        }
+        // As the repository bumps it on writing
+        game.version += 1;
+        games[index] = game.clone();
+        import.rows.games.push(game.clone());
+        import.data.games_updated.push(game);
+    }
+
+    import
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<plays username="ann" userid="1" total="2" page="1" termsofuse="https://boardgamegeek.com/xmlapi/termsofuse">
+    <play id="1001" date="2024-02-10" quantity="1" length="75" incomplete="0" nowinstats="0" location="Ann's place">
+        <item name="Catan" objecttype="thing" objectid="13">
+            <subtypes><subtype value="boardgame" /></subtypes>
+        </item>
+        <comments>Longest road decided it</comments>
+        <players>
+            <player username="ann" userid="1" name="Ann" startposition="" color="red" score="10" new="0" rating="0" win="1" />
+            <player username="" userid="0" name="Bob" startposition="" color="blue" score="8.5" new="1" rating="0" win="0" />
+        </players>
+    </play>
+    <play id="1002" date="2024-02-11" quantity="1" length="0" incomplete="0" nowinstats="0" location="">
+        <item name="Azul" objecttype="thing" objectid="230802">
+            <subtypes><subtype value="boardgame" /></subtypes>
+        </item>
+    </play>
+</plays>"#;
+
+    #[test]
+    fn plays_are_read_with_their_details() {
+        let plays = parse_plays(PLAYS).unwrap();
+
+        assert_eq!(plays.len(), 2);
+        let play = &plays[0];
+        assert_eq!(
+            (play.id, play.object_id, play.name.as_str()),
+            (1001, 13, "Catan")
+        );
+        assert_eq!(play.length_minutes, Some(75));
+        assert_eq!(play.location.as_deref(), Some("Ann's place"));
+        assert_eq!(play.comments.as_deref(), Some("Longest road decided it"));
+        assert_eq!(play.players[0].username.as_deref(), Some("ann"));
+        assert!(play.players[0].win);
+        assert_eq!(play.players[1].username, None);
+        assert_eq!(
+            (plays[1].length_minutes, plays[1].location.as_ref()),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn importing_twice_adds_nothing_the_second_time() {
+        let stored = BackupTables {
+            players: vec![PlayerModel {
+                id: Uuid::new_v4(),
+                name: "ann".to_string(),
+                is_owner: true,
+                bgg_username: None,
+                created_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            }],
+            ..Default::default()
+        };
+
+        let first = plan_plays_import(parse_plays(PLAYS).unwrap(), stored.clone());
+        assert_eq!(first.data.plays_imported, 2);
+        assert_eq!(first.data.games_created.len(), 2);
+        assert_eq!(first.data.players_matched, 1);
+        assert_eq!(first.data.players_created.len(), 1);
+        // Bob's 8.5 is not a score we can store
+        assert_eq!(first.data.warnings.len(), 1);
+        assert_eq!(first.rows.players[0].bgg_username.as_deref(), Some("ann"));
+
+        let mut after = stored;
+        after.players = first.rows.players;
+        after.games = first.rows.games;
+        after.plays = first.rows.plays;
+        let second = plan_plays_import(parse_plays(PLAYS).unwrap(), after);
+        assert_eq!(
+            (second.data.plays_imported, second.data.plays_skipped),
+            (0, 2)
+        );
+        assert!(second.rows.games.is_empty() && second.rows.players.is_empty());
+    }
+
+    const THING: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<items termsofuse="https://boardgamegeek.com/xmlapi/termsofuse">
+    <item type="boardgame" id="13">
+        <name type="alternate" sortindex="1" value="Die Siedler von Catan" />
+        <name type="primary" sortindex="1" value="Catan" />
+        <yearpublished value="1995" />
+        <minplayers value="3" />
+        <maxplayers value="4" />
+        <playingtime value="120" />
+        <minplaytime value="60" />
+        <maxplaytime value="120" />
+        <link type="boardgamecategory" id="1021" value="Economic" />
+        <link type="boardgamedesigner" id="11" value="Klaus Teuber" />
+        <statistics page="1"><ratings><averageweight value="2.2855" /></ratings></statistics>
+    </item>
+</items>"#;
+
+    const COLLECTION: &str = r#"<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<items totalitems="2" termsofuse="https://boardgamegeek.com/xmlapi/termsofuse" pubdate="Sat, 10 Feb 2024 10:00:00 +0000">
+    <item objecttype="thing" objectid="230802" subtype="boardgame" collid="1">
+        <name sortindex="1">Azul</name>
+        <yearpublished>2017</yearpublished>
+        <stats minplayers="2" maxplayers="4" minplaytime="30" maxplaytime="45" playingtime="45" numowned="1" />
+        <status own="1" />
+    </item>
+    <item objecttype="thing" objectid="266192" subtype="boardgame" collid="2">
+        <name sortindex="1">Wingspan</name>
+        <yearpublished>2019</yearpublished>
+        <stats minplayers="1" maxplayers="5" minplaytime="40" maxplaytime="70" playingtime="70" numowned="0" />
+        <status own="0" prevowned="1" wishlist="1" />
+    </item>
+</items>"#;
+
+    #[test]
+    fn things_and_collections_are_read() {
+        let thing = parse_games(THING).unwrap();
+        let catan = &thing[0];
+        assert_eq!((catan.id, catan.name.as_str()), (13, "Catan"));
+        assert_eq!((catan.min_players, catan.max_players), (Some(3), Some(4)));
+        assert_eq!(catan.min_playtime_minutes, Some(60));
+        assert_eq!(catan.weight, Some(2.2855));
+        assert_eq!(catan.designers, ["Klaus Teuber"]);
+
+        let collection = parse_games(COLLECTION).unwrap();
+        let azul = &collection[0];
+        assert_eq!((azul.id, azul.name.as_str()), (230802, "Azul"));
+        assert_eq!(azul.year_published, Some(2017));
+        assert_eq!(azul.max_playtime_minutes, Some(45));
+        assert_eq!((azul.weight, azul.designers.len()), (None, 0));
+        assert!(azul.owned && !collection[1].owned);
+    }
+
+    #[test]
+    fn collection_items_not_owned_are_left_out() {
+        let import = plan_games_import(parse_games(COLLECTION).unwrap(), BackupTables::default());
+
+        let created: Vec<_> = import
+            .data
+            .games_created
+            .iter()
+            .map(|game| game.name.as_str())
+            .collect();
+        assert_eq!(created, ["Azul"]);
+        assert_eq!(import.data.items_not_owned, 1);
+    }
+
+    #[test]
+    fn a_game_of_the_same_name_is_updated() {
+        let stored = BackupTables {
+            games: vec![GameModel {
+                id: Uuid::new_v4(),
+                name: "catan".to_string(),
+                creator_name: "Teuber".to_string(),
+                bgg_id: None,
+                year_published: None,
+                min_players: None,
+                max_players: None,
+                min_playtime_minutes: None,
+                max_playtime_minutes: None,
+                weight: None,
+                created_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            }],
+            ..Default::default()
+        };
+
+        let import = plan_games_import(parse_games(THING).unwrap(), stored);
+
+        assert!(import.data.games_created.is_empty());
+        let catan = &import.data.games_updated[0];
+        assert_eq!(catan.name, "catan");
+        assert_eq!(catan.creator_name, "Klaus Teuber");
+        assert_eq!((catan.bgg_id, catan.year_published), (Some(13), Some(1995)));
+
+        let again = plan_games_import(parse_games(THING).unwrap(), import.rows);
+        assert_eq!(again.data.games_unchanged, 1);
+    }
+}
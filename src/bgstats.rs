@@ -0,0 +1,747 @@
+//! Backups of the BG Stats app: JSON with `games`, `players`, `locations` and
+//! `plays`, the records of each referring to the others by their numeric
+//! `id`. Only the members we store are read and written; the app fills in
+//! the rest when it restores a file we export.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    bgg::{UNKNOWN_CREATOR, parse_score},
+    model::{BackupTables, GameModel, PlayModel, PlayParticipantModel, PlayerModel},
+    repository::normalize_name,
+    response::BgStatsImportData,
+    schema::{BgStatsImportQuery, MatchStrategy},
+    validation::{NAME_MAX_CHARS, normalize_text},
+};
+
+/// How BG Stats writes `playDate` and `modificationDate`, in local time; we
+/// read and write them as UTC
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A BG Stats backup
+#[derive(Serialize, Deserialize, Debug, Default, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsFile {
+    #[serde(default)]
+    pub games: Vec<BgStatsGame>,
+    #[serde(default)]
+    pub players: Vec<BgStatsPlayer>,
+    #[serde(default)]
+    pub locations: Vec<BgStatsLocation>,
+    #[serde(default)]
+    pub plays: Vec<BgStatsPlay>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_info: Option<BgStatsUserInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsGame {
+    pub id: i64,
+    #[serde(default)]
+    pub uuid: Option<String>,
+    pub name: String,
+    /// 0 when the game is not linked to BGG
+    #[serde(default)]
+    pub bgg_id: i32,
+    #[serde(default)]
+    pub modification_date: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsPlayer {
+    pub id: i64,
+    #[serde(default)]
+    pub uuid: Option<String>,
+    pub name: String,
+    /// The app's stand-in for players who were not named; not imported
+    #[serde(default)]
+    pub is_anonymous: bool,
+    #[serde(default)]
+    pub bgg_username: Option<String>,
+    #[serde(default)]
+    pub modification_date: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsLocation {
+    pub id: i64,
+    #[serde(default)]
+    pub uuid: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub modification_date: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsPlay {
+    #[serde(default)]
+    pub uuid: Option<String>,
+    /// `YYYY-MM-DD HH:MM:SS`
+    pub play_date: String,
+    /// 0 when not recorded
+    #[serde(default)]
+    pub duration_min: i32,
+    pub game_ref_id: i64,
+    /// 0 when not recorded
+    #[serde(default)]
+    pub location_ref_id: i64,
+    #[serde(default)]
+    pub comments: Option<String>,
+    /// ID of the play on BGG, 0 when it was not logged there
+    #[serde(default)]
+    pub bgg_id: i64,
+    #[serde(default)]
+    pub player_scores: Vec<BgStatsPlayerScore>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsPlayerScore {
+    pub player_ref_id: i64,
+    /// Text in the app's own files, a number is read too
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub score: Option<serde_json::Value>,
+    #[serde(default)]
+    pub winner: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BgStatsUserInfo {
+    /// The player the backup belongs to
+    pub me_ref_id: i64,
+}
+
+fn format_date(at: DateTime<Utc>) -> String {
+    at.format(DATE_FORMAT).to_string()
+}
+
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text, DATE_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(Default::default()))
+        })
+        .map(|at| at.and_utc())
+}
+
+/// Cleans up a name and cuts it down to the longest one we accept
+fn clean_name(name: &str) -> String {
+    normalize_text(name)
+        .chars()
+        .take(NAME_MAX_CHARS as usize)
+        .collect()
+}
+
+/// Live plays with their games and players as a BG Stats backup. Games and
+/// players in the trash are written when a live play refers to them, and
+/// each distinct play location becomes a location.
+pub fn write_bgstats(tables: &BackupTables) -> BgStatsFile {
+    let mut plays: Vec<_> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .collect();
+    plays.sort_by_key(|play| (play.created_at, play.id));
+
+    let played: HashSet<_> = plays.iter().map(|play| play.game_id).collect();
+    let play_ids: HashSet<_> = plays.iter().map(|play| play.id).collect();
+    let participants: Vec<_> = tables
+        .play_participants
+        .iter()
+        .filter(|participant| play_ids.contains(&participant.play_id))
+        .collect();
+    let seated: HashSet<_> = participants
+        .iter()
+        .map(|participant| participant.player_id)
+        .collect();
+
+    let mut file = BgStatsFile::default();
+    let mut game_refs = HashMap::new();
+    for game in tables
+        .games
+        .iter()
+        .filter(|game| game.deleted_at.is_none() || played.contains(&game.id))
+    {
+        let id = file.games.len() as i64 + 1;
+        game_refs.insert(game.id, id);
+        file.games.push(BgStatsGame {
+            id,
+            uuid: Some(game.id.to_string()),
+            name: game.name.clone(),
+            bgg_id: game.bgg_id.unwrap_or_default(),
+            modification_date: Some(format_date(game.created_at)),
+        });
+    }
+
+    let mut player_refs = HashMap::new();
+    for player in tables
+        .players
+        .iter()
+        .filter(|player| player.deleted_at.is_none() || seated.contains(&player.id))
+    {
+        let id = file.players.len() as i64 + 1;
+        player_refs.insert(player.id, id);
+        if player.is_owner && player.deleted_at.is_none() {
+            file.user_info = Some(BgStatsUserInfo { me_ref_id: id });
+        }
+        file.players.push(BgStatsPlayer {
+            id,
+            uuid: Some(player.id.to_string()),
+            name: player.name.clone(),
+            is_anonymous: false,
+            bgg_username: player.bgg_username.clone(),
+            modification_date: Some(format_date(player.created_at)),
+        });
+    }
+
+    let mut location_refs = HashMap::new();
+    for play in &plays {
+        let location_ref_id = match &play.location {
+            Some(name) => *location_refs.entry(name.clone()).or_insert_with(|| {
+                let id = file.locations.len() as i64 + 1;
+                file.locations.push(BgStatsLocation {
+                    id,
+                    uuid: Some(Uuid::new_v4().to_string()),
+                    name: name.clone(),
+                    modification_date: Some(format_date(play.created_at)),
+                });
+                id
+            }),
+            None => 0,
+        };
+
+        let player_scores = participants
+            .iter()
+            .filter(|participant| participant.play_id == play.id)
+            .map(|participant| BgStatsPlayerScore {
+                player_ref_id: player_refs[&participant.player_id],
+                score: participant
+                    .score
+                    .map(|score| serde_json::Value::String(score.to_string())),
+                winner: participant.is_winner,
+            })
+            .collect();
+
+        file.plays.push(BgStatsPlay {
+            uuid: Some(play.id.to_string()),
+            play_date: format_date(play.created_at),
+            duration_min: play.length_minutes.unwrap_or_default(),
+            game_ref_id: game_refs[&play.game_id],
+            location_ref_id,
+            comments: play.comments.clone(),
+            bgg_id: play.bgg_play_id.unwrap_or_default(),
+            player_scores,
+        });
+    }
+
+    file
+}
+
+/// New and updated rows to write and the report to answer with
+pub struct BgStatsImport {
+    pub rows: BackupTables,
+    pub data: BgStatsImportData,
+}
+
+/// Works out the rows importing `file` adds to `stored`.
+///
+/// Games and players are matched as `query` says; a game or player matched
+/// by name gets the file's BGG ID or username stored if it has none. Plays
+/// stored already, with the same game, time and players or the same BGG play
+/// ID, are skipped, so a backup can be imported again. If no live player is
+/// the owner, the player the backup belongs to becomes it.
+pub fn plan_bgstats_import(
+    file: BgStatsFile,
+    query: &BgStatsImportQuery,
+    stored: BackupTables,
+) -> BgStatsImport {
+    let live_plays: HashSet<_> = stored
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .map(|play| play.id)
+        .collect();
+    let mut seats: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for participant in &stored.play_participants {
+        seats
+            .entry(participant.play_id)
+            .or_default()
+            .push(participant.player_id);
+    }
+    let stored_plays = stored
+        .plays
+        .iter()
+        .filter(|play| live_plays.contains(&play.id))
+        .map(|play| {
+            let mut players = seats.remove(&play.id).unwrap_or_default();
+            players.sort();
+            (play.game_id, play.created_at, players)
+        })
+        .collect();
+
+    let mut importer = Importer {
+        query,
+        bgg_play_ids: stored
+            .plays
+            .iter()
+            .filter_map(|play| play.bgg_play_id)
+            .collect(),
+        stored_plays,
+        games: stored
+            .games
+            .into_iter()
+            .filter(|game| game.deleted_at.is_none())
+            .collect(),
+        players: stored
+            .players
+            .into_iter()
+            .filter(|player| player.deleted_at.is_none())
+            .collect(),
+        matched_games: HashSet::new(),
+        matched_players: HashSet::new(),
+        rows: BackupTables::default(),
+        data: BgStatsImportData::default(),
+    };
+
+    let me = file.user_info.map(|info| info.me_ref_id);
+    let mut games = HashMap::new();
+    for game in &file.games {
+        if let Some(id) = importer.game(game) {
+            games.insert(game.id, id);
+        }
+    }
+    let mut players = HashMap::new();
+    for player in file.players.iter().filter(|player| !player.is_anonymous) {
+        if let Some(id) = importer.player(player, me == Some(player.id)) {
+            players.insert(player.id, id);
+        }
+    }
+    let locations: HashMap<_, _> = file
+        .locations
+        .iter()
+        .map(|location| (location.id, clean_name(&location.name)))
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+
+    for play in file.plays {
+        importer.play(play, &games, &players, &locations);
+    }
+
+    importer.data.games_matched = importer.matched_games.len();
+    importer.data.players_matched = importer.matched_players.len();
+    BgStatsImport {
+        rows: importer.rows,
+        data: importer.data,
+    }
+}
+
+struct Importer<'a> {
+    query: &'a BgStatsImportQuery,
+    /// BGG play IDs already stored or imported
+    bgg_play_ids: HashSet<i64>,
+    /// Game, time and sorted players of the live plays and the imported ones
+    stored_plays: HashSet<(Uuid, DateTime<Utc>, Vec<Uuid>)>,
+    /// Live games and players, the ones this import creates included
+    games: Vec<GameModel>,
+    players: Vec<PlayerModel>,
+    /// Stored games and players the file's records were matched to
+    matched_games: HashSet<Uuid>,
+    matched_players: HashSet<Uuid>,
+    rows: BackupTables,
+    data: BgStatsImportData,
+}
+
+impl Importer<'_> {
+    fn game(&mut self, record: &BgStatsGame) -> Option<Uuid> {
+        let name = clean_name(&record.name);
+        if name.is_empty() {
+            self.data.warnings.push(format!(
+                "game {} has no name and is left out with its plays",
+                record.id
+            ));
+            return None;
+        }
+        let bgg_id = Some(record.bgg_id).filter(|&id| id > 0);
+        let strategy = self.query.games;
+
+        if matches!(strategy, MatchStrategy::Auto | MatchStrategy::Bgg)
+            && let Some(bgg_id) = bgg_id
+            && let Some(game) = self.games.iter().find(|game| game.bgg_id == Some(bgg_id))
+        {
+            self.matched_games.insert(game.id);
+            return Some(game.id);
+        }
+
+        if matches!(strategy, MatchStrategy::Auto | MatchStrategy::Name) {
+            let key = normalize_name(&name);
+            let bgg_id_taken = self
+                .games
+                .iter()
+                .any(|game| game.bgg_id.is_some() && game.bgg_id == bgg_id);
+            let mut same_name: Vec<_> = self
+                .games
+                .iter_mut()
+                .filter(|game| normalize_name(&game.name) == key)
+                .filter(|game| strategy == MatchStrategy::Name || game.bgg_id.is_none())
+                .collect();
+            same_name.sort_by_key(|game| game.created_at);
+            if let Some(game) = same_name.into_iter().next() {
+                if game.bgg_id.is_none() && bgg_id.is_some() && !bgg_id_taken {
+                    game.bgg_id = bgg_id;
+                    self.rows.games.push(game.clone());
+                }
+                self.matched_games.insert(game.id);
+                return Some(game.id);
+            }
+        }
+
+        let key = normalize_name(&name);
+        let creator_name = (1..)
+            .map(|n| match n {
+                1 => UNKNOWN_CREATOR.to_string(),
+                n => format!("{} ({})", UNKNOWN_CREATOR, n),
+            })
+            .find(|creator| {
+                !self.games.iter().any(|game| {
+                    normalize_name(&game.name) == key
+                        && normalize_name(&game.creator_name) == normalize_name(creator)
+                })
+            })
+            .unwrap_or_default();
+        let bgg_id_taken = self
+            .games
+            .iter()
+            .any(|game| game.bgg_id.is_some() && game.bgg_id == bgg_id);
+        let game = GameModel {
+            id: Uuid::new_v4(),
+            name,
+            creator_name,
+            bgg_id: bgg_id.filter(|_| !bgg_id_taken),
+            year_published: None,
+            min_players: None,
+            max_players: None,
+            min_playtime_minutes: None,
+            max_playtime_minutes: None,
+            weight: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        self.games.push(game.clone());
+        self.rows.games.push(game.clone());
+        let id = game.id;
+        self.data.games_created.push(game);
+        Some(id)
+    }
+
+    fn player(&mut self, record: &BgStatsPlayer, is_me: bool) -> Option<Uuid> {
+        let name = clean_name(&record.name);
+        if name.is_empty() {
+            self.data.warnings.push(format!(
+                "player {} has no name and is left out of every play",
+                record.id
+            ));
+            return None;
+        }
+        let username = record
+            .bgg_username
+            .as_deref()
+            .map(normalize_text)
+            .filter(|username| !username.is_empty());
+        let username_taken = |players: &[PlayerModel]| {
+            username.as_deref().is_some_and(|username| {
+                players.iter().any(|player| {
+                    player
+                        .bgg_username
+                        .as_deref()
+                        .is_some_and(|stored| stored.eq_ignore_ascii_case(username))
+                })
+            })
+        };
+        let strategy = self.query.players;
+
+        if matches!(strategy, MatchStrategy::Auto | MatchStrategy::Bgg)
+            && let Some(username) = username.as_deref()
+            && let Some(player) = self.players.iter().find(|player| {
+                player
+                    .bgg_username
+                    .as_deref()
+                    .is_some_and(|stored| stored.eq_ignore_ascii_case(username))
+            })
+        {
+            self.matched_players.insert(player.id);
+            return Some(player.id);
+        }
+
+        if matches!(strategy, MatchStrategy::Auto | MatchStrategy::Name) {
+            let key = normalize_name(&name);
+            let taken = username_taken(&self.players);
+            if let Some(player) = self
+                .players
+                .iter_mut()
+                .filter(|player| strategy == MatchStrategy::Name || player.bgg_username.is_none())
+                .find(|player| normalize_name(&player.name) == key)
+            {
+                if player.bgg_username.is_none() && username.is_some() && !taken {
+                    player.bgg_username = username;
+                    self.rows.players.push(player.clone());
+                }
+                self.matched_players.insert(player.id);
+                return Some(player.id);
+            }
+        }
+
+        let name = (1..)
+            .map(|n| match n {
+                1 => name.clone(),
+                n => format!("{} ({})", name, n),
+            })
+            .find(|candidate| {
+                let key = normalize_name(candidate);
+                !self
+                    .players
+                    .iter()
+                    .any(|player| normalize_name(&player.name) == key)
+            })
+            .unwrap_or_default();
+        let has_owner = self.players.iter().any(|player| player.is_owner);
+        let taken = username_taken(&self.players);
+        let player = PlayerModel {
+            id: Uuid::new_v4(),
+            name: clean_name(&name),
+            is_owner: is_me && !has_owner,
+            bgg_username: username.filter(|_| !taken),
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        self.players.push(player.clone());
+        self.rows.players.push(player.clone());
+        let id = player.id;
+        self.data.players_created.push(player);
+        Some(id)
+    }
+
+    fn play(
+        &mut self,
+        record: BgStatsPlay,
+        games: &HashMap<i64, Uuid>,
+        players: &HashMap<i64, Uuid>,
+        locations: &HashMap<i64, String>,
+    ) {
+        let Some(created_at) = parse_date(&record.play_date) else {
+            self.data.warnings.push(format!(
+                "a play of \"{}\" is left out: it has no valid date",
+                record.play_date
+            ));
+            return;
+        };
+        let Some(&game_id) = games.get(&record.game_ref_id) else {
+            self.data.warnings.push(format!(
+                "the play of {} is left out: game {} is not imported",
+                record.play_date, record.game_ref_id
+            ));
+            return;
+        };
+        let bgg_play_id = Some(record.bgg_id).filter(|&id| id > 0);
+        if bgg_play_id.is_some_and(|id| self.bgg_play_ids.contains(&id)) {
+            self.data.plays_skipped += 1;
+            return;
+        }
+
+        let play_id = Uuid::new_v4();
+        let mut participants: Vec<PlayParticipantModel> = Vec::new();
+        for score in record.player_scores {
+            let Some(&player_id) = players.get(&score.player_ref_id) else {
+                continue;
+            };
+            if participants
+                .iter()
+                .any(|participant| participant.player_id == player_id)
+            {
+                self.data.warnings.push(format!(
+                    "the play of {} lists player {} twice; only the first entry is imported",
+                    record.play_date, score.player_ref_id
+                ));
+                continue;
+            }
+
+            let text = match score.score {
+                Some(serde_json::Value::String(text)) => text.trim().to_string(),
+                Some(serde_json::Value::Number(number)) => number.to_string(),
+                _ => String::new(),
+            };
+            let parsed = parse_score(&text);
+            if parsed.is_none() && !text.is_empty() {
+                self.data.warnings.push(format!(
+                    "the play of {}: score \"{}\" is not a whole number in range and is left out",
+                    record.play_date, text
+                ));
+            }
+            participants.push(PlayParticipantModel {
+                play_id,
+                player_id,
+                score: parsed,
+                is_winner: score.winner,
+                version: 1,
+            });
+        }
+
+        let mut seated: Vec<_> = participants
+            .iter()
+            .map(|participant| participant.player_id)
+            .collect();
+        seated.sort();
+        if !self.stored_plays.insert((game_id, created_at, seated)) {
+            self.data.plays_skipped += 1;
+            return;
+        }
+        if let Some(id) = bgg_play_id {
+            self.bgg_play_ids.insert(id);
+        }
+
+        self.data.plays_imported += 1;
+        self.data.participants_imported += participants.len();
+        self.rows.play_participants.extend(participants);
+        self.rows.plays.push(PlayModel {
+            id: play_id,
+            game_id,
+            session_id: None,
+            location: locations.get(&record.location_ref_id).cloned(),
+            length_minutes: Some(record.duration_min).filter(|&length| length > 0),
+            comments: record
+                .comments
+                .map(|comments| comments.trim().to_string())
+                .filter(|comments| !comments.is_empty()),
+            bgg_play_id,
+            created_at,
+            deleted_at: None,
+            version: 1,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKUP: &str = r#"{
+        "games": [
+            {"id": 1, "uuid": "B0D6A6C2-1D4E-4E1D-9E25-0C6C0D1F0A01", "name": "Catan", "bggId": 13, "highestWins": true},
+            {"id": 2, "name": "Azul", "bggId": 0}
+        ],
+        "players": [
+            {"id": 1, "name": "Ann", "isAnonymous": false, "bggUsername": "ann"},
+            {"id": 2, "name": "Bob", "isAnonymous": false},
+            {"id": 3, "name": "Anonymous player", "isAnonymous": true}
+        ],
+        "locations": [{"id": 1, "name": "Home"}],
+        "plays": [
+            {
+                "playDate": "2024-03-01 19:30:00", "durationMin": 90, "gameRefId": 1,
+                "locationRefId": 1, "comments": "close one", "bggId": 0, "rounds": 0,
+                "playerScores": [
+                    {"playerRefId": 1, "score": "10", "winner": true},
+                    {"playerRefId": 2, "score": "9", "winner": false},
+                    {"playerRefId": 3, "score": "4", "winner": false}
+                ]
+            },
+            {"playDate": "2024-03-02 20:00:00", "gameRefId": 2, "playerScores": [{"playerRefId": 2, "score": 41}]}
+        ],
+        "userInfo": {"meRefId": 1}
+    }"#;
+
+    fn query(games: MatchStrategy, players: MatchStrategy) -> BgStatsImportQuery {
+        BgStatsImportQuery {
+            games,
+            players,
+            dry_run: false,
+        }
+    }
+
+    fn stored() -> BackupTables {
+        BackupTables {
+            games: vec![GameModel {
+                id: Uuid::new_v4(),
+                name: "catan".to_string(),
+                creator_name: "Klaus Teuber".to_string(),
+                bgg_id: None,
+                year_published: None,
+                min_players: None,
+                max_players: None,
+                min_playtime_minutes: None,
+                max_playtime_minutes: None,
+                weight: None,
+                created_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn games_are_matched_as_asked() {
+        let file: BgStatsFile = serde_json::from_str(BACKUP).unwrap();
+        let auto = query(MatchStrategy::Auto, MatchStrategy::Auto);
+        let import = plan_bgstats_import(file, &auto, stored());
+
+        assert_eq!(import.data.games_matched, 1);
+        assert_eq!(import.data.games_created.len(), 1);
+        assert_eq!(import.rows.games[0].bgg_id, Some(13));
+        assert_eq!(import.data.players_created.len(), 2);
+        assert!(import.data.players_created[0].is_owner);
+        assert_eq!(
+            (
+                import.data.plays_imported,
+                import.data.participants_imported
+            ),
+            (2, 3)
+        );
+        assert_eq!(import.rows.plays[0].location.as_deref(), Some("Home"));
+        assert_eq!(import.rows.plays[0].length_minutes, Some(90));
+
+        let file: BgStatsFile = serde_json::from_str(BACKUP).unwrap();
+        let none = query(MatchStrategy::None, MatchStrategy::Auto);
+        let import = plan_bgstats_import(file, &none, stored());
+
+        assert_eq!(import.data.games_matched, 0);
+        assert_eq!(import.data.games_created[0].creator_name, "Unknown");
+    }
+
+    #[test]
+    fn an_export_imports_as_already_stored() {
+        let file: BgStatsFile = serde_json::from_str(BACKUP).unwrap();
+        let auto = query(MatchStrategy::Auto, MatchStrategy::Auto);
+        let import = plan_bgstats_import(file, &auto, BackupTables::default());
+
+        let exported = serde_json::to_string(&write_bgstats(&import.rows)).unwrap();
+        let file: BgStatsFile = serde_json::from_str(&exported).unwrap();
+        assert_eq!(file.plays[0].player_scores.len(), 2);
+        let again = plan_bgstats_import(file, &auto, import.rows);
+
+        assert_eq!(
+            (again.data.plays_imported, again.data.plays_skipped),
+            (0, 2)
+        );
+        assert_eq!(
+            (again.data.games_matched, again.data.players_matched),
+            (2, 2)
+        );
+        assert!(again.rows.games.is_empty() && again.rows.players.is_empty());
+    }
+}
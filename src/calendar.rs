@@ -0,0 +1,481 @@
+//! Plays and game nights as an iCalendar (RFC 5545) feed, for calendar apps
+//! to subscribe to.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    model::{BackupTables, PlayModel, Rsvp, SessionModel},
+    schema::PlaysCalendarQuery,
+};
+
+/// Longest content line in octets, line break excluded
+const LINE_OCTETS: usize = 75;
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes a TEXT value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends a content line, folded so no line is longer than 75 octets
+fn push_line(calendar: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        // A continuation line starts with a space, which counts
+        if octets + c.len_utf8() > LINE_OCTETS {
+            calendar.push_str("\r\n ");
+            octets = 1;
+        }
+        calendar.push(c);
+        octets += c.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
+
+/// When an event starts
+enum Start {
+    /// All day, as a date is all there is to go by
+    Day(NaiveDate),
+    /// At a time, lasting the minutes when known
+    At(DateTime<Utc>, Option<i32>),
+}
+
+/// An event of the calendar
+struct Event<'a> {
+    id: Uuid,
+    start: Start,
+    summary: &'a str,
+    location: Option<&'a str>,
+    description: String,
+}
+
+/// When a play took place. A play of a session held on another day than the
+/// one it was logged on took place that day, and a play logged at midnight
+/// UTC only has the date it was played on, as the BGG import gives it.
+fn play_start(play: &PlayModel, session: Option<&SessionModel>) -> Start {
+    match session {
+        Some(session) if session.played_on != play.created_at.date_naive() => {
+            Start::Day(session.played_on)
+        }
+        _ if play.created_at.time() == NaiveTime::MIN => Start::Day(play.created_at.date_naive()),
+        _ => Start::At(play.created_at, play.length_minutes),
+    }
+}
+
+/// Appends `event` as a VEVENT
+fn push_event(calendar: &mut String, event: &Event, stamp: &str) {
+    push_line(calendar, "BEGIN:VEVENT");
+    push_line(calendar, &format!("UID:{}@bg-logger", event.id));
+    push_line(calendar, &format!("DTSTAMP:{}", stamp));
+    match event.start {
+        Start::Day(day) => {
+            push_line(
+                calendar,
+                &format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+            );
+        }
+        Start::At(at, length) => {
+            push_line(calendar, &format!("DTSTART:{}", format_time(at)));
+            if let Some(length) = length {
+                push_line(calendar, &format!("DURATION:PT{}M", length));
+            }
+        }
+    }
+    push_line(calendar, &format!("SUMMARY:{}", escape(event.summary)));
+    if let Some(location) = event.location {
+        push_line(calendar, &format!("LOCATION:{}", escape(location)));
+    }
+    push_line(
+        calendar,
+        &format!("DESCRIPTION:{}", escape(&event.description)),
+    );
+    push_line(calendar, "END:VEVENT");
+}
+
+/// Live plays matching `query` as VEVENTs lasting their length, with the
+/// sessions and events in `tables` as all-day VEVENTs. A play starts when it
+/// took place, see [`play_start`]. The description of a play names the game
+/// and the participants with their scores, that of a session the host, the
+/// attendees and the games played, and that of an event the host, the
+/// invited players with their answers and the games brought.
+pub fn write_plays_calendar(
+    tables: &BackupTables,
+    query: &PlaysCalendarQuery,
+    now: DateTime<Utc>,
+) -> String {
+    let games: HashMap<_, _> = tables
+        .games
+        .iter()
+        .map(|game| (game.id, game.name.as_str()))
+        .collect();
+    let players: HashMap<_, _> = tables
+        .players
+        .iter()
+        .map(|player| (player.id, player.name.as_str()))
+        .collect();
+    let sessions: HashMap<_, _> = tables
+        .sessions
+        .iter()
+        .map(|session| (session.id, session))
+        .collect();
+    let mut participants: HashMap<_, Vec<_>> = HashMap::new();
+    for participant in &tables.play_participants {
+        participants
+            .entry(participant.play_id)
+            .or_default()
+            .push(participant);
+    }
+
+    let mut plays: Vec<_> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .filter(|play| query.game_id.is_none_or(|id| play.game_id == id))
+        .filter(|play| {
+            query.player_id.is_none_or(|id| {
+                participants
+                    .get(&play.id)
+                    .is_some_and(|seated| seated.iter().any(|p| p.player_id == id))
+            })
+        })
+        .collect();
+    plays.sort_by_key(|play| (play.created_at, play.id));
+
+    let game_name = |id: &Uuid| games.get(id).copied().unwrap_or("Unknown game");
+    let player_name = |id: &Uuid| players.get(id).copied().unwrap_or_default();
+    let mut events = Vec::new();
+
+    let mut game_nights: Vec<_> = tables.sessions.iter().collect();
+    game_nights.sort_by_key(|session| (session.played_on, session.id));
+    for session in game_nights {
+        let mut description = Vec::new();
+        if let Some(host_id) = session.host_id {
+            description.push(format!("Host: {}", player_name(&host_id)));
+        }
+        if !session.attendee_ids.is_empty() {
+            let mut attendees: Vec<_> = session.attendee_ids.iter().map(player_name).collect();
+            attendees.sort();
+            description.push(format!("Attendees: {}", attendees.join(", ")));
+        }
+        let mut played: Vec<_> = plays
+            .iter()
+            .filter(|play| play.session_id == Some(session.id))
+            .map(|play| game_name(&play.game_id))
+            .collect();
+        played.sort();
+        played.dedup();
+        if !played.is_empty() {
+            description.push(format!("Games: {}", played.join(", ")));
+        }
+
+        events.push(Event {
+            id: session.id,
+            start: Start::Day(session.played_on),
+            summary: "Game night",
+            location: session.location.as_deref(),
+            description: description.join("\n"),
+        });
+    }
+
+    let mut planned: Vec<_> = tables.events.iter().collect();
+    planned.sort_by_key(|event| (event.scheduled_on, event.id));
+    for event in planned {
+        let mut description = Vec::new();
+        if let Some(host_id) = event.host_id {
+            description.push(format!("Host: {}", player_name(&host_id)));
+        }
+        let mut invited: Vec<_> = event
+            .invitations
+            .iter()
+            .map(|invitation| {
+                let name = player_name(&invitation.player_id);
+                match invitation.response {
+                    Some(Rsvp::Yes) => format!("{} (yes)", name),
+                    Some(Rsvp::No) => format!("{} (no)", name),
+                    Some(Rsvp::Maybe) => format!("{} (maybe)", name),
+                    None => name.to_string(),
+                }
+            })
+            .collect();
+        invited.sort();
+        if !invited.is_empty() {
+            description.push(format!("Invited: {}", invited.join(", ")));
+        }
+        let mut brought: Vec<_> = event
+            .invitations
+            .iter()
+            .flat_map(|invitation| invitation.game_ids.iter().map(game_name))
+            .collect();
+        brought.sort();
+        brought.dedup();
+        if !brought.is_empty() {
+            description.push(format!("Games brought: {}", brought.join(", ")));
+        }
+
+        events.push(Event {
+            id: event.id,
+            start: Start::Day(event.scheduled_on),
+            summary: "Planned game night",
+            location: event.location.as_deref(),
+            description: description.join("\n"),
+        });
+    }
+
+    for play in plays {
+        let game = game_name(&play.game_id);
+
+        let mut seated: Vec<_> = participants
+            .get(&play.id)
+            .into_iter()
+            .flatten()
+            .map(|participant| {
+                let name = player_name(&participant.player_id);
+                let result = match (participant.score, participant.is_winner) {
+                    (Some(score), true) => format!(" ({}, winner)", score),
+                    (Some(score), false) => format!(" ({})", score),
+                    (None, true) => " (winner)".to_string(),
+                    (None, false) => String::new(),
+                };
+                format!("{}{}", name, result)
+            })
+            .collect();
+        seated.sort();
+
+        let mut description = format!("Game: {}", game);
+        if !seated.is_empty() {
+            description.push_str(&format!("\nPlayers: {}", seated.join(", ")));
+        }
+        if let Some(comments) = &play.comments {
+            description.push_str(&format!("\n\n{}", comments));
+        }
+
+        let session = play.session_id.and_then(|id| sessions.get(&id).copied());
+        events.push(Event {
+            id: play.id,
+            start: play_start(play, session),
+            summary: game,
+            location: play.location.as_deref(),
+            description,
+        });
+    }
+
+    let mut calendar = String::new();
+    push_line(&mut calendar, "BEGIN:VCALENDAR");
+    push_line(&mut calendar, "VERSION:2.0");
+    push_line(&mut calendar, "PRODID:-//bg-logger//plays//EN");
+    push_line(&mut calendar, "CALSCALE:GREGORIAN");
+    push_line(&mut calendar, "X-WR-CALNAME:Board game plays");
+
+    let stamp = format_time(now);
+    for event in &events {
+        push_event(&mut calendar, event, &stamp);
+    }
+
+    push_line(&mut calendar, "END:VCALENDAR");
+    calendar
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::{EventModel, GameModel, InvitationModel, PlayParticipantModel, PlayerModel};
+
+    #[test]
+    fn a_play_becomes_an_event() {
+        let at = "2024-02-10T19:30:00Z".parse().unwrap();
+        let game = GameModel {
+            id: Uuid::new_v4(),
+            name: "Ticket to Ride: Europe".to_string(),
+            creator_name: "Alan R. Moon".to_string(),
+            bgg_id: None,
+            year_published: None,
+            min_players: None,
+            max_players: None,
+            min_playtime_minutes: None,
+            max_playtime_minutes: None,
+            weight: None,
+            created_at: at,
+            deleted_at: None,
+            version: 1,
+        };
+        let ann = PlayerModel {
+            id: Uuid::new_v4(),
+            name: "Ann".to_string(),
+            is_owner: true,
+            bgg_username: None,
+            created_at: at,
+            deleted_at: None,
+            version: 1,
+        };
+        let play = PlayModel {
+            id: Uuid::new_v4(),
+            game_id: game.id,
+            session_id: None,
+            location: Some("Ann's place, upstairs".to_string()),
+            length_minutes: Some(90),
+            comments: Some(
+                "A very long comment that goes on and on about the last round".to_string(),
+            ),
+            bgg_play_id: None,
+            created_at: at,
+            deleted_at: None,
+            version: 1,
+        };
+        let tables = BackupTables {
+            play_participants: vec![PlayParticipantModel {
+                play_id: play.id,
+                player_id: ann.id,
+                score: Some(102),
+                is_winner: true,
+                version: 1,
+            }],
+            games: vec![game],
+            players: vec![ann],
+            sessions: Vec::new(),
+            events: Vec::new(),
+            plays: vec![play],
+            audit_log: Vec::new(),
+        };
+        let query = PlaysCalendarQuery {
+            player_id: None,
+            game_id: None,
+        };
+
+        let calendar = write_plays_calendar(&tables, &query, at);
+
+        assert!(calendar.contains("\r\nDTSTART:20240210T193000Z\r\nDURATION:PT90M\r\n"));
+        assert!(calendar.contains("\r\nSUMMARY:Ticket to Ride: Europe\r\n"));
+        assert!(calendar.contains("\r\nLOCATION:Ann's place\\, upstairs\r\n"));
+        assert!(
+            calendar.contains(
+                "DESCRIPTION:Game: Ticket to Ride: Europe\\nPlayers: Ann (102\\, winner)"
+            )
+        );
+        assert!(calendar.lines().all(|line| line.len() <= LINE_OCTETS));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn game_nights_last_the_day_and_plays_start_when_played() {
+        let at: DateTime<Utc> = "2024-03-02T10:15:00Z".parse().unwrap();
+        let player = |name: &str| PlayerModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            is_owner: false,
+            bgg_username: None,
+            created_at: at,
+            deleted_at: None,
+            version: 1,
+        };
+        let (ann, bob) = (player("Ann"), player("Bob"));
+        let azul = GameModel {
+            id: Uuid::new_v4(),
+            name: "Azul".to_string(),
+            creator_name: "Michael Kiesling".to_string(),
+            bgg_id: None,
+            year_published: None,
+            min_players: None,
+            max_players: None,
+            min_playtime_minutes: None,
+            max_playtime_minutes: None,
+            weight: None,
+            created_at: at,
+            deleted_at: None,
+            version: 1,
+        };
+        let session = SessionModel {
+            id: Uuid::new_v4(),
+            played_on: "2024-03-01".parse().unwrap(),
+            location: Some("Ann's place".to_string()),
+            host_id: Some(ann.id),
+            attendee_ids: vec![ann.id, bob.id],
+            created_at: at,
+            version: 1,
+        };
+        let event = EventModel {
+            id: Uuid::new_v4(),
+            scheduled_on: "2024-03-15".parse().unwrap(),
+            location: None,
+            host_id: Some(bob.id),
+            session_id: None,
+            invitations: vec![
+                InvitationModel {
+                    player_id: ann.id,
+                    response: Some(Rsvp::Maybe),
+                    responded_at: Some(at),
+                    game_ids: vec![azul.id],
+                },
+                InvitationModel {
+                    player_id: bob.id,
+                    response: None,
+                    responded_at: None,
+                    game_ids: Vec::new(),
+                },
+            ],
+            created_at: at,
+            version: 1,
+        };
+        let play = |session_id, created_at, bgg_play_id| PlayModel {
+            id: Uuid::new_v4(),
+            game_id: azul.id,
+            session_id,
+            location: None,
+            length_minutes: Some(40),
+            comments: None,
+            bgg_play_id,
+            created_at,
+            deleted_at: None,
+            version: 1,
+        };
+        let tables = BackupTables {
+            plays: vec![
+                // Logged the morning after the session
+                play(Some(session.id), at, None),
+                play(None, "2023-12-24T00:00:00Z".parse().unwrap(), Some(7)),
+            ],
+            games: vec![azul],
+            players: vec![ann, bob],
+            sessions: vec![session],
+            events: vec![event],
+            ..BackupTables::default()
+        };
+        let query = PlaysCalendarQuery {
+            player_id: None,
+            game_id: None,
+        };
+
+        let calendar = write_plays_calendar(&tables, &query, at);
+
+        assert!(calendar.contains(concat!(
+            "DTSTART;VALUE=DATE:20240301\r\nSUMMARY:Game night\r\nLOCATION:Ann's place\r\n",
+            "DESCRIPTION:Host: Ann\\nAttendees: Ann\\, Bob\\nGames: Azul\r\n"
+        )));
+        assert!(calendar.contains(concat!(
+            "DTSTART;VALUE=DATE:20240315\r\nSUMMARY:Planned game night\r\n",
+            "DESCRIPTION:Host: Bob\\nInvited: Ann (maybe)\\, Bob\\nGames brought: Azul\r\n"
+        )));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20240301\r\nSUMMARY:Azul\r\n"));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20231224\r\nSUMMARY:Azul\r\n"));
+        assert!(!calendar.contains("DURATION"));
+    }
+}
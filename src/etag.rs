@@ -0,0 +1,55 @@
+use axum::http::{
+    HeaderMap, StatusCode,
+    header::{HeaderName, IF_MATCH, IF_NONE_MATCH},
+};
+
+use crate::response::ApiError;
+
+/// Strong entity tag for a row version, e.g. `"3"`
+pub fn entity_tag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Entity tags listed in every instance of a conditional header, or `None`
+/// when the client did not send the header at all
+fn listed_tags(headers: &HeaderMap, name: HeaderName) -> Option<Vec<String>> {
+    let mut values = headers.get_all(name).iter().peekable();
+    values.peek()?;
+
+    Some(
+        values
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect(),
+    )
+}
+
+/// Whether `If-Match` lets a change to the current row version go ahead.
+/// Requests without the header always do.
+pub fn if_match_allows(headers: &HeaderMap, version: i32) -> bool {
+    // If-Match uses the strong comparison, weak tags never match
+    let current = entity_tag(version);
+    listed_tags(headers, IF_MATCH)
+        .is_none_or(|tags| tags.iter().any(|tag| tag == "*" || *tag == current))
+}
+
+/// The 412 answer to a stale `If-Match`, carrying the current ETag
+pub fn precondition_failed(version: i32) -> ApiError {
+    ApiError::new(
+        StatusCode::PRECONDITION_FAILED,
+        "The resource was modified since it was fetched, reload it and try again",
+    )
+    .with_etag(version)
+}
+
+/// Whether `If-None-Match` already names the current row version, in which
+/// case a GET is answered with 304
+pub fn is_not_modified(headers: &HeaderMap, version: i32) -> bool {
+    let current = entity_tag(version);
+    listed_tags(headers, IF_NONE_MATCH).is_some_and(|tags| {
+        tags.iter()
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current)
+    })
+}
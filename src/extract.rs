@@ -0,0 +1,393 @@
+use axum::{
+    body::Bytes,
+    extract::{
+        FromRequest, FromRequestParts, RawPathParams, Request, path::ErrorKind,
+        rejection::PathRejection,
+    },
+    http::{StatusCode, header::CONTENT_TYPE, request::Parts},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    response::{ApiError, FieldError},
+    validation::{validation_error_response, validation_field_errors},
+};
+
+type Rejection = ApiError;
+
+/// JSON body extractor that runs the schema's `Validate` rules before the
+/// handler sees it. Parse and validation failures are answered with the usual
+/// `{"status": "fail", ...}` body instead of axum's plain text rejections.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = read_body(req, state, &["application/json"]).await?;
+        parse_validated(&bytes, "Invalid JSON body").map(ValidatedJson)
+    }
+}
+
+/// Extractor for PATCH bodies following JSON Merge Patch (RFC 7396).
+///
+/// Accepts both `application/merge-patch+json` and plain `application/json`.
+/// Absent members leave a field untouched and `null` clears it; see the
+/// `Update*Schema` structs for which fields may be cleared. Present members are
+/// validated like `ValidatedJson` bodies.
+pub struct MergePatch<T>(pub T);
+
+impl<T, S> FromRequest<S> for MergePatch<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = read_body(
+            req,
+            state,
+            &["application/merge-patch+json", "application/json"],
+        )
+        .await?;
+        parse_validated(&bytes, "Invalid merge patch").map(MergePatch)
+    }
+}
+
+/// Path parameters extractor answering values that do not parse, like an ID
+/// that is not a UUID, with a field error named after the parameter instead of
+/// axum's plain text rejection
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            Err(PathRejection::FailedToDeserializePathParams(e)) => {
+                let params = RawPathParams::from_request_parts(parts, state).await.ok();
+                let params: Vec<_> = params
+                    .iter()
+                    .flat_map(|params| params.iter())
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                Err(ApiError::validation(vec![path_field_error(
+                    e.into_kind(),
+                    &params,
+                )]))
+            }
+            Err(e) => Err(ApiError::new(e.status(), e.body_text())),
+        }
+    }
+}
+
+/// Names the parameter a path could not be deserialized at; axum only knows
+/// the key when there are several, so the others are found in `params`
+fn path_field_error(kind: ErrorKind, params: &[(String, String)]) -> FieldError {
+    let named = |value: &str| {
+        params
+            .iter()
+            .find(|(_, param)| param == value)
+            .map_or("path".to_string(), |(key, _)| key.clone())
+    };
+    let cannot_parse =
+        |value: &str, expected_type| format!("cannot parse `{}` to a `{}`", value, expected_type);
+    match kind {
+        ErrorKind::ParseErrorAtKey {
+            key,
+            value,
+            expected_type,
+        } => FieldError::new(key, cannot_parse(&value, expected_type)),
+        ErrorKind::ParseErrorAtIndex {
+            index,
+            value,
+            expected_type,
+        } => FieldError::new(
+            params
+                .get(index)
+                .map_or("path".to_string(), |(key, _)| key.clone()),
+            cannot_parse(&value, expected_type),
+        ),
+        ErrorKind::ParseError {
+            value,
+            expected_type,
+        } => FieldError::new(named(&value), cannot_parse(&value, expected_type)),
+        ErrorKind::DeserializeError { key, message, .. } => FieldError::new(key, message),
+        ErrorKind::InvalidUtf8InPathParam { key } => FieldError::new(key, "must be valid UTF-8"),
+        kind => FieldError::new("path", kind.to_string()),
+    }
+}
+
+/// Query string extractor that runs the parameters' `Validate` rules, naming
+/// the parameter that does not parse or validate like `ValidatedJson` names
+/// body fields
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        let query: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            ApiError::validation(vec![named_field_error(
+                &path,
+                e.inner().to_string(),
+                "query",
+            )])
+        })?;
+
+        query
+            .validate()
+            .map_err(|errors| validation_error_response(&errors))?;
+
+        Ok(Query(query))
+    }
+}
+
+/// Raw `text/csv` body, answering other content types with a 415
+pub struct CsvBody(pub Bytes);
+
+impl<S> FromRequest<S> for CsvBody
+where
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        read_body(req, state, &["text/csv"]).await.map(CsvBody)
+    }
+}
+
+/// `application/xml` or `text/xml` body as text
+pub struct XmlBody(pub String);
+
+impl<S> FromRequest<S> for XmlBody
+where
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = read_body(req, state, &["application/xml", "text/xml"]).await?;
+        String::from_utf8(bytes.to_vec())
+            .map(XmlBody)
+            .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, "Invalid XML body: not UTF-8"))
+    }
+}
+
+async fn read_body<S>(req: Request, state: &S, accepted: &[&str]) -> Result<Bytes, Rejection>
+where
+    S: Send + Sync,
+{
+    let mime = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::trim)
+        .unwrap_or_default();
+
+    if !accepted.contains(&mime) {
+        let expected = accepted
+            .iter()
+            .map(|mime| format!("`{}`", mime))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        return Err(ApiError::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Expected request with Content-Type {}", expected),
+        ));
+    }
+
+    Bytes::from_request(req, state)
+        .await
+        .map_err(|e| ApiError::new(e.status(), e.body_text()))
+}
+
+fn parse_validated<T>(bytes: &[u8], context: &str) -> Result<T, Rejection>
+where
+    T: DeserializeOwned + Validate,
+{
+    let body: Value = serde_json::from_slice(bytes)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("{}: {}", context, e)))?;
+    deserialize_validated(body)
+}
+
+/// Deserializes and validates one item of a bulk request, answering failures
+/// with the same body a single-item request would get
+pub fn validated_item<T>(item: Value) -> Result<T, Rejection>
+where
+    T: DeserializeOwned + Validate,
+{
+    deserialize_validated(item)
+}
+
+/// Deserializes and validates a body, listing every failing field. Each member
+/// of the wrong type is reported and replaced with a stand-in that
+/// deserializes, so the rest of the body is still checked and validated.
+/// Validation errors of replaced members are left out.
+fn deserialize_validated<T>(mut body: Value) -> Result<T, Rejection>
+where
+    T: DeserializeOwned + Validate,
+{
+    let mut errors: Vec<FieldError> = Vec::new();
+    let parsed = loop {
+        match serde_path_to_error::deserialize::<_, T>(&body) {
+            Ok(parsed) => break parsed,
+            Err(e) => {
+                let error = deserialize_field_error(&e);
+                let replaced = stand_in::<T>(&mut body, &e, &error.field);
+                errors.push(error);
+                if !replaced {
+                    return Err(ApiError::validation(errors));
+                }
+            }
+        }
+    };
+
+    if let Err(invalid) = parsed.validate() {
+        let invalid = validation_field_errors(&invalid)
+            .into_iter()
+            .filter(|error| errors.iter().all(|seen| seen.field != error.field))
+            .collect::<Vec<_>>();
+        errors.extend(invalid);
+    }
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+        Err(ApiError::validation(errors))
+    }
+}
+
+/// Values tried in place of a member that does not deserialize: one of each
+/// JSON type, then the string formats schemas parse, then the first variant an
+/// unknown variant error expects
+fn stand_ins(message: &str) -> Vec<Value> {
+    let variant = message
+        .split_once("expected one of `")
+        .and_then(|(_, rest)| rest.split_once('`'))
+        .map(|(variant, _)| Value::from(variant));
+
+    [
+        Value::Null,
+        Value::Bool(false),
+        Value::from(0),
+        Value::from(""),
+        Value::Array(Vec::new()),
+        Value::Object(Default::default()),
+        Value::from(Uuid::nil().to_string()),
+        Value::from("1970-01-01"),
+        Value::from("1970-01-01T00:00:00Z"),
+    ]
+    .into_iter()
+    .chain(variant)
+    .collect()
+}
+
+/// Drops the unknown member `e` is about, or puts a stand-in in place of the
+/// missing or mistyped one named `field`. False when nothing gets `body` past
+/// the error, e.g. when the body as a whole is of the wrong type.
+fn stand_in<T: DeserializeOwned>(
+    body: &mut Value,
+    e: &serde_path_to_error::Error<serde_json::Error>,
+    field: &str,
+) -> bool {
+    if !body.is_object() {
+        return false;
+    }
+    let message = e.inner().to_string();
+    let missing = match member(&message) {
+        Some((false, unknown)) => {
+            return value_at(body, e.path())
+                .and_then(Value::as_object_mut)
+                .is_some_and(|object| object.remove(unknown).is_some());
+        }
+        Some((true, missing)) => Some(missing),
+        None if e.path().iter().next().is_none() => return false,
+        None => None,
+    };
+
+    for value in stand_ins(&message) {
+        let slot = match (value_at(body, e.path()), missing) {
+            (Some(Value::Object(object)), Some(missing)) => {
+                object.entry(missing).or_insert(Value::Null)
+            }
+            (Some(slot), None) => slot,
+            _ => return false,
+        };
+        *slot = value;
+        match serde_path_to_error::deserialize::<_, T>(&*body) {
+            Ok(_) => return true,
+            Err(next) if deserialize_field_error(&next).field != field => return true,
+            Err(_) => {}
+        }
+    }
+    false
+}
+
+/// The value `path` leads to
+fn value_at<'a>(body: &'a mut Value, path: &serde_path_to_error::Path) -> Option<&'a mut Value> {
+    path.iter().try_fold(body, |value, segment| match segment {
+        Segment::Seq { index } => value.get_mut(index),
+        Segment::Map { key } => value.get_mut(key),
+        Segment::Enum { .. } => Some(value),
+        _ => None,
+    })
+}
+
+/// The member a missing (`true`) or unknown (`false`) member error is about
+fn member(message: &str) -> Option<(bool, &str)> {
+    [("missing field `", true), ("unknown field `", false)]
+        .iter()
+        .find_map(|(prefix, missing)| Some((*missing, message.strip_prefix(prefix)?)))
+        .and_then(|(missing, rest)| Some((missing, rest.split_once('`')?.0)))
+}
+
+/// Names the field a body could not be deserialized at by its path, e.g.
+/// `participants[0].score`, like the validation errors do. Missing and
+/// unknown members are named themselves rather than the object holding them.
+fn deserialize_field_error(e: &serde_path_to_error::Error<serde_json::Error>) -> FieldError {
+    named_field_error(&e.path().to_string(), e.inner().to_string(), "body")
+}
+
+/// Names a field by its path, or by the member a missing or unknown member
+/// error is about; `whole` names the input as a whole, for errors at its top
+fn named_field_error(path: &str, message: String, whole: &str) -> FieldError {
+    let path = if path == "." { "" } else { path };
+    match member(&message) {
+        Some((true, member)) => FieldError::new(join_path(path, member), "is required"),
+        Some((false, member)) => FieldError::new(join_path(path, member), "is not a known field"),
+        None if path.is_empty() => FieldError::new(whole, message),
+        None => FieldError::new(path, message),
+    }
+}
+
+fn join_path(path: &str, member: &str) -> String {
+    if path.is_empty() {
+        member.to_string()
+    } else {
+        format!("{}.{}", path, member)
+    }
+}
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use axum::{body::Body, extract::State, http::header, response::IntoResponse};
+
+use crate::{
+    AppState,
+    backup::{plan_import, write_archive},
+    extract::{Query, ValidatedJson},
+    repository::RepoError,
+    response::{ApiError, ApiResponse, ErrorResponse, ImportData},
+    schema::{ARCHIVE_FORMAT, ARCHIVE_VERSION, ArchiveSchema, ConflictStrategy, ImportQuery},
+    validation::field_error_response,
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "backup",
+    summary = "Export a backup",
+    description = "Every row of every table, trashed ones included, and the audit log as a JSON \
+        archive to download, all as of one point in time. The archive is streamed as it is \
+        written, and any archive can be imported again whatever its size. Unlike every other \
+        endpoint the archive is not wrapped in the response envelope.",
+    responses(
+        (status = 200, description = "The archive", body = ArchiveSchema),
+    )
+)]
+pub async fn export_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tables = data
+        .backup
+        .export_with_audit_log()
+        .await
+        .map_err(repo_error)?;
+    let exported_at = chrono::Utc::now();
+
+    let disposition = format!(
+        "attachment; filename=\"bg-logger-{}.json\"",
+        exported_at.format("%Y-%m-%d")
+    );
+    let archive = ArchiveSchema {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at,
+        tables,
+    };
+
+    let body = Body::from_stream(futures_util::stream::iter(write_archive(archive)));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "backup",
+    summary = "Import a backup",
+    description = "Writes the rows of an archive from `GET /api/v1/export`, keeping their IDs, \
+        in one transaction. `strategy` decides what happens to rows that clash with existing \
+        ones, see `ImportConflict`. Audit log entries not logged yet are appended under new IDs, \
+        before the entries of the import itself.",
+    params(
+        ImportQuery,
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when an import is retried"),
+    ),
+    request_body = ArchiveSchema,
+    responses(
+        (status = 200, description = "Archive imported, or what importing it would do", body = ApiResponse<ImportData>),
+        (status = 409, description = "Rows clash and `strategy` is `fail`", body = ErrorResponse),
+        (status = 422, description = "Not an archive this version can import, or rows refer to missing rows", body = ErrorResponse),
+    )
+)]
+pub async fn import_handler(
+    Query(query): Query<ImportQuery>,
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(archive): ValidatedJson<ArchiveSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(field_error_response(
+            "format",
+            &format!("must be \"{}\"", ARCHIVE_FORMAT),
+        ));
+    }
+    if archive.version > ARCHIVE_VERSION {
+        return Err(field_error_response(
+            "version",
+            &format!("archives up to version {} can be imported", ARCHIVE_VERSION),
+        ));
+    }
+
+    let stored = if archive.tables.audit_log.is_empty() {
+        data.backup.export().await
+    } else {
+        data.backup.export_with_audit_log().await
+    }
+    .map_err(repo_error)?;
+    let plan =
+        plan_import(stored, archive.tables, query.strategy, query.dry_run).map_err(|missing| {
+            field_error_response(
+                &format!("tables.{}", missing.table),
+                &format!(
+                    "{} refers to {} {}, which is neither in the archive nor stored",
+                    missing.id, missing.field, missing.reference
+                ),
+            )
+        })?;
+
+    if query.strategy == ConflictStrategy::Fail
+        && !plan.report.conflicts.is_empty()
+        && !query.dry_run
+    {
+        return Err(ApiError::conflict("Archived rows clash with existing ones")
+            .with_conflicts(plan.report.conflicts));
+    }
+
+    if query.dry_run {
+        let message = "Dry run, nothing was imported";
+        return Ok(ApiResponse::with_message(
+            message,
+            ImportData {
+                report: plan.report,
+            },
+        ));
+    }
+
+    data.backup.restore(&plan.rows).await.map_err(|e| match e {
+        RepoError::Duplicate { .. }
+        | RepoError::OwnerExists { .. }
+        | RepoError::MissingReferences(_) => {
+            ApiError::conflict("Data changed during the import, nothing was imported")
+        }
+        e => repo_error(e),
+    })?;
+
+    Ok(ApiResponse::with_message(
+        "Archive imported",
+        ImportData {
+            report: plan.report,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+    use crate::{
+        handlers::{create_game_handler, create_player_handler, response_json},
+        repository::memory::MemoryRepository,
+        schema::{GameSchema, PlayerSchema},
+    };
+
+    async fn export(data: &Arc<AppState>) -> ArchiveSchema {
+        let response = export_handler(State(data.clone())).await.into_response();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    async fn import(
+        data: &Arc<AppState>,
+        archive: ArchiveSchema,
+        strategy: ConflictStrategy,
+    ) -> (StatusCode, serde_json::Value) {
+        let query = ImportQuery {
+            strategy,
+            dry_run: false,
+        };
+        response_json(
+            import_handler(Query(query), State(data.clone()), ValidatedJson(archive)).await,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn an_export_imports_into_an_empty_database() {
+        let data = MemoryRepository::app_state();
+        let game = GameSchema {
+            name: "Catan".to_string(),
+            creator_name: "Klaus Teuber".to_string(),
+            ..Default::default()
+        };
+        create_game_handler(State(data.clone()), ValidatedJson(game))
+            .await
+            .unwrap();
+        let player = PlayerSchema {
+            name: "Ann".to_string(),
+            is_owner: Some(true),
+        };
+        create_player_handler(State(data.clone()), ValidatedJson(player))
+            .await
+            .unwrap();
+        let archive = export(&data).await;
+
+        let restored = MemoryRepository::app_state();
+        let (status, body) = import(&restored, archive, ConflictStrategy::Fail).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["report"]["games"]["created"], 1);
+        assert_eq!(body["data"]["report"]["players"]["created"], 1);
+
+        let again = export(&restored).await;
+        let (status, body) = import(&restored, again, ConflictStrategy::Fail).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["report"]["games"]["unchanged"], 1);
+        assert_eq!(body["data"]["report"]["players"]["unchanged"], 1);
+    }
+
+    #[tokio::test]
+    async fn an_archive_of_another_format_is_refused() {
+        let data = MemoryRepository::app_state();
+        let mut archive = export(&data).await;
+        archive.format = "something-else".to_string();
+
+        let (status, body) = import(&data, archive, ConflictStrategy::Fail).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "format");
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+
+use crate::{
+    AppState,
+    bgg::{parse_games, parse_plays, plan_games_import, plan_plays_import},
+    extract::XmlBody,
+    repository::RepoError,
+    response::{ApiError, ApiResponse, BggGamesImportData, BggPlaysImportData, ErrorResponse},
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    post,
+    path = "/import/bgg/plays",
+    tag = "backup",
+    summary = "Import a BoardGameGeek play history",
+    description = "Reads a page saved from `https://boardgamegeek.com/xmlapi2/plays?username=...` \
+        and imports its plays in one transaction. Plays imported before are skipped, so pages \
+        can be imported again. Games are matched by BGG ID, then by name; players by BGG \
+        username, then by name. Anything not matched is created.",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when an import is retried"),
+    ),
+    request_body(content = String, content_type = "application/xml"),
+    responses(
+        (status = 200, description = "Plays imported", body = ApiResponse<BggPlaysImportData>),
+        (status = 400, description = "Body is not UTF-8", body = ErrorResponse),
+        (status = 409, description = "Data changed during the import", body = ErrorResponse),
+        (status = 415, description = "Not `application/xml` or `text/xml`", body = ErrorResponse),
+        (status = 422, description = "Not well-formed XML or not a BGG play history", body = ErrorResponse),
+    )
+)]
+pub async fn import_bgg_plays_handler(
+    State(data): State<Arc<AppState>>,
+    XmlBody(xml): XmlBody,
+) -> Result<impl IntoResponse, ApiError> {
+    let plays = parse_plays(&xml).map_err(|message| {
+        ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid BGG play history: {}", message),
+        )
+    })?;
+
+    let stored = data.backup.export().await.map_err(repo_error)?;
+    let import = plan_plays_import(plays, stored);
+
+    data.backup
+        .restore(&import.rows)
+        .await
+        .map_err(|e| match e {
+            RepoError::Duplicate { .. } | RepoError::MissingReferences(_) => {
+                ApiError::conflict("Data changed during the import, nothing was imported")
+            }
+            e => repo_error(e),
+        })?;
+
+    Ok(ApiResponse::with_message("Plays imported", import.data))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import/bgg/games",
+    tag = "backup",
+    summary = "Import game details from BoardGameGeek",
+    description = "Reads a document saved from `https://boardgamegeek.com/xmlapi2/thing?id=...&stats=1` \
+        or `https://boardgamegeek.com/xmlapi2/collection?username=...&stats=1` and creates or \
+        updates its games in one transaction. Games are matched by BGG ID, then by name. A \
+        `thing` document also carries designers and weight, a `collection` document does not.",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when an import is retried"),
+    ),
+    request_body(content = String, content_type = "application/xml"),
+    responses(
+        (status = 200, description = "Games imported", body = ApiResponse<BggGamesImportData>),
+        (status = 400, description = "Body is not UTF-8", body = ErrorResponse),
+        (status = 409, description = "Data changed during the import", body = ErrorResponse),
+        (status = 415, description = "Not `application/xml` or `text/xml`", body = ErrorResponse),
+        (status = 422, description = "Not well-formed XML or not a BGG thing or collection", body = ErrorResponse),
+    )
+)]
+pub async fn import_bgg_games_handler(
+    State(data): State<Arc<AppState>>,
+    XmlBody(xml): XmlBody,
+) -> Result<impl IntoResponse, ApiError> {
+    let items = parse_games(&xml).map_err(|message| {
+        ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid BGG thing or collection: {}", message),
+        )
+    })?;
+
+    let stored = data.backup.export().await.map_err(repo_error)?;
+    let import = plan_games_import(items, stored);
+
+    data.backup
+        .restore(&import.rows)
+        .await
+        .map_err(|e| match e {
+            RepoError::Duplicate { .. } => {
+                ApiError::conflict("Data changed during the import, nothing was imported")
+            }
+            e => repo_error(e),
+        })?;
+
+    Ok(ApiResponse::with_message("Games imported", import.data))
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State, http::header, response::IntoResponse};
+
+use crate::{
+    AppState,
+    bgstats::{BgStatsFile, plan_bgstats_import, write_bgstats},
+    extract::{Query, ValidatedJson},
+    repository::RepoError,
+    response::{ApiError, ApiResponse, BgStatsImportData, ErrorResponse},
+    schema::BgStatsImportQuery,
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    get,
+    path = "/export/bgstats",
+    tag = "backup",
+    summary = "Export a BG Stats backup",
+    description = "Live plays with their games, players and locations in the JSON format of the \
+        BG Stats app, to restore there. Like `GET /api/v1/export` the file is not wrapped in the \
+        response envelope.",
+    responses(
+        (status = 200, description = "The backup", body = BgStatsFile),
+    )
+)]
+pub async fn export_bgstats_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tables = data.backup.export().await.map_err(repo_error)?;
+
+    let disposition = format!(
+        "attachment; filename=\"bg-logger-bgstats-{}.json\"",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+
+    Ok((
+        [(header::CONTENT_DISPOSITION, disposition)],
+        Json(write_bgstats(&tables)),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import/bgstats",
+    tag = "backup",
+    summary = "Import a BG Stats backup",
+    description = "Imports the games, players, locations and plays of a BG Stats backup in one \
+        transaction. `games` and `players` decide how records are matched to stored rows, see \
+        `MatchStrategy`; plays stored already are skipped. The report lists what was created \
+        and counts what was matched.",
+    params(
+        BgStatsImportQuery,
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when an import is retried"),
+    ),
+    request_body = BgStatsFile,
+    responses(
+        (status = 200, description = "Backup imported, or what importing it would do", body = ApiResponse<BgStatsImportData>),
+        (status = 409, description = "Data changed during the import", body = ErrorResponse),
+    )
+)]
+pub async fn import_bgstats_handler(
+    Query(query): Query<BgStatsImportQuery>,
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(file): ValidatedJson<BgStatsFile>,
+) -> Result<impl IntoResponse, ApiError> {
+    let stored = data.backup.export().await.map_err(repo_error)?;
+    let import = plan_bgstats_import(file, &query, stored);
+
+    if query.dry_run {
+        let message = "Dry run, nothing was imported";
+        return Ok(ApiResponse::with_message(message, import.data));
+    }
+
+    data.backup
+        .restore(&import.rows)
+        .await
+        .map_err(|e| match e {
+            RepoError::Duplicate { .. }
+            | RepoError::OwnerExists { .. }
+            | RepoError::MissingReferences(_) => {
+                ApiError::conflict("Data changed during the import, nothing was imported")
+            }
+            e => repo_error(e),
+        })?;
+
+    Ok(ApiResponse::with_message("Backup imported", import.data))
+}
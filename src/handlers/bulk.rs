@@ -0,0 +1,86 @@
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    repository::BulkCommit,
+    response::{ApiError, ApiResponse, BulkData},
+    schema::BulkMode,
+};
+
+/// Collects the outcome of every item of a bulk request. Items that fail
+/// validation are recorded up front; the rest are written by the repository
+/// in one go, and `commit` tells it from the mode whether the successful ones
+/// are kept.
+pub struct BulkResults {
+    mode: BulkMode,
+    results: Vec<(usize, serde_json::Value)>,
+    failed: usize,
+}
+
+impl BulkResults {
+    pub fn new(mode: BulkMode) -> Self {
+        BulkResults {
+            mode,
+            results: Vec::new(),
+            failed: 0,
+        }
+    }
+
+    /// What the repository keeps of the valid items
+    pub fn commit(&self) -> BulkCommit {
+        match self.mode {
+            BulkMode::PerItem => BulkCommit::Successful,
+            BulkMode::Atomic if self.failed > 0 => BulkCommit::Nothing,
+            BulkMode::Atomic => BulkCommit::AllOrNothing,
+        }
+    }
+
+    /// Records an item with the `data` the single-item endpoint would answer
+    /// with, or its error body tagged with the HTTP status it would have had
+    pub fn record<T: Serialize>(&mut self, index: usize, result: Result<T, ApiError>) {
+        let entry = match result {
+            Ok(data) => json!({
+                "index": index,
+                "status": "success",
+                "data": data
+            }),
+            Err(error) => {
+                self.failed += 1;
+                let code = error.code().as_u16();
+                let mut error = json!(error.body());
+                error["index"] = json!(index);
+                error["code"] = json!(code);
+                error
+            }
+        };
+        self.results.push((index, entry));
+    }
+
+    /// Lists every item in request order, unless the request is atomic and an
+    /// item failed: then nothing was kept and only the failures are listed.
+    pub fn finish(mut self, action: &str) -> Result<ApiResponse<BulkData>, ApiError> {
+        let total = self.results.len();
+        self.results.sort_by_key(|(index, _)| *index);
+        let results = self.results.into_iter().map(|(_, entry)| entry);
+
+        if self.mode == BulkMode::Atomic && self.failed > 0 {
+            let failures: Vec<_> = results
+                .filter(|result| result["status"] != "success")
+                .collect();
+            let message = format!(
+                "{} of {} items failed, nothing was {}",
+                self.failed, total, action
+            );
+            return Err(
+                ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, message).with_results(failures)
+            );
+        }
+
+        Ok(ApiResponse::new(BulkData {
+            succeeded: total - self.failed,
+            failed: self.failed,
+            results: results.collect(),
+        }))
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::header, response::IntoResponse};
+
+use crate::{
+    AppState, calendar::write_plays_calendar, extract::Query, response::ApiError,
+    schema::PlaysCalendarQuery,
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    get,
+    path = "/plays.ics",
+    tag = "plays",
+    summary = "Calendar of plays",
+    description = "Live plays as an iCalendar feed to subscribe to, one event per play, with the \
+        sessions and the events not held yet as all-day events. A play starts when it was logged \
+        and lasts its length, unless all there is to go by is a date: the day of its session \
+        when logged on another day, or the date of a play imported from BGG. The description of \
+        a play names the game and the participants with their scores, that of a session the \
+        host, the attendees and the games played, and that of an event the host, the invited \
+        players with their answers and the games brought. With `player_id` or `game_id`, the \
+        sessions are the ones the player attended where the game was played and the events the \
+        ones the player is invited to where the game is brought.",
+    params(
+        PlaysCalendarQuery,
+    ),
+    responses(
+        (status = 200, description = "The calendar", content_type = "text/calendar", body = String),
+    )
+)]
+pub async fn plays_calendar_handler(
+    Query(query): Query<PlaysCalendarQuery>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tables = data
+        .stats
+        .calendar_plays(query.player_id, query.game_id)
+        .await
+        .map_err(repo_error)?;
+    let calendar = write_plays_calendar(&tables, &query, chrono::Utc::now());
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar,
+    ))
+}
@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode, header::ETAG},
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    etag::{entity_tag, if_match_allows, is_not_modified},
+    extract::{MergePatch, Path, ValidatedJson},
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, DeletedEventData, ErrorResponse, EventData, EventList,
+        EventSessionData,
+    },
+    schema::{EventSchema, RsvpSchema, UpdateEventSchema},
+};
+
+use super::{missing_references, repo_error};
+
+/// Answers an event that does not exist, or one whose host, invitees or
+/// games are missing or trashed
+fn event_error(e: RepoError, id: Uuid) -> ApiError {
+    match e {
+        RepoError::NotFound => ApiError::not_found(format!("Event with ID: {} not found", id)),
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        e => repo_error(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/events",
+    tag = "events",
+    summary = "Plan a game night",
+    description = "The host is invited whether listed or not. Invited players answer through \
+        their RSVP.",
+    request_body = EventSchema,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Event created", body = ApiResponse<EventData>),
+        (status = 422, description = "Invalid body or a host or invitee that does not exist", body = ErrorResponse),
+    )
+)]
+pub async fn create_event_handler(
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<EventSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let event = data.events.create(&body).await.map_err(|e| match e {
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        e => repo_error(e),
+    })?;
+
+    Ok(ApiResponse::new(EventData { event }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    summary = "List planned game nights",
+    responses(
+        (status = 200, description = "Events, soonest first", body = ApiResponse<EventList>),
+    )
+)]
+pub async fn event_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let events = data.events.list().await.map_err(repo_error)?;
+
+    Ok(ApiResponse::new(EventList {
+        count: events.len(),
+        events,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/{id}",
+    tag = "events",
+    summary = "Get a planned game night",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already has; a current one gets a 304"),
+    ),
+    responses(
+        (status = 200, description = "The event", body = ApiResponse<EventData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 304, description = "If-None-Match matches the current version", headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Event not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_event_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let event = data.events.get(id).await.map_err(|e| event_error(e, id))?;
+
+    let etag = entity_tag(event.version);
+    if is_not_modified(&headers, event.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+    }
+
+    Ok(([(ETAG, etag)], ApiResponse::new(EventData { event })).into_response())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/events/{id}",
+    tag = "events",
+    summary = "Update a planned game night",
+    description = "`invitee_ids` replaces the invitations; the host stays invited, and players \
+        invited already keep their answer.",
+    request_body(content = UpdateEventSchema, content_type = "application/merge-patch+json"),
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Event updated", body = ApiResponse<EventData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Event not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Invalid body or a host or invitee that does not exist", body = ErrorResponse),
+    )
+)]
+pub async fn update_event_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+    MergePatch(body): MergePatch<UpdateEventSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let event = data
+        .events
+        .update(id, &body, &precondition)
+        .await
+        .map_err(|e| event_error(e, id))?;
+
+    let etag = entity_tag(event.version);
+    Ok(([(ETAG, etag)], ApiResponse::new(EventData { event })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/events/{id}",
+    tag = "events",
+    summary = "Cancel a planned game night",
+    description = "Deletes the event and its invitations for good. A session it turned into is \
+        kept.",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Event deleted", body = ApiResponse<DeletedEventData>),
+        (status = 404, description = "Event not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
+pub async fn delete_event_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let deleted_event = data
+        .events
+        .delete(id, &precondition)
+        .await
+        .map_err(|e| event_error(e, id))?;
+
+    Ok(ApiResponse::with_message(
+        "Event deleted successfully",
+        DeletedEventData { deleted_event },
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/events/{id}/rsvps/{player_id}",
+    tag = "events",
+    summary = "Answer an invitation",
+    description = "Records whether the player comes, `yes`, `no` or `maybe`, and the games they \
+        want to bring. A player who was not invited yet is invited with the answer.",
+    request_body = RsvpSchema,
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("player_id" = Uuid, Path, description = "Player ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Answer recorded", body = ApiResponse<EventData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Event not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Invalid body or a player or game that does not exist", body = ErrorResponse),
+    )
+)]
+pub async fn rsvp_handler(
+    Path((id, player_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidatedJson(body): ValidatedJson<RsvpSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let event = data
+        .events
+        .respond(id, player_id, &body, &precondition)
+        .await
+        .map_err(|e| event_error(e, id))?;
+
+    let etag = entity_tag(event.version);
+    Ok(([(ETAG, etag)], ApiResponse::new(EventData { event })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/events/{id}/session",
+    tag = "events",
+    summary = "Turn a game night into a session",
+    description = "Starts a session on the event's date and at its location, attended by the \
+        host and every live player who answered yes. Plays are then logged against the \
+        session.",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Session started", body = ApiResponse<EventSessionData>),
+        (status = 404, description = "Event not found", body = ErrorResponse),
+        (status = 409, description = "The event already turned into a session", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
+pub async fn start_session_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let started = data
+        .events
+        .start_session(id, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::Duplicate { existing_id } => {
+                ApiError::conflict("Event already has a session").with_existing_id(existing_id)
+            }
+            e => event_error(e, id),
+        })?;
+
+    Ok(ApiResponse::new(started))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        handlers::response_json, model::Rsvp, repository::memory::MemoryRepository,
+        schema::PlayerSchema,
+    };
+
+    fn night(host_id: Option<Uuid>, invitee_ids: Vec<Uuid>) -> EventSchema {
+        EventSchema {
+            scheduled_on: NaiveDate::from_ymd_opt(2026, 11, 6).unwrap(),
+            location: Some("Ann's place".to_string()),
+            host_id,
+            invitee_ids,
+        }
+    }
+
+    fn rsvp(response: Rsvp) -> ValidatedJson<RsvpSchema> {
+        ValidatedJson(RsvpSchema {
+            response,
+            game_ids: Vec::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn a_missing_invitee_is_a_validation_error() {
+        let data = MemoryRepository::app_state();
+
+        let create = create_event_handler(
+            State(data),
+            ValidatedJson(night(None, vec![Uuid::new_v4()])),
+        );
+        let (status, body) = response_json(create.await).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body.to_string().contains("invitee_ids"));
+    }
+
+    #[tokio::test]
+    async fn the_session_is_attended_by_the_host_and_players_who_said_yes() {
+        let data = MemoryRepository::app_state();
+        let mut players = Vec::new();
+        for name in ["Ann", "Bob", "Cid"] {
+            let player = PlayerSchema {
+                name: name.to_string(),
+                is_owner: None,
+            };
+            players.push(data.players.create(&player).await.unwrap().id);
+        }
+        let (ann, bob, cid) = (players[0], players[1], players[2]);
+        let event = data
+            .events
+            .create(&night(Some(ann), vec![bob, cid]))
+            .await
+            .unwrap();
+        assert_eq!(event.invitations.len(), 3);
+
+        for (player_id, response) in [(bob, Rsvp::Yes), (cid, Rsvp::Maybe)] {
+            let answer = rsvp_handler(
+                Path((event.id, player_id)),
+                State(data.clone()),
+                HeaderMap::new(),
+                rsvp(response),
+            );
+            let (status, _) = response_json(answer.await).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let start = start_session_handler(Path(event.id), State(data.clone()), HeaderMap::new());
+        let (status, body) = response_json(start.await).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut expected = vec![ann, bob];
+        expected.sort();
+        let session = &body["data"]["session"];
+        assert_eq!(session["attendee_ids"], json!(expected));
+        assert_eq!(session["location"], "Ann's place");
+        assert_eq!(body["data"]["event"]["session_id"], session["id"]);
+
+        let again = start_session_handler(Path(event.id), State(data), HeaderMap::new());
+        let (status, body) = response_json(again.await).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["existing_id"], session["id"]);
+    }
+}
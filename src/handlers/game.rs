@@ -1,214 +1,394 @@
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::State,
+    http::{HeaderMap, StatusCode, header::ETAG},
     response::IntoResponse,
 };
 use uuid::Uuid;
 
-use serde_json::json;
-
 use crate::{
     AppState,
-    model::GameModel,
-    schema::{GameSchema, UpdateGameSchema},
+    etag::{entity_tag, if_match_allows, is_not_modified},
+    extract::{MergePatch, Path, ValidatedJson},
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, DeletedGameData, ErrorResponse, GameData, GameList, MergedGameData,
+    },
+    schema::{GameSchema, MergeSchema, UpdateGameSchema},
 };
 
+use super::repo_error;
+
+/// Builds the 409 response for a name and creator that clash with an existing
+/// game once normalized, pointing at the existing game's ID
+fn game_conflict_response(existing_id: Option<Uuid>) -> ApiError {
+    ApiError::conflict("Game already exists").with_existing_id(existing_id)
+}
+
+#[utoipa::path(
+    post,
+    path = "/games",
+    tag = "games",
+    summary = "Create a game",
+    request_body = GameSchema,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Game created", body = ApiResponse<GameData>),
+        (status = 409, description = "A game with this name and creator, or this BGG ID, exists", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn create_game_handler(
     State(data): State<Arc<AppState>>,
-    Json(body): Json<GameSchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let id = uuid::Uuid::new_v4();
-    let game = sqlx::query_as!(
-        GameModel,
-        r#"INSERT INTO games (id, name, creator_name) VALUES ($1, $2, $3) RETURNING *"#,
-        &id,
-        &body.name,
-        &body.creator_name,
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| e.to_string());
-
-    if let Err(err) = game {
-        if err.contains("duplicate key value") {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": "Game already exists",
-            });
-            return Err((StatusCode::CONFLICT, Json(error_response)));
-        }
-
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error","message": format!("{:?}", err)})),
-        ));
-    }
+    ValidatedJson(body): ValidatedJson<GameSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let game = data.games.create(&body).await.map_err(|e| match e {
+        RepoError::Duplicate { existing_id } => game_conflict_response(existing_id),
+        e => repo_error(e),
+    })?;
 
-    let game_response = json!({
-            "status": "success",
-            "data": json!({
-                "game": game
-        })
-    });
+    let game_response = ApiResponse::new(GameData { game });
 
-    Ok(Json(game_response))
+    Ok(game_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/games",
+    tag = "games",
+    summary = "List games",
+    responses(
+        (status = 200, description = "Live games by name", body = ApiResponse<GameList>),
+    )
+)]
 pub async fn game_list_handler(
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    // Query with macro
-    let games = sqlx::query_as!(GameModel, r#"SELECT * FROM games ORDER by name"#)
-        .fetch_all(&data.db)
-        .await
-        .map_err(|e| {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Database error: { }", e),
-            });
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
-        })?;
+) -> Result<impl IntoResponse, ApiError> {
+    let games = data.games.list().await.map_err(repo_error)?;
 
-    let json_response = serde_json::json!({
-        "status": "ok",
-        "count": games.len(),
-        "games": games
-    });
+    let count = games.len();
+    let json_response = ApiResponse::new(GameList { count, games });
 
-    Ok(Json(json_response))
+    Ok(json_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/games/{id}",
+    tag = "games",
+    summary = "Get a game",
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already has; a current one gets a 304"),
+    ),
+    responses(
+        (status = 200, description = "The game", body = ApiResponse<GameData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 304, description = "If-None-Match matches the current version", headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_game_handler(
     Path(game_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(GameModel, r#"SELECT * FROM games WHERE id = $1"#, &game_id)
-        .fetch_one(&data.db)
-        .await;
-
-    match query_result {
-        Ok(game) => {
-            let game_response = serde_json::json!({
-                "status" : "success",
-                "data": serde_json::json!({
-                    "game": game
-                })
-            });
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let game = data.games.get(game_id).await.map_err(|e| match e {
+        RepoError::NotFound => ApiError::not_found(format!("Game with ID: {} not found", game_id)),
+        e => repo_error(e),
+    })?;
 
-            Ok(Json(game_response))
-        }
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = serde_json::json!({
-                "status": "fail",
-                "message": format!("Game with ID: {} not found", game_id)
-            });
-            Err((StatusCode::NOT_FOUND, Json(error_response)))
-        }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error", "message": format!("{:?}", e)})),
-        )),
+    let etag = entity_tag(game.version);
+    if is_not_modified(&headers, game.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
     }
+
+    let game_response = ApiResponse::new(GameData { game });
+
+    Ok(([(ETAG, etag)], game_response).into_response())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/games/{id}",
+    tag = "games",
+    summary = "Move a game to the trash",
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Game moved to trash", body = ApiResponse<DeletedGameData>),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+        (status = 409, description = "Game still has plays", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
 pub async fn delete_game_handler(
     Path(game_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(
-        GameModel,
-        r#"DELETE FROM games WHERE id = $1 RETURNING *"#,
-        &game_id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => (
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "status": "error",
-                "message": "Game not found"
-            })),
-        ),
-        _ => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
-        ),
-    })?;
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let deleted_game = data
+        .games
+        .trash(game_id, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found("Game not found"),
+            RepoError::InUse => {
+                ApiError::conflict("Game still has plays, delete or merge them first")
+            }
+            e => repo_error(e),
+        })?;
 
-    let response = json!({
-        "status": "success",
-        "message": "Game delete successfully",
-        "data": {
-            "deleted_game" : query_result
-        }
-    });
+    let response =
+        ApiResponse::with_message("Game moved to trash", DeletedGameData { deleted_game });
 
-    Ok(Json(response))
+    Ok(response)
 }
 
+#[utoipa::path(
+    patch,
+    path = "/games/{id}",
+    tag = "games",
+    summary = "Update a game",
+    request_body(content = UpdateGameSchema, content_type = "application/merge-patch+json"),
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Game updated", body = ApiResponse<GameData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+        (status = 409, description = "A game with this name and creator, or this BGG ID, exists", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn update_game_handler(
     Path(id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-    Json(body): Json<UpdateGameSchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(GameModel, r#"SELECT * FROM games WHERE id = $1"#, &id)
-        .fetch_one(&data.db)
-        .await;
+    headers: HeaderMap,
+    MergePatch(body): MergePatch<UpdateGameSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let updated_game = data
+        .games
+        .update(id, &body, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found(format!("Game with ID: {} not found", id)),
+            RepoError::Duplicate { existing_id } => game_conflict_response(existing_id),
+            e => repo_error(e),
+        })?;
 
-    let game = match query_result {
-        Ok(game) => game,
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Game with ID: {} not found", id)
-            });
-            return Err((StatusCode::NOT_FOUND, Json(error_response)));
-        }
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "status": "error",
-                    "message": format!("{:?}",e)
-                })),
-            ));
+    let etag = entity_tag(updated_game.version);
+    let response = ApiResponse::new(GameData { game: updated_game });
+    Ok(([(ETAG, etag)], response))
+}
+
+/// Merges the game at `game_id` into `body.target_id`: every play of the
+/// source game is re-pointed at the target and the source is then deleted.
+#[utoipa::path(
+    post,
+    path = "/games/{id}/merge",
+    tag = "games",
+    summary = "Merge a duplicate game into another",
+    request_body = MergeSchema,
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+    ),
+    responses(
+        (status = 200, description = "Game merged", body = ApiResponse<MergedGameData>),
+        (status = 400, description = "Game merged into itself", body = ErrorResponse),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn merge_game_handler(
+    Path(game_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<MergeSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    if game_id == body.target_id {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "A game cannot be merged into itself",
+        ));
+    }
+
+    let not_found = |id: Uuid| ApiError::not_found(format!("Game with ID: {} not found", id));
+
+    let merged = data
+        .games
+        .merge(game_id, body.target_id)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => not_found(game_id),
+            RepoError::MissingReferences(_) => not_found(body.target_id),
+            e => repo_error(e),
+        })?;
+
+    let response = ApiResponse::with_message("Game merged successfully", merged);
+
+    Ok(response)
+}
+
+/// Takes a game back out of the trash
+#[utoipa::path(
+    post,
+    path = "/games/{id}/restore",
+    tag = "trash",
+    summary = "Restore a game from the trash",
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+    ),
+    responses(
+        (status = 200, description = "Game restored", body = ApiResponse<GameData>),
+        (status = 404, description = "Game not found in trash", body = ErrorResponse),
+        (status = 409, description = "A live game with this name and creator, or this BGG ID, exists", body = ErrorResponse),
+    )
+)]
+pub async fn restore_game_handler(
+    Path(game_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let game = data.games.restore(game_id).await.map_err(|e| match e {
+        RepoError::NotFound => {
+            ApiError::not_found(format!("Game with ID: {} not found in trash", game_id))
         }
+        RepoError::Duplicate { existing_id } => game_conflict_response(existing_id),
+        e => repo_error(e),
+    })?;
+
+    let response = ApiResponse::with_message("Game restored from trash", GameData { game });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        extract::validated_item,
+        handlers::{create_play_handler, delete_play_handler, response_json},
+        repository::memory::MemoryRepository,
+        schema::PlaySchema,
     };
 
-    let new_name = body.name.as_ref().unwrap_or(&game.name);
-    let new_creator = body.creator_name.as_ref().unwrap_or(&game.creator_name);
+    async fn create_game(
+        data: &Arc<AppState>,
+        name: &str,
+        creator_name: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let body = GameSchema {
+            name: name.to_string(),
+            creator_name: creator_name.to_string(),
+            ..Default::default()
+        };
+        response_json(create_game_handler(State(data.clone()), ValidatedJson(body)).await).await
+    }
 
-    let updated_game = sqlx::query_as!(
-        GameModel,
-        r#"UPDATE games SET name = $1, creator_name = $2 WHERE id = $3 RETURNING *"#,
-        &new_name,
-        &new_creator,
-        &id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
+    #[tokio::test]
+    async fn clashing_game_points_at_the_existing_one() {
+        let data = MemoryRepository::app_state();
+        let (_, created) = create_game(&data, "Catan", "Klaus Teuber").await;
+
+        let (status, body) = create_game(&data, "  catan", "KLAUS   teuber").await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["existing_id"], created["data"]["game"]["id"]);
+    }
+
+    #[tokio::test]
+    async fn game_with_live_plays_stays_out_of_the_trash() {
+        let data = MemoryRepository::app_state();
+        let (_, game) = create_game(&data, "Azul", "Michael Kiesling").await;
+        let game_id: Uuid = serde_json::from_value(game["data"]["game"]["id"].clone()).unwrap();
+        let body = PlaySchema {
+            game_id,
+            session_id: None,
+            ..Default::default()
+        };
+        let play = create_play_handler(State(data.clone()), ValidatedJson(body));
+        let (_, play) = response_json(play.await).await;
+        let play_id: Uuid = serde_json::from_value(play["data"]["play"]["id"].clone()).unwrap();
+
+        let delete = delete_game_handler(Path(game_id), State(data.clone()), HeaderMap::new());
+        let (status, _) = response_json(delete.await).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let delete = delete_play_handler(Path(play_id), State(data.clone()), HeaderMap::new());
+        response_json(delete.await).await;
+        let delete = delete_game_handler(Path(game_id), State(data.clone()), HeaderMap::new());
+        let (status, body) = response_json(delete.await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["deleted_game"]["version"], 2);
+    }
+
+    #[tokio::test]
+    async fn game_details_are_checked_against_the_stored_ones() {
+        let data = MemoryRepository::app_state();
+        let game = |bgg_id, min_players, max_players| GameSchema {
+            name: "Catan".to_string(),
+            creator_name: "Klaus Teuber".to_string(),
+            bgg_id: Some(bgg_id),
+            min_players: Some(min_players),
+            max_players: Some(max_players),
+            ..Default::default()
+        };
+
+        let create = create_game_handler(State(data.clone()), ValidatedJson(game(13, 4, 3)));
+        let (status, body) = response_json(create.await).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "max_players");
+
+        let create = create_game_handler(State(data.clone()), ValidatedJson(game(13, 3, 4)));
+        let (_, created) = response_json(create.await).await;
+        let game_id: Uuid = serde_json::from_value(created["data"]["game"]["id"].clone()).unwrap();
+
+        let (status, body) = response_json(
+            create_game_handler(
+                State(data.clone()),
+                ValidatedJson(GameSchema {
+                    name: "Settlers".to_string(),
+                    ..game(13, 3, 4)
+                }),
+            )
+            .await,
         )
-    })?;
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["existing_id"], created["data"]["game"]["id"]);
 
-    let response = json!({
-        "status": "success",
-        "data": json!({
-            "game": updated_game
-        })
-    });
-    Ok(Json(response))
+        let update = |body| {
+            let data = data.clone();
+            async move {
+                let body = validated_item::<UpdateGameSchema>(body).unwrap();
+                let update = update_game_handler(
+                    Path(game_id),
+                    State(data),
+                    HeaderMap::new(),
+                    MergePatch(body),
+                );
+                response_json(update.await).await
+            }
+        };
+        let (status, body) = update(json!({"max_players": 2})).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "max_players");
+
+        let (status, body) =
+            update(json!({"min_players": null, "max_players": 2, "weight": 2.3})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["game"]["min_players"], serde_json::Value::Null);
+        assert_eq!(body["data"]["game"]["max_players"], 2);
+        assert_eq!(body["data"]["game"]["version"], 2);
+    }
 }
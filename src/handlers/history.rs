@@ -0,0 +1,441 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    extract::Path,
+    model::AuditLogModel,
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, ErrorResponse, EventData, GameData, HistoryList, PlayData,
+        PlayParticipantData, PlayerData, SessionData,
+    },
+};
+
+use super::repo_error;
+
+fn history_response(history: Vec<AuditLogModel>) -> ApiResponse<HistoryList> {
+    ApiResponse::new(HistoryList {
+        count: history.len(),
+        history,
+    })
+}
+
+/// Looks up the row snapshot recorded after the audit entry `audit_id`, which
+/// has to belong to the given entity
+async fn fetch_snapshot(
+    data: &AppState,
+    entity: &str,
+    entity_id: &str,
+    audit_id: i64,
+) -> Result<serde_json::Value, ApiError> {
+    let entry = data
+        .history
+        .get(entity, entity_id, audit_id)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found(format!(
+                "History entry {} not found for {} {}",
+                audit_id, entity, entity_id
+            )),
+            e => repo_error(e),
+        })?;
+
+    entry.after.ok_or_else(|| {
+        ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "History entry {} removed the row, revert to an earlier entry",
+                audit_id
+            ),
+        )
+    })
+}
+
+fn revert_error(e: RepoError) -> ApiError {
+    match e {
+        RepoError::Duplicate { .. } => {
+            ApiError::conflict("The previous version clashes with an existing record")
+        }
+        RepoError::MissingReferences(_) => {
+            ApiError::conflict("The previous version references a record that no longer exists")
+        }
+        e => repo_error(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/games/{id}/history",
+    tag = "history",
+    summary = "List a game's changes",
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+    ),
+    responses(
+        (status = 200, description = "Changes, newest first", body = ApiResponse<HistoryList>),
+    )
+)]
+pub async fn game_history_handler(
+    Path(game_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let history = data
+        .history
+        .list("games", &game_id.to_string())
+        .await
+        .map_err(repo_error)?;
+    Ok(history_response(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/players/{id}/history",
+    tag = "history",
+    summary = "List a player's changes",
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+    ),
+    responses(
+        (status = 200, description = "Changes, newest first", body = ApiResponse<HistoryList>),
+    )
+)]
+pub async fn player_history_handler(
+    Path(player_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let history = data
+        .history
+        .list("players", &player_id.to_string())
+        .await
+        .map_err(repo_error)?;
+    Ok(history_response(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/plays/{id}/history",
+    tag = "history",
+    summary = "List a play's changes",
+    params(
+        ("id" = Uuid, Path, description = "Play ID"),
+    ),
+    responses(
+        (status = 200, description = "Changes, newest first", body = ApiResponse<HistoryList>),
+    )
+)]
+pub async fn play_history_handler(
+    Path(play_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let history = data
+        .history
+        .list("plays", &play_id.to_string())
+        .await
+        .map_err(repo_error)?;
+    Ok(history_response(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/plays/{play_id}/participants/{player_id}/history",
+    tag = "history",
+    summary = "List a participant's changes",
+    params(
+        ("play_id" = Uuid, Path, description = "Play ID"),
+        ("player_id" = Uuid, Path, description = "Player ID"),
+    ),
+    responses(
+        (status = 200, description = "Changes, newest first", body = ApiResponse<HistoryList>),
+    )
+)]
+pub async fn play_participant_history_handler(
+    Path((play_id, player_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let entity_id = format!("{}/{}", play_id, player_id);
+    let history = data
+        .history
+        .list("play_participants", &entity_id)
+        .await
+        .map_err(repo_error)?;
+    Ok(history_response(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}/history",
+    tag = "history",
+    summary = "List a session's changes",
+    description = "Attendance is not recorded on its own; changing it bumps the session's version",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+    ),
+    responses(
+        (status = 200, description = "Changes, newest first", body = ApiResponse<HistoryList>),
+    )
+)]
+pub async fn session_history_handler(
+    Path(session_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let history = data
+        .history
+        .list("sessions", &session_id.to_string())
+        .await
+        .map_err(repo_error)?;
+    Ok(history_response(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/{id}/history",
+    tag = "history",
+    summary = "List an event's changes",
+    description = "Invitations and RSVPs are not recorded on their own; changing them bumps the event's version",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 200, description = "Changes, newest first", body = ApiResponse<HistoryList>),
+    )
+)]
+pub async fn event_history_handler(
+    Path(event_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let history = data
+        .history
+        .list("events", &event_id.to_string())
+        .await
+        .map_err(repo_error)?;
+    Ok(history_response(history))
+}
+
+/// Puts a game back into the state recorded by one of its history entries,
+/// re-creating it if it has been purged since
+#[utoipa::path(
+    post,
+    path = "/games/{id}/history/{audit_id}/revert",
+    tag = "history",
+    summary = "Revert a game to a history entry",
+    params(
+        ("id" = Uuid, Path, description = "Game ID"),
+        ("audit_id" = i64, Path, description = "History entry to revert to"),
+    ),
+    responses(
+        (status = 200, description = "Game reverted", body = ApiResponse<GameData>),
+        (status = 404, description = "History entry not found", body = ErrorResponse),
+        (status = 409, description = "The old version clashes with current data", body = ErrorResponse),
+        (status = 422, description = "The entry removed the game", body = ErrorResponse),
+    )
+)]
+pub async fn revert_game_handler(
+    Path((game_id, audit_id)): Path<(Uuid, i64)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let snapshot = fetch_snapshot(&data, "games", &game_id.to_string(), audit_id).await?;
+
+    let game = data
+        .history
+        .revert_game(&snapshot)
+        .await
+        .map_err(revert_error)?;
+
+    let response = ApiResponse::with_message(
+        format!("Game reverted to history entry {}", audit_id),
+        GameData { game },
+    );
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/players/{id}/history/{audit_id}/revert",
+    tag = "history",
+    summary = "Revert a player to a history entry",
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+        ("audit_id" = i64, Path, description = "History entry to revert to"),
+    ),
+    responses(
+        (status = 200, description = "Player reverted", body = ApiResponse<PlayerData>),
+        (status = 404, description = "History entry not found", body = ErrorResponse),
+        (status = 409, description = "The old version clashes with current data", body = ErrorResponse),
+        (status = 422, description = "The entry removed the player", body = ErrorResponse),
+    )
+)]
+pub async fn revert_player_handler(
+    Path((player_id, audit_id)): Path<(Uuid, i64)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let snapshot = fetch_snapshot(&data, "players", &player_id.to_string(), audit_id).await?;
+
+    let player = data
+        .history
+        .revert_player(&snapshot)
+        .await
+        .map_err(revert_error)?;
+
+    let response = ApiResponse::with_message(
+        format!("Player reverted to history entry {}", audit_id),
+        PlayerData { player },
+    );
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/plays/{id}/history/{audit_id}/revert",
+    tag = "history",
+    summary = "Revert a play to a history entry",
+    params(
+        ("id" = Uuid, Path, description = "Play ID"),
+        ("audit_id" = i64, Path, description = "History entry to revert to"),
+    ),
+    responses(
+        (status = 200, description = "Play reverted", body = ApiResponse<PlayData>),
+        (status = 404, description = "History entry not found", body = ErrorResponse),
+        (status = 409, description = "The old version references a purged game", body = ErrorResponse),
+        (status = 422, description = "The entry removed the play", body = ErrorResponse),
+    )
+)]
+pub async fn revert_play_handler(
+    Path((play_id, audit_id)): Path<(Uuid, i64)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let snapshot = fetch_snapshot(&data, "plays", &play_id.to_string(), audit_id).await?;
+
+    let play = data
+        .history
+        .revert_play(&snapshot)
+        .await
+        .map_err(revert_error)?;
+
+    let response = ApiResponse::with_message(
+        format!("Play reverted to history entry {}", audit_id),
+        PlayData { play },
+    );
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/plays/{play_id}/participants/{player_id}/history/{audit_id}/revert",
+    tag = "history",
+    summary = "Revert a participant to a history entry",
+    params(
+        ("play_id" = Uuid, Path, description = "Play ID"),
+        ("player_id" = Uuid, Path, description = "Player ID"),
+        ("audit_id" = i64, Path, description = "History entry to revert to"),
+    ),
+    responses(
+        (status = 200, description = "Participant reverted", body = ApiResponse<PlayParticipantData>),
+        (status = 404, description = "History entry not found", body = ErrorResponse),
+        (status = 409, description = "The old version references a purged play or player", body = ErrorResponse),
+        (status = 422, description = "The entry removed the participant", body = ErrorResponse),
+    )
+)]
+pub async fn revert_play_participant_handler(
+    Path((play_id, player_id, audit_id)): Path<(Uuid, Uuid, i64)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let entity_id = format!("{}/{}", play_id, player_id);
+    let snapshot = fetch_snapshot(&data, "play_participants", &entity_id, audit_id).await?;
+
+    let play_participant = data
+        .history
+        .revert_participant(&snapshot)
+        .await
+        .map_err(revert_error)?;
+
+    let response = ApiResponse::with_message(
+        format!("Play participant reverted to history entry {}", audit_id),
+        PlayParticipantData { play_participant },
+    );
+
+    Ok(response)
+}
+
+/// Puts a session's date, location and host back as recorded by one of its
+/// history entries, re-creating it if it has been deleted since. The current
+/// attendees stay, with the recorded host added.
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/history/{audit_id}/revert",
+    tag = "history",
+    summary = "Revert a session to a history entry",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        ("audit_id" = i64, Path, description = "History entry to revert to"),
+    ),
+    responses(
+        (status = 200, description = "Session reverted", body = ApiResponse<SessionData>),
+        (status = 404, description = "History entry not found", body = ErrorResponse),
+        (status = 409, description = "The old version references a purged host", body = ErrorResponse),
+        (status = 422, description = "The entry removed the session", body = ErrorResponse),
+    )
+)]
+pub async fn revert_session_handler(
+    Path((session_id, audit_id)): Path<(Uuid, i64)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let snapshot = fetch_snapshot(&data, "sessions", &session_id.to_string(), audit_id).await?;
+
+    let session = data
+        .history
+        .revert_session(&snapshot)
+        .await
+        .map_err(revert_error)?;
+
+    let response = ApiResponse::with_message(
+        format!("Session reverted to history entry {}", audit_id),
+        SessionData { session },
+    );
+
+    Ok(response)
+}
+
+/// Puts an event's date, location, host and session back as recorded by one
+/// of its history entries, re-creating it if it has been deleted since. The
+/// current invitations stay, with the recorded host invited.
+#[utoipa::path(
+    post,
+    path = "/events/{id}/history/{audit_id}/revert",
+    tag = "history",
+    summary = "Revert an event to a history entry",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("audit_id" = i64, Path, description = "History entry to revert to"),
+    ),
+    responses(
+        (status = 200, description = "Event reverted", body = ApiResponse<EventData>),
+        (status = 404, description = "History entry not found", body = ErrorResponse),
+        (status = 409, description = "The old version references a purged host or session, or a session another event turned into", body = ErrorResponse),
+        (status = 422, description = "The entry removed the event", body = ErrorResponse),
+    )
+)]
+pub async fn revert_event_handler(
+    Path((event_id, audit_id)): Path<(Uuid, i64)>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let snapshot = fetch_snapshot(&data, "events", &event_id.to_string(), audit_id).await?;
+
+    let event = data
+        .history
+        .revert_event(&snapshot)
+        .await
+        .map_err(revert_error)?;
+
+    let response = ApiResponse::with_message(
+        format!("Event reverted to history entry {}", audit_id),
+        EventData { event },
+    );
+
+    Ok(response)
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse};
+
+use crate::{
+    AppState,
+    model::PlayerModel,
+    response::{ApiError, ApiResponse, ErrorResponse, MeData, MeStats, PlayerPlayList},
+};
+
+use super::repo_error;
+
+/// Looks up the owner player, answering 404 when nobody has been made owner yet
+async fn fetch_owner(data: &AppState) -> Result<PlayerModel, ApiError> {
+    data.players
+        .owner()
+        .await
+        .map_err(repo_error)?
+        .ok_or_else(|| ApiError::not_found("No owner has been set"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me",
+    tag = "me",
+    summary = "Get the owner with their stats",
+    responses(
+        (status = 200, description = "The owner", body = ApiResponse<MeData>),
+        (status = 404, description = "No owner has been set", body = ErrorResponse),
+    )
+)]
+pub async fn get_me_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let owner = fetch_owner(&data).await?;
+
+    let stats = data.players.stats(owner.id).await.map_err(repo_error)?;
+
+    let win_rate = if stats.plays > 0 {
+        stats.wins as f64 / stats.plays as f64
+    } else {
+        0.0
+    };
+
+    let response = ApiResponse::new(MeData {
+        player: owner,
+        stats: MeStats {
+            plays: stats.plays,
+            wins: stats.wins,
+            games: stats.games,
+            win_rate,
+        },
+    });
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/plays",
+    tag = "me",
+    summary = "List the owner's plays",
+    responses(
+        (status = 200, description = "Plays the owner took part in, newest first", body = ApiResponse<PlayerPlayList>),
+        (status = 404, description = "No owner has been set", body = ErrorResponse),
+    )
+)]
+pub async fn my_play_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let owner = fetch_owner(&data).await?;
+
+    let plays = data.players.plays(owner.id).await.map_err(repo_error)?;
+
+    let count = plays.len();
+    let json_response = ApiResponse::new(PlayerPlayList { count, plays });
+
+    Ok(json_response)
+}
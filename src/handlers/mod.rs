@@ -1,10 +1,124 @@
+pub mod backup;
+pub mod bgg;
+pub mod bgstats;
+mod bulk;
+pub mod calendar;
+pub mod event;
 pub mod game;
+pub mod history;
+pub mod me;
 pub mod play;
+pub mod play_csv;
 pub mod play_participant;
 pub mod player;
+pub mod report;
+pub mod session;
+pub mod suggest;
+pub mod trash;
 
 // Re-export all handlers for easy importing
+pub use backup::*;
+pub use bgg::*;
+pub use bgstats::*;
+pub use calendar::*;
+pub use event::*;
 pub use game::*;
+pub use history::*;
+pub use me::*;
 pub use play::*;
+pub use play_csv::*;
 pub use play_participant::*;
 pub use player::*;
+pub use report::*;
+pub use session::*;
+pub use suggest::*;
+pub use trash::*;
+
+use axum::http::StatusCode;
+
+use crate::{
+    etag::precondition_failed, repository::RepoError, response::ApiError,
+    validation::field_errors_response,
+};
+
+/// Answers the repository errors a handler has no response of its own for,
+/// each with its own status: a missing row gets a 404, a clash a 409, a
+/// rejected `If-Match` a 412 with the current ETag and a missing reference or
+/// contradicting fields a 422. Internal failures are logged and answered with a 500 that does not
+/// reveal them.
+fn repo_error(e: RepoError) -> ApiError {
+    match e {
+        RepoError::NotFound => ApiError::not_found("Not found"),
+        RepoError::PreconditionFailed { version } => precondition_failed(version),
+        RepoError::Duplicate { existing_id } => {
+            ApiError::conflict("Clashes with an existing record").with_existing_id(existing_id)
+        }
+        RepoError::OwnerExists { owner_id } => {
+            ApiError::conflict("Another player is already the owner").with_existing_id(owner_id)
+        }
+        RepoError::InUse => ApiError::conflict("Still referenced by other records"),
+        RepoError::ReferenceTrashed { id } => ApiError::conflict(format!(
+            "Record with ID: {} is in the trash, restore it first",
+            id
+        )),
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        RepoError::Invalid(errors) => field_errors_response(&errors),
+        RepoError::Internal(message) => {
+            println!("Repository call failed: {}", message);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+        }
+    }
+}
+
+/// Lists every reference field that points at a missing or trashed row
+fn missing_references(fields: &[&str]) -> ApiError {
+    let errors: Vec<_> = fields
+        .iter()
+        .map(|field| match *field {
+            "game_id" | "game_ids" => (*field, "Game not found"),
+            "play_id" => (*field, "Play not found"),
+            "session_id" => (*field, "Session not found"),
+            _ => (*field, "Player not found"),
+        })
+        .collect();
+    field_errors_response(&errors)
+}
+
+/// Status and JSON body of a handler's answer
+#[cfg(test)]
+async fn response_json(
+    response: impl axum::response::IntoResponse,
+) -> (StatusCode, serde_json::Value) {
+    let response = response.into_response();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn repository_errors_get_their_own_status() {
+        let (status, body) = response_json(repo_error(RepoError::NotFound)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["status"], "fail");
+
+        let existing_id = uuid::Uuid::new_v4();
+        let duplicate = repo_error(RepoError::Duplicate {
+            existing_id: Some(existing_id),
+        });
+        let (status, body) = response_json(duplicate).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["existing_id"], existing_id.to_string());
+
+        let internal = repo_error(RepoError::Internal("connection refused".to_string()));
+        let (status, body) = response_json(internal).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["message"], "Internal server error");
+    }
+}
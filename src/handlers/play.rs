@@ -1,196 +1,432 @@
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::State,
+    http::{HeaderMap, StatusCode, header::ETAG},
     response::IntoResponse,
 };
 use uuid::Uuid;
 
-use serde_json::json;
+use crate::{
+    AppState,
+    etag::{entity_tag, if_match_allows, is_not_modified},
+    extract::{MergePatch, Path, Query, ValidatedJson, validated_item},
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, BulkData, DeletedPlayData, ErrorResponse, PlayData, PlayList,
+    },
+    schema::{BulkDeleteQuery, BulkQuery, BulkSchema, PlaySchema, UpdatePlaySchema},
+    validation::{BULK_MAX_ITEMS, field_error_response},
+};
 
-use crate::{AppState, model::PlayModel, schema::PlaySchema};
+use super::{bulk::BulkResults, missing_references, repo_error};
 
+/// Answers a play that could not be written, e.g. because its game is missing
+fn play_error(e: RepoError) -> ApiError {
+    match e {
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        e => repo_error(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/plays",
+    tag = "plays",
+    summary = "Log a play",
+    request_body = PlaySchema,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Play created", body = ApiResponse<PlayData>),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn create_play_handler(
     State(data): State<Arc<AppState>>,
-    Json(body): Json<PlaySchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let play = sqlx::query_as!(
-        PlayModel,
-        r#"INSERT INTO plays (game_id) VALUES ($1) RETURNING *"#,
-        &body.game_id,
+    ValidatedJson(body): ValidatedJson<PlaySchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let play = data.plays.create(&body).await.map_err(play_error)?;
+
+    let play_response = ApiResponse::new(PlayData { play });
+
+    Ok(play_response)
+}
+
+/// Creates several plays at once, see `BulkResults` for how failures are
+/// handled
+#[utoipa::path(
+    post,
+    path = "/plays/bulk",
+    tag = "plays",
+    summary = "Log several plays",
+    request_body = BulkSchema,
+    params(
+        BulkQuery,
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Result of every item", body = ApiResponse<BulkData>),
+        (status = 422, description = "Invalid body, or an atomic request with failing items", body = ErrorResponse),
     )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| e.to_string());
-
-    if let Err(err) = play {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error","message": format!("{:?}", err)})),
-        ));
+)]
+pub async fn bulk_create_play_handler(
+    Query(query): Query<BulkQuery>,
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<BulkSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut results = BulkResults::new(query.mode);
+    let mut indices = Vec::new();
+    let mut plays = Vec::new();
+    for (index, item) in body.items.into_iter().enumerate() {
+        match validated_item::<PlaySchema>(item) {
+            Ok(play) => {
+                indices.push(index);
+                plays.push(play);
+            }
+            Err(e) => results.record::<PlayData>(index, Err(e)),
+        }
     }
 
-    let play_response = json!({
-            "status": "success",
-            "data": json!({
-                "play": play
-        })
-    });
+    let created = data
+        .plays
+        .create_many(&plays, results.commit())
+        .await
+        .map_err(repo_error)?;
+    for (index, result) in indices.into_iter().zip(created) {
+        results.record(
+            index,
+            result.map(|play| PlayData { play }).map_err(play_error),
+        );
+    }
 
-    Ok(Json(play_response))
+    results.finish("created")
 }
 
-pub async fn play_list_handler(
+/// Moves every play in the comma separated `ids` to the trash
+#[utoipa::path(
+    delete,
+    path = "/plays",
+    tag = "plays",
+    summary = "Move several plays to the trash",
+    params(
+        BulkDeleteQuery,
+    ),
+    responses(
+        (status = 200, description = "Result of every ID", body = ApiResponse<BulkData>),
+        (status = 422, description = "Invalid IDs, or an atomic request with failing items", body = ErrorResponse),
+    )
+)]
+pub async fn bulk_delete_play_handler(
+    Query(query): Query<BulkDeleteQuery>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let plays = sqlx::query_as!(PlayModel, r#"SELECT * FROM plays ORDER BY game_id"#)
-        .fetch_all(&data.db)
+) -> Result<impl IntoResponse, ApiError> {
+    let ids: Vec<&str> = query.ids.split(',').map(str::trim).collect();
+    if ids.len() as u64 > BULK_MAX_ITEMS {
+        let message = format!("must list at most {} IDs", BULK_MAX_ITEMS);
+        return Err(field_error_response("ids", &message));
+    }
+
+    let mut results = BulkResults::new(query.mode);
+    let mut indices = Vec::new();
+    let mut play_ids = Vec::new();
+    for (index, id) in ids.into_iter().enumerate() {
+        match Uuid::parse_str(id) {
+            Ok(play_id) => {
+                indices.push(index);
+                play_ids.push(play_id);
+            }
+            Err(_) => results.record::<DeletedPlayData>(
+                index,
+                Err(field_error_response("ids", "must be a UUID")),
+            ),
+        }
+    }
+
+    let trashed = data
+        .plays
+        .trash_many(&play_ids, results.commit())
         .await
-        .map_err(|e| {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Database error: {}", e),
+        .map_err(repo_error)?;
+    for (index, result) in indices.into_iter().zip(trashed) {
+        let result = result
+            .map(|deleted_play| DeletedPlayData { deleted_play })
+            .map_err(|e| match e {
+                RepoError::NotFound => ApiError::not_found("Play not found"),
+                e => repo_error(e),
             });
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
-        })?;
+        results.record(index, result);
+    }
+
+    results.finish("deleted")
+}
 
-    let json_response = serde_json::json!({
-        "status": "ok",
-        "count": plays.len(),
-        "plays": plays
-    });
+#[utoipa::path(
+    get,
+    path = "/plays",
+    tag = "plays",
+    summary = "List plays",
+    responses(
+        (status = 200, description = "Live plays", body = ApiResponse<PlayList>),
+    )
+)]
+pub async fn play_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let plays = data.plays.list().await.map_err(repo_error)?;
 
-    Ok(Json(json_response))
+    let count = plays.len();
+    let json_response = ApiResponse::new(PlayList { count, plays });
+
+    Ok(json_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/plays/{id}",
+    tag = "plays",
+    summary = "Get a play",
+    params(
+        ("id" = Uuid, Path, description = "Play ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already has; a current one gets a 304"),
+    ),
+    responses(
+        (status = 200, description = "The play", body = ApiResponse<PlayData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 304, description = "If-None-Match matches the current version", headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Play not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_play_handler(
     Path(play_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(PlayModel, r#"SELECT * FROM plays WHERE id = $1"#, &play_id)
-        .fetch_one(&data.db)
-        .await;
-
-    match query_result {
-        Ok(play) => {
-            let play_response = serde_json::json!({
-                "status" : "success",
-                "data": json!({
-                    "play": play
-                })
-            });
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let play = data.plays.get(play_id).await.map_err(|e| match e {
+        RepoError::NotFound => ApiError::not_found(format!("Play with ID: {} not found", play_id)),
+        e => repo_error(e),
+    })?;
 
-            Ok(Json(play_response))
-        }
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = json!({
-                "status": "fail",
-                "message": format!("Play with ID: {} not found", play_id)
-            });
-            Err((StatusCode::NOT_FOUND, Json(error_response)))
-        }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error", "message": format!("{:?}", e)})),
-        )),
+    let etag = entity_tag(play.version);
+    if is_not_modified(&headers, play.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
     }
+
+    let play_response = ApiResponse::new(PlayData { play });
+
+    Ok(([(ETAG, etag)], play_response).into_response())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/plays/{id}",
+    tag = "plays",
+    summary = "Move a play to the trash",
+    params(
+        ("id" = Uuid, Path, description = "Play ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Play moved to trash", body = ApiResponse<DeletedPlayData>),
+        (status = 404, description = "Play not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
 pub async fn delete_play_handler(
     Path(play_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(
-        PlayModel,
-        r#"DELETE FROM plays WHERE id = $1 RETURNING *"#,
-        &play_id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => (
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "status": "error",
-                "message": "Play not found"
-            })),
-        ),
-        _ => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
-        ),
-    })?;
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let deleted_play = data
+        .plays
+        .trash(play_id, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found("Play not found"),
+            e => repo_error(e),
+        })?;
 
-    let response = json!({
-        "status": "success",
-        "message": "Play deleted successfully",
-        "data": {
-            "deleted_play" : query_result
-        }
-    });
+    let response =
+        ApiResponse::with_message("Play moved to trash", DeletedPlayData { deleted_play });
 
-    Ok(Json(response))
+    Ok(response)
 }
 
+#[utoipa::path(
+    patch,
+    path = "/plays/{id}",
+    tag = "plays",
+    summary = "Update a play",
+    request_body(content = UpdatePlaySchema, content_type = "application/merge-patch+json"),
+    params(
+        ("id" = Uuid, Path, description = "Play ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Play updated", body = ApiResponse<PlayData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Play not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn update_play_handler(
     Path(id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-    Json(body): Json<PlaySchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(PlayModel, r#"SELECT * FROM plays WHERE id = $1"#, &id)
-        .fetch_one(&data.db)
-        .await;
-
-    let _play = match query_result {
-        Ok(play) => play,
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Play with ID: {} not found", id)
-            });
-            return Err((StatusCode::NOT_FOUND, Json(error_response)));
-        }
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "status": "error",
-                    "message": format!("{:?}",e)
-                })),
-            ));
-        }
-    };
+    headers: HeaderMap,
+    MergePatch(body): MergePatch<UpdatePlaySchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let updated_play = data
+        .plays
+        .update(id, &body, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found(format!("Play with ID: {} not found", id)),
+            e => play_error(e),
+        })?;
 
-    let new_game_id = &body.game_id;
+    let etag = entity_tag(updated_play.version);
+    let response = ApiResponse::new(PlayData { play: updated_play });
+    Ok(([(ETAG, etag)], response))
+}
 
-    let updated_play = sqlx::query_as!(
-        PlayModel,
-        r#"UPDATE plays SET game_id = $1 WHERE id = $2 RETURNING *"#,
-        &new_game_id,
-        &id
+/// Takes a play back out of the trash; its game has to be live
+#[utoipa::path(
+    post,
+    path = "/plays/{id}/restore",
+    tag = "trash",
+    summary = "Restore a play from the trash",
+    params(
+        ("id" = Uuid, Path, description = "Play ID"),
+    ),
+    responses(
+        (status = 200, description = "Play restored", body = ApiResponse<PlayData>),
+        (status = 404, description = "Play not found in trash", body = ErrorResponse),
+        (status = 409, description = "The play's game is in the trash", body = ErrorResponse),
     )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
-        )
+)]
+pub async fn restore_play_handler(
+    Path(play_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let play = data.plays.restore(play_id).await.map_err(|e| match e {
+        RepoError::NotFound => {
+            ApiError::not_found(format!("Play with ID: {} not found in trash", play_id))
+        }
+        RepoError::ReferenceTrashed { id } => ApiError::conflict(format!(
+            "Game with ID: {} is in the trash, restore it first",
+            id
+        )),
+        e => repo_error(e),
     })?;
 
-    let response = json!({
-        "status": "success",
-        "data": json!({
-            "play": updated_play
-        })
-    });
-    Ok(Json(response))
+    let response = ApiResponse::with_message("Play restored from trash", PlayData { play });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderValue, header::IF_MATCH};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        handlers::response_json, repository::memory::MemoryRepository, schema::BulkMode,
+        schema::GameSchema,
+    };
+
+    /// A live game with one play of it, returning the play's ID
+    async fn logged_play(data: &Arc<AppState>) -> Uuid {
+        let game = GameSchema {
+            name: "Carcassonne".to_string(),
+            creator_name: "Klaus-Jürgen Wrede".to_string(),
+            ..Default::default()
+        };
+        let game = data.games.create(&game).await.unwrap();
+        let play = PlaySchema {
+            game_id: game.id,
+            session_id: None,
+            ..Default::default()
+        };
+        data.plays.create(&play).await.unwrap().id
+    }
+
+    #[tokio::test]
+    async fn stale_if_match_is_rejected_with_the_current_etag() {
+        let data = MemoryRepository::app_state();
+        let play_id = logged_play(&data).await;
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MATCH, HeaderValue::from_static("\"0\""));
+
+        let body = UpdatePlaySchema::default();
+        let update = update_play_handler(
+            Path(play_id),
+            State(data.clone()),
+            headers,
+            MergePatch(body),
+        );
+        let (status, body) = response_json(update.await).await;
+
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(body["etag"], "\"1\"");
+    }
+
+    #[tokio::test]
+    async fn play_details_are_validated_set_and_cleared() {
+        let data = MemoryRepository::app_state();
+        let play_id = logged_play(&data).await;
+
+        let invalid = json!({"location": "", "length_minutes": 0, "comments": "Close one"});
+        let (status, body) =
+            response_json(validated_item::<UpdatePlaySchema>(invalid).unwrap_err()).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "length_minutes");
+        assert_eq!(body["errors"][1]["field"], "location");
+
+        let details =
+            json!({"location": " Ann's place ", "length_minutes": 75, "comments": "Close one"});
+        let body = validated_item::<UpdatePlaySchema>(details).unwrap();
+        let update = update_play_handler(
+            Path(play_id),
+            State(data.clone()),
+            HeaderMap::new(),
+            MergePatch(body),
+        );
+        let (status, body) = response_json(update.await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["play"]["location"], "Ann's place");
+        assert_eq!(body["data"]["play"]["length_minutes"], 75);
+
+        let cleared = validated_item::<UpdatePlaySchema>(json!({"comments": null})).unwrap();
+        let update = update_play_handler(
+            Path(play_id),
+            State(data.clone()),
+            HeaderMap::new(),
+            MergePatch(cleared),
+        );
+        let (_, body) = response_json(update.await).await;
+        assert_eq!(body["data"]["play"]["comments"], serde_json::Value::Null);
+        assert_eq!(body["data"]["play"]["length_minutes"], 75);
+        assert_eq!(body["data"]["play"]["version"], 3);
+    }
+
+    #[tokio::test]
+    async fn atomic_bulk_delete_keeps_every_play_when_one_fails() {
+        let data = MemoryRepository::app_state();
+        let play_id = logged_play(&data).await;
+        let query = BulkDeleteQuery {
+            ids: format!("{},{}", play_id, Uuid::new_v4()),
+            mode: BulkMode::Atomic,
+        };
+
+        let delete = bulk_delete_play_handler(Query(query), State(data.clone()));
+        let (status, body) = response_json(delete.await).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["results"][0]["index"], 1);
+        assert!(data.plays.get(play_id).await.is_ok());
+    }
 }
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+
+use crate::{
+    AppState,
+    extract::{CsvBody, Query},
+    play_csv::{CsvImportError, plan_csv_import, write_plays},
+    repository::RepoError,
+    response::{ApiError, ApiResponse, ErrorResponse, PlayCsvImportData},
+    schema::PlayCsvImportQuery,
+    validation::field_error_response,
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    get,
+    path = "/plays/export.csv",
+    tag = "plays",
+    summary = "Export plays as CSV",
+    description = "Live plays, oldest first, with one row per participant: \
+        `play_id,played_at,game,creator,player,score,winner`. A play without participants \
+        gets one row with the player columns left empty.",
+    responses(
+        (status = 200, description = "The CSV file", content_type = "text/csv", body = String),
+    )
+)]
+pub async fn export_plays_csv_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tables = data.backup.export().await.map_err(repo_error)?;
+    let csv = write_plays(&tables)
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let disposition = format!(
+        "attachment; filename=\"bg-logger-plays-{}.csv\"",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    let headers = [
+        (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+        (header::CONTENT_DISPOSITION, disposition),
+    ];
+
+    Ok((headers, csv))
+}
+
+#[utoipa::path(
+    post,
+    path = "/plays/import.csv",
+    tag = "plays",
+    summary = "Import plays from CSV",
+    description = "Creates a play for every group of rows and a participant for every row \
+        naming a player, in one transaction. Games and players are looked up by name among \
+        the live ones. If any row cannot be imported nothing is, and every failing row is \
+        listed.",
+    params(
+        PlayCsvImportQuery,
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when an import is retried"),
+    ),
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (status = 201, description = "Plays imported", body = ApiResponse<PlayCsvImportData>),
+        (status = 409, description = "A game or player was created meanwhile under a name the import creates", body = ErrorResponse),
+        (status = 415, description = "Not `text/csv`", body = ErrorResponse),
+        (status = 422, description = "Rows that cannot be imported", body = ErrorResponse),
+    )
+)]
+pub async fn import_plays_csv_handler(
+    Query(query): Query<PlayCsvImportQuery>,
+    State(data): State<Arc<AppState>>,
+    CsvBody(csv): CsvBody,
+) -> Result<impl IntoResponse, ApiError> {
+    let stored = data.backup.export().await.map_err(repo_error)?;
+    let import = plan_csv_import(&csv, &query, stored).map_err(|e| match e {
+        CsvImportError::MissingColumn { parameter, column } => field_error_response(
+            parameter,
+            &format!("the header has no \"{}\" column", column),
+        ),
+        CsvImportError::Unreadable(message) => {
+            ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid CSV: {}", message))
+        }
+        CsvImportError::Rows(rows) => ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Some rows cannot be imported, nothing was imported",
+        )
+        .with_rows(rows),
+    })?;
+
+    data.backup
+        .restore(&import.rows)
+        .await
+        .map_err(|e| match e {
+            RepoError::Duplicate { .. } | RepoError::MissingReferences(_) => {
+                ApiError::conflict("Data changed during the import, nothing was imported")
+            }
+            e => repo_error(e),
+        })?;
+
+    let response = ApiResponse::with_message("Plays imported", import.data);
+
+    Ok((StatusCode::CREATED, response))
+}
@@ -1,211 +1,204 @@
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::State,
+    http::{HeaderMap, header::ETAG},
     response::IntoResponse,
 };
 use uuid::Uuid;
 
-use serde_json::json;
-
 use crate::{
     AppState,
-    model::PlayParticipantModel,
+    etag::{entity_tag, if_match_allows},
+    extract::{MergePatch, Path, ValidatedJson},
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, DeletedPlayParticipantData, ErrorResponse, PlayParticipantData,
+        PlayParticipantList,
+    },
     schema::{PlayParticipantSchema, UpdatePlayParticipantSchema},
 };
 
+use super::{missing_references, repo_error};
+
+/// Answers a participation pointing at a missing or trashed play or player,
+/// or adding a player to the same play twice
+fn participant_error(e: RepoError) -> ApiError {
+    match e {
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        RepoError::Duplicate { .. } => {
+            ApiError::conflict("Player is already a participant of this play")
+        }
+        e => repo_error(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/playparticipants",
+    tag = "play participants",
+    summary = "Add a player to a play",
+    request_body = PlayParticipantSchema,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Participant added", body = ApiResponse<PlayParticipantData>),
+        (status = 409, description = "Player is already a participant of this play", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn create_play_participant_handler(
     State(data): State<Arc<AppState>>,
-    Json(body): Json<PlayParticipantSchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let play_participant = sqlx::query_as!(
-        PlayParticipantModel,
-        r#"INSERT INTO play_participants (play_id, player_id) VALUES ($1, $2) RETURNING *"#,
-        &body.play_id,
-        &body.player_id,
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| e.to_string());
-
-    if let Err(err) = play_participant {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error","message": format!("{:?}", err)})),
-        ));
-    }
+    ValidatedJson(body): ValidatedJson<PlayParticipantSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let play_participant = data
+        .participants
+        .create(&body)
+        .await
+        .map_err(participant_error)?;
 
-    let play_participant_response = json!({
-            "status": "success",
-            "data": json!({
-                "play_participant": play_participant
-        })
-    });
+    let play_participant_response = ApiResponse::new(PlayParticipantData { play_participant });
 
-    Ok(Json(play_participant_response))
+    Ok(play_participant_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/playparticipants",
+    tag = "play participants",
+    summary = "List participants of all plays",
+    responses(
+        (status = 200, description = "Participants of live plays", body = ApiResponse<PlayParticipantList>),
+    )
+)]
 pub async fn play_participant_list_handler(
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let play_participants =
-        sqlx::query_as!(PlayParticipantModel, r#"SELECT * FROM play_participants"#)
-            .fetch_all(&data.db)
-            .await
-            .map_err(|e| {
-                let error_response = serde_json::json!({
-                    "status": "error",
-                    "message": format!("Database error: {}", e),
-                });
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
-            })?;
-
-    let json_response = serde_json::json!({
-        "status": "ok",
-        "count": play_participants.len(),
-        "play_participants": play_participants
+) -> Result<impl IntoResponse, ApiError> {
+    let play_participants = data.participants.list().await.map_err(repo_error)?;
+
+    let count = play_participants.len();
+    let json_response = ApiResponse::new(PlayParticipantList {
+        count,
+        play_participants,
     });
 
-    Ok(Json(json_response))
+    Ok(json_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/plays/{play_id}/participants",
+    tag = "play participants",
+    summary = "List a play's participants",
+    params(
+        ("play_id" = Uuid, Path, description = "Play ID"),
+    ),
+    responses(
+        (status = 200, description = "The play's participants", body = ApiResponse<PlayParticipantList>),
+    )
+)]
 pub async fn get_play_participants_handler(
     Path(play_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let play_participants = sqlx::query_as!(
-        PlayParticipantModel,
-        r#"SELECT * FROM play_participants WHERE play_id = $1"#,
-        &play_id
-    )
-    .fetch_all(&data.db)
-    .await
-    .map_err(|e| {
-        let error_response = serde_json::json!({
-            "status": "error",
-            "message": format!("Database error: {}", e),
-        });
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
-    })?;
-
-    let play_participants_response = serde_json::json!({
-        "status": "success",
-        "count": play_participants.len(),
-        "data": json!({
-            "play_participants": play_participants
-        })
+) -> Result<impl IntoResponse, ApiError> {
+    let play_participants = data
+        .participants
+        .list_for_play(play_id)
+        .await
+        .map_err(repo_error)?;
+
+    let count = play_participants.len();
+    let play_participants_response = ApiResponse::new(PlayParticipantList {
+        count,
+        play_participants,
     });
 
-    Ok(Json(play_participants_response))
+    Ok(play_participants_response)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/plays/{play_id}/participants/{player_id}",
+    tag = "play participants",
+    summary = "Remove a player from a play",
+    params(
+        ("play_id" = Uuid, Path, description = "Play ID"),
+        ("player_id" = Uuid, Path, description = "Player ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Participant removed", body = ApiResponse<DeletedPlayParticipantData>),
+        (status = 404, description = "Play participant not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
 pub async fn delete_play_participant_handler(
     Path((play_id, player_id)): Path<(Uuid, Uuid)>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let deleted_participant = sqlx::query_as!(
-        PlayParticipantModel,
-        r#"DELETE FROM play_participants WHERE play_id = $1 AND player_id = $2 RETURNING *"#,
-        &play_id,
-        &player_id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => (
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "status": "error",
-                "message": "Play participant not found"
-            })),
-        ),
-        _ => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("Database error: {}", e)
-            })),
-        ),
-    })?;
-
-    let response = json!({
-        "status": "success",
-        "message": "Play participant deleted successfully",
-        "data": {
-            "deleted_participant": deleted_participant
-        }
-    });
-
-    Ok(Json(response))
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let deleted_participant = data
+        .participants
+        .delete(play_id, player_id, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found("Play participant not found"),
+            e => repo_error(e),
+        })?;
+
+    let response = ApiResponse::with_message(
+        "Play participant deleted successfully",
+        DeletedPlayParticipantData {
+            deleted_participant,
+        },
+    );
+
+    Ok(response)
 }
 
+#[utoipa::path(
+    patch,
+    path = "/plays/{play_id}/participants/{player_id}",
+    tag = "play participants",
+    summary = "Update a participant's result",
+    request_body(content = UpdatePlayParticipantSchema, content_type = "application/merge-patch+json"),
+    params(
+        ("play_id" = Uuid, Path, description = "Play ID"),
+        ("player_id" = Uuid, Path, description = "Player ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Participant updated", body = ApiResponse<PlayParticipantData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Play participant not found", body = ErrorResponse),
+        (status = 409, description = "Player is already a participant of this play", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn update_play_participant_handler(
     Path((play_id, player_id)): Path<(Uuid, Uuid)>,
     State(data): State<Arc<AppState>>,
-    Json(body): Json<UpdatePlayParticipantSchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(
-        PlayParticipantModel,
-        r#"SELECT * FROM play_participants WHERE play_id = $1 AND player_id = $2"#,
-        &play_id,
-        &player_id
-    )
-    .fetch_one(&data.db)
-    .await;
-
-    let play_participant: PlayParticipantModel = match query_result {
-        Ok(play_participant) => play_participant,
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Play participant with ID: ({},{}) not found", play_id, player_id)
-            });
-            return Err((StatusCode::NOT_FOUND, Json(error_response)));
-        }
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "status": "error",
-                    "message": format!("{:?}",e)
-                })),
-            ));
-        }
-    };
-
-    let new_play_id = body.play_id.as_ref().unwrap_or(&play_participant.play_id);
-    let new_player_id = body
-        .player_id
-        .as_ref()
-        .unwrap_or(&play_participant.player_id);
-
-    let updated_play_participant = sqlx::query_as!(
-        PlayParticipantModel,
-        r#"UPDATE play_participants SET play_id = $1, player_id = $2 WHERE play_id = $3 AND player_id = $4 RETURNING *"#,
-        &new_play_id,
-        &new_player_id,
-        &play_id,
-        &player_id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
-        )
-    })?;
-
-    let response = json!({
-        "status": "success",
-        "data": json!({
-            "play_participant": updated_play_participant
-        })
+    headers: HeaderMap,
+    MergePatch(body): MergePatch<UpdatePlayParticipantSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let updated_play_participant = data
+        .participants
+        .update(play_id, player_id, &body, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found(format!(
+                "Play participant with ID: ({},{}) not found",
+                play_id, player_id
+            )),
+            e => participant_error(e),
+        })?;
+
+    let etag = entity_tag(updated_play_participant.version);
+    let response = ApiResponse::new(PlayParticipantData {
+        play_participant: updated_play_participant,
     });
-    Ok(Json(response))
+    Ok(([(ETAG, etag)], response))
 }
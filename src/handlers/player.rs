@@ -1,217 +1,430 @@
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::State,
+    http::{HeaderMap, StatusCode, header::ETAG},
     response::IntoResponse,
 };
 use uuid::Uuid;
 
-use serde_json::json;
+use crate::{
+    AppState,
+    etag::{entity_tag, if_match_allows, is_not_modified},
+    extract::{MergePatch, Path, Query, ValidatedJson, validated_item},
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, BulkData, DeletedPlayerData, ErrorResponse, MergedPlayerData,
+        OwnerData, PlayerData, PlayerList,
+    },
+    schema::{BulkQuery, BulkSchema, MergeSchema, PlayerSchema, UpdatePlayerSchema},
+};
+
+use super::{bulk::BulkResults, repo_error};
+
+/// Answers a player clashing with an existing one, either on the normalized
+/// name or on `uniq_single_owner`, with a 409 pointing at the existing
+/// player's ID
+fn player_error(e: RepoError) -> ApiError {
+    let (message, existing_id) = match e {
+        RepoError::Duplicate { existing_id } => ("Player name already exists", existing_id),
+        RepoError::OwnerExists { owner_id } => (
+            "Only one owner is allowed, use make-owner to transfer ownership",
+            owner_id,
+        ),
+        e => return repo_error(e),
+    };
 
-use crate::{AppState, model::PlayerModel, schema::PlayerSchema};
+    ApiError::conflict(message).with_existing_id(existing_id)
+}
 
+#[utoipa::path(
+    post,
+    path = "/players",
+    tag = "players",
+    summary = "Create a player",
+    request_body = PlayerSchema,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Player created", body = ApiResponse<PlayerData>),
+        (status = 409, description = "Name taken or an owner already exists", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn create_player_handler(
     State(data): State<Arc<AppState>>,
-    Json(body): Json<PlayerSchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let player = sqlx::query_as!(
-        PlayerModel,
-        r#"INSERT INTO players (name, is_owner) VALUES ($1, $2) RETURNING *"#,
-        &body.name,
-        &body.is_owner.unwrap_or(false),
+    ValidatedJson(body): ValidatedJson<PlayerSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let player = data.players.create(&body).await.map_err(player_error)?;
+
+    let player_response = ApiResponse::new(PlayerData { player });
+
+    Ok(player_response)
+}
+
+/// Creates several players at once, see `BulkResults` for how failures are
+/// handled
+#[utoipa::path(
+    post,
+    path = "/players/bulk",
+    tag = "players",
+    summary = "Create several players",
+    request_body = BulkSchema,
+    params(
+        BulkQuery,
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Result of every item", body = ApiResponse<BulkData>),
+        (status = 422, description = "Invalid body, or an atomic request with failing items", body = ErrorResponse),
     )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| e.to_string());
-
-    if let Err(err) = player {
-        if err.contains("duplicate key value") {
-            if err.contains("uniq_single_owner") {
-                let error_response = serde_json::json!({
-                    "status": "error",
-                    "message": "Only one owner is allowed",
-                });
-                return Err((StatusCode::CONFLICT, Json(error_response)));
-            } else {
-                let error_response = serde_json::json!({
-                    "status": "error",
-                    "message": "Player name already exists",
-                });
-                return Err((StatusCode::CONFLICT, Json(error_response)));
+)]
+pub async fn bulk_create_player_handler(
+    Query(query): Query<BulkQuery>,
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<BulkSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut results = BulkResults::new(query.mode);
+    let mut indices = Vec::new();
+    let mut players = Vec::new();
+    for (index, item) in body.items.into_iter().enumerate() {
+        match validated_item::<PlayerSchema>(item) {
+            Ok(player) => {
+                indices.push(index);
+                players.push(player);
             }
+            Err(e) => results.record::<PlayerData>(index, Err(e)),
         }
-
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error","message": format!("{:?}", err)})),
-        ));
     }
 
-    let player_response = json!({
-            "status": "success",
-            "data": json!({
-                "player": player
-        })
-    });
+    let created = data
+        .players
+        .create_many(&players, results.commit())
+        .await
+        .map_err(repo_error)?;
+    for (index, result) in indices.into_iter().zip(created) {
+        results.record(
+            index,
+            result
+                .map(|player| PlayerData { player })
+                .map_err(player_error),
+        );
+    }
 
-    Ok(Json(player_response))
+    results.finish("created")
 }
 
+#[utoipa::path(
+    get,
+    path = "/players",
+    tag = "players",
+    summary = "List players",
+    responses(
+        (status = 200, description = "Live players", body = ApiResponse<PlayerList>),
+    )
+)]
 pub async fn player_list_handler(
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let players = sqlx::query_as!(PlayerModel, r#"SELECT * FROM players ORDER BY name"#)
-        .fetch_all(&data.db)
-        .await
-        .map_err(|e| {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Database error: {}", e),
-            });
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
-        })?;
+) -> Result<impl IntoResponse, ApiError> {
+    let players = data.players.list().await.map_err(repo_error)?;
 
-    let json_response = serde_json::json!({
-        "status": "ok",
-        "count": players.len(),
-        "players": players
-    });
+    let count = players.len();
+    let json_response = ApiResponse::new(PlayerList { count, players });
 
-    Ok(Json(json_response))
+    Ok(json_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/players/{id}",
+    tag = "players",
+    summary = "Get a player",
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already has; a current one gets a 304"),
+    ),
+    responses(
+        (status = 200, description = "The player", body = ApiResponse<PlayerData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 304, description = "If-None-Match matches the current version", headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Player not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_player_handler(
     Path(player_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(
-        PlayerModel,
-        r#"SELECT * FROM players WHERE id = $1"#,
-        &player_id
-    )
-    .fetch_one(&data.db)
-    .await;
-
-    match query_result {
-        Ok(player) => {
-            let player_response = serde_json::json!({
-                "status" : "success",
-                "data": serde_json::json!({
-                    "player": player
-                })
-            });
-
-            Ok(Json(player_response))
-        }
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = serde_json::json!({
-                "status": "fail",
-                "message": format!("Player with ID: {} not found", player_id)
-            });
-            Err((StatusCode::NOT_FOUND, Json(error_response)))
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let player = data.players.get(player_id).await.map_err(|e| match e {
+        RepoError::NotFound => {
+            ApiError::not_found(format!("Player with ID: {} not found", player_id))
         }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "error", "message": format!("{:?}", e)})),
-        )),
+        e => repo_error(e),
+    })?;
+
+    let etag = entity_tag(player.version);
+    if is_not_modified(&headers, player.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
     }
+
+    let player_response = ApiResponse::new(PlayerData { player });
+
+    Ok(([(ETAG, etag)], player_response).into_response())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/players/{id}",
+    tag = "players",
+    summary = "Move a player to the trash",
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Player moved to trash", body = ApiResponse<DeletedPlayerData>),
+        (status = 404, description = "Player not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
 pub async fn delete_player_handler(
     Path(player_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(
-        PlayerModel,
-        r#"DELETE FROM players WHERE id = $1 RETURNING *"#,
-        &player_id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => (
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "status": "error",
-                "message": "Player not found"
-            })),
-        ),
-        _ => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
-        ),
-    })?;
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let deleted_player =
+        data.players
+            .trash(player_id, &precondition)
+            .await
+            .map_err(|e| match e {
+                RepoError::NotFound => ApiError::not_found("Player not found"),
+                e => repo_error(e),
+            })?;
 
-    let response = json!({
-        "status": "success",
-        "message": "Player deleted successfully",
-        "data": {
-            "deleted_player" : query_result
-        }
-    });
+    let response = ApiResponse::with_message(
+        "Player moved to trash",
+        DeletedPlayerData { deleted_player },
+    );
 
-    Ok(Json(response))
+    Ok(response)
 }
 
+#[utoipa::path(
+    patch,
+    path = "/players/{id}",
+    tag = "players",
+    summary = "Update a player",
+    request_body(content = UpdatePlayerSchema, content_type = "application/merge-patch+json"),
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Player updated", body = ApiResponse<PlayerData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Player not found", body = ErrorResponse),
+        (status = 409, description = "Name taken or an owner already exists", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
 pub async fn update_player_handler(
     Path(id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
-    Json(body): Json<PlayerSchema>,
-) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(PlayerModel, r#"SELECT * FROM players WHERE id = $1"#, &id)
-        .fetch_one(&data.db)
-        .await;
+    headers: HeaderMap,
+    MergePatch(body): MergePatch<UpdatePlayerSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let updated_player = data
+        .players
+        .update(id, &body, &precondition)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => ApiError::not_found(format!("Player with ID: {} not found", id)),
+            e => player_error(e),
+        })?;
+
+    let etag = entity_tag(updated_player.version);
+    let response = ApiResponse::new(PlayerData {
+        player: updated_player,
+    });
+    Ok(([(ETAG, etag)], response))
+}
+
+/// Merges the player at `player_id` into `body.target_id`. Participations are
+/// re-pointed at the target; plays both players took part in keep only the
+/// target's row. Ownership moves to the target if the source was the owner.
+#[utoipa::path(
+    post,
+    path = "/players/{id}/merge",
+    tag = "players",
+    summary = "Merge a duplicate player into another",
+    request_body = MergeSchema,
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+    ),
+    responses(
+        (status = 200, description = "Player merged", body = ApiResponse<MergedPlayerData>),
+        (status = 400, description = "Player merged into itself", body = ErrorResponse),
+        (status = 404, description = "Player not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn merge_player_handler(
+    Path(player_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<MergeSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    if player_id == body.target_id {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "A player cannot be merged into itself",
+        ));
+    }
+
+    let not_found = |id: Uuid| ApiError::not_found(format!("Player with ID: {} not found", id));
+
+    let merged = data
+        .players
+        .merge(player_id, body.target_id)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => not_found(player_id),
+            RepoError::MissingReferences(_) => not_found(body.target_id),
+            e => repo_error(e),
+        })?;
+
+    let response = ApiResponse::with_message("Player merged successfully", merged);
+
+    Ok(response)
+}
+
+/// Makes `player_id` the owner, taking ownership away from the current owner
+/// in the same transaction so `uniq_single_owner` is never violated.
+#[utoipa::path(
+    post,
+    path = "/players/{id}/make-owner",
+    tag = "players",
+    summary = "Transfer ownership to a player",
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+    ),
+    responses(
+        (status = 200, description = "Ownership transferred", body = ApiResponse<OwnerData>),
+        (status = 404, description = "Player not found", body = ErrorResponse),
+    )
+)]
+pub async fn make_owner_handler(
+    Path(player_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let owner = data
+        .players
+        .make_owner(player_id)
+        .await
+        .map_err(|e| match e {
+            RepoError::NotFound => {
+                ApiError::not_found(format!("Player with ID: {} not found", player_id))
+            }
+            e => repo_error(e),
+        })?;
+
+    let response = ApiResponse::new(owner);
+
+    Ok(response)
+}
 
-    let _player = match query_result {
-        Ok(player) => player,
-        Err(sqlx::Error::RowNotFound) => {
-            let error_response = serde_json::json!({
-                "status": "error",
-                "message": format!("Player with ID: {} not found", id)
-            });
-            return Err((StatusCode::NOT_FOUND, Json(error_response)));
+/// Takes a player, and with it their participations, back out of the trash
+#[utoipa::path(
+    post,
+    path = "/players/{id}/restore",
+    tag = "trash",
+    summary = "Restore a player from the trash",
+    params(
+        ("id" = Uuid, Path, description = "Player ID"),
+    ),
+    responses(
+        (status = 200, description = "Player restored", body = ApiResponse<PlayerData>),
+        (status = 404, description = "Player not found in trash", body = ErrorResponse),
+        (status = 409, description = "Name taken or an owner already exists", body = ErrorResponse),
+    )
+)]
+pub async fn restore_player_handler(
+    Path(player_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let player = data.players.restore(player_id).await.map_err(|e| match e {
+        RepoError::NotFound => {
+            ApiError::not_found(format!("Player with ID: {} not found in trash", player_id))
         }
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "status": "error",
-                    "message": format!("{:?}",e)
-                })),
-            ));
+        e => player_error(e),
+    })?;
+
+    let response = ApiResponse::with_message("Player restored from trash", PlayerData { player });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{handlers::response_json, repository::memory::MemoryRepository, schema::BulkMode};
+
+    fn player(name: &str, is_owner: bool) -> PlayerSchema {
+        PlayerSchema {
+            name: name.to_string(),
+            is_owner: Some(is_owner),
         }
-    };
+    }
 
-    let new_name = &body.name;
+    #[tokio::test]
+    async fn bulk_create_follows_the_mode() {
+        let data = MemoryRepository::app_state();
+        let items = || BulkSchema {
+            items: vec![json!({"name": "Ann"}), json!({"name": " ann "}), json!({})],
+        };
 
-    let updated_player = sqlx::query_as!(
-        PlayerModel,
-        r#"UPDATE players SET name = $1 WHERE id = $2 RETURNING *"#,
-        &new_name,
-        &id
-    )
-    .fetch_one(&data.db)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "error",
-                "message": format!("{:?}", e)
-            })),
+        let query = BulkQuery {
+            mode: BulkMode::Atomic,
+        };
+        let create =
+            bulk_create_player_handler(Query(query), State(data.clone()), ValidatedJson(items()));
+        let (status, body) = response_json(create.await).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+        assert!(data.players.list().await.unwrap().is_empty());
+
+        let query = BulkQuery {
+            mode: BulkMode::PerItem,
+        };
+        let create =
+            bulk_create_player_handler(Query(query), State(data.clone()), ValidatedJson(items()));
+        let (status, body) = response_json(create.await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["succeeded"], 1);
+        assert_eq!(body["data"]["results"][1]["code"], 409);
+        assert_eq!(body["data"]["results"][2]["code"], 422);
+        assert_eq!(body["data"]["results"][2]["errors"][0]["field"], "name");
+    }
+
+    #[tokio::test]
+    async fn make_owner_takes_ownership_from_the_current_owner() {
+        let data = MemoryRepository::app_state();
+        let ann = data.players.create(&player("Ann", true)).await.unwrap();
+        let bob = data.players.create(&player("Bob", false)).await.unwrap();
+
+        let (status, _) = response_json(
+            create_player_handler(State(data.clone()), ValidatedJson(player("Cid", true))).await,
         )
-    })?;
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
 
-    let response = json!({
-        "status": "success",
-        "data": json!({
-            "player": updated_player
-        })
-    });
-    Ok(Json(response))
+        let (status, body) =
+            response_json(make_owner_handler(Path(bob.id), State(data.clone())).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["previous_owner"]["id"], json!(ann.id));
+        assert_eq!(data.players.owner().await.unwrap().unwrap().id, bob.id);
+    }
 }
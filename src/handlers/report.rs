@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    extract::{Path, Query},
+    milestones::{end_of, milestones},
+    report::{render_html, render_markdown, year_report, year_start},
+    response::{ApiError, ApiResponse, ErrorResponse, MilestonesData, YearReportData},
+    schema::{MilestonesQuery, ReportFormat, ReportQuery},
+    validation::field_error_response,
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    get,
+    path = "/reports/year/{year}",
+    tag = "reports",
+    summary = "Year in review",
+    description = "What the live plays of a calendar year, in UTC, add up to. `format=markdown` \
+        and `format=html` render the report to share instead.",
+    params(
+        ("year" = i32, Path, description = "The year, like 2024"),
+        ReportQuery,
+    ),
+    responses(
+        (status = 200, description = "The report", body = ApiResponse<YearReportData>),
+        (status = 422, description = "Not a year between 1 and 9999", body = ErrorResponse),
+    )
+)]
+pub async fn year_report_handler(
+    Path(year): Path<i32>,
+    Query(query): Query<ReportQuery>,
+    State(data): State<Arc<AppState>>,
+) -> Result<Response, ApiError> {
+    if !(1..=9999).contains(&year) {
+        return Err(field_error_response("year", "must be between 1 and 9999"));
+    }
+
+    let tables = data
+        .stats
+        .year_plays(year_start(year), year_start(year + 1))
+        .await
+        .map_err(repo_error)?;
+    let report = year_report(&tables, year);
+
+    let response = match query.format {
+        ReportFormat::Json => ApiResponse::new(YearReportData { report }).into_response(),
+        ReportFormat::Markdown => (
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            render_markdown(&report),
+        )
+            .into_response(),
+        ReportFormat::Html => (
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            render_html(&report),
+        )
+            .into_response(),
+    };
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/milestones",
+    tag = "reports",
+    summary = "Play count milestones",
+    description = "The games played at least 5, 10 and 25 times (nickels, dimes and quarters), \
+        the milestones reached this month and the h-indexes of games and players, counting \
+        the live plays up to `as_of`.",
+    params(
+        MilestonesQuery,
+    ),
+    responses(
+        (status = 200, description = "The milestones", body = ApiResponse<MilestonesData>),
+    )
+)]
+pub async fn milestones_handler(
+    Query(query): Query<MilestonesQuery>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let as_of = query
+        .as_of
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let tables = data
+        .stats
+        .plays_until(end_of(as_of))
+        .await
+        .map_err(repo_error)?;
+
+    Ok(ApiResponse::new(milestones(&tables, as_of)))
+}
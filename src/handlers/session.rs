@@ -0,0 +1,360 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode, header::ETAG},
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    etag::{entity_tag, if_match_allows, is_not_modified},
+    extract::{MergePatch, Path, Query, ValidatedJson},
+    repository::RepoError,
+    response::{
+        ApiError, ApiResponse, AttendanceList, DeletedSessionData, ErrorResponse, SessionData,
+        SessionList, SessionSummaryData,
+    },
+    schema::{SessionSchema, SessionSummaryQuery, UpdateSessionSchema},
+    session::{attendance, session_summary},
+};
+
+use super::{missing_references, repo_error};
+
+/// Answers a session that does not exist, or one whose host or attendees are
+/// missing or trashed
+fn session_error(e: RepoError, id: Uuid) -> ApiError {
+    match e {
+        RepoError::NotFound => ApiError::not_found(format!("Session with ID: {} not found", id)),
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        e => repo_error(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions",
+    tag = "sessions",
+    summary = "Start a game night",
+    description = "The host counts as an attendee whether listed or not. Plays join the \
+        session through their `session_id`.",
+    request_body = SessionSchema,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a create request is retried"),
+    ),
+    responses(
+        (status = 200, description = "Session created", body = ApiResponse<SessionData>),
+        (status = 422, description = "Invalid body or a host or attendee that does not exist", body = ErrorResponse),
+    )
+)]
+pub async fn create_session_handler(
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<SessionSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let session = data.sessions.create(&body).await.map_err(|e| match e {
+        RepoError::MissingReferences(fields) => missing_references(&fields),
+        e => repo_error(e),
+    })?;
+
+    Ok(ApiResponse::new(SessionData { session }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    tag = "sessions",
+    summary = "List game nights",
+    responses(
+        (status = 200, description = "Sessions, most recent first", body = ApiResponse<SessionList>),
+    )
+)]
+pub async fn session_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sessions = data.sessions.list().await.map_err(repo_error)?;
+
+    Ok(ApiResponse::new(SessionList {
+        count: sessions.len(),
+        sessions,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}",
+    tag = "sessions",
+    summary = "Get a game night",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already has; a current one gets a 304"),
+    ),
+    responses(
+        (status = 200, description = "The session", body = ApiResponse<SessionData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 304, description = "If-None-Match matches the current version", headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_session_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let session = data
+        .sessions
+        .get(id)
+        .await
+        .map_err(|e| session_error(e, id))?;
+
+    let etag = entity_tag(session.version);
+    if is_not_modified(&headers, session.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+    }
+
+    Ok(([(ETAG, etag)], ApiResponse::new(SessionData { session })).into_response())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/sessions/{id}",
+    tag = "sessions",
+    summary = "Update a game night",
+    description = "`attendee_ids` replaces the attendees; the host stays one of them.",
+    request_body(content = UpdateSessionSchema, content_type = "application/merge-patch+json"),
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Session updated", body = ApiResponse<SessionData>, headers(("ETag" = String, description = "Current version of the resource"))),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+        (status = 422, description = "Invalid body or a host or attendee that does not exist", body = ErrorResponse),
+    )
+)]
+pub async fn update_session_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+    MergePatch(body): MergePatch<UpdateSessionSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let session = data
+        .sessions
+        .update(id, &body, &precondition)
+        .await
+        .map_err(|e| session_error(e, id))?;
+
+    let etag = entity_tag(session.version);
+    Ok(([(ETAG, etag)], ApiResponse::new(SessionData { session })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/{id}",
+    tag = "sessions",
+    summary = "Delete a game night",
+    description = "Deletes the session for good. Its plays are kept, without a session.",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the change is based on; a stale one gets a 412"),
+    ),
+    responses(
+        (status = 200, description = "Session deleted", body = ApiResponse<DeletedSessionData>),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorResponse),
+    )
+)]
+pub async fn delete_session_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let precondition = |version| if_match_allows(&headers, version);
+    let deleted_session = data
+        .sessions
+        .delete(id, &precondition)
+        .await
+        .map_err(|e| session_error(e, id))?;
+
+    Ok(ApiResponse::with_message(
+        "Session deleted successfully",
+        DeletedSessionData { deleted_session },
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}/summary",
+    tag = "sessions",
+    summary = "Summarize a game night",
+    description = "The games played at the session, and a standing for every attendee and \
+        participant. The night goes to whoever won the most plays, or with `by=points` scored \
+        the most points in total; tied players share it.",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        SessionSummaryQuery,
+    ),
+    responses(
+        (status = 200, description = "The summary", body = ApiResponse<SessionSummaryData>),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+    )
+)]
+pub async fn session_summary_handler(
+    Path(id): Path<Uuid>,
+    Query(query): Query<SessionSummaryQuery>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut tables = data
+        .stats
+        .session_plays(id)
+        .await
+        .map_err(|e| session_error(e, id))?;
+    let session = tables.sessions.remove(0);
+
+    Ok(ApiResponse::new(SessionSummaryData {
+        summary: session_summary(&tables, session, query.by),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/attendance",
+    tag = "sessions",
+    summary = "Game night attendance",
+    description = "How many sessions each player attended and hosted, their share of all \
+        sessions and the plays they took part in at them.",
+    responses(
+        (status = 200, description = "Players who attended a session, most sessions first", body = ApiResponse<AttendanceList>),
+    )
+)]
+pub async fn attendance_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tables = data.stats.session_attendance().await.map_err(repo_error)?;
+
+    Ok(ApiResponse::new(attendance(&tables)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{
+        handlers::response_json,
+        repository::memory::MemoryRepository,
+        schema::{GameSchema, PlayParticipantSchema, PlaySchema, PlayerSchema},
+    };
+
+    fn night(host_id: Option<Uuid>) -> SessionSchema {
+        SessionSchema {
+            played_on: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            location: None,
+            host_id,
+            attendee_ids: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_missing_host_is_a_validation_error() {
+        let data = MemoryRepository::app_state();
+
+        let create =
+            create_session_handler(State(data), ValidatedJson(night(Some(Uuid::new_v4()))));
+        let (status, body) = response_json(create.await).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body.to_string().contains("host_id"));
+    }
+
+    #[tokio::test]
+    async fn deleting_a_session_keeps_its_plays() {
+        let data = MemoryRepository::app_state();
+        let session = data.sessions.create(&night(None)).await.unwrap();
+        let game = GameSchema {
+            name: "Azul".to_string(),
+            creator_name: "Michael Kiesling".to_string(),
+            ..Default::default()
+        };
+        let game = data.games.create(&game).await.unwrap();
+        let play = PlaySchema {
+            game_id: game.id,
+            session_id: Some(session.id),
+            ..Default::default()
+        };
+        let play = data.plays.create(&play).await.unwrap();
+
+        let delete =
+            delete_session_handler(Path(session.id), State(data.clone()), HeaderMap::new());
+        let (status, _) = response_json(delete.await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let play = data.plays.get(play.id).await.unwrap();
+        assert_eq!((play.session_id, play.version), (None, 2));
+        let summary = session_summary_handler(
+            Path(session.id),
+            Query(SessionSummaryQuery {
+                by: Default::default(),
+            }),
+            State(data),
+        );
+        let (status, _) = response_json(summary.await).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn a_summary_names_attendees_who_did_not_play() {
+        let data = MemoryRepository::app_state();
+        let mut players = Vec::new();
+        for name in ["Ann", "Bob"] {
+            let player = PlayerSchema {
+                name: name.to_string(),
+                is_owner: None,
+            };
+            players.push(data.players.create(&player).await.unwrap());
+        }
+        let session = data
+            .sessions
+            .create(&night(Some(players[0].id)))
+            .await
+            .unwrap();
+        let game = GameSchema {
+            name: "Azul".to_string(),
+            creator_name: "Michael Kiesling".to_string(),
+            ..Default::default()
+        };
+        let game = data.games.create(&game).await.unwrap();
+        let play = PlaySchema {
+            game_id: game.id,
+            session_id: Some(session.id),
+            ..Default::default()
+        };
+        let play = data.plays.create(&play).await.unwrap();
+        let bob = PlayParticipantSchema {
+            play_id: play.id,
+            player_id: players[1].id,
+            score: None,
+            is_winner: Some(true),
+        };
+        data.participants.create(&bob).await.unwrap();
+
+        let summary = session_summary_handler(
+            Path(session.id),
+            Query(SessionSummaryQuery {
+                by: Default::default(),
+            }),
+            State(data),
+        );
+        let (status, body) = response_json(summary.await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let names: Vec<_> = body["data"]["summary"]["standings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|standing| standing["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Bob", "Ann"]);
+    }
+}
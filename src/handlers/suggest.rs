@@ -0,0 +1,75 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{extract::State, response::IntoResponse};
+
+use crate::{
+    AppState,
+    extract::ValidatedJson,
+    response::{ApiError, ApiResponse, ErrorResponse, SuggestionList},
+    schema::SuggestSchema,
+    suggest::suggest,
+    validation::field_error_response,
+};
+
+use super::repo_error;
+
+/// Games suggested when the request does not say how many
+const DEFAULT_SUGGESTIONS: usize = 10;
+
+#[utoipa::path(
+    post,
+    path = "/suggest",
+    tag = "games",
+    summary = "Suggest games to play",
+    description = "Ranks the live games that support the number of players and fit in `minutes`. \
+        Games none of the players has played for a while, games some of them have never played \
+        and games they have won about equally often rank higher. Player counts and playtimes \
+        come from `POST /api/v1/import/bgg/games`; a game without them is assumed to fit.",
+    request_body = SuggestSchema,
+    responses(
+        (status = 200, description = "Best suggestion first", body = ApiResponse<SuggestionList>),
+        (status = 422, description = "Invalid body or a player that does not exist", body = ErrorResponse),
+    )
+)]
+pub async fn suggest_handler(
+    State(data): State<Arc<AppState>>,
+    ValidatedJson(body): ValidatedJson<SuggestSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tables = data
+        .stats
+        .player_history(&body.player_ids)
+        .await
+        .map_err(repo_error)?;
+
+    let live: HashSet<_> = tables
+        .players
+        .iter()
+        .filter(|player| player.deleted_at.is_none())
+        .map(|player| player.id)
+        .collect();
+    let mut players = Vec::new();
+    for id in body.player_ids {
+        if !live.contains(&id) {
+            return Err(field_error_response(
+                "player_ids",
+                &format!("player {} not found", id),
+            ));
+        }
+        if !players.contains(&id) {
+            players.push(id);
+        }
+    }
+
+    let suggestions = suggest(
+        &tables,
+        &players,
+        body.minutes,
+        body.limit.unwrap_or(DEFAULT_SUGGESTIONS),
+        chrono::Utc::now(),
+    );
+
+    Ok(ApiResponse::new(SuggestionList {
+        count: suggestions.len(),
+        suggestions,
+    }))
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse};
+
+use crate::{
+    AppState,
+    extract::Query,
+    response::{ApiError, ApiResponse, ErrorResponse, PurgedData, TrashList},
+    schema::PurgeTrashQuery,
+    validation::field_error_response,
+};
+
+use super::repo_error;
+
+#[utoipa::path(
+    get,
+    path = "/trash",
+    tag = "trash",
+    summary = "List the trash",
+    responses(
+        (status = 200, description = "Trashed rows, most recently deleted first", body = ApiResponse<TrashList>),
+    )
+)]
+pub async fn trash_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let games = data.games.list_trashed().await.map_err(repo_error)?;
+    let players = data.players.list_trashed().await.map_err(repo_error)?;
+    let plays = data.plays.list_trashed().await.map_err(repo_error)?;
+
+    let json_response = ApiResponse::new(TrashList {
+        count: games.len() + players.len() + plays.len(),
+        retention_days: data.trash_retention_days,
+        games,
+        players,
+        plays,
+    });
+
+    Ok(json_response)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/trash",
+    tag = "trash",
+    summary = "Purge the trash",
+    params(
+        PurgeTrashQuery,
+    ),
+    responses(
+        (status = 200, description = "Trash purged", body = ApiResponse<PurgedData>),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn purge_trash_handler(
+    Query(query): Query<PurgeTrashQuery>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let older_than_days = query.older_than_days.unwrap_or(data.trash_retention_days);
+    let cutoff = chrono::TimeDelta::try_days(older_than_days)
+        .and_then(|age| chrono::Utc::now().checked_sub_signed(age))
+        .ok_or_else(|| field_error_response("older_than_days", "is too far in the past"))?;
+
+    let purged = data.trash.purge(cutoff).await.map_err(repo_error)?;
+
+    let response = ApiResponse::with_message("Trash purged", PurgedData { purged });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+    use crate::{handlers::response_json, repository::memory::MemoryRepository};
+
+    #[tokio::test]
+    async fn purge_refuses_a_cutoff_before_any_date() {
+        let query = PurgeTrashQuery {
+            older_than_days: Some(i64::MAX),
+        };
+        let purge = purge_trash_handler(Query(query), State(MemoryRepository::app_state()));
+        let (status, body) = response_json(purge.await).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "older_than_days");
+    }
+}
@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use axum::{
+    RequestExt,
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header::CONTENT_TYPE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{AppState, actor, response::ApiError};
+
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Set on responses replayed from an earlier request with the same key
+const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+/// Longest key we accept, in bytes
+const KEY_MAX_LEN: usize = 255;
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    ApiError::new(status, message).into_response()
+}
+
+/// Hashes a request's query string and body. JSON bodies are hashed in
+/// canonical form, so a retry that only differs in whitespace or member order
+/// counts as the same request.
+fn request_hash(query: Option<&str>, body: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(query.unwrap_or_default());
+    hasher.update([0]);
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(value) => hasher.update(value.to_string()),
+        Err(_) => hasher.update(body),
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Middleware for create and import endpoints honouring an `Idempotency-Key`
+/// header. The body is read within the route's `DefaultBodyLimit`, so it has
+/// to be layered inside that limit.
+///
+/// The first request with a key claims it and its response is stored. Within
+/// `IDEMPOTENCY_RETENTION_HOURS` a retry with the same key and body gets the
+/// stored response back, the same key with another body gets a 422 and a retry
+/// while the first request is still running gets a 409. Server errors are not
+/// stored, so the request can simply be retried. Requests without the header
+/// are passed through untouched.
+pub async fn idempotency(
+    State(data): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= KEY_MAX_LEN => key.to_string(),
+        _ => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "Idempotency-Key must be 1 to 255 visible ASCII characters",
+            );
+        }
+    };
+
+    let (parts, body) = request.with_limited_body().into_parts();
+    let path = parts.uri.path().to_string();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"),
+    };
+    let request_hash = request_hash(parts.uri.query(), &body);
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(data.idempotency_retention_hours);
+
+    // Claims the key, taking over an entry that has outlived the retention
+    let claimed = data
+        .idempotency
+        .claim(&key, &path, &request_hash, cutoff)
+        .await;
+
+    match claimed {
+        Ok(true) => {}
+        Ok(false) => return replay(&data, &key, &path, &request_hash).await,
+        Err(e) => {
+            println!("Failed to claim Idempotency-Key {}: {:?}", key, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error");
+        }
+    }
+
+    // Run in a task of its own, so the response is stored even when the
+    // client goes away and this future is dropped
+    let request = Request::from_parts(parts, Body::from(body));
+    let run = actor::scope(
+        actor::current(),
+        run_and_store(data.clone(), key.clone(), path.clone(), request, next),
+    );
+    match tokio::spawn(run).await {
+        Ok(response) => response,
+        Err(e) => {
+            println!("Request with Idempotency-Key {} failed: {:?}", key, e);
+            if let Err(e) = data.idempotency.release(&key, &path).await {
+                println!("Failed to release Idempotency-Key {}: {:?}", key, e);
+            }
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+        }
+    }
+}
+
+/// Runs a request whose key was claimed and stores its response, or releases
+/// the key after a server error
+async fn run_and_store(
+    data: Arc<AppState>,
+    key: String,
+    path: String,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            println!(
+                "Failed to read response for Idempotency-Key {}: {:?}",
+                key, e
+            );
+            if let Err(e) = data.idempotency.release(&key, &path).await {
+                println!("Failed to release Idempotency-Key {}: {:?}", key, e);
+            }
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error");
+        }
+    };
+
+    let stored = if parts.status.is_server_error() {
+        data.idempotency.release(&key, &path).await
+    } else {
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        data.idempotency
+            .store(
+                &key,
+                &path,
+                parts.status.as_u16() as i16,
+                content_type,
+                &body,
+            )
+            .await
+    };
+
+    if let Err(e) = stored {
+        println!(
+            "Failed to store response for Idempotency-Key {}: {:?}",
+            key, e
+        );
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Answers a request whose key has already been claimed
+async fn replay(data: &AppState, key: &str, path: &str, request_hash: &[u8]) -> Response {
+    let stored = data.idempotency.get(key, path).await;
+
+    let stored = match stored {
+        Ok(stored) => stored,
+        Err(e) => {
+            println!("Failed to look up Idempotency-Key {}: {:?}", key, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error");
+        }
+    };
+
+    if stored.request_hash != request_hash {
+        return error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Idempotency-Key was already used for a different request",
+        );
+    }
+
+    let (Some(status_code), Some(response_body)) = (stored.status_code, stored.response_body)
+    else {
+        return error_response(
+            StatusCode::CONFLICT,
+            "A request with this Idempotency-Key is still being processed",
+        );
+    };
+
+    let status = StatusCode::from_u16(status_code as u16).unwrap_or(StatusCode::OK);
+    let mut response = Response::new(Body::from(response_body));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|content_type| HeaderValue::from_str(&content_type).ok())
+    {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use axum::{Router, middleware, routing::post};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::repository::memory::MemoryRepository;
+
+    #[tokio::test]
+    async fn a_dropped_request_still_stores_its_response() {
+        let data = MemoryRepository::app_state();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let runs = runs.clone();
+            move || async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                runs.fetch_add(1, Ordering::SeqCst);
+                StatusCode::CREATED
+            }
+        };
+        let router = Router::new()
+            .route("/games", post(handler))
+            .layer(middleware::from_fn_with_state(data.clone(), idempotency))
+            .with_state(data);
+        let request = || {
+            Request::post("/games")
+                .header(IDEMPOTENCY_KEY, "flaky")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+
+        // The client gives up before the handler is done
+        let dropped =
+            tokio::time::timeout(Duration::from_millis(20), router.clone().oneshot(request()));
+        assert!(dropped.await.is_err());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let retry = router.oneshot(request()).await.unwrap();
+        assert_eq!(retry.status(), StatusCode::CREATED);
+        assert!(retry.headers().contains_key(IDEMPOTENT_REPLAYED));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+}
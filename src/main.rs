@@ -1,42 +1,226 @@
 use std::sync::Arc;
 
 use dotenv::dotenv;
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::postgres::PgPoolOptions;
 
-use crate::route::create_router;
+use crate::{
+    repository::{
+        BackupRepository, EventRepository, GameRepository, HistoryRepository,
+        IdempotencyRepository, ParticipantRepository, PlayRepository, PlayerRepository,
+        SessionRepository, StatsRepository, TrashRepository,
+        postgres::{PgRepository, set_actor},
+    },
+    route::create_router,
+    validation::RETENTION_MAX_DAYS,
+};
 
+mod actor;
+mod backup;
+mod bgg;
+mod bgstats;
+mod calendar;
+mod etag;
+mod extract;
 mod handlers;
+mod idempotency;
+mod milestones;
 mod model;
+mod openapi;
+mod play_csv;
+mod report;
+mod repository;
+mod response;
 mod route;
 mod schema;
+mod session;
+mod suggest;
+mod validation;
+
+/// How often trashed rows and idempotency keys older than their retention are
+/// purged
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
 pub struct AppState {
-    db: PgPool,
+    games: Arc<dyn GameRepository>,
+    players: Arc<dyn PlayerRepository>,
+    plays: Arc<dyn PlayRepository>,
+    participants: Arc<dyn ParticipantRepository>,
+    sessions: Arc<dyn SessionRepository>,
+    events: Arc<dyn EventRepository>,
+    history: Arc<dyn HistoryRepository>,
+    idempotency: Arc<dyn IdempotencyRepository>,
+    trash: Arc<dyn TrashRepository>,
+    backup: Arc<dyn BackupRepository>,
+    stats: Arc<dyn StatsRepository>,
+    /// Days a deleted row stays in the trash before it is purged
+    trash_retention_days: i64,
+    /// Hours a stored response is replayed for a repeated Idempotency-Key
+    idempotency_retention_hours: i64,
+}
+
+impl AppState {
+    /// State whose repositories are all served by `repository`
+    fn new<R>(
+        repository: Arc<R>,
+        trash_retention_days: i64,
+        idempotency_retention_hours: i64,
+    ) -> Self
+    where
+        R: GameRepository
+            + PlayerRepository
+            + PlayRepository
+            + ParticipantRepository
+            + SessionRepository
+            + EventRepository
+            + HistoryRepository
+            + IdempotencyRepository
+            + TrashRepository
+            + BackupRepository
+            + StatsRepository
+            + 'static,
+    {
+        AppState {
+            games: repository.clone(),
+            players: repository.clone(),
+            plays: repository.clone(),
+            participants: repository.clone(),
+            sessions: repository.clone(),
+            events: repository.clone(),
+            history: repository.clone(),
+            idempotency: repository.clone(),
+            trash: repository.clone(),
+            backup: repository.clone(),
+            stats: repository,
+            trash_retention_days,
+            idempotency_retention_hours,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
     let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = match PgPoolOptions::new()
+
+    let trash_retention_days =
+        retention_from_env("TRASH_RETENTION_DAYS", "days", 30, RETENTION_MAX_DAYS);
+    let idempotency_retention_hours = retention_from_env(
+        "IDEMPOTENCY_RETENTION_HOURS",
+        "hours",
+        24,
+        RETENTION_MAX_DAYS * 24,
+    );
+
+    let state = Arc::new(connect(&db_url, trash_retention_days, idempotency_retention_hours).await);
+
+    tokio::spawn(purge_trash_periodically(
+        state.trash.clone(),
+        trash_retention_days,
+    ));
+    tokio::spawn(purge_idempotency_keys_periodically(
+        state.idempotency.clone(),
+        idempotency_retention_hours,
+    ));
+
+    let app = create_router(state);
+
+    // run our app with hyper, listening globally on port 3000
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
+    println!("Server started successfully at 0.0.0.0:3000");
+    axum::serve(listener, app).await.unwrap();
+}
+
+/// Connects to the database `db_url` points at. `sqlite:` URLs need the
+/// `sqlite` feature, anything else is taken to be Postgres.
+async fn connect(
+    db_url: &str,
+    trash_retention_days: i64,
+    idempotency_retention_hours: i64,
+) -> AppState {
+    #[cfg(feature = "sqlite")]
+    if db_url.starts_with("sqlite:") {
+        return match repository::sqlite::SqliteRepository::connect(db_url).await {
+            Ok(repository) => {
+                println!("Connected to DB successfully");
+                AppState::new(
+                    Arc::new(repository),
+                    trash_retention_days,
+                    idempotency_retention_hours,
+                )
+            }
+            Err(err) => {
+                println!("Failed to connect to DB: {}", err);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    match PgPoolOptions::new()
         .max_connections(10)
-        .connect(&db_url)
+        .after_connect(|conn, _| Box::pin(set_actor(conn)))
+        .before_acquire(|conn, _| Box::pin(async move { set_actor(conn).await.map(|_| true) }))
+        .connect(db_url)
         .await
     {
         Ok(pool) => {
             println!("Connected to DB successfully");
-            pool
+            AppState::new(
+                Arc::new(PgRepository::new(pool)),
+                trash_retention_days,
+                idempotency_retention_hours,
+            )
         }
         Err(err) => {
             println!("Failed to connect to DB: {}", err);
             std::process::exit(1);
         }
+    }
+}
+
+/// Reads a retention from the environment, refusing negative ones and ones
+/// too long to subtract from the current time
+fn retention_from_env(name: &str, unit: &str, default: i64, max: i64) -> i64 {
+    let retention = match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number of {}", name, unit)),
+        Err(_) => default,
     };
+    if !(0..=max).contains(&retention) {
+        panic!("{} must be between 0 and {} {}", name, max, unit);
+    }
+    retention
+}
 
-    let app = create_router(Arc::new(AppState { db: pool.clone() }));
+async fn purge_trash_periodically(trash: Arc<dyn TrashRepository>, retention_days: i64) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days);
+        match trash.purge(cutoff).await {
+            Ok(purged) => println!(
+                "Purged trash older than {} days: {:?}",
+                retention_days, purged
+            ),
+            Err(err) => println!("Failed to purge trash: {:?}", err),
+        }
+    }
+}
 
-    // run our app with hyper, listening globally on port 3000
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    println!("Server started successfully at 0.0.0.0:3000");
-    axum::serve(listener, app).await.unwrap();
+async fn purge_idempotency_keys_periodically(
+    idempotency: Arc<dyn IdempotencyRepository>,
+    retention_hours: i64,
+) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = chrono::Utc::now() - chrono::Duration::hours(retention_hours);
+        match idempotency.purge(cutoff).await {
+            Ok(purged) => println!(
+                "Purged {} idempotency keys older than {} hours",
+                purged, retention_hours
+            ),
+            Err(err) => println!("Failed to purge idempotency keys: {:?}", err),
+        }
+    }
 }
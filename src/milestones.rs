@@ -0,0 +1,163 @@
+//! Play count milestones: the games played 5, 10 and 25 times, known as
+//! nickels, dimes and quarters, and the h-indexes of the collection.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use crate::{
+    model::BackupTables,
+    report::h_index,
+    response::{Milestone, MilestoneGame, MilestonesData, NewMilestone},
+};
+
+/// Plays needed for each milestone, smallest first
+pub const MILESTONES: [(usize, &str); 3] = [(5, "nickel"), (10, "dime"), (25, "quarter")];
+
+/// When `as_of` ends, in UTC
+pub fn end_of(as_of: NaiveDate) -> DateTime<Utc> {
+    as_of.succ_opt().map_or(DateTime::<Utc>::MAX_UTC, |next| {
+        next.and_time(Default::default()).and_utc()
+    })
+}
+
+/// The milestones as they stood at the end of `as_of`, counting live plays
+pub fn milestones(tables: &BackupTables, as_of: NaiveDate) -> MilestonesData {
+    let end = end_of(as_of);
+    let month_start = as_of
+        .with_day(1)
+        .unwrap_or(as_of)
+        .and_time(Default::default())
+        .and_utc();
+    let names: HashMap<_, _> = tables
+        .games
+        .iter()
+        .map(|game| (game.id, game.name.as_str()))
+        .collect();
+
+    let mut plays: Vec<_> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none() && play.created_at < end)
+        .collect();
+    plays.sort_by_key(|play| (play.created_at, play.id));
+
+    // When each game was played, in order
+    let mut played: HashMap<_, Vec<_>> = HashMap::new();
+    for play in &plays {
+        played
+            .entry(play.game_id)
+            .or_default()
+            .push(play.created_at);
+    }
+    let mut seated: HashMap<_, usize> = HashMap::new();
+    let counted: HashSet<_> = plays.iter().map(|play| play.id).collect();
+    for participant in &tables.play_participants {
+        if counted.contains(&participant.play_id) {
+            *seated.entry(participant.player_id).or_default() += 1;
+        }
+    }
+
+    let name = |game_id| names.get(game_id).copied().unwrap_or_default().to_string();
+    let mut new_this_month = Vec::new();
+    let milestones = MILESTONES
+        .iter()
+        .map(|&(count, milestone)| {
+            let mut games: Vec<_> = played
+                .iter()
+                .filter(|(_, dates)| dates.len() >= count)
+                .map(|(game_id, dates)| MilestoneGame {
+                    game_id: *game_id,
+                    name: name(game_id),
+                    plays: dates.len(),
+                    reached_at: dates[count - 1],
+                })
+                .collect();
+            games.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.name.cmp(&b.name)));
+
+            new_this_month.extend(
+                games
+                    .iter()
+                    .filter(|game| game.reached_at >= month_start)
+                    .map(|game| NewMilestone {
+                        game_id: game.game_id,
+                        name: game.name.clone(),
+                        milestone,
+                        reached_at: game.reached_at,
+                    }),
+            );
+            Milestone {
+                plays: count,
+                name: milestone,
+                games,
+            }
+        })
+        .collect();
+    new_this_month.sort_by_key(|milestone| milestone.reached_at);
+
+    MilestonesData {
+        as_of,
+        game_h_index: h_index(played.values().map(Vec::len)),
+        player_h_index: h_index(seated.into_values()),
+        milestones,
+        new_this_month,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::{PlayModel, PlayParticipantModel};
+
+    #[test]
+    fn milestones_count_the_plays_up_to_the_date() {
+        let catan = Uuid::new_v4();
+        let ann = Uuid::new_v4();
+        // Catan on the first ten days of March
+        let plays: Vec<_> = (1..=10)
+            .map(|day| PlayModel {
+                id: Uuid::new_v4(),
+                game_id: catan,
+                session_id: None,
+                location: None,
+                length_minutes: None,
+                comments: None,
+                bgg_play_id: None,
+                created_at: Utc.with_ymd_and_hms(2024, 3, day, 20, 0, 0).unwrap(),
+                deleted_at: None,
+                version: 1,
+            })
+            .collect();
+        let tables = BackupTables {
+            play_participants: plays
+                .iter()
+                .map(|play| PlayParticipantModel {
+                    play_id: play.id,
+                    player_id: ann,
+                    score: None,
+                    is_winner: false,
+                    version: 1,
+                })
+                .collect(),
+            plays,
+            ..Default::default()
+        };
+
+        let march = milestones(&tables, NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+        assert_eq!((march.game_h_index, march.player_h_index), (1, 1));
+        assert_eq!(march.milestones[1].games[0].plays, 10);
+        assert_eq!(march.milestones[1].games[0].reached_at.day(), 10);
+        assert!(march.milestones[2].games.is_empty());
+        assert_eq!(march.new_this_month.len(), 2);
+
+        let early = milestones(&tables, NaiveDate::from_ymd_opt(2024, 3, 7).unwrap());
+        assert!(early.milestones[1].games.is_empty());
+        assert_eq!(early.new_this_month[0].milestone, "nickel");
+
+        let april = milestones(&tables, NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+        assert!(april.new_this_month.is_empty());
+    }
+}
@@ -1,35 +1,184 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// Database model for a game
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
 pub struct GameModel {
     pub id: Uuid,
     pub name: String,
     pub creator_name: String,
+    /// BoardGameGeek object ID
+    pub bgg_id: Option<i32>,
+    pub year_published: Option<i32>,
+    pub min_players: Option<i32>,
+    pub max_players: Option<i32>,
+    pub min_playtime_minutes: Option<i32>,
+    pub max_playtime_minutes: Option<i32>,
+    /// Average complexity rating of BGG users, 1 to 5
+    pub weight: Option<f64>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub version: i32,
 }
 
 /// Database model for a player
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
 pub struct PlayerModel {
     pub id: Uuid,
     pub name: String,
     pub is_owner: bool,
+    /// BoardGameGeek username, matched case-insensitively
+    pub bgg_username: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub version: i32,
 }
 
 /// Database model for a play
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
 pub struct PlayModel {
     pub id: Uuid,
     pub game_id: Uuid,
+    /// Game night the play belongs to
+    pub session_id: Option<Uuid>,
+    pub location: Option<String>,
+    pub length_minutes: Option<i32>,
+    pub comments: Option<String>,
+    /// ID of the BoardGameGeek play this play was imported from
+    pub bgg_play_id: Option<i64>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub version: i32,
+}
+
+/// Database model for a game night. The attendees are stored in
+/// `session_attendees` and always include the host.
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
+pub struct SessionModel {
+    pub id: Uuid,
+    pub played_on: chrono::NaiveDate,
+    pub location: Option<String>,
+    pub host_id: Option<Uuid>,
+    /// Sorted, without repeats
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub attendee_ids: Vec<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub version: i32,
+}
+
+/// An invited player's answer to an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Rsvp {
+    Yes,
+    No,
+    Maybe,
+}
+
+/// A player invited to an event, with their answer once they gave one and
+/// the games they want to bring
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct InvitationModel {
+    pub player_id: Uuid,
+    pub response: Option<Rsvp>,
+    pub responded_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Sorted, without repeats
+    #[serde(default)]
+    pub game_ids: Vec<Uuid>,
+}
+
+/// Database model for a planned game night. The invitations are stored in
+/// `event_invitations` and `event_games` and always include the host.
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
+pub struct EventModel {
+    pub id: Uuid,
+    pub scheduled_on: chrono::NaiveDate,
+    pub location: Option<String>,
+    pub host_id: Option<Uuid>,
+    /// The session the event turned into
+    pub session_id: Option<Uuid>,
+    /// By player ID
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub invitations: Vec<InvitationModel>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub version: i32,
 }
 
 /// Database model for a play participant
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
 pub struct PlayParticipantModel {
     pub play_id: Uuid,
     pub player_id: Uuid,
+    pub score: Option<i32>,
+    pub is_winner: bool,
+    pub version: i32,
+}
+
+/// A play seen from one player's side, with that player's result
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
+pub struct PlayerPlayModel {
+    pub id: Uuid,
+    pub game_id: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub score: Option<i32>,
+    pub is_winner: bool,
+}
+
+/// Aggregated results for a single player
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
+pub struct PlayerStatsModel {
+    pub plays: i64,
+    pub wins: i64,
+    pub games: i64,
+}
+
+/// Database model for an audit log entry; `before` and `after` are row snapshots
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
+pub struct AuditLogModel {
+    pub id: i64,
+    pub entity: String,
+    pub entity_id: String,
+    pub action: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub changed_at: chrono::DateTime<chrono::Utc>,
+    /// `X-Actor` header of the request that made the change
+    pub actor: Option<String>,
+}
+
+/// Database model for a claimed Idempotency-Key; the response columns stay
+/// empty while the first request is still running
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IdempotencyKeyModel {
+    pub request_hash: Vec<u8>,
+    pub status_code: Option<i16>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+/// Every row of every table, as backed up by `GET /api/v1/export`. A table
+/// added later gets a field here that defaults to empty, so older archives
+/// still import.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct BackupTables {
+    #[serde(default)]
+    pub games: Vec<GameModel>,
+    #[serde(default)]
+    pub players: Vec<PlayerModel>,
+    #[serde(default)]
+    pub sessions: Vec<SessionModel>,
+    #[serde(default)]
+    pub events: Vec<EventModel>,
+    #[serde(default)]
+    pub plays: Vec<PlayModel>,
+    #[serde(default)]
+    pub play_participants: Vec<PlayParticipantModel>,
+    /// Oldest first. Only archives carry the audit log; the other imports
+    /// leave it empty.
+    #[serde(default)]
+    pub audit_log: Vec<AuditLogModel>,
 }
@@ -0,0 +1,91 @@
+//! OpenAPI document for the API, served at `/api/openapi.json` with Swagger UI
+//! at `/api/docs`.
+//!
+//! Paths are collected by the router in `crate::route` from the
+//! `#[utoipa::path]` attributes on the handlers, and bodies come from
+//! `crate::response`. The members of bulk results and CSV row failures are
+//! built as JSON, so their shapes below only exist to describe them in the
+//! document.
+#![allow(dead_code)]
+
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    bgstats::BgStatsFile,
+    response::{ErrorResponse, FieldError},
+    schema::{
+        ArchiveSchema, BulkMode, BulkSchema, EventSchema, GameSchema, MergeSchema,
+        PlayParticipantSchema, PlaySchema, PlayerSchema, RsvpSchema, SessionSchema,
+        UpdateEventSchema, UpdateGameSchema, UpdatePlayParticipantSchema, UpdatePlaySchema,
+        UpdatePlayerSchema, UpdateSessionSchema,
+    },
+};
+
+/// Outcome of one item of a bulk request
+#[derive(ToSchema)]
+pub struct BulkItemResult {
+    index: usize,
+    #[schema(example = "success")]
+    status: String,
+    /// What the single-item endpoint answers with, for successful items
+    #[schema(nullable = false)]
+    data: Option<serde_json::Value>,
+    /// HTTP status the single-item request would have got, for failed items
+    #[schema(nullable = false)]
+    code: Option<u16>,
+    #[schema(nullable = false)]
+    message: Option<String>,
+    #[schema(nullable = false)]
+    errors: Option<Vec<FieldError>>,
+}
+
+#[derive(ToSchema)]
+pub struct CsvRowError {
+    /// Line of the row in the file, the header being line 1
+    row: u64,
+    /// `field` is the header of the failing column
+    errors: Vec<FieldError>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Board Game Logger",
+        description = "An app to log your board game plays"
+    ),
+    components(schemas(
+        GameSchema,
+        UpdateGameSchema,
+        PlayerSchema,
+        UpdatePlayerSchema,
+        PlaySchema,
+        UpdatePlaySchema,
+        PlayParticipantSchema,
+        UpdatePlayParticipantSchema,
+        SessionSchema,
+        UpdateSessionSchema,
+        EventSchema,
+        UpdateEventSchema,
+        RsvpSchema,
+        MergeSchema,
+        BulkSchema,
+        BulkMode,
+        ArchiveSchema,
+        BgStatsFile,
+        ErrorResponse,
+    )),
+    tags(
+        (name = "games"),
+        (name = "players"),
+        (name = "me", description = "The player marked as owner"),
+        (name = "plays"),
+        (name = "play participants"),
+        (name = "sessions", description = "Game nights that group plays, with attendance"),
+        (name = "events", description = "Upcoming game nights with invitations and RSVPs, until they turn into sessions"),
+        (name = "trash", description = "Deleted games, players and plays until they are purged"),
+        (name = "history", description = "Audit log of every change, with revert"),
+        (name = "reports", description = "Summaries of the plays to share"),
+        (name = "backup", description = "Export and import of every row as one JSON archive, and of the BG Stats and BoardGameGeek formats"),
+    )
+)]
+pub struct ApiDoc;
@@ -0,0 +1,645 @@
+//! Plays as CSV, one row per participant per play, for groups keeping their
+//! history in a spreadsheet. Games and players are named rather than referred
+//! to by ID: an import looks the names up among the live rows and hands the
+//! new rows to `BackupRepository::restore`, which writes them in one
+//! transaction.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use csv::StringRecord;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    model::{BackupTables, GameModel, PlayModel, PlayParticipantModel, PlayerModel},
+    repository::normalize_name,
+    response::PlayCsvImportData,
+    schema::PlayCsvImportQuery,
+    validation::{NAME_MAX_CHARS, SCORE_MAX, SCORE_MIN, no_control_chars, normalize_text},
+};
+
+/// Headers written by the export, which the import looks for by default
+const HEADERS: [&str; 7] = [
+    "play_id",
+    "played_at",
+    "game",
+    "creator",
+    "player",
+    "score",
+    "winner",
+];
+
+/// Live plays, oldest first, with a row per participant or a single row
+/// without player for a play nobody has been added to
+pub fn write_plays(tables: &BackupTables) -> Result<Vec<u8>, csv::Error> {
+    let games: HashMap<_, _> = tables.games.iter().map(|game| (game.id, game)).collect();
+    let players: HashMap<_, _> = tables
+        .players
+        .iter()
+        .map(|player| (player.id, player.name.as_str()))
+        .collect();
+
+    let mut plays: Vec<_> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .collect();
+    plays.sort_by_key(|play| (play.created_at, play.id));
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(HEADERS)?;
+
+    for play in plays {
+        let id = play.id.to_string();
+        let played_at = play.created_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let (game, creator) = games
+            .get(&play.game_id)
+            .map_or(("", ""), |game| (&game.name, &game.creator_name));
+
+        let mut participants: Vec<_> = tables
+            .play_participants
+            .iter()
+            .filter(|participant| participant.play_id == play.id)
+            .map(|participant| {
+                let name = players.get(&participant.player_id).copied();
+                (name.unwrap_or_default(), participant)
+            })
+            .collect();
+        participants.sort_by_key(|(name, _)| *name);
+
+        if participants.is_empty() {
+            writer.write_record([id.as_str(), &played_at, game, creator, "", "", ""])?;
+        }
+        for (name, participant) in participants {
+            let score = participant.score.map(|score| score.to_string());
+            let winner = if participant.is_winner {
+                "true"
+            } else {
+                "false"
+            };
+            writer.write_record([
+                id.as_str(),
+                &played_at,
+                game,
+                creator,
+                name,
+                score.as_deref().unwrap_or_default(),
+                winner,
+            ])?;
+        }
+    }
+
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+/// A cell, or the row as a whole, that could not be imported
+#[derive(Debug, Serialize)]
+pub struct CellError {
+    /// Header of the column; `row` for rows that could not be read at all
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    /// Line of the row in the file, the header being line 1
+    pub row: u64,
+    pub errors: Vec<CellError>,
+}
+
+#[derive(Debug)]
+pub enum CsvImportError {
+    /// The header lacks a column, by the query parameter naming it
+    MissingColumn {
+        parameter: &'static str,
+        column: String,
+    },
+    /// The header could not be read
+    Unreadable(String),
+    /// Rows that could not be imported; nothing is imported then
+    Rows(Vec<RowError>),
+}
+
+/// New rows to write and the report to answer with
+pub struct CsvImport {
+    pub rows: BackupTables,
+    pub data: PlayCsvImportData,
+}
+
+/// Where each value is found in a row
+struct Columns {
+    play: Option<usize>,
+    played_at: Option<usize>,
+    game: usize,
+    creator: Option<usize>,
+    player: Option<usize>,
+    score: Option<usize>,
+    winner: Option<usize>,
+}
+
+/// Finds the column a query parameter names, or the default header. A column
+/// named explicitly has to exist; a default one only if `required`.
+fn column(
+    headers: &StringRecord,
+    parameter: &'static str,
+    mapped: &Option<String>,
+    default: &str,
+    required: bool,
+) -> Result<Option<usize>, CsvImportError> {
+    let name = mapped.as_deref().unwrap_or(default).trim();
+    let index = headers
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(name));
+
+    if index.is_none() && (required || mapped.is_some()) {
+        return Err(CsvImportError::MissingColumn {
+            parameter,
+            column: name.to_string(),
+        });
+    }
+    Ok(index)
+}
+
+/// The problem with a name, if any, judged like the names of JSON bodies
+fn name_problem(name: &str) -> Option<String> {
+    let length = name.chars().count() as u64;
+    if length == 0 || length > NAME_MAX_CHARS {
+        Some(format!(
+            "must be between 1 and {} characters",
+            NAME_MAX_CHARS
+        ))
+    } else if no_control_chars(name).is_err() {
+        Some("must not contain control characters".to_string())
+    } else {
+        None
+    }
+}
+
+fn parse_played_at(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Some(at.with_timezone(&Utc));
+    }
+    let formats = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ];
+    formats
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|at| at.and_utc())
+}
+
+fn parse_winner(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" => Some(true),
+        "false" | "no" | "n" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads every row of `csv` into new plays and participants, naming games and
+/// players that exist in `stored` or, with `create_missing`, creating them.
+/// Fails with every row that cannot be imported.
+pub fn plan_csv_import(
+    csv: &[u8],
+    query: &PlayCsvImportQuery,
+    stored: BackupTables,
+) -> Result<CsvImport, CsvImportError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv);
+    let headers = reader
+        .headers()
+        .map_err(|e| CsvImportError::Unreadable(e.to_string()))?
+        .clone();
+    let [play, played_at, game, creator, player, score, winner] = HEADERS;
+    let columns = Columns {
+        play: column(&headers, "play_column", &query.play_column, play, false)?,
+        played_at: column(
+            &headers,
+            "played_at_column",
+            &query.played_at_column,
+            played_at,
+            false,
+        )?,
+        game: column(&headers, "game_column", &query.game_column, game, true)?.unwrap_or_default(),
+        creator: column(
+            &headers,
+            "creator_column",
+            &query.creator_column,
+            creator,
+            false,
+        )?,
+        player: column(
+            &headers,
+            "player_column",
+            &query.player_column,
+            player,
+            false,
+        )?,
+        score: column(&headers, "score_column", &query.score_column, score, false)?,
+        winner: column(
+            &headers,
+            "winner_column",
+            &query.winner_column,
+            winner,
+            false,
+        )?,
+    };
+
+    let mut importer = Importer {
+        create_missing: query.create_missing,
+        now: Utc::now(),
+        games: stored
+            .games
+            .into_iter()
+            .filter(|game| game.deleted_at.is_none())
+            .collect(),
+        players: stored
+            .players
+            .into_iter()
+            .filter(|player| player.deleted_at.is_none())
+            .collect(),
+        plays: HashMap::new(),
+        participants: HashSet::new(),
+        rows: BackupTables::default(),
+        data: PlayCsvImportData::default(),
+    };
+
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let result = match record {
+            Ok(record) if record.iter().all(|cell| cell.trim().is_empty()) => continue,
+            Ok(record) => {
+                let row = record.position().map_or(0, |position| position.line());
+                importer
+                    .row(&headers, &columns, &record)
+                    .map_err(|errors| RowError { row, errors })
+            }
+            Err(e) => Err(RowError {
+                row: e.position().map_or(0, |position| position.line()),
+                errors: vec![CellError {
+                    field: "row".to_string(),
+                    message: e.to_string(),
+                }],
+            }),
+        };
+        importer.data.rows += 1;
+        if let Err(error) = result {
+            errors.push(error);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(CsvImportError::Rows(errors));
+    }
+    Ok(CsvImport {
+        rows: importer.rows,
+        data: importer.data,
+    })
+}
+
+struct Importer {
+    create_missing: bool,
+    /// When plays without a date took place
+    now: DateTime<Utc>,
+    /// Live games and players, the ones this import creates included
+    games: Vec<GameModel>,
+    players: Vec<PlayerModel>,
+    /// Plays created so far by the value of the play column, or by date and game
+    plays: HashMap<String, usize>,
+    participants: HashSet<(Uuid, Uuid)>,
+    rows: BackupTables,
+    data: PlayCsvImportData,
+}
+
+impl Importer {
+    fn row(
+        &mut self,
+        headers: &StringRecord,
+        columns: &Columns,
+        record: &StringRecord,
+    ) -> Result<(), Vec<CellError>> {
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .map(normalize_text)
+                .unwrap_or_default()
+        };
+        let field = |index: usize| headers.get(index).unwrap_or_default().trim().to_string();
+        let mut errors = Vec::new();
+        let mut fail = |index: usize, message: String| {
+            errors.push(CellError {
+                field: field(index),
+                message,
+            })
+        };
+
+        let game = cell(Some(columns.game));
+        if let Some(problem) = name_problem(&game) {
+            fail(columns.game, problem);
+        }
+        let creator = cell(columns.creator);
+        if let (Some(index), false) = (columns.creator, creator.is_empty())
+            && let Some(problem) = name_problem(&creator)
+        {
+            fail(index, problem);
+        }
+        let player = cell(columns.player);
+        if let (Some(index), false) = (columns.player, player.is_empty())
+            && let Some(problem) = name_problem(&player)
+        {
+            fail(index, problem);
+        }
+
+        let score = match (columns.score, cell(columns.score)) {
+            (_, text) if text.is_empty() => None,
+            (index, text) => match text.parse::<i32>() {
+                Ok(score) if (SCORE_MIN..=SCORE_MAX).contains(&score) => Some(score),
+                _ => {
+                    let message = format!(
+                        "must be a whole number between {} and {}",
+                        SCORE_MIN, SCORE_MAX
+                    );
+                    fail(index.unwrap_or_default(), message);
+                    None
+                }
+            },
+        };
+        let is_winner = parse_winner(&cell(columns.winner)).unwrap_or_else(|| {
+            let message = "must be true/false, yes/no, 1/0 or x/empty".to_string();
+            fail(columns.winner.unwrap_or_default(), message);
+            false
+        });
+        let played_at = match (columns.played_at, cell(columns.played_at)) {
+            (_, text) if text.is_empty() => self.now,
+            (index, text) => parse_played_at(&text).unwrap_or_else(|| {
+                let message =
+                    "must be a date (YYYY-MM-DD), a date and time (YYYY-MM-DD HH:MM) or RFC 3339"
+                        .to_string();
+                fail(index.unwrap_or_default(), message);
+                self.now
+            }),
+        };
+        if (score.is_some() || is_winner) && player.is_empty() {
+            fail(
+                columns.player.unwrap_or(columns.game),
+                "is needed for a score or a win".to_string(),
+            );
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let game_id = self.game(&game, &creator).map_err(|message| {
+            vec![CellError {
+                field: field(columns.game),
+                message,
+            }]
+        })?;
+        let player_id = match columns.player {
+            Some(index) if !player.is_empty() => Some(self.player(&player).map_err(|message| {
+                vec![CellError {
+                    field: field(index),
+                    message,
+                }]
+            })?),
+            _ => None,
+        };
+
+        let play = cell(columns.play);
+        let key = if play.is_empty() {
+            format!("{}|{}", played_at.to_rfc3339(), game_id)
+        } else {
+            play
+        };
+        let play_id = match self.plays.get(&key) {
+            Some(&index) => {
+                let play = &self.rows.plays[index];
+                if play.game_id != game_id || play.created_at != played_at {
+                    let message = "differs from an earlier row of the same play".to_string();
+                    let index = columns.played_at.filter(|_| play.game_id == game_id);
+                    return Err(vec![CellError {
+                        field: field(index.unwrap_or(columns.game)),
+                        message,
+                    }]);
+                }
+                play.id
+            }
+            None => {
+                let play = PlayModel {
+                    id: Uuid::new_v4(),
+                    game_id,
+                    session_id: None,
+                    location: None,
+                    length_minutes: None,
+                    comments: None,
+                    bgg_play_id: None,
+                    created_at: played_at,
+                    deleted_at: None,
+                    version: 1,
+                };
+                self.plays.insert(key, self.rows.plays.len());
+                self.data.plays_created += 1;
+                let id = play.id;
+                self.rows.plays.push(play);
+                id
+            }
+        };
+
+        if let Some(player_id) = player_id {
+            if !self.participants.insert((play_id, player_id)) {
+                return Err(vec![CellError {
+                    field: field(columns.player.unwrap_or_default()),
+                    message: "is listed twice for the same play".to_string(),
+                }]);
+            }
+            self.data.participants_created += 1;
+            self.rows.play_participants.push(PlayParticipantModel {
+                play_id,
+                player_id,
+                score,
+                is_winner,
+                version: 1,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The live game of this name, and of this creator if one is given
+    fn game(&mut self, name: &str, creator: &str) -> Result<Uuid, String> {
+        let name_key = normalize_name(name);
+        let creator_key = normalize_name(creator);
+        let matches: Vec<_> = self
+            .games
+            .iter()
+            .filter(|game| {
+                normalize_name(&game.name) == name_key
+                    && (creator.is_empty() || normalize_name(&game.creator_name) == creator_key)
+            })
+            .collect();
+
+        match matches.as_slice() {
+            [game] => Ok(game.id),
+            [] if !self.create_missing => {
+                Err("Game not found; import with create_missing=true to create it".to_string())
+            }
+            [] if creator.is_empty() => {
+                Err("Game not found; a creator is needed to create it".to_string())
+            }
+            [] => {
+                let game = GameModel {
+                    id: Uuid::new_v4(),
+                    name: name.to_string(),
+                    creator_name: creator.to_string(),
+                    bgg_id: None,
+                    year_published: None,
+                    min_players: None,
+                    max_players: None,
+                    min_playtime_minutes: None,
+                    max_playtime_minutes: None,
+                    weight: None,
+                    created_at: self.now,
+                    deleted_at: None,
+                    version: 1,
+                };
+                self.games.push(game.clone());
+                self.rows.games.push(game.clone());
+                let id = game.id;
+                self.data.games_created.push(game);
+                Ok(id)
+            }
+            _ => Err("Several games have this name; add a creator to pick one".to_string()),
+        }
+    }
+
+    fn player(&mut self, name: &str) -> Result<Uuid, String> {
+        let key = normalize_name(name);
+        if let Some(player) = self
+            .players
+            .iter()
+            .find(|player| normalize_name(&player.name) == key)
+        {
+            return Ok(player.id);
+        }
+        if !self.create_missing {
+            return Err(
+                "Player not found; import with create_missing=true to create it".to_string(),
+            );
+        }
+
+        let player = PlayerModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            is_owner: false,
+            bgg_username: None,
+            created_at: self.now,
+            deleted_at: None,
+            version: 1,
+        };
+        self.players.push(player.clone());
+        self.rows.players.push(player.clone());
+        let id = player.id;
+        self.data.players_created.push(player);
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(create_missing: bool) -> PlayCsvImportQuery {
+        PlayCsvImportQuery {
+            create_missing,
+            play_column: None,
+            played_at_column: None,
+            game_column: None,
+            creator_column: None,
+            player_column: None,
+            score_column: None,
+            winner_column: None,
+        }
+    }
+
+    #[test]
+    fn an_export_imports_as_the_same_plays() {
+        let csv = "\
+play_id,played_at,game,creator,player,score,winner
+1,2024-03-01,Catan,Klaus Teuber,Ann,10,yes
+1,2024-03-01,Catan,Klaus Teuber,Bob,7,
+2,2024-03-02 20:15,Catan,,,,
+";
+        let import =
+            plan_csv_import(csv.as_bytes(), &query(true), BackupTables::default()).unwrap();
+        let exported = String::from_utf8(write_plays(&import.rows).unwrap()).unwrap();
+
+        let lines: Vec<_> = exported
+            .lines()
+            .skip(1)
+            .map(|line| line.split_once(',').unwrap().1)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "2024-03-01T00:00:00Z,Catan,Klaus Teuber,Ann,10,true",
+                "2024-03-01T00:00:00Z,Catan,Klaus Teuber,Bob,7,false",
+                "2024-03-02T20:15:00Z,Catan,Klaus Teuber,,,",
+            ]
+        );
+        assert_eq!(import.data.games_created.len(), 1);
+        assert_eq!(import.data.players_created.len(), 2);
+    }
+
+    #[test]
+    fn every_failing_row_is_reported() {
+        let csv = "\
+Date,Game,Who,Points
+2024-03-01,Catan,Ann,ten
+2024-13-01,Catan,Ann,
+2024-03-03,Azul,Ann,3
+";
+        let mut query = query(false);
+        query.played_at_column = Some("Date".to_string());
+        query.game_column = Some("Game".to_string());
+        query.player_column = Some("Who".to_string());
+        query.score_column = Some("Points".to_string());
+        let stored = BackupTables {
+            games: vec![GameModel {
+                id: Uuid::new_v4(),
+                name: "Catan".to_string(),
+                creator_name: "Klaus Teuber".to_string(),
+                bgg_id: None,
+                year_published: None,
+                min_players: None,
+                max_players: None,
+                min_playtime_minutes: None,
+                max_playtime_minutes: None,
+                weight: None,
+                created_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            }],
+            ..Default::default()
+        };
+
+        let Err(CsvImportError::Rows(rows)) = plan_csv_import(csv.as_bytes(), &query, stored)
+        else {
+            panic!("expected row errors");
+        };
+
+        let fields: Vec<_> = rows
+            .iter()
+            .map(|row| (row.row, row.errors[0].field.as_str()))
+            .collect();
+        assert_eq!(fields, [(2, "Points"), (3, "Date"), (4, "Game")]);
+    }
+}
@@ -0,0 +1,443 @@
+//! The year in review: what a group played in a calendar year, in UTC, and
+//! the same report rendered as Markdown or HTML to share.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Month, NaiveDate, Utc};
+
+use crate::{
+    model::BackupTables,
+    response::{BusiestMonth, ReportGame, ReportPlayer, WinningStreak, YearReport},
+};
+
+/// The largest `h` such that `h` of the counts are at least `h`
+pub fn h_index(counts: impl IntoIterator<Item = usize>) -> usize {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts
+        .iter()
+        .enumerate()
+        .take_while(|(index, count)| **count > *index)
+        .count()
+}
+
+/// When `year` starts, in UTC
+pub fn year_start(year: i32) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(year, 1, 1).map_or(DateTime::<Utc>::MAX_UTC, |first| {
+        first.and_time(Default::default()).and_utc()
+    })
+}
+
+/// Rounds to one decimal
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// What the live plays of `year` add up to
+pub fn year_report(tables: &BackupTables, year: i32) -> YearReport {
+    let game_names: HashMap<_, _> = tables
+        .games
+        .iter()
+        .map(|game| (game.id, game.name.as_str()))
+        .collect();
+    let player_names: HashMap<_, _> = tables
+        .players
+        .iter()
+        .map(|player| (player.id, player.name.as_str()))
+        .collect();
+
+    let live: Vec<_> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .collect();
+    let mut plays: Vec<_> = live
+        .iter()
+        .filter(|play| play.created_at.year() == year)
+        .copied()
+        .collect();
+    plays.sort_by_key(|play| (play.created_at, play.id));
+    let played_before: HashSet<_> = live
+        .iter()
+        .filter(|play| play.created_at.year() < year)
+        .map(|play| play.game_id)
+        .collect();
+
+    let mut game_plays: HashMap<_, usize> = HashMap::new();
+    let mut month_plays = [0; 12];
+    for play in &plays {
+        *game_plays.entry(play.game_id).or_default() += 1;
+        month_plays[play.created_at.month0() as usize] += 1;
+    }
+    let share = |count: usize| round(100.0 * count as f64 / plays.len() as f64);
+    let mut games: Vec<_> = game_plays
+        .iter()
+        .map(|(&game_id, &count)| ReportGame {
+            game_id,
+            name: game_names
+                .get(&game_id)
+                .copied()
+                .unwrap_or_default()
+                .to_string(),
+            plays: count,
+            share: share(count),
+        })
+        .collect();
+    games.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.name.cmp(&b.name)));
+    let new_games = games
+        .iter()
+        .filter(|game| !played_before.contains(&game.game_id))
+        .cloned()
+        .collect();
+
+    // Each player's results in the order played
+    let order: HashMap<_, _> = plays
+        .iter()
+        .enumerate()
+        .map(|(index, play)| (play.id, index))
+        .collect();
+    let mut results: HashMap<_, Vec<_>> = HashMap::new();
+    for participant in &tables.play_participants {
+        if let Some(&index) = order.get(&participant.play_id) {
+            results
+                .entry(participant.player_id)
+                .or_default()
+                .push((index, participant.is_winner));
+        }
+    }
+
+    let mut players = Vec::new();
+    let mut winning_streaks = Vec::new();
+    for (player_id, mut played) in results {
+        played.sort_unstable();
+        let name = player_names
+            .get(&player_id)
+            .copied()
+            .unwrap_or_default()
+            .to_string();
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut start = None;
+        for (position, &(_, won)) in played.iter().enumerate() {
+            if !won {
+                start = None;
+                continue;
+            }
+            let from = *start.get_or_insert(position);
+            let length = position - from + 1;
+            if best.is_none_or(|(_, best)| length > best) {
+                best = Some((from, length));
+            }
+        }
+        if let Some((from, wins)) = best {
+            winning_streaks.push(WinningStreak {
+                player_id,
+                name: name.clone(),
+                wins,
+                from: plays[played[from].0].created_at,
+                to: plays[played[from + wins - 1].0].created_at,
+            });
+        }
+
+        players.push(ReportPlayer {
+            player_id,
+            name,
+            plays: played.len(),
+            wins: played.iter().filter(|(_, won)| *won).count(),
+        });
+    }
+    players.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.name.cmp(&b.name)));
+    winning_streaks.sort_by(|a, b| b.wins.cmp(&a.wins).then_with(|| a.name.cmp(&b.name)));
+
+    let busiest_month = (1..=12)
+        .zip(month_plays)
+        .filter(|(_, plays)| *plays > 0)
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(month, plays)| BusiestMonth { month, plays });
+
+    let minutes: i64 = plays
+        .iter()
+        .filter_map(|play| play.length_minutes)
+        .map(i64::from)
+        .sum();
+
+    YearReport {
+        year,
+        plays: plays.len(),
+        hours: round(minutes as f64 / 60.0),
+        plays_without_length: plays
+            .iter()
+            .filter(|play| play.length_minutes.is_none())
+            .count(),
+        new_games,
+        h_index: h_index(game_plays.into_values()),
+        games,
+        players,
+        winning_streaks,
+        busiest_month,
+    }
+}
+
+fn month_name(month: u32) -> &'static str {
+    Month::try_from(month as u8).map_or("", |month| month.name())
+}
+
+/// A headed table of the report
+struct Section {
+    title: &'static str,
+    headers: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+/// The summary lines and tables both renderings show
+fn layout(report: &YearReport) -> (Vec<(&'static str, String)>, Vec<Section>) {
+    let mut summary = vec![
+        ("Plays", report.plays.to_string()),
+        ("Hours played", report.hours.to_string()),
+    ];
+    if report.plays_without_length > 0 {
+        summary[1].1 += &format!(
+            " (not counting {} plays without a length)",
+            report.plays_without_length
+        );
+    }
+    if let Some(month) = &report.busiest_month {
+        summary.push((
+            "Busiest month",
+            format!("{}, {} plays", month_name(month.month), month.plays),
+        ));
+    }
+    summary.push(("H-index", report.h_index.to_string()));
+
+    let game_rows = |games: &[ReportGame]| {
+        games
+            .iter()
+            .map(|game| {
+                vec![
+                    game.name.clone(),
+                    game.plays.to_string(),
+                    format!("{}%", game.share),
+                ]
+            })
+            .collect()
+    };
+    let sections = vec![
+        Section {
+            title: "New to us",
+            headers: &["Game", "Plays", "Share"],
+            rows: game_rows(&report.new_games),
+        },
+        Section {
+            title: "Most played games",
+            headers: &["Game", "Plays", "Share"],
+            rows: game_rows(&report.games),
+        },
+        Section {
+            title: "Players",
+            headers: &["Player", "Plays", "Wins"],
+            rows: report
+                .players
+                .iter()
+                .map(|player| {
+                    vec![
+                        player.name.clone(),
+                        player.plays.to_string(),
+                        player.wins.to_string(),
+                    ]
+                })
+                .collect(),
+        },
+        Section {
+            title: "Best winning streaks",
+            headers: &["Player", "Wins in a row", "From", "To"],
+            rows: report
+                .winning_streaks
+                .iter()
+                .map(|streak| {
+                    vec![
+                        streak.name.clone(),
+                        streak.wins.to_string(),
+                        streak.from.format("%Y-%m-%d").to_string(),
+                        streak.to.format("%Y-%m-%d").to_string(),
+                    ]
+                })
+                .collect(),
+        },
+    ];
+
+    (summary, sections)
+}
+
+pub fn render_markdown(report: &YearReport) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let (summary, sections) = layout(report);
+
+    let mut markdown = format!("# {} in review\n\n", report.year);
+    for (label, value) in summary {
+        markdown += &format!("- **{}:** {}\n", label, value);
+    }
+    for section in sections.iter().filter(|section| !section.rows.is_empty()) {
+        markdown += &format!("\n## {}\n\n", section.title);
+        markdown += &format!("| {} |\n", section.headers.join(" | "));
+        markdown += &format!("|{}\n", " --- |".repeat(section.headers.len()));
+        for row in &section.rows {
+            let cells: Vec<_> = row.iter().map(|text| cell(text)).collect();
+            markdown += &format!("| {} |\n", cells.join(" | "));
+        }
+    }
+    markdown
+}
+
+pub fn render_html(report: &YearReport) -> String {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let (summary, sections) = layout(report);
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0} in review</title>\n</head>\n<body>\n<h1>{0} in review</h1>\n<ul>\n",
+        report.year
+    );
+    for (label, value) in summary {
+        html += &format!("<li><strong>{}:</strong> {}</li>\n", label, escape(&value));
+    }
+    html += "</ul>\n";
+    for section in sections.iter().filter(|section| !section.rows.is_empty()) {
+        html += &format!("<h2>{}</h2>\n<table>\n<tr>", section.title);
+        for header in section.headers {
+            html += &format!("<th>{}</th>", header);
+        }
+        html += "</tr>\n";
+        for row in &section.rows {
+            html += "<tr>";
+            for text in row {
+                html += &format!("<td>{}</td>", escape(text));
+            }
+            html += "</tr>\n";
+        }
+        html += "</table>\n";
+    }
+    html += "</body>\n</html>\n";
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::{GameModel, PlayModel, PlayParticipantModel, PlayerModel};
+
+    fn game(name: &str) -> GameModel {
+        GameModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            creator_name: "Someone".to_string(),
+            bgg_id: None,
+            year_published: None,
+            min_players: None,
+            max_players: None,
+            min_playtime_minutes: None,
+            max_playtime_minutes: None,
+            weight: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    fn play(game: &GameModel, at: &str, length_minutes: Option<i32>) -> PlayModel {
+        PlayModel {
+            id: Uuid::new_v4(),
+            game_id: game.id,
+            session_id: None,
+            location: None,
+            length_minutes,
+            comments: None,
+            bgg_play_id: None,
+            created_at: at.parse::<DateTime<Utc>>().unwrap(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn h_index_counts_games_played_at_least_as_often() {
+        assert_eq!(h_index([]), 0);
+        assert_eq!(h_index([1, 1, 1]), 1);
+        assert_eq!(h_index([5, 4, 3, 1]), 3);
+        assert_eq!(h_index([10, 10]), 2);
+    }
+
+    #[test]
+    fn a_year_is_summed_up() {
+        let catan = game("Catan");
+        let azul = game("Azul");
+        let ann = PlayerModel {
+            id: Uuid::new_v4(),
+            name: "Ann".to_string(),
+            is_owner: true,
+            bgg_username: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        let plays = vec![
+            play(&catan, "2023-12-31T20:00:00Z", None),
+            play(&catan, "2024-03-01T20:00:00Z", Some(90)),
+            play(&azul, "2024-03-02T20:00:00Z", Some(30)),
+            play(&catan, "2024-03-03T20:00:00Z", None),
+            play(&azul, "2024-05-01T20:00:00Z", None),
+            play(&catan, "2024-06-01T20:00:00Z", None),
+        ];
+        let results = [true, true, true, false, true, true];
+        let tables = BackupTables {
+            play_participants: plays
+                .iter()
+                .zip(results)
+                .map(|(play, is_winner)| PlayParticipantModel {
+                    play_id: play.id,
+                    player_id: ann.id,
+                    score: None,
+                    is_winner,
+                    version: 1,
+                })
+                .collect(),
+            games: vec![catan, azul],
+            players: vec![ann],
+            sessions: Vec::new(),
+            events: Vec::new(),
+            plays,
+            audit_log: Vec::new(),
+        };
+
+        let report = year_report(&tables, 2024);
+
+        assert_eq!(
+            (report.plays, report.hours, report.plays_without_length),
+            (5, 2.0, 3)
+        );
+        assert_eq!(report.new_games.len(), 1);
+        assert_eq!(report.new_games[0].name, "Azul");
+        assert_eq!(
+            (report.games[0].name.as_str(), report.games[0].share),
+            ("Catan", 60.0)
+        );
+        assert_eq!((report.players[0].plays, report.players[0].wins), (5, 4));
+        assert_eq!(report.winning_streaks[0].wins, 2);
+        assert_eq!(report.winning_streaks[0].from.month(), 3);
+        assert_eq!(
+            report.busiest_month.as_ref().map(|month| month.month),
+            Some(3)
+        );
+        assert_eq!(report.h_index, 2);
+
+        let markdown = render_markdown(&report);
+        assert!(markdown.starts_with("# 2024 in review\n"));
+        assert!(markdown.contains("| Catan | 3 | 60% |"));
+    }
+}
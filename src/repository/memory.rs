@@ -0,0 +1,1797 @@
+//! In-memory repositories for unit tests. They keep the rules the Postgres
+//! schema enforces: normalized unique names among live rows, a single live
+//! owner, live references, row versions that go up on every change, and
+//! all-or-nothing bulk writes. They keep no change history.
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    backup::same_game,
+    model::{
+        AuditLogModel, BackupTables, EventModel, GameModel, IdempotencyKeyModel, InvitationModel,
+        PlayModel, PlayParticipantModel, PlayerModel, PlayerPlayModel, PlayerStatsModel, Rsvp,
+        SessionModel,
+    },
+    response::{EventSessionData, MergedGameData, MergedPlayerData, OwnerData, PurgedCounts},
+    schema::{
+        EventSchema, GameSchema, PlayParticipantSchema, PlaySchema, PlayerSchema, RsvpSchema,
+        SessionSchema, UpdateEventSchema, UpdateGameSchema, UpdatePlayParticipantSchema,
+        UpdatePlaySchema, UpdatePlayerSchema, UpdateSessionSchema,
+    },
+};
+
+use super::{
+    BackupRepository, BulkCommit, EventRepository, GameRepository, HistoryRepository,
+    IdempotencyRepository, ParticipantRepository, PlayRepository, PlayerRepository, Precondition,
+    RepoError, SessionRepository, StatsRepository, TrashRepository, attendees, check,
+    calendar_event, calendar_session, check_game_ranges, invitations, normalize_name,
+};
+
+#[derive(Clone, Default)]
+struct Tables {
+    games: Vec<GameModel>,
+    players: Vec<PlayerModel>,
+    sessions: Vec<SessionModel>,
+    events: Vec<EventModel>,
+    plays: Vec<PlayModel>,
+    participants: Vec<PlayParticipantModel>,
+    /// Claimed keys by key and path, with the time they were claimed
+    idempotency_keys: HashMap<(String, String), (DateTime<Utc>, IdempotencyKeyModel)>,
+}
+
+/// Every repository trait, implemented over one set of in-memory tables
+#[derive(Default)]
+pub struct MemoryRepository {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryRepository {
+    /// App state whose repositories all share one empty `MemoryRepository`
+    pub fn app_state() -> Arc<AppState> {
+        Arc::new(AppState::new(Arc::new(MemoryRepository::default()), 30, 24))
+    }
+
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap()
+    }
+}
+
+/// Runs `write` for every item, keeping the tables as they were before when
+/// `commit` says nothing is kept
+fn bulk<I, T>(
+    tables: &mut Tables,
+    items: &[I],
+    commit: BulkCommit,
+    mut write: impl FnMut(&mut Tables, &I) -> Result<T, RepoError>,
+) -> Vec<Result<T, RepoError>> {
+    let before = tables.clone();
+    let results: Vec<_> = items.iter().map(|item| write(tables, item)).collect();
+
+    let keep = match commit {
+        BulkCommit::Successful => true,
+        BulkCommit::AllOrNothing => results.iter().all(Result::is_ok),
+        BulkCommit::Nothing => false,
+    };
+    if !keep {
+        *tables = before;
+    }
+    results
+}
+
+impl Tables {
+    fn live_game(&self, id: Uuid) -> Option<usize> {
+        self.games
+            .iter()
+            .position(|game| game.id == id && game.deleted_at.is_none())
+    }
+
+    fn live_player(&self, id: Uuid) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.id == id && player.deleted_at.is_none())
+    }
+
+    fn live_play(&self, id: Uuid) -> Option<usize> {
+        self.plays
+            .iter()
+            .position(|play| play.id == id && play.deleted_at.is_none())
+    }
+
+    fn session(&self, id: Uuid) -> Option<usize> {
+        self.sessions.iter().position(|session| session.id == id)
+    }
+
+    fn event(&self, id: Uuid) -> Option<usize> {
+        self.events.iter().position(|event| event.id == id)
+    }
+
+    /// Checks that the host of a session or event and the given players are
+    /// live players; `field` names the players
+    fn check_players(
+        &self,
+        host_id: Option<Uuid>,
+        player_ids: &[Uuid],
+        field: &'static str,
+    ) -> Result<(), RepoError> {
+        let mut missing = Vec::new();
+        if host_id.is_some_and(|id| self.live_player(id).is_none()) {
+            missing.push("host_id");
+        }
+        if player_ids.iter().any(|&id| self.live_player(id).is_none()) {
+            missing.push(field);
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(RepoError::MissingReferences(missing))
+        }
+    }
+
+    fn participant(&self, play_id: Uuid, player_id: Uuid) -> Option<usize> {
+        self.participants
+            .iter()
+            .position(|p| p.play_id == play_id && p.player_id == player_id)
+    }
+
+    /// The live game other than `id` a name and creator clash with, or the
+    /// one with the same BGG ID
+    fn game_clash(
+        &self,
+        id: Option<Uuid>,
+        name: &str,
+        creator_name: &str,
+        bgg_id: Option<i32>,
+    ) -> Option<Uuid> {
+        self.games
+            .iter()
+            .find(|game| {
+                Some(game.id) != id
+                    && game.deleted_at.is_none()
+                    && (normalize_name(&game.name) == normalize_name(name)
+                        && normalize_name(&game.creator_name) == normalize_name(creator_name)
+                        || bgg_id.is_some() && game.bgg_id == bgg_id)
+            })
+            .map(|game| game.id)
+    }
+
+    /// Checks a player's name and owner flag against the live players other
+    /// than `id`
+    fn player_clash(&self, id: Option<Uuid>, name: &str, is_owner: bool) -> Result<(), RepoError> {
+        let others = || {
+            self.players
+                .iter()
+                .filter(move |player| Some(player.id) != id && player.deleted_at.is_none())
+        };
+
+        if let Some(player) = others().find(|p| normalize_name(&p.name) == normalize_name(name)) {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(player.id),
+            });
+        }
+        if is_owner && let Some(owner) = others().find(|player| player.is_owner) {
+            return Err(RepoError::OwnerExists {
+                owner_id: Some(owner.id),
+            });
+        }
+        Ok(())
+    }
+
+    fn insert_player(&mut self, body: &PlayerSchema) -> Result<PlayerModel, RepoError> {
+        let is_owner = body.is_owner.unwrap_or(false);
+        self.player_clash(None, &body.name, is_owner)?;
+
+        let player = PlayerModel {
+            id: Uuid::new_v4(),
+            name: body.name.clone(),
+            is_owner,
+            bgg_username: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        self.players.push(player.clone());
+        Ok(player)
+    }
+
+    fn insert_play(&mut self, body: &PlaySchema) -> Result<PlayModel, RepoError> {
+        if self.live_game(body.game_id).is_none() {
+            return Err(RepoError::MissingReferences(vec!["game_id"]));
+        }
+        if body.session_id.is_some_and(|id| self.session(id).is_none()) {
+            return Err(RepoError::MissingReferences(vec!["session_id"]));
+        }
+
+        let play = PlayModel {
+            id: Uuid::new_v4(),
+            game_id: body.game_id,
+            session_id: body.session_id,
+            location: body.location.clone(),
+            length_minutes: body.length_minutes,
+            comments: body.comments.clone(),
+            bgg_play_id: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        self.plays.push(play.clone());
+        Ok(play)
+    }
+
+    fn trash_play(&mut self, id: Uuid) -> Result<PlayModel, RepoError> {
+        let index = self.live_play(id).ok_or(RepoError::NotFound)?;
+        let play = &mut self.plays[index];
+        play.deleted_at = Some(Utc::now());
+        play.version += 1;
+        Ok(play.clone())
+    }
+
+    fn check_references(&self, play_id: Uuid, player_id: Uuid) -> Result<(), RepoError> {
+        let mut missing = Vec::new();
+        if self.live_play(play_id).is_none() {
+            missing.push("play_id");
+        }
+        if self.live_player(player_id).is_none() {
+            missing.push("player_id");
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(RepoError::MissingReferences(missing))
+        }
+    }
+
+    /// `sessions`, `events` and `plays`, with the participants of the plays,
+    /// the games played or brought and the players who took part, attended
+    /// or were invited
+    fn play_tables(
+        &self,
+        sessions: Vec<SessionModel>,
+        events: Vec<EventModel>,
+        mut plays: Vec<PlayModel>,
+    ) -> BackupTables {
+        plays.sort_by_key(|play| (play.created_at, play.id));
+        let play_ids: HashSet<_> = plays.iter().map(|play| play.id).collect();
+        let play_participants: Vec<_> = self
+            .participants
+            .iter()
+            .filter(|participant| play_ids.contains(&participant.play_id))
+            .cloned()
+            .collect();
+        let invitations = events.iter().flat_map(|event| event.invitations.iter());
+        let game_ids: HashSet<_> = plays
+            .iter()
+            .map(|play| play.game_id)
+            .chain(invitations.clone().flat_map(|i| i.game_ids.clone()))
+            .collect();
+        let player_ids: HashSet<_> = play_participants
+            .iter()
+            .map(|participant| participant.player_id)
+            .chain(
+                sessions
+                    .iter()
+                    .flat_map(|session| session.attendee_ids.clone()),
+            )
+            .chain(invitations.map(|invitation| invitation.player_id))
+            .collect();
+
+        BackupTables {
+            games: self
+                .games
+                .iter()
+                .filter(|game| game_ids.contains(&game.id))
+                .cloned()
+                .collect(),
+            players: self
+                .players
+                .iter()
+                .filter(|player| player_ids.contains(&player.id))
+                .cloned()
+                .collect(),
+            sessions,
+            events,
+            plays,
+            play_participants,
+            ..BackupTables::default()
+        }
+    }
+
+    /// The live plays `keep` picks
+    fn live_plays(&self, keep: impl Fn(&PlayModel) -> bool) -> Vec<PlayModel> {
+        self.plays
+            .iter()
+            .filter(|play| play.deleted_at.is_none() && keep(play))
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl GameRepository for MemoryRepository {
+    async fn create(&self, game: &GameSchema) -> Result<GameModel, RepoError> {
+        check_game_ranges(
+            game.min_players,
+            game.max_players,
+            game.min_playtime_minutes,
+            game.max_playtime_minutes,
+        )?;
+        let mut tables = self.tables();
+        if let Some(existing_id) =
+            tables.game_clash(None, &game.name, &game.creator_name, game.bgg_id)
+        {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(existing_id),
+            });
+        }
+
+        let game = GameModel {
+            id: Uuid::new_v4(),
+            name: game.name.clone(),
+            creator_name: game.creator_name.clone(),
+            bgg_id: game.bgg_id,
+            year_published: game.year_published,
+            min_players: game.min_players,
+            max_players: game.max_players,
+            min_playtime_minutes: game.min_playtime_minutes,
+            max_playtime_minutes: game.max_playtime_minutes,
+            weight: game.weight,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        };
+        tables.games.push(game.clone());
+        Ok(game)
+    }
+
+    async fn list(&self) -> Result<Vec<GameModel>, RepoError> {
+        let mut games: Vec<_> = self
+            .tables()
+            .games
+            .iter()
+            .filter(|game| game.deleted_at.is_none())
+            .cloned()
+            .collect();
+        games.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(games)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<GameModel, RepoError> {
+        let tables = self.tables();
+        let index = tables.live_game(id).ok_or(RepoError::NotFound)?;
+        Ok(tables.games[index].clone())
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateGameSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_game(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.games[index].version)?;
+
+        let game = &tables.games[index];
+        let updated = GameModel {
+            name: changes.name.clone().unwrap_or_else(|| game.name.clone()),
+            creator_name: changes
+                .creator_name
+                .clone()
+                .unwrap_or_else(|| game.creator_name.clone()),
+            bgg_id: changes.bgg_id.unwrap_or(game.bgg_id),
+            year_published: changes.year_published.unwrap_or(game.year_published),
+            min_players: changes.min_players.unwrap_or(game.min_players),
+            max_players: changes.max_players.unwrap_or(game.max_players),
+            min_playtime_minutes: changes
+                .min_playtime_minutes
+                .unwrap_or(game.min_playtime_minutes),
+            max_playtime_minutes: changes
+                .max_playtime_minutes
+                .unwrap_or(game.max_playtime_minutes),
+            weight: changes.weight.unwrap_or(game.weight),
+            ..game.clone()
+        };
+        check_game_ranges(
+            updated.min_players,
+            updated.max_players,
+            updated.min_playtime_minutes,
+            updated.max_playtime_minutes,
+        )?;
+        if let Some(existing_id) = tables.game_clash(
+            Some(id),
+            &updated.name,
+            &updated.creator_name,
+            updated.bgg_id,
+        ) {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(existing_id),
+            });
+        }
+
+        let game = &mut tables.games[index];
+        if !same_game(game, &updated) {
+            *game = updated;
+            game.version += 1;
+        }
+        Ok(game.clone())
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_game(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.games[index].version)?;
+
+        if tables
+            .plays
+            .iter()
+            .any(|play| play.game_id == id && play.deleted_at.is_none())
+        {
+            return Err(RepoError::InUse);
+        }
+
+        let game = &mut tables.games[index];
+        game.deleted_at = Some(Utc::now());
+        game.version += 1;
+        Ok(game.clone())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<GameModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables
+            .games
+            .iter()
+            .position(|game| game.id == id && game.deleted_at.is_some())
+            .ok_or(RepoError::NotFound)?;
+
+        let game = &tables.games[index];
+        if let Some(existing_id) =
+            tables.game_clash(Some(id), &game.name, &game.creator_name, game.bgg_id)
+        {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(existing_id),
+            });
+        }
+
+        let game = &mut tables.games[index];
+        game.deleted_at = None;
+        game.version += 1;
+        Ok(game.clone())
+    }
+
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedGameData, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_game(id).ok_or(RepoError::NotFound)?;
+        let target = tables
+            .live_game(target_id)
+            .ok_or(RepoError::MissingReferences(vec!["target_id"]))?;
+
+        let mut moved_plays = 0;
+        for play in tables.plays.iter_mut().filter(|play| play.game_id == id) {
+            play.game_id = target_id;
+            play.version += 1;
+            moved_plays += 1;
+        }
+
+        // Games brought to an event change the event; a player bringing both
+        // brings the target once
+        for event in tables.events.iter_mut() {
+            let mut brought = false;
+            for invitation in event.invitations.iter_mut() {
+                if invitation.game_ids.contains(&id) {
+                    invitation.game_ids.retain(|&game_id| game_id != id);
+                    invitation.game_ids.push(target_id);
+                    invitation.game_ids.sort();
+                    invitation.game_ids.dedup();
+                    brought = true;
+                }
+            }
+            if brought {
+                event.version += 1;
+            }
+        }
+
+        let game = tables.games[target].clone();
+        let merged_game = tables.games.remove(index);
+
+        Ok(MergedGameData {
+            game,
+            merged_game,
+            moved_plays,
+        })
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<GameModel>, RepoError> {
+        let mut games: Vec<_> = self
+            .tables()
+            .games
+            .iter()
+            .filter(|game| game.deleted_at.is_some())
+            .cloned()
+            .collect();
+        games.sort_by_key(|row| Reverse(row.deleted_at));
+        Ok(games)
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for MemoryRepository {
+    async fn create(&self, player: &PlayerSchema) -> Result<PlayerModel, RepoError> {
+        self.tables().insert_player(player)
+    }
+
+    async fn create_many(
+        &self,
+        players: &[PlayerSchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayerModel, RepoError>>, RepoError> {
+        Ok(bulk(
+            &mut self.tables(),
+            players,
+            commit,
+            Tables::insert_player,
+        ))
+    }
+
+    async fn list(&self) -> Result<Vec<PlayerModel>, RepoError> {
+        let mut players: Vec<_> = self
+            .tables()
+            .players
+            .iter()
+            .filter(|player| player.deleted_at.is_none())
+            .cloned()
+            .collect();
+        players.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(players)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<PlayerModel, RepoError> {
+        let tables = self.tables();
+        let index = tables.live_player(id).ok_or(RepoError::NotFound)?;
+        Ok(tables.players[index].clone())
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlayerSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_player(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.players[index].version)?;
+
+        let player = &tables.players[index];
+        let name = changes.name.clone().unwrap_or_else(|| player.name.clone());
+        let is_owner = changes.is_owner.unwrap_or(player.is_owner);
+        tables.player_clash(Some(id), &name, is_owner)?;
+
+        let player = &mut tables.players[index];
+        if player.name != name || player.is_owner != is_owner {
+            player.name = name;
+            player.is_owner = is_owner;
+            player.version += 1;
+        }
+        Ok(player.clone())
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_player(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.players[index].version)?;
+
+        let player = &mut tables.players[index];
+        player.deleted_at = Some(Utc::now());
+        player.version += 1;
+        Ok(player.clone())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<PlayerModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables
+            .players
+            .iter()
+            .position(|player| player.id == id && player.deleted_at.is_some())
+            .ok_or(RepoError::NotFound)?;
+
+        let player = &tables.players[index];
+        tables.player_clash(Some(id), &player.name, player.is_owner)?;
+
+        let player = &mut tables.players[index];
+        player.deleted_at = None;
+        player.version += 1;
+        Ok(player.clone())
+    }
+
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedPlayerData, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_player(id).ok_or(RepoError::NotFound)?;
+        let target = tables
+            .live_player(target_id)
+            .ok_or(RepoError::MissingReferences(vec!["target_id"]))?;
+
+        // Plays both players took part in would collide on (play_id, player_id)
+        let shared: HashSet<Uuid> = tables
+            .participants
+            .iter()
+            .filter(|p| p.player_id == target_id)
+            .map(|p| p.play_id)
+            .collect();
+        let before = tables.participants.len();
+        tables
+            .participants
+            .retain(|p| !(p.player_id == id && shared.contains(&p.play_id)));
+        let dropped_participations = (before - tables.participants.len()) as u64;
+
+        let mut moved_participations = 0;
+        for participant in tables.participants.iter_mut().filter(|p| p.player_id == id) {
+            participant.player_id = target_id;
+            participant.version += 1;
+            moved_participations += 1;
+        }
+
+        // The sessions change along with their host or attendance
+        for session in tables
+            .sessions
+            .iter_mut()
+            .filter(|s| s.host_id == Some(id) || s.attendee_ids.contains(&id))
+        {
+            if session.host_id == Some(id) {
+                session.host_id = Some(target_id);
+            }
+            let listed: Vec<_> = session
+                .attendee_ids
+                .iter()
+                .map(|&player_id| {
+                    if player_id == id {
+                        target_id
+                    } else {
+                        player_id
+                    }
+                })
+                .collect();
+            session.attendee_ids = attendees(None, &listed);
+            session.version += 1;
+        }
+
+        // Events likewise; an invitation both players had keeps the target's
+        // answer, and the games brought follow a moved invitation
+        for event in tables
+            .events
+            .iter_mut()
+            .filter(|e| e.host_id == Some(id) || e.invitations.iter().any(|i| i.player_id == id))
+        {
+            if event.host_id == Some(id) {
+                event.host_id = Some(target_id);
+            }
+            if event.invitations.iter().any(|i| i.player_id == target_id) {
+                event.invitations.retain(|i| i.player_id != id);
+            }
+            for invitation in event.invitations.iter_mut().filter(|i| i.player_id == id) {
+                invitation.player_id = target_id;
+            }
+            event.invitations.sort_by_key(|i| i.player_id);
+            event.version += 1;
+        }
+
+        let merged_player = tables.players[index].clone();
+        let player = &mut tables.players[target];
+        if merged_player.is_owner && !player.is_owner {
+            player.is_owner = true;
+            player.version += 1;
+        }
+        let player = player.clone();
+        tables.players.remove(index);
+
+        Ok(MergedPlayerData {
+            player,
+            merged_player,
+            moved_participations,
+            dropped_participations,
+        })
+    }
+
+    async fn make_owner(&self, id: Uuid) -> Result<OwnerData, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_player(id).ok_or(RepoError::NotFound)?;
+
+        // Also clears the flag on a trashed former owner so restoring it cannot clash
+        let mut previous_owner = None;
+        for player in tables
+            .players
+            .iter_mut()
+            .filter(|player| player.is_owner && player.id != id)
+        {
+            player.is_owner = false;
+            player.version += 1;
+            if player.deleted_at.is_none() {
+                previous_owner = Some(player.clone());
+            }
+        }
+
+        let player = &mut tables.players[index];
+        if !player.is_owner {
+            player.is_owner = true;
+            player.version += 1;
+        }
+
+        Ok(OwnerData {
+            player: player.clone(),
+            previous_owner,
+        })
+    }
+
+    async fn owner(&self) -> Result<Option<PlayerModel>, RepoError> {
+        Ok(self
+            .tables()
+            .players
+            .iter()
+            .find(|player| player.is_owner && player.deleted_at.is_none())
+            .cloned())
+    }
+
+    async fn stats(&self, id: Uuid) -> Result<PlayerStatsModel, RepoError> {
+        let plays = self.plays(id).await?;
+        let games: HashSet<Uuid> = plays.iter().map(|play| play.game_id).collect();
+
+        Ok(PlayerStatsModel {
+            plays: plays.len() as i64,
+            wins: plays.iter().filter(|play| play.is_winner).count() as i64,
+            games: games.len() as i64,
+        })
+    }
+
+    async fn plays(&self, id: Uuid) -> Result<Vec<PlayerPlayModel>, RepoError> {
+        let tables = self.tables();
+        let mut plays: Vec<_> = tables
+            .participants
+            .iter()
+            .filter(|p| p.player_id == id)
+            .filter_map(|p| {
+                let play = &tables.plays[tables.live_play(p.play_id)?];
+                Some(PlayerPlayModel {
+                    id: play.id,
+                    game_id: play.game_id,
+                    created_at: play.created_at,
+                    score: p.score,
+                    is_winner: p.is_winner,
+                })
+            })
+            .collect();
+        plays.sort_by_key(|play| Reverse(play.created_at));
+        Ok(plays)
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<PlayerModel>, RepoError> {
+        let mut players: Vec<_> = self
+            .tables()
+            .players
+            .iter()
+            .filter(|player| player.deleted_at.is_some())
+            .cloned()
+            .collect();
+        players.sort_by_key(|row| Reverse(row.deleted_at));
+        Ok(players)
+    }
+}
+
+#[async_trait]
+impl PlayRepository for MemoryRepository {
+    async fn create(&self, play: &PlaySchema) -> Result<PlayModel, RepoError> {
+        self.tables().insert_play(play)
+    }
+
+    async fn create_many(
+        &self,
+        plays: &[PlaySchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError> {
+        Ok(bulk(&mut self.tables(), plays, commit, Tables::insert_play))
+    }
+
+    async fn list(&self) -> Result<Vec<PlayModel>, RepoError> {
+        let mut plays: Vec<_> = self
+            .tables()
+            .plays
+            .iter()
+            .filter(|play| play.deleted_at.is_none())
+            .cloned()
+            .collect();
+        plays.sort_by_key(|play| play.game_id);
+        Ok(plays)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<PlayModel, RepoError> {
+        let tables = self.tables();
+        let index = tables.live_play(id).ok_or(RepoError::NotFound)?;
+        Ok(tables.plays[index].clone())
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlaySchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_play(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.plays[index].version)?;
+
+        let game_id = changes.game_id.unwrap_or(tables.plays[index].game_id);
+        if tables.live_game(game_id).is_none() {
+            return Err(RepoError::MissingReferences(vec!["game_id"]));
+        }
+        let session_id = changes.session_id.unwrap_or(tables.plays[index].session_id);
+        if session_id.is_some_and(|id| tables.session(id).is_none()) {
+            return Err(RepoError::MissingReferences(vec!["session_id"]));
+        }
+
+        let play = &mut tables.plays[index];
+        let location = changes.location.clone().unwrap_or(play.location.clone());
+        let length_minutes = changes.length_minutes.unwrap_or(play.length_minutes);
+        let comments = changes.comments.clone().unwrap_or(play.comments.clone());
+        if play.game_id != game_id
+            || play.session_id != session_id
+            || play.location != location
+            || play.length_minutes != length_minutes
+            || play.comments != comments
+        {
+            play.game_id = game_id;
+            play.session_id = session_id;
+            play.location = location;
+            play.length_minutes = length_minutes;
+            play.comments = comments;
+            play.version += 1;
+        }
+        Ok(play.clone())
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.live_play(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.plays[index].version)?;
+        tables.trash_play(id)
+    }
+
+    async fn trash_many(
+        &self,
+        ids: &[Uuid],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError> {
+        Ok(bulk(&mut self.tables(), ids, commit, |tables, id| {
+            tables.trash_play(*id)
+        }))
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<PlayModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables
+            .plays
+            .iter()
+            .position(|play| play.id == id && play.deleted_at.is_some())
+            .ok_or(RepoError::NotFound)?;
+
+        let game_id = tables.plays[index].game_id;
+        if tables.live_game(game_id).is_none() {
+            return Err(RepoError::ReferenceTrashed { id: game_id });
+        }
+
+        let play = &mut tables.plays[index];
+        play.deleted_at = None;
+        play.version += 1;
+        Ok(play.clone())
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<PlayModel>, RepoError> {
+        let mut plays: Vec<_> = self
+            .tables()
+            .plays
+            .iter()
+            .filter(|play| play.deleted_at.is_some())
+            .cloned()
+            .collect();
+        plays.sort_by_key(|row| Reverse(row.deleted_at));
+        Ok(plays)
+    }
+}
+
+#[async_trait]
+impl ParticipantRepository for MemoryRepository {
+    async fn create(
+        &self,
+        participant: &PlayParticipantSchema,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tables = self.tables();
+        tables.check_references(participant.play_id, participant.player_id)?;
+        if tables
+            .participant(participant.play_id, participant.player_id)
+            .is_some()
+        {
+            return Err(RepoError::Duplicate { existing_id: None });
+        }
+
+        let participant = PlayParticipantModel {
+            play_id: participant.play_id,
+            player_id: participant.player_id,
+            score: participant.score,
+            is_winner: participant.is_winner.unwrap_or(false),
+            version: 1,
+        };
+        tables.participants.push(participant.clone());
+        Ok(participant)
+    }
+
+    async fn list(&self) -> Result<Vec<PlayParticipantModel>, RepoError> {
+        let tables = self.tables();
+        Ok(tables
+            .participants
+            .iter()
+            .filter(|p| {
+                tables.live_play(p.play_id).is_some() && tables.live_player(p.player_id).is_some()
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn list_for_play(&self, play_id: Uuid) -> Result<Vec<PlayParticipantModel>, RepoError> {
+        let participants = ParticipantRepository::list(self).await?;
+        Ok(participants
+            .into_iter()
+            .filter(|p| p.play_id == play_id)
+            .collect())
+    }
+
+    async fn update(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        changes: &UpdatePlayParticipantSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables
+            .participant(play_id, player_id)
+            .ok_or(RepoError::NotFound)?;
+        check(precondition, tables.participants[index].version)?;
+
+        let current = tables.participants[index].clone();
+        let updated = PlayParticipantModel {
+            play_id: changes.play_id.unwrap_or(current.play_id),
+            player_id: changes.player_id.unwrap_or(current.player_id),
+            score: changes.score.unwrap_or(current.score),
+            is_winner: changes.is_winner.unwrap_or(current.is_winner),
+            version: current.version,
+        };
+
+        tables.check_references(updated.play_id, updated.player_id)?;
+        if (updated.play_id, updated.player_id) != (play_id, player_id)
+            && tables
+                .participant(updated.play_id, updated.player_id)
+                .is_some()
+        {
+            return Err(RepoError::Duplicate { existing_id: None });
+        }
+
+        let participant = &mut tables.participants[index];
+        if (
+            updated.play_id,
+            updated.player_id,
+            updated.score,
+            updated.is_winner,
+        ) != (
+            participant.play_id,
+            participant.player_id,
+            participant.score,
+            participant.is_winner,
+        ) {
+            *participant = PlayParticipantModel {
+                version: participant.version + 1,
+                ..updated
+            };
+        }
+        Ok(participant.clone())
+    }
+
+    async fn delete(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables
+            .participant(play_id, player_id)
+            .ok_or(RepoError::NotFound)?;
+        check(precondition, tables.participants[index].version)?;
+        Ok(tables.participants.remove(index))
+    }
+}
+
+/// Nothing is recorded, so there is no history to list or revert to
+#[async_trait]
+impl SessionRepository for MemoryRepository {
+    async fn create(&self, session: &SessionSchema) -> Result<SessionModel, RepoError> {
+        let mut tables = self.tables();
+        let attendee_ids = attendees(session.host_id, &session.attendee_ids);
+        tables.check_players(session.host_id, &attendee_ids, "attendee_ids")?;
+
+        let session = SessionModel {
+            id: Uuid::new_v4(),
+            played_on: session.played_on,
+            location: session.location.clone(),
+            host_id: session.host_id,
+            attendee_ids,
+            created_at: Utc::now(),
+            version: 1,
+        };
+        tables.sessions.push(session.clone());
+        Ok(session)
+    }
+
+    async fn list(&self) -> Result<Vec<SessionModel>, RepoError> {
+        let mut sessions = self.tables().sessions.clone();
+        sessions.sort_by_key(|session| Reverse((session.played_on, session.created_at)));
+        Ok(sessions)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<SessionModel, RepoError> {
+        let tables = self.tables();
+        let index = tables.session(id).ok_or(RepoError::NotFound)?;
+        Ok(tables.sessions[index].clone())
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateSessionSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.session(id).ok_or(RepoError::NotFound)?;
+        let session = tables.sessions[index].clone();
+        check(precondition, session.version)?;
+
+        let played_on = changes.played_on.unwrap_or(session.played_on);
+        let location = changes.location.clone().unwrap_or(session.location.clone());
+        let host_id = changes.host_id.unwrap_or(session.host_id);
+        let listed = changes
+            .attendee_ids
+            .as_deref()
+            .unwrap_or(&session.attendee_ids);
+        let attendee_ids = attendees(host_id, listed);
+
+        // Players who already attend may have been trashed since
+        let added: Vec<_> = attendee_ids
+            .iter()
+            .copied()
+            .filter(|player_id| !session.attendee_ids.contains(player_id))
+            .collect();
+        let new_host = host_id.filter(|_| host_id != session.host_id);
+        tables.check_players(new_host, &added, "attendee_ids")?;
+
+        let stored = &mut tables.sessions[index];
+        if (played_on, &location, host_id, &attendee_ids)
+            != (
+                stored.played_on,
+                &stored.location,
+                stored.host_id,
+                &stored.attendee_ids,
+            )
+        {
+            stored.played_on = played_on;
+            stored.location = location;
+            stored.host_id = host_id;
+            stored.attendee_ids = attendee_ids;
+            stored.version += 1;
+        }
+        Ok(stored.clone())
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.session(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.sessions[index].version)?;
+
+        for play in tables
+            .plays
+            .iter_mut()
+            .filter(|play| play.session_id == Some(id))
+        {
+            play.session_id = None;
+            play.version += 1;
+        }
+        for event in tables
+            .events
+            .iter_mut()
+            .filter(|event| event.session_id == Some(id))
+        {
+            event.session_id = None;
+            event.version += 1;
+        }
+        Ok(tables.sessions.remove(index))
+    }
+}
+
+#[async_trait]
+impl EventRepository for MemoryRepository {
+    async fn create(&self, event: &EventSchema) -> Result<EventModel, RepoError> {
+        let mut tables = self.tables();
+        tables.check_players(event.host_id, &event.invitee_ids, "invitee_ids")?;
+
+        let event = EventModel {
+            id: Uuid::new_v4(),
+            scheduled_on: event.scheduled_on,
+            location: event.location.clone(),
+            host_id: event.host_id,
+            session_id: None,
+            invitations: invitations(&[], event.host_id, &event.invitee_ids),
+            created_at: Utc::now(),
+            version: 1,
+        };
+        tables.events.push(event.clone());
+        Ok(event)
+    }
+
+    async fn list(&self) -> Result<Vec<EventModel>, RepoError> {
+        let mut events = self.tables().events.clone();
+        events.sort_by_key(|event| (event.scheduled_on, event.created_at));
+        Ok(events)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<EventModel, RepoError> {
+        let tables = self.tables();
+        let index = tables.event(id).ok_or(RepoError::NotFound)?;
+        Ok(tables.events[index].clone())
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateEventSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.event(id).ok_or(RepoError::NotFound)?;
+        let event = tables.events[index].clone();
+        check(precondition, event.version)?;
+
+        let scheduled_on = changes.scheduled_on.unwrap_or(event.scheduled_on);
+        let location = changes.location.clone().unwrap_or(event.location.clone());
+        let host_id = changes.host_id.unwrap_or(event.host_id);
+        let current_ids: Vec<_> = event.invitations.iter().map(|i| i.player_id).collect();
+        let listed = changes.invitee_ids.as_deref().unwrap_or(&current_ids);
+        let new_invitations = invitations(&event.invitations, host_id, listed);
+
+        // Players who are invited already may have been trashed since
+        let added: Vec<_> = new_invitations
+            .iter()
+            .map(|invitation| invitation.player_id)
+            .filter(|player_id| !current_ids.contains(player_id))
+            .collect();
+        let new_host = host_id.filter(|_| host_id != event.host_id);
+        tables.check_players(new_host, &added, "invitee_ids")?;
+
+        let stored = &mut tables.events[index];
+        if (scheduled_on, &location, host_id, &new_invitations)
+            != (
+                stored.scheduled_on,
+                &stored.location,
+                stored.host_id,
+                &stored.invitations,
+            )
+        {
+            stored.scheduled_on = scheduled_on;
+            stored.location = location;
+            stored.host_id = host_id;
+            stored.invitations = new_invitations;
+            stored.version += 1;
+        }
+        Ok(stored.clone())
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.event(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.events[index].version)?;
+        Ok(tables.events.remove(index))
+    }
+
+    async fn respond(
+        &self,
+        id: Uuid,
+        player_id: Uuid,
+        rsvp: &RsvpSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.event(id).ok_or(RepoError::NotFound)?;
+        check(precondition, tables.events[index].version)?;
+
+        let mut missing = Vec::new();
+        if tables.live_player(player_id).is_none() {
+            missing.push("player_id");
+        }
+        if rsvp
+            .game_ids
+            .iter()
+            .any(|&game_id| tables.live_game(game_id).is_none())
+        {
+            missing.push("game_ids");
+        }
+        if !missing.is_empty() {
+            return Err(RepoError::MissingReferences(missing));
+        }
+
+        let mut game_ids = rsvp.game_ids.clone();
+        game_ids.sort();
+        game_ids.dedup();
+        let event = &mut tables.events[index];
+        event.invitations.retain(|i| i.player_id != player_id);
+        event.invitations.push(InvitationModel {
+            player_id,
+            response: Some(rsvp.response),
+            responded_at: Some(Utc::now()),
+            game_ids,
+        });
+        event.invitations.sort_by_key(|i| i.player_id);
+        event.version += 1;
+        Ok(event.clone())
+    }
+
+    async fn start_session(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventSessionData, RepoError> {
+        let mut tables = self.tables();
+        let index = tables.event(id).ok_or(RepoError::NotFound)?;
+        let event = tables.events[index].clone();
+        check(precondition, event.version)?;
+        if let Some(session_id) = event.session_id {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(session_id),
+            });
+        }
+
+        // Players who answered yes and the host, as far as they are still live
+        let host_id = event.host_id.filter(|&id| tables.live_player(id).is_some());
+        let yes: Vec<_> = event
+            .invitations
+            .iter()
+            .filter(|i| i.response == Some(Rsvp::Yes) && tables.live_player(i.player_id).is_some())
+            .map(|i| i.player_id)
+            .collect();
+
+        let session = SessionModel {
+            id: Uuid::new_v4(),
+            played_on: event.scheduled_on,
+            location: event.location.clone(),
+            host_id,
+            attendee_ids: attendees(host_id, &yes),
+            created_at: Utc::now(),
+            version: 1,
+        };
+        tables.sessions.push(session.clone());
+
+        let event = &mut tables.events[index];
+        event.session_id = Some(session.id);
+        event.version += 1;
+        Ok(EventSessionData {
+            event: event.clone(),
+            session,
+        })
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for MemoryRepository {
+    async fn list(&self, _entity: &str, _entity_id: &str) -> Result<Vec<AuditLogModel>, RepoError> {
+        Ok(Vec::new())
+    }
+
+    async fn get(
+        &self,
+        _entity: &str,
+        _entity_id: &str,
+        _audit_id: i64,
+    ) -> Result<AuditLogModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn revert_game(&self, _snapshot: &serde_json::Value) -> Result<GameModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn revert_player(&self, _snapshot: &serde_json::Value) -> Result<PlayerModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn revert_play(&self, _snapshot: &serde_json::Value) -> Result<PlayModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn revert_participant(
+        &self,
+        _snapshot: &serde_json::Value,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn revert_session(
+        &self,
+        _snapshot: &serde_json::Value,
+    ) -> Result<SessionModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn revert_event(&self, _snapshot: &serde_json::Value) -> Result<EventModel, RepoError> {
+        Err(RepoError::NotFound)
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for MemoryRepository {
+    async fn claim(
+        &self,
+        key: &str,
+        path: &str,
+        request_hash: &[u8],
+        cutoff: DateTime<Utc>,
+    ) -> Result<bool, RepoError> {
+        let mut tables = self.tables();
+        let id = (key.to_string(), path.to_string());
+        if let Some((created_at, _)) = tables.idempotency_keys.get(&id)
+            && *created_at >= cutoff
+        {
+            return Ok(false);
+        }
+        let entry = IdempotencyKeyModel {
+            request_hash: request_hash.to_vec(),
+            status_code: None,
+            content_type: None,
+            response_body: None,
+        };
+        tables.idempotency_keys.insert(id, (Utc::now(), entry));
+        Ok(true)
+    }
+
+    async fn get(&self, key: &str, path: &str) -> Result<IdempotencyKeyModel, RepoError> {
+        self.tables()
+            .idempotency_keys
+            .get(&(key.to_string(), path.to_string()))
+            .map(|(_, entry)| entry.clone())
+            .ok_or(RepoError::NotFound)
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        path: &str,
+        status_code: i16,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> Result<(), RepoError> {
+        if let Some((_, entry)) = self
+            .tables()
+            .idempotency_keys
+            .get_mut(&(key.to_string(), path.to_string()))
+        {
+            entry.status_code = Some(status_code);
+            entry.content_type = content_type.map(str::to_string);
+            entry.response_body = Some(response_body.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn release(&self, key: &str, path: &str) -> Result<(), RepoError> {
+        self.tables()
+            .idempotency_keys
+            .remove(&(key.to_string(), path.to_string()));
+        Ok(())
+    }
+
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<u64, RepoError> {
+        let mut tables = self.tables();
+        let before = tables.idempotency_keys.len();
+        tables
+            .idempotency_keys
+            .retain(|_, (created_at, _)| *created_at >= cutoff);
+        Ok((before - tables.idempotency_keys.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl TrashRepository for MemoryRepository {
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<PurgedCounts, RepoError> {
+        let mut tables = self.tables();
+        let purged = |deleted_at: Option<DateTime<Utc>>| deleted_at.is_some_and(|at| at < cutoff);
+
+        let Tables {
+            games,
+            players,
+            sessions,
+            events,
+            plays,
+            participants,
+            ..
+        } = &mut *tables;
+
+        let plays_before = plays.len();
+        plays.retain(|play| !purged(play.deleted_at));
+        participants.retain(|participant| plays.iter().any(|play| play.id == participant.play_id));
+
+        // Players who took part in a play or attended a session stay in the
+        // trash, so their history is kept
+        let players_before = players.len();
+        players.retain(|player| {
+            !purged(player.deleted_at)
+                || participants
+                    .iter()
+                    .any(|participant| participant.player_id == player.id)
+                || sessions
+                    .iter()
+                    .any(|session| session.attendee_ids.contains(&player.id))
+        });
+
+        for session in sessions.iter_mut() {
+            if session
+                .host_id
+                .is_some_and(|id| !players.iter().any(|player| player.id == id))
+            {
+                session.host_id = None;
+                session.version += 1;
+            }
+        }
+        let games_before = games.len();
+        games.retain(|game| {
+            !purged(game.deleted_at) || plays.iter().any(|play| play.game_id == game.id)
+        });
+        for event in events.iter_mut() {
+            if event
+                .host_id
+                .is_some_and(|id| !players.iter().any(|player| player.id == id))
+            {
+                event.host_id = None;
+                event.version += 1;
+            }
+            event
+                .invitations
+                .retain(|i| players.iter().any(|player| player.id == i.player_id));
+            for invitation in event.invitations.iter_mut() {
+                invitation
+                    .game_ids
+                    .retain(|id| games.iter().any(|game| game.id == *id));
+            }
+        }
+
+        Ok(PurgedCounts {
+            games: (games_before - games.len()) as u64,
+            players: (players_before - players.len()) as u64,
+            plays: (plays_before - plays.len()) as u64,
+        })
+    }
+}
+
+/// Replaces the row `same` finds, with its version bumped, or adds `row`
+fn upsert<T: Clone>(
+    rows: &mut Vec<T>,
+    row: &T,
+    same: impl Fn(&T) -> bool,
+    version: fn(&mut T) -> &mut i32,
+) {
+    match rows.iter_mut().find(|stored| same(stored)) {
+        Some(stored) => {
+            let next = *version(stored) + 1;
+            *stored = row.clone();
+            *version(stored) = next;
+        }
+        None => rows.push(row.clone()),
+    }
+}
+
+#[async_trait]
+impl BackupRepository for MemoryRepository {
+    async fn export(&self) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        Ok(BackupTables {
+            games: tables.games.clone(),
+            players: tables.players.clone(),
+            sessions: tables.sessions.clone(),
+            events: tables.events.clone(),
+            plays: tables.plays.clone(),
+            play_participants: tables.participants.clone(),
+            audit_log: Vec::new(),
+        })
+    }
+
+    // Nothing is audited in memory
+    async fn export_with_audit_log(&self) -> Result<BackupTables, RepoError> {
+        self.export().await
+    }
+
+    async fn restore(&self, rows: &BackupTables) -> Result<(), RepoError> {
+        let mut tables = self.tables();
+        let mut restored = tables.clone();
+
+        for game in &rows.games {
+            upsert(
+                &mut restored.games,
+                game,
+                |stored| stored.id == game.id,
+                |row| &mut row.version,
+            );
+        }
+        for player in &rows.players {
+            upsert(
+                &mut restored.players,
+                player,
+                |stored| stored.id == player.id,
+                |row| &mut row.version,
+            );
+        }
+        for session in &rows.sessions {
+            upsert(
+                &mut restored.sessions,
+                session,
+                |stored| stored.id == session.id,
+                |row| &mut row.version,
+            );
+        }
+        for event in &rows.events {
+            upsert(
+                &mut restored.events,
+                event,
+                |stored| stored.id == event.id,
+                |row| &mut row.version,
+            );
+        }
+        for play in &rows.plays {
+            upsert(
+                &mut restored.plays,
+                play,
+                |stored| stored.id == play.id,
+                |row| &mut row.version,
+            );
+        }
+        for participant in &rows.play_participants {
+            upsert(
+                &mut restored.participants,
+                participant,
+                |stored| {
+                    stored.play_id == participant.play_id
+                        && stored.player_id == participant.player_id
+                },
+                |row| &mut row.version,
+            );
+        }
+
+        let live_games: Vec<_> = restored
+            .games
+            .iter()
+            .filter(|game| game.deleted_at.is_none())
+            .collect();
+        let game_names: HashSet<_> = live_games
+            .iter()
+            .map(|game| {
+                (
+                    normalize_name(&game.name),
+                    normalize_name(&game.creator_name),
+                )
+            })
+            .collect();
+        let live_players: Vec<_> = restored
+            .players
+            .iter()
+            .filter(|player| player.deleted_at.is_none())
+            .collect();
+        let player_names: HashSet<_> = live_players
+            .iter()
+            .map(|player| normalize_name(&player.name))
+            .collect();
+        if game_names.len() < live_games.len() || player_names.len() < live_players.len() {
+            return Err(RepoError::Duplicate { existing_id: None });
+        }
+        if live_players.iter().filter(|player| player.is_owner).count() > 1 {
+            return Err(RepoError::OwnerExists { owner_id: None });
+        }
+
+        let mut missing = Vec::new();
+        if restored
+            .plays
+            .iter()
+            .any(|play| !restored.games.iter().any(|game| game.id == play.game_id))
+        {
+            missing.push("game_id");
+        }
+        let player_exists = |id: &Uuid| restored.players.iter().any(|player| player.id == *id);
+        if restored
+            .sessions
+            .iter()
+            .any(|session| session.host_id.is_some_and(|id| !player_exists(&id)))
+            || restored
+                .events
+                .iter()
+                .any(|event| event.host_id.is_some_and(|id| !player_exists(&id)))
+        {
+            missing.push("host_id");
+        }
+        if restored
+            .sessions
+            .iter()
+            .any(|session| !session.attendee_ids.iter().all(player_exists))
+        {
+            missing.push("attendee_ids");
+        }
+        let session_exists = |id: Uuid| restored.sessions.iter().any(|session| session.id == id);
+        if restored
+            .plays
+            .iter()
+            .any(|play| play.session_id.is_some_and(|id| !session_exists(id)))
+            || restored
+                .events
+                .iter()
+                .any(|event| event.session_id.is_some_and(|id| !session_exists(id)))
+        {
+            missing.push("session_id");
+        }
+        if restored.events.iter().any(|event| {
+            event.invitations.iter().any(|invitation| {
+                !invitation
+                    .game_ids
+                    .iter()
+                    .all(|id| restored.games.iter().any(|game| game.id == *id))
+            })
+        }) {
+            missing.push("game_ids");
+        }
+        if restored.participants.iter().any(|participant| {
+            !restored
+                .plays
+                .iter()
+                .any(|play| play.id == participant.play_id)
+        }) {
+            missing.push("play_id");
+        }
+        if restored.participants.iter().any(|participant| {
+            !restored
+                .players
+                .iter()
+                .any(|player| player.id == participant.player_id)
+        }) || restored.events.iter().any(|event| {
+            !event
+                .invitations
+                .iter()
+                .all(|invitation| player_exists(&invitation.player_id))
+        }) {
+            missing.push("player_id");
+        }
+        if !missing.is_empty() {
+            return Err(RepoError::MissingReferences(missing));
+        }
+
+        *tables = restored;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StatsRepository for MemoryRepository {
+    async fn calendar_plays(
+        &self,
+        player_id: Option<Uuid>,
+        game_id: Option<Uuid>,
+    ) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        let plays = tables.live_plays(|play| {
+            game_id.is_none_or(|id| play.game_id == id)
+                && player_id.is_none_or(|id| {
+                    tables.participants.iter().any(|participant| {
+                        participant.play_id == play.id && participant.player_id == id
+                    })
+                })
+        });
+        let sessions = tables
+            .sessions
+            .iter()
+            .filter(|session| calendar_session(session, &plays, player_id, game_id))
+            .cloned()
+            .collect();
+        let events = tables
+            .events
+            .iter()
+            .filter(|event| calendar_event(event, player_id, game_id))
+            .cloned()
+            .collect();
+        Ok(tables.play_tables(sessions, events, plays))
+    }
+
+    async fn year_plays(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        let mut first_plays: HashMap<_, PlayModel> = HashMap::new();
+        for play in tables.live_plays(|play| play.created_at < from) {
+            let first = first_plays.entry(play.game_id).or_insert(play.clone());
+            if (play.created_at, play.id) < (first.created_at, first.id) {
+                *first = play;
+            }
+        }
+        let mut plays: Vec<_> = first_plays.into_values().collect();
+        plays.extend(tables.live_plays(|play| from <= play.created_at && play.created_at < until));
+        Ok(tables.play_tables(Vec::new(), Vec::new(), plays))
+    }
+
+    async fn plays_until(&self, until: DateTime<Utc>) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        let plays = tables.live_plays(|play| play.created_at < until);
+        Ok(tables.play_tables(Vec::new(), Vec::new(), plays))
+    }
+
+    async fn player_history(&self, player_ids: &[Uuid]) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        let plays = tables.live_plays(|play| {
+            tables.participants.iter().any(|participant| {
+                participant.play_id == play.id && player_ids.contains(&participant.player_id)
+            })
+        });
+        Ok(BackupTables {
+            games: tables
+                .games
+                .iter()
+                .filter(|game| game.deleted_at.is_none())
+                .cloned()
+                .collect(),
+            players: tables
+                .players
+                .iter()
+                .filter(|player| player_ids.contains(&player.id))
+                .cloned()
+                .collect(),
+            ..tables.play_tables(Vec::new(), Vec::new(), plays)
+        })
+    }
+
+    async fn session_plays(&self, id: Uuid) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        let session = tables.session(id).ok_or(RepoError::NotFound)?;
+        let plays = tables.live_plays(|play| play.session_id == Some(id));
+        Ok(tables.play_tables(vec![tables.sessions[session].clone()], Vec::new(), plays))
+    }
+
+    async fn session_attendance(&self) -> Result<BackupTables, RepoError> {
+        let tables = self.tables();
+        let plays = tables.live_plays(|play| play.session_id.is_some());
+        Ok(tables.play_tables(tables.sessions.clone(), Vec::new(), plays))
+    }
+}
@@ -0,0 +1,505 @@
+//! Storage behind the handlers. Handlers only see these traits through
+//! `AppState`, so their logic runs the same against Postgres, against SQLite
+//! with the `sqlite` feature and against the in-memory implementation used by
+//! the unit tests.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        AuditLogModel, BackupTables, EventModel, GameModel, IdempotencyKeyModel, InvitationModel,
+        PlayModel, PlayParticipantModel, PlayerModel, PlayerPlayModel, PlayerStatsModel,
+        SessionModel,
+    },
+    response::{EventSessionData, MergedGameData, MergedPlayerData, OwnerData, PurgedCounts},
+    schema::{
+        EventSchema, GameSchema, PlayParticipantSchema, PlaySchema, PlayerSchema, RsvpSchema,
+        SessionSchema, UpdateEventSchema, UpdateGameSchema, UpdatePlayParticipantSchema,
+        UpdatePlaySchema, UpdatePlayerSchema, UpdateSessionSchema,
+    },
+};
+
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Decides from the current row version whether a change may go ahead, see
+/// `etag::if_match_allows`
+pub type Precondition<'a> = &'a (dyn Fn(i32) -> bool + Send + Sync);
+
+/// Why a repository call failed
+#[derive(Debug)]
+pub enum RepoError {
+    /// The row does not exist, or is not in the state the call needs, e.g. a
+    /// restore of a row that is not in the trash
+    NotFound,
+    /// The precondition rejected the current version
+    PreconditionFailed {
+        version: i32,
+    },
+    /// The row would clash with a live row on a unique name, a participant
+    /// would be added to a play twice or an event would get a second session
+    Duplicate {
+        existing_id: Option<Uuid>,
+    },
+    /// The row would become a second owner
+    OwnerExists {
+        owner_id: Option<Uuid>,
+    },
+    /// The row is still referenced by live rows, e.g. a game with plays
+    InUse,
+    /// Rows the change points at are missing or in the trash, by field name
+    MissingReferences(Vec<&'static str>),
+    /// The row points at a trashed row that has to be restored first
+    ReferenceTrashed {
+        id: Uuid,
+    },
+    /// The row would contradict itself, e.g. a game for more players at least
+    /// than at most, as field name and message
+    Invalid(Vec<(&'static str, &'static str)>),
+    Internal(String),
+}
+
+impl From<sqlx::Error> for RepoError {
+    fn from(e: sqlx::Error) -> Self {
+        RepoError::Internal(format!("{:?}", e))
+    }
+}
+
+/// Whether a session goes in the calendar of `plays`: `player_id` attended
+/// and, when `game_id` is given, one of `plays` belongs to it
+fn calendar_session(
+    session: &SessionModel,
+    plays: &[PlayModel],
+    player_id: Option<Uuid>,
+    game_id: Option<Uuid>,
+) -> bool {
+    player_id.is_none_or(|id| session.attendee_ids.contains(&id))
+        && (game_id.is_none() || plays.iter().any(|play| play.session_id == Some(session.id)))
+}
+
+/// Whether an event goes in the calendar: it has not turned into a session
+/// yet, `player_id` is invited and someone brings `game_id`, each filter
+/// applying when given
+fn calendar_event(event: &EventModel, player_id: Option<Uuid>, game_id: Option<Uuid>) -> bool {
+    event.session_id.is_none()
+        && player_id.is_none_or(|id| event.invitations.iter().any(|i| i.player_id == id))
+        && game_id.is_none_or(|id| {
+            event
+                .invitations
+                .iter()
+                .any(|invitation| invitation.game_ids.contains(&id))
+        })
+}
+
+/// Checks that the minimums a game would be stored with are not above its
+/// maximums, which a patch of either one alone cannot tell
+fn check_game_ranges(
+    min_players: Option<i32>,
+    max_players: Option<i32>,
+    min_playtime_minutes: Option<i32>,
+    max_playtime_minutes: Option<i32>,
+) -> Result<(), RepoError> {
+    let above =
+        |min: Option<i32>, max: Option<i32>| min.zip(max).is_some_and(|(min, max)| min > max);
+
+    let mut errors = Vec::new();
+    if above(min_players, max_players) {
+        errors.push(("max_players", "must be at least min_players"));
+    }
+    if above(min_playtime_minutes, max_playtime_minutes) {
+        errors.push((
+            "max_playtime_minutes",
+            "must be at least min_playtime_minutes",
+        ));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(RepoError::Invalid(errors))
+    }
+}
+
+fn check(precondition: Precondition<'_>, version: i32) -> Result<(), RepoError> {
+    if precondition(version) {
+        Ok(())
+    } else {
+        Err(RepoError::PreconditionFailed { version })
+    }
+}
+
+/// Same as the `normalize_name` SQL function of the Postgres schema, for
+/// comparing names in Rust: runs of ASCII whitespace become one space, leading
+/// and trailing ones are dropped and ASCII letters are lowercased. Anything
+/// beyond ASCII is kept as it is, since what the database's `lower` and `\s`
+/// make of it depends on its encoding and collation.
+pub fn normalize_name(name: &str) -> String {
+    name.split([' ', '\t', '\n', '\x0B', '\x0C', '\r'])
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_lowercase()
+}
+
+/// What a bulk write keeps once every item has been tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkCommit {
+    /// Keep the items that succeeded
+    Successful,
+    /// Keep the items only if every one of them succeeded
+    AllOrNothing,
+    /// Keep nothing, e.g. for an atomic request that already has invalid items
+    Nothing,
+}
+
+#[async_trait]
+pub trait GameRepository: Send + Sync {
+    async fn create(&self, game: &GameSchema) -> Result<GameModel, RepoError>;
+    /// Live games by name
+    async fn list(&self) -> Result<Vec<GameModel>, RepoError>;
+    async fn get(&self, id: Uuid) -> Result<GameModel, RepoError>;
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateGameSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError>;
+    /// Moves a game without live plays to the trash
+    async fn trash(&self, id: Uuid, precondition: Precondition<'_>)
+    -> Result<GameModel, RepoError>;
+    async fn restore(&self, id: Uuid) -> Result<GameModel, RepoError>;
+    /// Re-points every play of `id` at `target_id` and deletes `id`. A missing
+    /// target is reported as a missing `target_id` reference.
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedGameData, RepoError>;
+    /// Trashed games, most recently deleted first
+    async fn list_trashed(&self) -> Result<Vec<GameModel>, RepoError>;
+}
+
+#[async_trait]
+pub trait PlayerRepository: Send + Sync {
+    async fn create(&self, player: &PlayerSchema) -> Result<PlayerModel, RepoError>;
+    /// Creates every player, each on its own; `commit` decides what is kept
+    async fn create_many(
+        &self,
+        players: &[PlayerSchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayerModel, RepoError>>, RepoError>;
+    /// Live players by name
+    async fn list(&self) -> Result<Vec<PlayerModel>, RepoError>;
+    async fn get(&self, id: Uuid) -> Result<PlayerModel, RepoError>;
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlayerSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError>;
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError>;
+    async fn restore(&self, id: Uuid) -> Result<PlayerModel, RepoError>;
+    /// Re-points the participations of `id` at `target_id`, dropping those in
+    /// plays both took part in, and deletes `id`; ownership, hosted sessions
+    /// and attendance move along. A missing target is reported as a missing
+    /// `target_id` reference.
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedPlayerData, RepoError>;
+    /// Makes `id` the owner, taking ownership away from the current owner
+    async fn make_owner(&self, id: Uuid) -> Result<OwnerData, RepoError>;
+    /// The live owner, if one has been set
+    async fn owner(&self) -> Result<Option<PlayerModel>, RepoError>;
+    /// Plays, wins and distinct games over the player's live plays
+    async fn stats(&self, id: Uuid) -> Result<PlayerStatsModel, RepoError>;
+    /// Live plays the player took part in, newest first
+    async fn plays(&self, id: Uuid) -> Result<Vec<PlayerPlayModel>, RepoError>;
+    /// Trashed players, most recently deleted first
+    async fn list_trashed(&self) -> Result<Vec<PlayerModel>, RepoError>;
+}
+
+#[async_trait]
+pub trait PlayRepository: Send + Sync {
+    /// Logs a play of a live game, in a session if one is given
+    async fn create(&self, play: &PlaySchema) -> Result<PlayModel, RepoError>;
+    /// Logs every play, each on its own; `commit` decides what is kept
+    async fn create_many(
+        &self,
+        plays: &[PlaySchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError>;
+    /// Live plays by game
+    async fn list(&self) -> Result<Vec<PlayModel>, RepoError>;
+    async fn get(&self, id: Uuid) -> Result<PlayModel, RepoError>;
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlaySchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError>;
+    async fn trash(&self, id: Uuid, precondition: Precondition<'_>)
+    -> Result<PlayModel, RepoError>;
+    /// Trashes every play, each on its own; `commit` decides what is kept
+    async fn trash_many(
+        &self,
+        ids: &[Uuid],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError>;
+    /// Takes a play out of the trash; its game has to be live
+    async fn restore(&self, id: Uuid) -> Result<PlayModel, RepoError>;
+    /// Trashed plays, most recently deleted first
+    async fn list_trashed(&self) -> Result<Vec<PlayModel>, RepoError>;
+}
+
+#[async_trait]
+pub trait ParticipantRepository: Send + Sync {
+    /// Adds a live player to a live play
+    async fn create(
+        &self,
+        participant: &PlayParticipantSchema,
+    ) -> Result<PlayParticipantModel, RepoError>;
+    /// Participants of live plays who are live players themselves
+    async fn list(&self) -> Result<Vec<PlayParticipantModel>, RepoError>;
+    async fn list_for_play(&self, play_id: Uuid) -> Result<Vec<PlayParticipantModel>, RepoError>;
+    async fn update(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        changes: &UpdatePlayParticipantSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError>;
+    async fn delete(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError>;
+}
+
+/// Game nights. The host and the attendees have to be live players when
+/// they are set, which is reported as a missing `host_id` or `attendee_ids`
+/// reference.
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn create(&self, session: &SessionSchema) -> Result<SessionModel, RepoError>;
+    /// Sessions, most recent first
+    async fn list(&self) -> Result<Vec<SessionModel>, RepoError>;
+    async fn get(&self, id: Uuid) -> Result<SessionModel, RepoError>;
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateSessionSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError>;
+    /// Deletes a session for good; its plays stay, without a session
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError>;
+}
+
+/// Game nights being planned. The host and the invitees have to be live
+/// players when they are set, and the games brought live games, which is
+/// reported as a missing `host_id`, `invitee_ids` or `game_ids` reference.
+#[async_trait]
+pub trait EventRepository: Send + Sync {
+    async fn create(&self, event: &EventSchema) -> Result<EventModel, RepoError>;
+    /// Events, soonest first
+    async fn list(&self) -> Result<Vec<EventModel>, RepoError>;
+    async fn get(&self, id: Uuid) -> Result<EventModel, RepoError>;
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateEventSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError>;
+    /// Deletes an event for good; the session it turned into stays
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError>;
+    /// Records a live player's answer, inviting them if they were not. A
+    /// player who is not live is a missing `player_id` reference.
+    async fn respond(
+        &self,
+        id: Uuid,
+        player_id: Uuid,
+        rsvp: &RsvpSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError>;
+    /// Turns the event into a session on its date and at its location,
+    /// attended by its host and the live players who answered yes. An event
+    /// that already has a session is a `Duplicate` of it.
+    async fn start_session(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventSessionData, RepoError>;
+}
+
+/// The attendees a session ends up with, or the players an event invites:
+/// the listed players and the host, sorted and without repeats
+pub fn attendees(host_id: Option<Uuid>, attendee_ids: &[Uuid]) -> Vec<Uuid> {
+    let mut attendees: Vec<_> = attendee_ids.iter().copied().chain(host_id).collect();
+    attendees.sort();
+    attendees.dedup();
+    attendees
+}
+
+/// The invitations of an event that invites `invitee_ids` and its host.
+/// Players invited already keep their answer and the games they bring.
+pub fn invitations(
+    current: &[InvitationModel],
+    host_id: Option<Uuid>,
+    invitee_ids: &[Uuid],
+) -> Vec<InvitationModel> {
+    attendees(host_id, invitee_ids)
+        .into_iter()
+        .map(|player_id| {
+            current
+                .iter()
+                .find(|invitation| invitation.player_id == player_id)
+                .cloned()
+                .unwrap_or(InvitationModel {
+                    player_id,
+                    response: None,
+                    responded_at: None,
+                    game_ids: Vec::new(),
+                })
+        })
+        .collect()
+}
+
+/// The change history the audit log records for every row
+#[async_trait]
+pub trait HistoryRepository: Send + Sync {
+    /// Changes of one row, newest first
+    async fn list(&self, entity: &str, entity_id: &str) -> Result<Vec<AuditLogModel>, RepoError>;
+    /// One change of one row
+    async fn get(
+        &self,
+        entity: &str,
+        entity_id: &str,
+        audit_id: i64,
+    ) -> Result<AuditLogModel, RepoError>;
+    /// Writes a recorded game back, re-creating it if it has been purged since
+    async fn revert_game(&self, snapshot: &serde_json::Value) -> Result<GameModel, RepoError>;
+    async fn revert_player(&self, snapshot: &serde_json::Value) -> Result<PlayerModel, RepoError>;
+    async fn revert_play(&self, snapshot: &serde_json::Value) -> Result<PlayModel, RepoError>;
+    async fn revert_participant(
+        &self,
+        snapshot: &serde_json::Value,
+    ) -> Result<PlayParticipantModel, RepoError>;
+    /// Writes a recorded session back, re-creating it if it has been deleted
+    /// since. Snapshots hold no attendance: the current attendees stay, with
+    /// the recorded host added.
+    async fn revert_session(&self, snapshot: &serde_json::Value)
+    -> Result<SessionModel, RepoError>;
+    /// Writes a recorded event back, re-creating it if it has been deleted
+    /// since. Snapshots hold no invitations: the current ones stay, with the
+    /// recorded host invited.
+    async fn revert_event(&self, snapshot: &serde_json::Value) -> Result<EventModel, RepoError>;
+}
+
+/// Responses stored for requests sent with an Idempotency-Key
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Claims a key for a path, taking over an entry created before `cutoff`.
+    /// Returns false when the key is already held.
+    async fn claim(
+        &self,
+        key: &str,
+        path: &str,
+        request_hash: &[u8],
+        cutoff: DateTime<Utc>,
+    ) -> Result<bool, RepoError>;
+    async fn get(&self, key: &str, path: &str) -> Result<IdempotencyKeyModel, RepoError>;
+    /// Stores the response of the request that claimed the key
+    async fn store(
+        &self,
+        key: &str,
+        path: &str,
+        status_code: i16,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> Result<(), RepoError>;
+    /// Gives up a claimed key so the request can be retried
+    async fn release(&self, key: &str, path: &str) -> Result<(), RepoError>;
+    /// Deletes keys created before `cutoff`, returning how many went
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<u64, RepoError>;
+}
+
+#[async_trait]
+pub trait TrashRepository: Send + Sync {
+    /// Permanently deletes every game, player and play trashed before `cutoff`.
+    /// Games are only purged once none of their plays remain.
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<PurgedCounts, RepoError>;
+}
+
+#[async_trait]
+pub trait BackupRepository: Send + Sync {
+    /// Every row of every table, trashed ones included, as of one point in
+    /// time. The audit log is left out, see `export_with_audit_log`.
+    async fn export(&self) -> Result<BackupTables, RepoError>;
+    /// Like `export`, with every audit log entry, oldest first, as of the
+    /// same point in time as the rows
+    async fn export_with_audit_log(&self) -> Result<BackupTables, RepoError>;
+    /// Writes every row in the given order, IDs and timestamps included,
+    /// replacing the row with the same key, all in one transaction. Replaced
+    /// rows get a new version like any other change. Audit log entries are
+    /// appended under new IDs before the rows, whose changes are logged after
+    /// them.
+    async fn restore(&self, rows: &BackupTables) -> Result<(), RepoError>;
+}
+
+/// The rows the statistics are computed from, so they don't have to read the
+/// whole database. Each call returns live plays with their participants, the
+/// games played and the players who took part, as of one point in time.
+/// Sessions and events are left out unless a call says otherwise.
+#[async_trait]
+pub trait StatsRepository: Send + Sync {
+    /// The live plays of `game_id` that `player_id` took part in, each filter
+    /// applying when given, with the sessions and the events that go with
+    /// them in a calendar, the players invited and the games brought
+    async fn calendar_plays(
+        &self,
+        player_id: Option<Uuid>,
+        game_id: Option<Uuid>,
+    ) -> Result<BackupTables, RepoError>;
+    /// The live plays from `from` until `until`, and the first live play of
+    /// each game played before `from`
+    async fn year_plays(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<BackupTables, RepoError>;
+    /// The live plays before `until`
+    async fn plays_until(&self, until: DateTime<Utc>) -> Result<BackupTables, RepoError>;
+    /// Every live game, the given players, trashed or not, and the live plays
+    /// any of them took part in
+    async fn player_history(&self, player_ids: &[Uuid]) -> Result<BackupTables, RepoError>;
+    /// The session `id` with its live plays, and the players who attended it
+    async fn session_plays(&self, id: Uuid) -> Result<BackupTables, RepoError>;
+    /// Every session with its live plays, and the players who attended one
+    async fn session_attendance(&self) -> Result<BackupTables, RepoError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_name;
+
+    #[test]
+    fn normalize_name_only_folds_ascii() {
+        assert_eq!(normalize_name("  Ticket \t to\n\nRIDE "), "ticket to ride");
+        // Kept like the Postgres function keeps them, whatever the collation
+        assert_eq!(
+            normalize_name("Die Siedler\u{a0}von CATAN"),
+            "die siedler\u{a0}von catan"
+        );
+        assert_eq!(normalize_name("ÄRGER"), "Ärger");
+    }
+}
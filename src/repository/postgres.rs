@@ -0,0 +1,2606 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    actor,
+    model::{
+        AuditLogModel, BackupTables, EventModel, GameModel, IdempotencyKeyModel, InvitationModel,
+        PlayModel, PlayParticipantModel, PlayerModel, PlayerPlayModel, PlayerStatsModel, Rsvp,
+        SessionModel,
+    },
+    response::{EventSessionData, MergedGameData, MergedPlayerData, OwnerData, PurgedCounts},
+    schema::{
+        EventSchema, GameSchema, PlayParticipantSchema, PlaySchema, PlayerSchema, RsvpSchema,
+        SessionSchema, UpdateEventSchema, UpdateGameSchema, UpdatePlayParticipantSchema,
+        UpdatePlaySchema, UpdatePlayerSchema, UpdateSessionSchema,
+    },
+};
+
+use super::{
+    BackupRepository, BulkCommit, EventRepository, GameRepository, HistoryRepository,
+    IdempotencyRepository, ParticipantRepository, PlayRepository, PlayerRepository, Precondition,
+    RepoError, SessionRepository, StatsRepository, TrashRepository, attendees, check,
+    calendar_event, calendar_session, check_game_ranges, invitations,
+};
+
+/// Every repository trait, implemented over one Postgres connection pool
+pub struct PgRepository {
+    db: PgPool,
+}
+
+impl PgRepository {
+    pub fn new(db: PgPool) -> Self {
+        PgRepository { db }
+    }
+}
+
+/// Hands the actor of the current request to a connection taken from the
+/// pool, for the audit log to record
+pub async fn set_actor(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT set_config('bg.actor', $1, false)"#,
+        actor::current().unwrap_or_default()
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(())
+}
+
+/// Maps `RowNotFound` to `NotFound` and everything else to `Internal`
+fn not_found(e: sqlx::Error) -> RepoError {
+    match e {
+        sqlx::Error::RowNotFound => RepoError::NotFound,
+        _ => e.into(),
+    }
+}
+
+/// Looks up the live game a name and creator clash with once normalized, or
+/// the one with the same BGG ID
+async fn game_conflict<'c>(
+    db: impl PgExecutor<'c>,
+    name: &str,
+    creator_name: &str,
+    bgg_id: Option<i32>,
+) -> RepoError {
+    let existing_id = sqlx::query_scalar!(
+        r#"SELECT id FROM games
+        WHERE (
+            normalize_name(name) = normalize_name($1)
+            AND normalize_name(creator_name) = normalize_name($2)
+            OR bgg_id = $3
+        )
+        AND deleted_at IS NULL
+        LIMIT 1"#,
+        name,
+        creator_name,
+        bgg_id
+    )
+    .fetch_optional(db)
+    .await
+    .ok()
+    .flatten();
+
+    RepoError::Duplicate { existing_id }
+}
+
+/// Looks up the live player a clash is with, either on the normalized name or
+/// on `uniq_single_owner`
+async fn player_conflict<'c>(
+    db: impl PgExecutor<'c>,
+    constraint: Option<&str>,
+    name: &str,
+) -> RepoError {
+    if constraint == Some("uniq_single_owner") {
+        let owner_id = sqlx::query_scalar!(
+            r#"SELECT id FROM players WHERE is_owner = true AND deleted_at IS NULL"#
+        )
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten();
+        RepoError::OwnerExists { owner_id }
+    } else {
+        let existing_id = sqlx::query_scalar!(
+            r#"SELECT id FROM players
+            WHERE normalize_name(name) = normalize_name($1) AND deleted_at IS NULL"#,
+            name
+        )
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten();
+        RepoError::Duplicate { existing_id }
+    }
+}
+
+/// Maps a failed write to `play_participants` to the reference or clash behind it
+fn participant_error(e: sqlx::Error) -> RepoError {
+    match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+            match db_err.constraint() {
+                Some("play_participants_play_id_fkey") => {
+                    RepoError::MissingReferences(vec!["play_id"])
+                }
+                _ => RepoError::MissingReferences(vec!["player_id"]),
+            }
+        }
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            RepoError::Duplicate { existing_id: None }
+        }
+        _ => e.into(),
+    }
+}
+
+/// Maps a failed revert or restore to the unique index or the reference it ran into
+fn revert_error(e: sqlx::Error) -> RepoError {
+    match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            RepoError::Duplicate { existing_id: None }
+        }
+        sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+            let field = match db_err.constraint() {
+                Some("plays_game_id_fkey") => "game_id",
+                Some("plays_session_id_fkey") => "session_id",
+                Some("play_participants_play_id_fkey") => "play_id",
+                Some("sessions_host_id_fkey" | "events_host_id_fkey") => "host_id",
+                Some("events_session_id_fkey") => "session_id",
+                Some("event_games_game_id_fkey") => "game_ids",
+                _ => "player_id",
+            };
+            RepoError::MissingReferences(vec![field])
+        }
+        _ => e.into(),
+    }
+}
+
+/// Inserts a player in a savepoint of its own, so a failure leaves a
+/// surrounding transaction usable
+async fn insert_player(
+    conn: &mut PgConnection,
+    body: &PlayerSchema,
+) -> Result<PlayerModel, RepoError> {
+    let mut savepoint = conn.begin().await?;
+
+    let player = sqlx::query_as!(
+        PlayerModel,
+        r#"INSERT INTO players (name, is_owner) VALUES ($1, $2) RETURNING *"#,
+        &body.name,
+        &body.is_owner.unwrap_or(false),
+    )
+    .fetch_one(&mut *savepoint)
+    .await;
+
+    match player {
+        Ok(player) => {
+            savepoint.commit().await?;
+            Ok(player)
+        }
+        Err(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+            savepoint.rollback().await?;
+            Err(player_conflict(&mut *conn, db_err.constraint(), &body.name).await)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Maps a failed write to `plays` to the reference it ran into; a play of a
+/// game that is not live is not written at all
+fn play_error(e: sqlx::Error) -> RepoError {
+    match e {
+        sqlx::Error::RowNotFound => RepoError::MissingReferences(vec!["game_id"]),
+        sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+            match db_err.constraint() {
+                Some("plays_session_id_fkey") => RepoError::MissingReferences(vec!["session_id"]),
+                _ => RepoError::MissingReferences(vec!["game_id"]),
+            }
+        }
+        _ => e.into(),
+    }
+}
+
+/// Inserts a play of a live game in a savepoint of its own
+async fn insert_play(conn: &mut PgConnection, body: &PlaySchema) -> Result<PlayModel, RepoError> {
+    let mut savepoint = conn.begin().await?;
+
+    let play = sqlx::query_as!(
+        PlayModel,
+        r#"INSERT INTO plays (game_id, session_id, location, length_minutes, comments)
+        SELECT id, $2, $3, $4, $5 FROM games WHERE id = $1 AND deleted_at IS NULL
+        RETURNING *"#,
+        &body.game_id,
+        body.session_id,
+        body.location,
+        body.length_minutes,
+        body.comments,
+    )
+    .fetch_one(&mut *savepoint)
+    .await
+    .map_err(play_error)?;
+
+    savepoint.commit().await?;
+    Ok(play)
+}
+
+/// Moves a live play to the trash in a savepoint of its own
+async fn trash_play(conn: &mut PgConnection, id: Uuid) -> Result<PlayModel, RepoError> {
+    let mut savepoint = conn.begin().await?;
+
+    let play = sqlx::query_as!(
+        PlayModel,
+        r#"UPDATE plays SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL RETURNING *"#,
+        &id
+    )
+    .fetch_one(&mut *savepoint)
+    .await
+    .map_err(not_found)?;
+
+    savepoint.commit().await?;
+    Ok(play)
+}
+
+/// Checks that the play and player a participation points at exist and are
+/// not in the trash, listing each one that fails
+async fn check_references(
+    conn: &mut PgConnection,
+    play_id: &Uuid,
+    player_id: &Uuid,
+) -> Result<(), RepoError> {
+    let references = sqlx::query!(
+        r#"SELECT
+            EXISTS (SELECT 1 FROM plays WHERE id = $1 AND deleted_at IS NULL) AS "play!",
+            EXISTS (SELECT 1 FROM players WHERE id = $2 AND deleted_at IS NULL) AS "player!""#,
+        play_id,
+        player_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut missing = Vec::new();
+    if !references.play {
+        missing.push("play_id");
+    }
+    if !references.player {
+        missing.push("player_id");
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(RepoError::MissingReferences(missing))
+    }
+}
+
+/// A session with its attendees
+async fn select_session(conn: &mut PgConnection, id: Uuid) -> Result<SessionModel, RepoError> {
+    sqlx::query_as!(
+        SessionModel,
+        r#"SELECT s.*, ARRAY(
+            SELECT player_id FROM session_attendees
+            WHERE session_id = s.id ORDER BY player_id
+        ) AS "attendee_ids!"
+        FROM sessions s WHERE id = $1"#,
+        &id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(not_found)
+}
+
+/// Every session with its attendees
+async fn select_sessions(conn: &mut PgConnection) -> Result<Vec<SessionModel>, RepoError> {
+    let sessions = sqlx::query_as!(
+        SessionModel,
+        r#"SELECT s.*, ARRAY(
+            SELECT player_id FROM session_attendees
+            WHERE session_id = s.id ORDER BY player_id
+        ) AS "attendee_ids!"
+        FROM sessions s ORDER BY created_at, id"#
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(sessions)
+}
+
+/// Checks that the host of a session or event and the given players are
+/// live players, listing each field that fails; `field` names the players
+async fn check_players(
+    conn: &mut PgConnection,
+    host_id: Option<Uuid>,
+    player_ids: &[Uuid],
+    field: &'static str,
+) -> Result<(), RepoError> {
+    let ids: Vec<Uuid> = player_ids.iter().copied().chain(host_id).collect();
+    let live = sqlx::query_scalar!(
+        r#"SELECT id FROM players WHERE id = ANY($1) AND deleted_at IS NULL"#,
+        &ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut missing = Vec::new();
+    if host_id.is_some_and(|id| !live.contains(&id)) {
+        missing.push("host_id");
+    }
+    if player_ids.iter().any(|id| !live.contains(id)) {
+        missing.push(field);
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(RepoError::MissingReferences(missing))
+    }
+}
+
+/// Makes `attendee_ids` the attendees of a session
+async fn set_attendees(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    attendee_ids: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"DELETE FROM session_attendees WHERE session_id = $1 AND player_id <> ALL($2)"#,
+        &session_id,
+        attendee_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO session_attendees (session_id, player_id)
+        SELECT $1, UNNEST($2::uuid[])
+        ON CONFLICT DO NOTHING"#,
+        &session_id,
+        attendee_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Events with their invitations, soonest first; only the one with `id`
+/// when given
+async fn select_events(
+    conn: &mut PgConnection,
+    id: Option<Uuid>,
+) -> Result<Vec<EventModel>, sqlx::Error> {
+    let events = sqlx::query!(
+        r#"SELECT * FROM events WHERE $1::uuid IS NULL OR id = $1
+        ORDER BY scheduled_on, created_at, id"#,
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let rows = sqlx::query!(
+        r#"SELECT i.event_id, i.player_id, i.response AS "response: Rsvp", i.responded_at,
+            ARRAY(
+                SELECT game_id FROM event_games
+                WHERE event_id = i.event_id AND player_id = i.player_id ORDER BY game_id
+            ) AS "game_ids!"
+        FROM event_invitations i WHERE $1::uuid IS NULL OR i.event_id = $1
+        ORDER BY i.player_id"#,
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut invitations: HashMap<Uuid, Vec<InvitationModel>> = HashMap::new();
+    for row in rows {
+        invitations
+            .entry(row.event_id)
+            .or_default()
+            .push(InvitationModel {
+                player_id: row.player_id,
+                response: row.response,
+                responded_at: row.responded_at,
+                game_ids: row.game_ids,
+            });
+    }
+
+    Ok(events
+        .into_iter()
+        .map(|event| EventModel {
+            id: event.id,
+            scheduled_on: event.scheduled_on,
+            location: event.location,
+            host_id: event.host_id,
+            session_id: event.session_id,
+            invitations: invitations.remove(&event.id).unwrap_or_default(),
+            created_at: event.created_at,
+            version: event.version,
+        })
+        .collect())
+}
+
+/// An event with its invitations
+async fn select_event(conn: &mut PgConnection, id: Uuid) -> Result<EventModel, RepoError> {
+    select_events(conn, Some(id))
+        .await?
+        .pop()
+        .ok_or(RepoError::NotFound)
+}
+
+/// Locks an event for a change and checks the precondition against it
+async fn lock_event(
+    conn: &mut PgConnection,
+    id: Uuid,
+    precondition: Precondition<'_>,
+) -> Result<EventModel, RepoError> {
+    sqlx::query_scalar!(r#"SELECT id FROM events WHERE id = $1 FOR UPDATE"#, &id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(not_found)?;
+    let event = select_event(conn, id).await?;
+
+    check(precondition, event.version)?;
+    Ok(event)
+}
+
+/// Makes `invitations` the invitations of an event, with the games they bring
+async fn set_invitations(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    invitations: &[InvitationModel],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"DELETE FROM event_invitations WHERE event_id = $1"#,
+        &event_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for invitation in invitations {
+        sqlx::query!(
+            r#"INSERT INTO event_invitations (event_id, player_id, response, responded_at)
+            VALUES ($1, $2, $3, $4)"#,
+            &event_id,
+            &invitation.player_id,
+            invitation.response as Option<Rsvp>,
+            invitation.responded_at
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO event_games (event_id, player_id, game_id)
+            SELECT $1, $2, UNNEST($3::uuid[])"#,
+            &event_id,
+            &invitation.player_id,
+            &invitation.game_ids
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Finishes a bulk write: the transaction is only committed when `commit`
+/// allows it for these results
+async fn finish_bulk<T>(
+    tx: sqlx::Transaction<'_, sqlx::Postgres>,
+    results: Vec<Result<T, RepoError>>,
+    commit: BulkCommit,
+) -> Result<Vec<Result<T, RepoError>>, RepoError> {
+    let keep = match commit {
+        BulkCommit::Successful => true,
+        BulkCommit::AllOrNothing => results.iter().all(Result::is_ok),
+        BulkCommit::Nothing => false,
+    };
+
+    if keep {
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+    Ok(results)
+}
+
+#[async_trait]
+impl GameRepository for PgRepository {
+    async fn create(&self, game: &GameSchema) -> Result<GameModel, RepoError> {
+        check_game_ranges(
+            game.min_players,
+            game.max_players,
+            game.min_playtime_minutes,
+            game.max_playtime_minutes,
+        )?;
+
+        let id = uuid::Uuid::new_v4();
+        let created = sqlx::query_as!(
+            GameModel,
+            r#"INSERT INTO games (
+                id, name, creator_name, bgg_id, year_published, min_players, max_players,
+                min_playtime_minutes, max_playtime_minutes, weight
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"#,
+            &id,
+            &game.name,
+            &game.creator_name,
+            game.bgg_id,
+            game.year_published,
+            game.min_players,
+            game.max_players,
+            game.min_playtime_minutes,
+            game.max_playtime_minutes,
+            game.weight,
+        )
+        .fetch_one(&self.db)
+        .await;
+
+        match created {
+            Ok(game) => Ok(game),
+            Err(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+                Err(game_conflict(&self.db, &game.name, &game.creator_name, game.bgg_id).await)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<GameModel>, RepoError> {
+        let games = sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE deleted_at IS NULL ORDER by name"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(games)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<GameModel, RepoError> {
+        sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE id = $1 AND deleted_at IS NULL"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateGameSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let game = sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, game.version)?;
+
+        let new_name = changes.name.as_ref().unwrap_or(&game.name);
+        let new_creator = changes.creator_name.as_ref().unwrap_or(&game.creator_name);
+        let new_bgg_id = changes.bgg_id.unwrap_or(game.bgg_id);
+        let new_min_players = changes.min_players.unwrap_or(game.min_players);
+        let new_max_players = changes.max_players.unwrap_or(game.max_players);
+        let new_min_playtime = changes
+            .min_playtime_minutes
+            .unwrap_or(game.min_playtime_minutes);
+        let new_max_playtime = changes
+            .max_playtime_minutes
+            .unwrap_or(game.max_playtime_minutes);
+        check_game_ranges(
+            new_min_players,
+            new_max_players,
+            new_min_playtime,
+            new_max_playtime,
+        )?;
+
+        let updated_game = sqlx::query_as!(
+            GameModel,
+            r#"UPDATE games SET
+                name = $1,
+                creator_name = $2,
+                bgg_id = $4,
+                year_published = $5,
+                min_players = $6,
+                max_players = $7,
+                min_playtime_minutes = $8,
+                max_playtime_minutes = $9,
+                weight = $10
+            WHERE id = $3 RETURNING *"#,
+            &new_name,
+            &new_creator,
+            &id,
+            new_bgg_id,
+            changes.year_published.unwrap_or(game.year_published),
+            new_min_players,
+            new_max_players,
+            new_min_playtime,
+            new_max_playtime,
+            changes.weight.unwrap_or(game.weight)
+        )
+        .fetch_one(&mut *tx)
+        .await;
+
+        let updated_game = match updated_game {
+            Ok(game) => game,
+            Err(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+                return Err(game_conflict(&self.db, new_name, new_creator, new_bgg_id).await);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.commit().await?;
+        Ok(updated_game)
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let game = sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, game.version)?;
+
+        // Mirrors the ON DELETE RESTRICT on plays.game_id for live plays
+        let has_plays = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM plays WHERE game_id = $1 AND deleted_at IS NULL) AS "exists!""#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if has_plays {
+            return Err(RepoError::InUse);
+        }
+
+        let game = sqlx::query_as!(
+            GameModel,
+            r#"UPDATE games SET deleted_at = now() WHERE id = $1 RETURNING *"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(game)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<GameModel, RepoError> {
+        let restored = sqlx::query_as!(
+            GameModel,
+            r#"UPDATE games SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await;
+
+        match restored {
+            Ok(game) => Ok(game),
+            Err(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+                let game = sqlx::query_as!(GameModel, r#"SELECT * FROM games WHERE id = $1"#, &id)
+                    .fetch_one(&self.db)
+                    .await?;
+                Err(game_conflict(&self.db, &game.name, &game.creator_name, game.bgg_id).await)
+            }
+            Err(e) => Err(not_found(e)),
+        }
+    }
+
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedGameData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query_scalar!(
+            r#"SELECT id FROM games WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        sqlx::query_scalar!(
+            r#"SELECT id FROM games WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &target_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepoError::MissingReferences(vec!["target_id"]),
+            _ => e.into(),
+        })?;
+
+        let moved_plays = sqlx::query!(
+            r#"UPDATE plays SET game_id = $1 WHERE game_id = $2"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Games brought to an event change the event; a player bringing both
+        // brings the target once
+        sqlx::query!(
+            r#"UPDATE events SET version = version + 1
+            WHERE id IN (SELECT event_id FROM event_games WHERE game_id = $1)"#,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"DELETE FROM event_games
+            WHERE game_id = $1
+            AND (event_id, player_id) IN (
+                SELECT event_id, player_id FROM event_games WHERE game_id = $2
+            )"#,
+            &id,
+            &target_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"UPDATE event_games SET game_id = $1 WHERE game_id = $2"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let merged_game = sqlx::query_as!(
+            GameModel,
+            r#"DELETE FROM games WHERE id = $1 RETURNING *"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let game = sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE id = $1"#,
+            &target_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(MergedGameData {
+            game,
+            merged_game,
+            moved_plays,
+        })
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<GameModel>, RepoError> {
+        let games = sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(games)
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for PgRepository {
+    async fn create(&self, player: &PlayerSchema) -> Result<PlayerModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        insert_player(&mut conn, player).await
+    }
+
+    async fn create_many(
+        &self,
+        players: &[PlayerSchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayerModel, RepoError>>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut results = Vec::with_capacity(players.len());
+        for player in players {
+            results.push(insert_player(&mut tx, player).await);
+        }
+
+        finish_bulk(tx, results, commit).await
+    }
+
+    async fn list(&self) -> Result<Vec<PlayerModel>, RepoError> {
+        let players = sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE deleted_at IS NULL ORDER BY name"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(players)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<PlayerModel, RepoError> {
+        sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE id = $1 AND deleted_at IS NULL"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlayerSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let player = sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, player.version)?;
+
+        let new_name = changes.name.as_ref().unwrap_or(&player.name);
+        let new_is_owner = changes.is_owner.unwrap_or(player.is_owner);
+
+        let updated_player = sqlx::query_as!(
+            PlayerModel,
+            r#"UPDATE players SET name = $1, is_owner = $2 WHERE id = $3 RETURNING *"#,
+            &new_name,
+            &new_is_owner,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await;
+
+        let updated_player = match updated_player {
+            Ok(player) => player,
+            Err(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+                return Err(player_conflict(&self.db, db_err.constraint(), new_name).await);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.commit().await?;
+        Ok(updated_player)
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let player = sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, player.version)?;
+
+        let player = sqlx::query_as!(
+            PlayerModel,
+            r#"UPDATE players SET deleted_at = now() WHERE id = $1 RETURNING *"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(player)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<PlayerModel, RepoError> {
+        let restored = sqlx::query_as!(
+            PlayerModel,
+            r#"UPDATE players SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await;
+
+        match restored {
+            Ok(player) => Ok(player),
+            Err(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+                let player =
+                    sqlx::query_as!(PlayerModel, r#"SELECT * FROM players WHERE id = $1"#, &id)
+                        .fetch_one(&self.db)
+                        .await?;
+                Err(player_conflict(&self.db, db_err.constraint(), &player.name).await)
+            }
+            Err(e) => Err(not_found(e)),
+        }
+    }
+
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedPlayerData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query_scalar!(
+            r#"SELECT id FROM players WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        sqlx::query_scalar!(
+            r#"SELECT id FROM players WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &target_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepoError::MissingReferences(vec!["target_id"]),
+            _ => e.into(),
+        })?;
+
+        // Plays both players took part in would collide on (play_id, player_id)
+        let dropped_participations = sqlx::query!(
+            r#"DELETE FROM play_participants
+            WHERE player_id = $1
+            AND play_id IN (SELECT play_id FROM play_participants WHERE player_id = $2)"#,
+            &id,
+            &target_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let moved_participations = sqlx::query!(
+            r#"UPDATE play_participants SET player_id = $1 WHERE player_id = $2"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // The sessions change along with their host or attendance
+        sqlx::query!(
+            r#"UPDATE sessions SET
+                host_id = CASE WHEN host_id = $2 THEN $1 ELSE host_id END,
+                version = version + 1
+            WHERE host_id = $2
+            OR id IN (SELECT session_id FROM session_attendees WHERE player_id = $2)"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO session_attendees (session_id, player_id)
+            SELECT session_id, $1 FROM session_attendees WHERE player_id = $2
+            ON CONFLICT DO NOTHING"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Events likewise; an invitation both players had keeps the target's
+        // answer, and the games brought follow a moved invitation
+        sqlx::query!(
+            r#"UPDATE events SET
+                host_id = CASE WHEN host_id = $2 THEN $1 ELSE host_id END,
+                version = version + 1
+            WHERE host_id = $2
+            OR id IN (SELECT event_id FROM event_invitations WHERE player_id = $2)"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"DELETE FROM event_invitations
+            WHERE player_id = $1
+            AND event_id IN (SELECT event_id FROM event_invitations WHERE player_id = $2)"#,
+            &id,
+            &target_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"UPDATE event_invitations SET player_id = $1 WHERE player_id = $2"#,
+            &target_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let merged_player = sqlx::query_as!(
+            PlayerModel,
+            r#"DELETE FROM players WHERE id = $1 RETURNING *"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let player = sqlx::query_as!(
+            PlayerModel,
+            r#"UPDATE players SET is_owner = is_owner OR $1 WHERE id = $2 RETURNING *"#,
+            &merged_player.is_owner,
+            &target_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(MergedPlayerData {
+            player,
+            merged_player,
+            moved_participations,
+            dropped_participations,
+        })
+    }
+
+    async fn make_owner(&self, id: Uuid) -> Result<OwnerData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        // Also clears the flag on a trashed former owner so restoring it cannot clash
+        let previous_owner = sqlx::query_as!(
+            PlayerModel,
+            r#"UPDATE players SET is_owner = false WHERE is_owner = true AND id <> $1 RETURNING *"#,
+            &id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .find(|player| player.deleted_at.is_none());
+
+        let player = sqlx::query_as!(
+            PlayerModel,
+            r#"UPDATE players SET is_owner = true WHERE id = $1 AND deleted_at IS NULL RETURNING *"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        tx.commit().await?;
+
+        Ok(OwnerData {
+            player,
+            previous_owner,
+        })
+    }
+
+    async fn owner(&self) -> Result<Option<PlayerModel>, RepoError> {
+        let owner = sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE is_owner = true AND deleted_at IS NULL"#
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(owner)
+    }
+
+    async fn stats(&self, id: Uuid) -> Result<PlayerStatsModel, RepoError> {
+        let stats = sqlx::query_as!(
+            PlayerStatsModel,
+            r#"SELECT
+                COUNT(*) AS "plays!",
+                COUNT(*) FILTER (WHERE pp.is_winner) AS "wins!",
+                COUNT(DISTINCT p.game_id) AS "games!"
+            FROM play_participants pp
+            JOIN plays p ON p.id = pp.play_id AND p.deleted_at IS NULL
+            WHERE pp.player_id = $1"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await?;
+        Ok(stats)
+    }
+
+    async fn plays(&self, id: Uuid) -> Result<Vec<PlayerPlayModel>, RepoError> {
+        let plays = sqlx::query_as!(
+            PlayerPlayModel,
+            r#"SELECT p.id, p.game_id, p.created_at, pp.score, pp.is_winner
+            FROM plays p
+            JOIN play_participants pp ON pp.play_id = p.id
+            WHERE pp.player_id = $1 AND p.deleted_at IS NULL
+            ORDER BY p.created_at DESC"#,
+            &id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(plays)
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<PlayerModel>, RepoError> {
+        let players = sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(players)
+    }
+}
+
+#[async_trait]
+impl PlayRepository for PgRepository {
+    async fn create(&self, play: &PlaySchema) -> Result<PlayModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        insert_play(&mut conn, play).await
+    }
+
+    async fn create_many(
+        &self,
+        plays: &[PlaySchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut results = Vec::with_capacity(plays.len());
+        for play in plays {
+            results.push(insert_play(&mut tx, play).await);
+        }
+
+        finish_bulk(tx, results, commit).await
+    }
+
+    async fn list(&self) -> Result<Vec<PlayModel>, RepoError> {
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays WHERE deleted_at IS NULL ORDER BY game_id"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(plays)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<PlayModel, RepoError> {
+        sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays WHERE id = $1 AND deleted_at IS NULL"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlaySchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let play = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, play.version)?;
+
+        let new_game_id = changes.game_id.as_ref().unwrap_or(&play.game_id);
+        let new_session_id = changes.session_id.unwrap_or(play.session_id);
+        let new_location = changes.location.clone().unwrap_or(play.location);
+        let new_length = changes.length_minutes.unwrap_or(play.length_minutes);
+        let new_comments = changes.comments.clone().unwrap_or(play.comments);
+
+        let updated_play = sqlx::query_as!(
+            PlayModel,
+            r#"UPDATE plays SET
+                game_id = $1,
+                session_id = $3,
+                location = $4,
+                length_minutes = $5,
+                comments = $6
+            WHERE id = $2
+            AND EXISTS (SELECT 1 FROM games WHERE id = $1 AND deleted_at IS NULL)
+            RETURNING *"#,
+            &new_game_id,
+            &id,
+            new_session_id,
+            new_location,
+            new_length,
+            new_comments
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(play_error)?;
+
+        tx.commit().await?;
+        Ok(updated_play)
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let play = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, play.version)?;
+
+        let play = trash_play(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(play)
+    }
+
+    async fn trash_many(
+        &self,
+        ids: &[Uuid],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut results = Vec::with_capacity(ids.len());
+        for id in ids {
+            results.push(trash_play(&mut tx, *id).await);
+        }
+
+        finish_bulk(tx, results, commit).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<PlayModel, RepoError> {
+        let play = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays WHERE id = $1 AND deleted_at IS NOT NULL"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)?;
+
+        sqlx::query_as!(
+            PlayModel,
+            r#"UPDATE plays SET deleted_at = NULL
+            WHERE id = $1
+            AND EXISTS (SELECT 1 FROM games WHERE id = plays.game_id AND deleted_at IS NULL)
+            RETURNING *"#,
+            &id
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepoError::ReferenceTrashed { id: play.game_id },
+            _ => e.into(),
+        })
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<PlayModel>, RepoError> {
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(plays)
+    }
+}
+
+#[async_trait]
+impl ParticipantRepository for PgRepository {
+    async fn create(
+        &self,
+        participant: &PlayParticipantSchema,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        check_references(&mut conn, &participant.play_id, &participant.player_id).await?;
+
+        sqlx::query_as!(
+            PlayParticipantModel,
+            r#"INSERT INTO play_participants (play_id, player_id, score, is_winner) VALUES ($1, $2, $3, $4) RETURNING *"#,
+            &participant.play_id,
+            &participant.player_id,
+            participant.score,
+            &participant.is_winner.unwrap_or(false),
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(participant_error)
+    }
+
+    async fn list(&self) -> Result<Vec<PlayParticipantModel>, RepoError> {
+        let participants = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"SELECT pp.* FROM play_participants pp
+                JOIN plays p ON p.id = pp.play_id AND p.deleted_at IS NULL
+                JOIN players pl ON pl.id = pp.player_id AND pl.deleted_at IS NULL"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(participants)
+    }
+
+    async fn list_for_play(&self, play_id: Uuid) -> Result<Vec<PlayParticipantModel>, RepoError> {
+        let participants = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"SELECT pp.* FROM play_participants pp
+            JOIN plays p ON p.id = pp.play_id AND p.deleted_at IS NULL
+            JOIN players pl ON pl.id = pp.player_id AND pl.deleted_at IS NULL
+            WHERE pp.play_id = $1"#,
+            &play_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(participants)
+    }
+
+    async fn update(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        changes: &UpdatePlayParticipantSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let participant = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"SELECT * FROM play_participants WHERE play_id = $1 AND player_id = $2 FOR UPDATE"#,
+            &play_id,
+            &player_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, participant.version)?;
+
+        let new_play_id = changes.play_id.unwrap_or(participant.play_id);
+        let new_player_id = changes.player_id.unwrap_or(participant.player_id);
+        let new_score = changes.score.unwrap_or(participant.score);
+        let new_is_winner = changes.is_winner.unwrap_or(participant.is_winner);
+
+        check_references(&mut tx, &new_play_id, &new_player_id).await?;
+
+        let updated_participant = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"UPDATE play_participants SET play_id = $1, player_id = $2, score = $3, is_winner = $4 WHERE play_id = $5 AND player_id = $6 RETURNING *"#,
+            &new_play_id,
+            &new_player_id,
+            new_score,
+            &new_is_winner,
+            &play_id,
+            &player_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(participant_error)?;
+
+        tx.commit().await?;
+        Ok(updated_participant)
+    }
+
+    async fn delete(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let participant = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"SELECT * FROM play_participants WHERE play_id = $1 AND player_id = $2 FOR UPDATE"#,
+            &play_id,
+            &player_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, participant.version)?;
+
+        let deleted_participant = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"DELETE FROM play_participants WHERE play_id = $1 AND player_id = $2 RETURNING *"#,
+            &play_id,
+            &player_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(deleted_participant)
+    }
+}
+
+#[async_trait]
+impl SessionRepository for PgRepository {
+    async fn create(&self, session: &SessionSchema) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let attendee_ids = attendees(session.host_id, &session.attendee_ids);
+        check_players(&mut tx, session.host_id, &attendee_ids, "attendee_ids").await?;
+
+        let id = sqlx::query_scalar!(
+            r#"INSERT INTO sessions (played_on, location, host_id) VALUES ($1, $2, $3) RETURNING id"#,
+            &session.played_on,
+            session.location,
+            session.host_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        set_attendees(&mut tx, id, &attendee_ids).await?;
+        let session = select_session(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(session)
+    }
+
+    async fn list(&self) -> Result<Vec<SessionModel>, RepoError> {
+        let sessions = sqlx::query_as!(
+            SessionModel,
+            r#"SELECT s.*, ARRAY(
+                SELECT player_id FROM session_attendees
+                WHERE session_id = s.id ORDER BY player_id
+            ) AS "attendee_ids!"
+            FROM sessions s ORDER BY played_on DESC, created_at DESC"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(sessions)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<SessionModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        select_session(&mut conn, id).await
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateSessionSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query_scalar!(r#"SELECT id FROM sessions WHERE id = $1 FOR UPDATE"#, &id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(not_found)?;
+        let session = select_session(&mut tx, id).await?;
+
+        check(precondition, session.version)?;
+
+        let new_played_on = changes.played_on.unwrap_or(session.played_on);
+        let new_location = changes.location.clone().unwrap_or(session.location);
+        let new_host_id = changes.host_id.unwrap_or(session.host_id);
+        let listed = changes
+            .attendee_ids
+            .as_deref()
+            .unwrap_or(&session.attendee_ids);
+        let new_attendee_ids = attendees(new_host_id, listed);
+
+        // Players who already attend may have been trashed since
+        let added: Vec<_> = new_attendee_ids
+            .iter()
+            .copied()
+            .filter(|player_id| !session.attendee_ids.contains(player_id))
+            .collect();
+        let new_host = new_host_id.filter(|_| new_host_id != session.host_id);
+        check_players(&mut tx, new_host, &added, "attendee_ids").await?;
+
+        let attendance_changed = new_attendee_ids != session.attendee_ids;
+        sqlx::query!(
+            r#"UPDATE sessions SET
+                played_on = $1,
+                location = $2,
+                host_id = $3,
+                version = CASE WHEN $4 THEN version + 1 ELSE version END
+            WHERE id = $5"#,
+            &new_played_on,
+            new_location,
+            new_host_id,
+            &attendance_changed,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+        set_attendees(&mut tx, id, &new_attendee_ids).await?;
+        let updated_session = select_session(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(updated_session)
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query_scalar!(r#"SELECT id FROM sessions WHERE id = $1 FOR UPDATE"#, &id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(not_found)?;
+        let session = select_session(&mut tx, id).await?;
+
+        check(precondition, session.version)?;
+
+        // plays.session_id is ON DELETE SET NULL, attendance ON DELETE CASCADE
+        sqlx::query!(r#"DELETE FROM sessions WHERE id = $1"#, &id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl EventRepository for PgRepository {
+    async fn create(&self, event: &EventSchema) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let invitations = invitations(&[], event.host_id, &event.invitee_ids);
+        check_players(&mut tx, event.host_id, &event.invitee_ids, "invitee_ids").await?;
+
+        let id = sqlx::query_scalar!(
+            r#"INSERT INTO events (scheduled_on, location, host_id) VALUES ($1, $2, $3) RETURNING id"#,
+            &event.scheduled_on,
+            event.location,
+            event.host_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        set_invitations(&mut tx, id, &invitations).await?;
+        let event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(event)
+    }
+
+    async fn list(&self) -> Result<Vec<EventModel>, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        Ok(select_events(&mut conn, None).await?)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<EventModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        select_event(&mut conn, id).await
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateEventSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+        let event = lock_event(&mut tx, id, precondition).await?;
+
+        let new_scheduled_on = changes.scheduled_on.unwrap_or(event.scheduled_on);
+        let new_location = changes.location.clone().unwrap_or(event.location);
+        let new_host_id = changes.host_id.unwrap_or(event.host_id);
+        let current_ids: Vec<_> = event.invitations.iter().map(|i| i.player_id).collect();
+        let listed = changes.invitee_ids.as_deref().unwrap_or(&current_ids);
+        let new_invitations = invitations(&event.invitations, new_host_id, listed);
+
+        // Players who are invited already may have been trashed since
+        let added: Vec<_> = new_invitations
+            .iter()
+            .map(|invitation| invitation.player_id)
+            .filter(|player_id| !current_ids.contains(player_id))
+            .collect();
+        let new_host = new_host_id.filter(|_| new_host_id != event.host_id);
+        check_players(&mut tx, new_host, &added, "invitee_ids").await?;
+
+        let invitations_changed = new_invitations != event.invitations;
+        sqlx::query!(
+            r#"UPDATE events SET
+                scheduled_on = $1,
+                location = $2,
+                host_id = $3,
+                version = CASE WHEN $4 THEN version + 1 ELSE version END
+            WHERE id = $5"#,
+            &new_scheduled_on,
+            new_location,
+            new_host_id,
+            &invitations_changed,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if invitations_changed {
+            set_invitations(&mut tx, id, &new_invitations).await?;
+        }
+        let updated_event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(updated_event)
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+        let event = lock_event(&mut tx, id, precondition).await?;
+
+        // The invitations go with it, the session it turned into stays
+        sqlx::query!(r#"DELETE FROM events WHERE id = $1"#, &id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(event)
+    }
+
+    async fn respond(
+        &self,
+        id: Uuid,
+        player_id: Uuid,
+        rsvp: &RsvpSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+        let event = lock_event(&mut tx, id, precondition).await?;
+
+        let mut game_ids = rsvp.game_ids.clone();
+        game_ids.sort();
+        game_ids.dedup();
+        let references = sqlx::query!(
+            r#"SELECT
+                EXISTS (
+                    SELECT 1 FROM players WHERE id = $1 AND deleted_at IS NULL
+                ) AS "player!",
+                ARRAY(
+                    SELECT id FROM games WHERE id = ANY($2) AND deleted_at IS NULL
+                ) AS "live_game_ids!""#,
+            &player_id,
+            &game_ids
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut missing = Vec::new();
+        if !references.player {
+            missing.push("player_id");
+        }
+        if game_ids.len() != references.live_game_ids.len() {
+            missing.push("game_ids");
+        }
+        if !missing.is_empty() {
+            return Err(RepoError::MissingReferences(missing));
+        }
+
+        let mut new_invitations = event.invitations;
+        new_invitations.retain(|invitation| invitation.player_id != player_id);
+        new_invitations.push(InvitationModel {
+            player_id,
+            response: Some(rsvp.response),
+            responded_at: Some(Utc::now()),
+            game_ids,
+        });
+        new_invitations.sort_by_key(|invitation| invitation.player_id);
+
+        sqlx::query!(
+            r#"UPDATE events SET version = version + 1 WHERE id = $1"#,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+        set_invitations(&mut tx, id, &new_invitations).await?;
+        let updated_event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(updated_event)
+    }
+
+    async fn start_session(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventSessionData, RepoError> {
+        let mut tx = self.db.begin().await?;
+        let event = lock_event(&mut tx, id, precondition).await?;
+
+        if let Some(session_id) = event.session_id {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(session_id),
+            });
+        }
+
+        // Players who answered yes and the host, as far as they are still live
+        let candidate_ids: Vec<_> = event
+            .invitations
+            .iter()
+            .filter(|invitation| invitation.response == Some(Rsvp::Yes))
+            .map(|invitation| invitation.player_id)
+            .chain(event.host_id)
+            .collect();
+        let live = sqlx::query_scalar!(
+            r#"SELECT id FROM players WHERE id = ANY($1) AND deleted_at IS NULL"#,
+            &candidate_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let host_id = event.host_id.filter(|id| live.contains(id));
+        let attendee_ids = attendees(host_id, &live);
+
+        let session_id = sqlx::query_scalar!(
+            r#"INSERT INTO sessions (played_on, location, host_id) VALUES ($1, $2, $3) RETURNING id"#,
+            &event.scheduled_on,
+            event.location,
+            host_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        set_attendees(&mut tx, session_id, &attendee_ids).await?;
+
+        sqlx::query!(
+            r#"UPDATE events SET session_id = $1 WHERE id = $2"#,
+            &session_id,
+            &id
+        )
+        .execute(&mut *tx)
+        .await?;
+        let session = select_session(&mut tx, session_id).await?;
+        let event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(EventSessionData { event, session })
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for PgRepository {
+    async fn list(&self, entity: &str, entity_id: &str) -> Result<Vec<AuditLogModel>, RepoError> {
+        let history = sqlx::query_as!(
+            AuditLogModel,
+            r#"SELECT * FROM audit_log WHERE entity = $1 AND entity_id = $2 ORDER BY id DESC"#,
+            entity,
+            entity_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(history)
+    }
+
+    async fn get(
+        &self,
+        entity: &str,
+        entity_id: &str,
+        audit_id: i64,
+    ) -> Result<AuditLogModel, RepoError> {
+        sqlx::query_as!(
+            AuditLogModel,
+            r#"SELECT * FROM audit_log WHERE id = $1 AND entity = $2 AND entity_id = $3"#,
+            audit_id,
+            entity,
+            entity_id
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    // Snapshots taken before rows were versioned start over at version 1
+
+    async fn revert_game(&self, snapshot: &serde_json::Value) -> Result<GameModel, RepoError> {
+        sqlx::query_as!(
+            GameModel,
+            r#"INSERT INTO games SELECT * FROM jsonb_populate_record(NULL::games, '{"version": 1}'::jsonb || $1)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                creator_name = EXCLUDED.creator_name,
+                bgg_id = EXCLUDED.bgg_id,
+                year_published = EXCLUDED.year_published,
+                min_players = EXCLUDED.min_players,
+                max_players = EXCLUDED.max_players,
+                min_playtime_minutes = EXCLUDED.min_playtime_minutes,
+                max_playtime_minutes = EXCLUDED.max_playtime_minutes,
+                weight = EXCLUDED.weight,
+                deleted_at = EXCLUDED.deleted_at
+            RETURNING *"#,
+            snapshot
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(revert_error)
+    }
+
+    async fn revert_player(&self, snapshot: &serde_json::Value) -> Result<PlayerModel, RepoError> {
+        sqlx::query_as!(
+            PlayerModel,
+            r#"INSERT INTO players SELECT * FROM jsonb_populate_record(NULL::players, '{"version": 1}'::jsonb || $1)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                is_owner = EXCLUDED.is_owner,
+                bgg_username = EXCLUDED.bgg_username,
+                deleted_at = EXCLUDED.deleted_at
+            RETURNING *"#,
+            snapshot
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(revert_error)
+    }
+
+    async fn revert_play(&self, snapshot: &serde_json::Value) -> Result<PlayModel, RepoError> {
+        sqlx::query_as!(
+            PlayModel,
+            r#"INSERT INTO plays SELECT * FROM jsonb_populate_record(NULL::plays, '{"version": 1}'::jsonb || $1)
+            ON CONFLICT (id) DO UPDATE SET
+                game_id = EXCLUDED.game_id,
+                location = EXCLUDED.location,
+                length_minutes = EXCLUDED.length_minutes,
+                comments = EXCLUDED.comments,
+                bgg_play_id = EXCLUDED.bgg_play_id,
+                session_id = EXCLUDED.session_id,
+                deleted_at = EXCLUDED.deleted_at
+            RETURNING *"#,
+            snapshot
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(revert_error)
+    }
+
+    async fn revert_participant(
+        &self,
+        snapshot: &serde_json::Value,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        sqlx::query_as!(
+            PlayParticipantModel,
+            r#"INSERT INTO play_participants
+            SELECT * FROM jsonb_populate_record(NULL::play_participants, '{"version": 1}'::jsonb || $1)
+            ON CONFLICT (play_id, player_id) DO UPDATE SET
+                score = EXCLUDED.score,
+                is_winner = EXCLUDED.is_winner
+            RETURNING *"#,
+            snapshot
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(revert_error)
+    }
+
+    async fn revert_session(
+        &self,
+        snapshot: &serde_json::Value,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let id = sqlx::query_scalar!(
+            r#"INSERT INTO sessions SELECT * FROM jsonb_populate_record(NULL::sessions, '{"version": 1}'::jsonb || $1)
+            ON CONFLICT (id) DO UPDATE SET
+                played_on = EXCLUDED.played_on,
+                location = EXCLUDED.location,
+                host_id = EXCLUDED.host_id
+            RETURNING id"#,
+            snapshot
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(revert_error)?;
+
+        // The host only changes along with the row, whose version is bumped
+        let mut session = select_session(&mut tx, id).await?;
+        let attendee_ids = attendees(session.host_id, &session.attendee_ids);
+        if attendee_ids != session.attendee_ids {
+            set_attendees(&mut tx, id, &attendee_ids).await?;
+            session.attendee_ids = attendee_ids;
+        }
+
+        tx.commit().await?;
+        Ok(session)
+    }
+
+    async fn revert_event(&self, snapshot: &serde_json::Value) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let id = sqlx::query_scalar!(
+            r#"INSERT INTO events SELECT * FROM jsonb_populate_record(NULL::events, '{"version": 1}'::jsonb || $1)
+            ON CONFLICT (id) DO UPDATE SET
+                scheduled_on = EXCLUDED.scheduled_on,
+                location = EXCLUDED.location,
+                host_id = EXCLUDED.host_id,
+                session_id = EXCLUDED.session_id
+            RETURNING id"#,
+            snapshot
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(revert_error)?;
+
+        // The host only changes along with the row, whose version is bumped
+        let mut event = select_event(&mut tx, id).await?;
+        let invitee_ids: Vec<_> = event.invitations.iter().map(|i| i.player_id).collect();
+        if event
+            .host_id
+            .is_some_and(|host_id| !invitee_ids.contains(&host_id))
+        {
+            let invitations = invitations(&event.invitations, event.host_id, &invitee_ids);
+            set_invitations(&mut tx, id, &invitations).await?;
+            event.invitations = invitations;
+        }
+
+        tx.commit().await?;
+        Ok(event)
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for PgRepository {
+    async fn claim(
+        &self,
+        key: &str,
+        path: &str,
+        request_hash: &[u8],
+        cutoff: DateTime<Utc>,
+    ) -> Result<bool, RepoError> {
+        let claimed = sqlx::query_scalar!(
+            r#"INSERT INTO idempotency_keys (key, path, request_hash) VALUES ($1, $2, $3)
+            ON CONFLICT (key, path) DO UPDATE SET
+                request_hash = EXCLUDED.request_hash,
+                status_code = NULL,
+                content_type = NULL,
+                response_body = NULL,
+                created_at = now()
+            WHERE idempotency_keys.created_at < $4
+            RETURNING key"#,
+            key,
+            path,
+            request_hash,
+            cutoff
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(claimed.is_some())
+    }
+
+    async fn get(&self, key: &str, path: &str) -> Result<IdempotencyKeyModel, RepoError> {
+        sqlx::query_as!(
+            IdempotencyKeyModel,
+            r#"SELECT request_hash, status_code, content_type, response_body
+            FROM idempotency_keys WHERE key = $1 AND path = $2"#,
+            key,
+            path
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        path: &str,
+        status_code: i16,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE idempotency_keys
+            SET status_code = $3, content_type = $4, response_body = $5
+            WHERE key = $1 AND path = $2"#,
+            key,
+            path,
+            status_code,
+            content_type,
+            response_body
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str, path: &str) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"DELETE FROM idempotency_keys WHERE key = $1 AND path = $2"#,
+            key,
+            path
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<u64, RepoError> {
+        let result = sqlx::query!(
+            r#"DELETE FROM idempotency_keys WHERE created_at < $1"#,
+            cutoff
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl TrashRepository for PgRepository {
+    // Games and players keep their history: plays.game_id is ON DELETE
+    // RESTRICT, and players who took part in a play or attended a session stay
+    // in the trash instead of losing it through ON DELETE CASCADE
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<PurgedCounts, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let plays = sqlx::query!(r#"DELETE FROM plays WHERE deleted_at < $1"#, cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        let players = sqlx::query!(
+            r#"DELETE FROM players
+            WHERE deleted_at < $1
+            AND NOT EXISTS (SELECT 1 FROM play_participants pp WHERE pp.player_id = players.id)
+            AND NOT EXISTS (SELECT 1 FROM session_attendees sa WHERE sa.player_id = players.id)"#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let games = sqlx::query!(
+            r#"DELETE FROM games
+            WHERE deleted_at < $1
+            AND NOT EXISTS (SELECT 1 FROM plays WHERE plays.game_id = games.id)"#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(PurgedCounts {
+            games,
+            players,
+            plays,
+        })
+    }
+}
+
+/// Every row of every table, and the audit log when `audit_log` is set, in
+/// one transaction
+async fn export_tables(db: &PgPool, audit_log: bool) -> Result<BackupTables, RepoError> {
+    let mut tx = db.begin().await?;
+    sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+        .execute(&mut *tx)
+        .await?;
+
+    let games = sqlx::query_as!(GameModel, r#"SELECT * FROM games ORDER BY created_at, id"#)
+        .fetch_all(&mut *tx)
+        .await?;
+    let players = sqlx::query_as!(
+        PlayerModel,
+        r#"SELECT * FROM players ORDER BY created_at, id"#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let sessions = select_sessions(&mut tx).await?;
+    let events = select_events(&mut tx, None).await?;
+    let plays = sqlx::query_as!(PlayModel, r#"SELECT * FROM plays ORDER BY created_at, id"#)
+        .fetch_all(&mut *tx)
+        .await?;
+    let play_participants = sqlx::query_as!(
+        PlayParticipantModel,
+        r#"SELECT * FROM play_participants ORDER BY play_id, player_id"#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let audit_log = if audit_log {
+        sqlx::query_as!(AuditLogModel, r#"SELECT * FROM audit_log ORDER BY id"#)
+            .fetch_all(&mut *tx)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    tx.commit().await?;
+
+    Ok(BackupTables {
+        games,
+        players,
+        sessions,
+        events,
+        plays,
+        play_participants,
+        audit_log,
+    })
+}
+
+#[async_trait]
+impl BackupRepository for PgRepository {
+    async fn export(&self) -> Result<BackupTables, RepoError> {
+        export_tables(&self.db, false).await
+    }
+
+    async fn export_with_audit_log(&self) -> Result<BackupTables, RepoError> {
+        export_tables(&self.db, true).await
+    }
+
+    // New rows keep their archived version, replaced ones are bumped by the
+    // version trigger
+    async fn restore(&self, rows: &BackupTables) -> Result<(), RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        for entry in &rows.audit_log {
+            sqlx::query!(
+                r#"INSERT INTO audit_log (entity, entity_id, action, before, after, changed_at, actor)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+                entry.entity,
+                entry.entity_id,
+                entry.action,
+                entry.before,
+                entry.after,
+                entry.changed_at,
+                entry.actor
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for game in &rows.games {
+            sqlx::query!(
+                r#"INSERT INTO games (
+                    id, name, creator_name, bgg_id, year_published, min_players, max_players,
+                    min_playtime_minutes, max_playtime_minutes, weight, created_at, deleted_at,
+                    version
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                ON CONFLICT (id) DO UPDATE SET
+                    name = EXCLUDED.name,
+                    creator_name = EXCLUDED.creator_name,
+                    bgg_id = EXCLUDED.bgg_id,
+                    year_published = EXCLUDED.year_published,
+                    min_players = EXCLUDED.min_players,
+                    max_players = EXCLUDED.max_players,
+                    min_playtime_minutes = EXCLUDED.min_playtime_minutes,
+                    max_playtime_minutes = EXCLUDED.max_playtime_minutes,
+                    weight = EXCLUDED.weight,
+                    created_at = EXCLUDED.created_at,
+                    deleted_at = EXCLUDED.deleted_at"#,
+                game.id,
+                game.name,
+                game.creator_name,
+                game.bgg_id,
+                game.year_published,
+                game.min_players,
+                game.max_players,
+                game.min_playtime_minutes,
+                game.max_playtime_minutes,
+                game.weight,
+                game.created_at,
+                game.deleted_at,
+                game.version
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(revert_error)?;
+        }
+
+        for player in &rows.players {
+            sqlx::query!(
+                r#"INSERT INTO players (
+                    id, name, is_owner, bgg_username, created_at, deleted_at, version
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (id) DO UPDATE SET
+                    name = EXCLUDED.name,
+                    is_owner = EXCLUDED.is_owner,
+                    bgg_username = EXCLUDED.bgg_username,
+                    created_at = EXCLUDED.created_at,
+                    deleted_at = EXCLUDED.deleted_at"#,
+                player.id,
+                player.name,
+                player.is_owner,
+                player.bgg_username,
+                player.created_at,
+                player.deleted_at,
+                player.version
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(revert_error)?;
+        }
+
+        // Attendance is part of the session, so a replaced session is bumped
+        // even when only its attendees differ
+        for session in &rows.sessions {
+            sqlx::query!(
+                r#"INSERT INTO sessions (id, played_on, location, host_id, created_at, version)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (id) DO UPDATE SET
+                    played_on = EXCLUDED.played_on,
+                    location = EXCLUDED.location,
+                    host_id = EXCLUDED.host_id,
+                    created_at = EXCLUDED.created_at,
+                    version = sessions.version + 1"#,
+                session.id,
+                session.played_on,
+                session.location,
+                session.host_id,
+                session.created_at,
+                session.version
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(revert_error)?;
+
+            set_attendees(&mut tx, session.id, &session.attendee_ids)
+                .await
+                .map_err(revert_error)?;
+        }
+
+        // Invitations are part of the event in the same way
+        for event in &rows.events {
+            sqlx::query!(
+                r#"INSERT INTO events (
+                    id, scheduled_on, location, host_id, session_id, created_at, version
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (id) DO UPDATE SET
+                    scheduled_on = EXCLUDED.scheduled_on,
+                    location = EXCLUDED.location,
+                    host_id = EXCLUDED.host_id,
+                    session_id = EXCLUDED.session_id,
+                    created_at = EXCLUDED.created_at,
+                    version = events.version + 1"#,
+                event.id,
+                event.scheduled_on,
+                event.location,
+                event.host_id,
+                event.session_id,
+                event.created_at,
+                event.version
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(revert_error)?;
+
+            set_invitations(&mut tx, event.id, &event.invitations)
+                .await
+                .map_err(revert_error)?;
+        }
+
+        for play in &rows.plays {
+            sqlx::query!(
+                r#"INSERT INTO plays (
+                    id, game_id, location, length_minutes, comments, bgg_play_id, session_id,
+                    created_at, deleted_at, version
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (id) DO UPDATE SET
+                    game_id = EXCLUDED.game_id,
+                    location = EXCLUDED.location,
+                    length_minutes = EXCLUDED.length_minutes,
+                    comments = EXCLUDED.comments,
+                    bgg_play_id = EXCLUDED.bgg_play_id,
+                    session_id = EXCLUDED.session_id,
+                    created_at = EXCLUDED.created_at,
+                    deleted_at = EXCLUDED.deleted_at"#,
+                play.id,
+                play.game_id,
+                play.location,
+                play.length_minutes,
+                play.comments,
+                play.bgg_play_id,
+                play.session_id,
+                play.created_at,
+                play.deleted_at,
+                play.version
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(revert_error)?;
+        }
+
+        for participant in &rows.play_participants {
+            sqlx::query!(
+                r#"INSERT INTO play_participants (play_id, player_id, score, is_winner, version)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (play_id, player_id) DO UPDATE SET
+                    score = EXCLUDED.score,
+                    is_winner = EXCLUDED.is_winner"#,
+                participant.play_id,
+                participant.player_id,
+                participant.score,
+                participant.is_winner,
+                participant.version
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(revert_error)?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// `sessions`, `events` and `plays`, with the participants of the plays, the
+/// games played or brought and the players who took part, attended or were
+/// invited
+async fn play_tables(
+    conn: &mut PgConnection,
+    sessions: Vec<SessionModel>,
+    events: Vec<EventModel>,
+    plays: Vec<PlayModel>,
+) -> Result<BackupTables, RepoError> {
+    let play_ids: Vec<Uuid> = plays.iter().map(|play| play.id).collect();
+    let player_ids: Vec<Uuid> = sessions
+        .iter()
+        .flat_map(|session| session.attendee_ids.iter().copied())
+        .chain(
+            events
+                .iter()
+                .flat_map(|event| event.invitations.iter().map(|i| i.player_id)),
+        )
+        .collect();
+    let brought_ids: Vec<Uuid> = events
+        .iter()
+        .flat_map(|event| event.invitations.iter())
+        .flat_map(|invitation| invitation.game_ids.iter().copied())
+        .collect();
+    let play_participants = sqlx::query_as!(
+        PlayParticipantModel,
+        r#"SELECT * FROM play_participants WHERE play_id = ANY($1)
+        ORDER BY play_id, player_id"#,
+        &play_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let games = sqlx::query_as!(
+        GameModel,
+        r#"SELECT * FROM games
+        WHERE id IN (SELECT game_id FROM plays WHERE id = ANY($1)) OR id = ANY($2)
+        ORDER BY created_at, id"#,
+        &play_ids,
+        &brought_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let players = sqlx::query_as!(
+        PlayerModel,
+        r#"SELECT * FROM players
+        WHERE id IN (SELECT player_id FROM play_participants WHERE play_id = ANY($1))
+            OR id = ANY($2)
+        ORDER BY created_at, id"#,
+        &play_ids,
+        &player_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(BackupTables {
+        games,
+        players,
+        sessions,
+        events,
+        plays,
+        play_participants,
+        ..BackupTables::default()
+    })
+}
+
+#[async_trait]
+impl StatsRepository for PgRepository {
+    async fn calendar_plays(
+        &self,
+        player_id: Option<Uuid>,
+        game_id: Option<Uuid>,
+    ) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *tx)
+            .await?;
+
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays p
+            WHERE deleted_at IS NULL
+                AND ($1::uuid IS NULL OR game_id = $1)
+                AND ($2::uuid IS NULL OR EXISTS (
+                    SELECT 1 FROM play_participants
+                    WHERE play_id = p.id AND player_id = $2
+                ))
+            ORDER BY created_at, id"#,
+            game_id,
+            player_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut sessions = select_sessions(&mut tx).await?;
+        sessions.retain(|session| calendar_session(session, &plays, player_id, game_id));
+        let mut events = select_events(&mut tx, None).await?;
+        events.retain(|event| calendar_event(event, player_id, game_id));
+        let tables = play_tables(&mut tx, sessions, events, plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn year_plays(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM (
+                SELECT DISTINCT ON (game_id) * FROM plays
+                WHERE deleted_at IS NULL AND created_at < $1
+                ORDER BY game_id, created_at, id
+            ) first_plays
+            ORDER BY created_at, id"#,
+            from
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        plays.extend(
+            sqlx::query_as!(
+                PlayModel,
+                r#"SELECT * FROM plays
+                WHERE deleted_at IS NULL AND created_at >= $1 AND created_at < $2
+                ORDER BY created_at, id"#,
+                from,
+                until
+            )
+            .fetch_all(&mut *tx)
+            .await?,
+        );
+        let tables = play_tables(&mut tx, Vec::new(), Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn plays_until(&self, until: DateTime<Utc>) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *tx)
+            .await?;
+
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays
+            WHERE deleted_at IS NULL AND created_at < $1
+            ORDER BY created_at, id"#,
+            until
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let tables = play_tables(&mut tx, Vec::new(), Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn player_history(&self, player_ids: &[Uuid]) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *tx)
+            .await?;
+
+        let games = sqlx::query_as!(
+            GameModel,
+            r#"SELECT * FROM games WHERE deleted_at IS NULL ORDER BY created_at, id"#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let players = sqlx::query_as!(
+            PlayerModel,
+            r#"SELECT * FROM players WHERE id = ANY($1) ORDER BY created_at, id"#,
+            player_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays p
+            WHERE deleted_at IS NULL AND EXISTS (
+                SELECT 1 FROM play_participants
+                WHERE play_id = p.id AND player_id = ANY($1)
+            )
+            ORDER BY created_at, id"#,
+            player_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let play_ids: Vec<Uuid> = plays.iter().map(|play| play.id).collect();
+        let play_participants = sqlx::query_as!(
+            PlayParticipantModel,
+            r#"SELECT * FROM play_participants WHERE play_id = ANY($1)
+            ORDER BY play_id, player_id"#,
+            &play_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(BackupTables {
+            games,
+            players,
+            plays,
+            play_participants,
+            ..BackupTables::default()
+        })
+    }
+
+    async fn session_plays(&self, id: Uuid) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *tx)
+            .await?;
+
+        let session = select_session(&mut tx, id).await?;
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays
+            WHERE session_id = $1 AND deleted_at IS NULL
+            ORDER BY created_at, id"#,
+            id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let tables = play_tables(&mut tx, vec![session], Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn session_attendance(&self) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(r#"SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY"#)
+            .execute(&mut *tx)
+            .await?;
+
+        let sessions = select_sessions(&mut tx).await?;
+        let plays = sqlx::query_as!(
+            PlayModel,
+            r#"SELECT * FROM plays
+            WHERE session_id IS NOT NULL AND deleted_at IS NULL
+            ORDER BY created_at, id"#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let tables = play_tables(&mut tx, sessions, Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+}
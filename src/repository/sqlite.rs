@@ -0,0 +1,3302 @@
+//! SQLite repositories, for running without a Postgres server. The schema in
+//! `migrations/sqlite` is applied on connect. SQLite has no `normalize_name`,
+//! no `gen_random_uuid` and no triggers that can rewrite the row being
+//! updated, so names are normalized, IDs generated and versions bumped here.
+//! The audit log is still written by triggers.
+
+use std::{
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    SqliteConnection, SqlitePool,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+};
+use uuid::Uuid;
+
+use crate::{
+    actor,
+    model::{
+        AuditLogModel, BackupTables, EventModel, GameModel, IdempotencyKeyModel, InvitationModel,
+        PlayModel, PlayParticipantModel, PlayerModel, PlayerPlayModel, PlayerStatsModel, Rsvp,
+        SessionModel,
+    },
+    response::{EventSessionData, MergedGameData, MergedPlayerData, OwnerData, PurgedCounts},
+    schema::{
+        EventSchema, GameSchema, PlayParticipantSchema, PlaySchema, PlayerSchema, RsvpSchema,
+        SessionSchema, UpdateEventSchema, UpdateGameSchema, UpdatePlayParticipantSchema,
+        UpdatePlaySchema, UpdatePlayerSchema, UpdateSessionSchema,
+    },
+};
+
+use super::{
+    BackupRepository, BulkCommit, EventRepository, GameRepository, HistoryRepository,
+    IdempotencyRepository, ParticipantRepository, PlayRepository, PlayerRepository, Precondition,
+    RepoError, SessionRepository, StatsRepository, TrashRepository, attendees, check,
+    calendar_event, calendar_session, check_game_ranges, invitations, normalize_name,
+};
+
+/// Every repository trait, implemented over one single-connection SQLite pool
+pub struct SqliteRepository {
+    db: SqlitePool,
+}
+
+impl SqliteRepository {
+    /// Opens the database at a `sqlite:` URL, creating it if needed, and
+    /// migrates it. The pool holds a single connection, as SQLite only has one
+    /// writer at a time anyway; a transaction therefore has to be finished
+    /// before the pool is used again.
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal);
+
+        // The actor is handed over once `audit_context` has been created
+        let migrated = Arc::new(AtomicBool::new(false));
+        let has_audit_context = migrated.clone();
+
+        // An in-memory database lives as long as its connection
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .before_acquire(move |conn, _| {
+                let has_audit_context = has_audit_context.load(Ordering::Relaxed);
+                Box::pin(async move {
+                    if has_audit_context {
+                        set_actor(conn).await?;
+                    }
+                    Ok(true)
+                })
+            })
+            .connect_with(options)
+            .await?;
+
+        sqlx::migrate!("./migrations/sqlite").run(&db).await?;
+        migrated.store(true, Ordering::Relaxed);
+
+        Ok(SqliteRepository { db })
+    }
+}
+
+/// Hands the actor of the current request to the connection taken from the
+/// pool, for the audit log to record
+async fn set_actor(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE audit_context SET actor = ?1"#)
+        .bind(actor::current())
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Maps `RowNotFound` to `NotFound` and everything else to `Internal`
+fn not_found(e: sqlx::Error) -> RepoError {
+    match e {
+        sqlx::Error::RowNotFound => RepoError::NotFound,
+        _ => e.into(),
+    }
+}
+
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db_err) if db_err.is_unique_violation())
+}
+
+fn is_foreign_key_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation())
+}
+
+/// Looks up the live game other than `id` a name and creator clash with, or
+/// the one with the same BGG ID
+async fn game_conflict(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    name: &str,
+    creator_name: &str,
+    bgg_id: Option<i32>,
+) -> RepoError {
+    let existing_id = sqlx::query_scalar(
+        r#"SELECT id FROM games
+        WHERE (normalized_name = ?1 AND normalized_creator_name = ?2 OR bgg_id = ?4)
+        AND deleted_at IS NULL AND id <> ?3
+        LIMIT 1"#,
+    )
+    .bind(normalize_name(name))
+    .bind(normalize_name(creator_name))
+    .bind(id)
+    .bind(bgg_id)
+    .fetch_optional(conn)
+    .await
+    .ok()
+    .flatten();
+
+    RepoError::Duplicate { existing_id }
+}
+
+/// Looks up the live player other than `id` a clash is with. SQLite does not
+/// name the violated index, so a player with the same name is looked for
+/// first and the owner otherwise.
+async fn player_conflict(conn: &mut SqliteConnection, id: Uuid, name: &str) -> RepoError {
+    let existing_id = sqlx::query_scalar(
+        r#"SELECT id FROM players WHERE normalized_name = ?1 AND deleted_at IS NULL AND id <> ?2"#,
+    )
+    .bind(normalize_name(name))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .ok()
+    .flatten();
+
+    if existing_id.is_some() {
+        return RepoError::Duplicate { existing_id };
+    }
+
+    let owner_id = sqlx::query_scalar(
+        r#"SELECT id FROM players WHERE is_owner AND deleted_at IS NULL AND id <> ?1"#,
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .ok()
+    .flatten();
+
+    RepoError::OwnerExists { owner_id }
+}
+
+/// Maps a failed revert or restore to the unique index or the references it ran into
+fn revert_error(e: sqlx::Error, references: &[&'static str]) -> RepoError {
+    if is_unique_violation(&e) {
+        RepoError::Duplicate { existing_id: None }
+    } else if is_foreign_key_violation(&e) {
+        RepoError::MissingReferences(references.to_vec())
+    } else {
+        e.into()
+    }
+}
+
+/// Reads a snapshot the audit log recorded back into a model. Snapshots taken
+/// before rows were versioned start over at version 1.
+fn from_snapshot<T: serde::de::DeserializeOwned>(
+    snapshot: &serde_json::Value,
+) -> Result<T, RepoError> {
+    let mut row = serde_json::json!({"version": 1});
+    if let (Some(row), Some(snapshot)) = (row.as_object_mut(), snapshot.as_object()) {
+        row.extend(snapshot.clone());
+    }
+    serde_json::from_value(row).map_err(|e| RepoError::Internal(format!("{:?}", e)))
+}
+
+async fn insert_player(
+    conn: &mut SqliteConnection,
+    body: &PlayerSchema,
+) -> Result<PlayerModel, RepoError> {
+    let id = Uuid::new_v4();
+    let player = sqlx::query_as::<_, PlayerModel>(
+        r#"INSERT INTO players (id, name, normalized_name, is_owner, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5) RETURNING *"#,
+    )
+    .bind(id)
+    .bind(&body.name)
+    .bind(normalize_name(&body.name))
+    .bind(body.is_owner.unwrap_or(false))
+    .bind(Utc::now())
+    .fetch_one(&mut *conn)
+    .await;
+
+    match player {
+        Ok(player) => Ok(player),
+        Err(e) if is_unique_violation(&e) => Err(player_conflict(conn, id, &body.name).await),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Maps a failed play write: the live game is looked up by the statement, so
+/// a foreign key can only fail on the session
+fn play_error(e: sqlx::Error) -> RepoError {
+    match e {
+        sqlx::Error::RowNotFound => RepoError::MissingReferences(vec!["game_id"]),
+        e if is_foreign_key_violation(&e) => RepoError::MissingReferences(vec!["session_id"]),
+        e => e.into(),
+    }
+}
+
+/// Inserts a play of a live game
+async fn insert_play(
+    conn: &mut SqliteConnection,
+    body: &PlaySchema,
+) -> Result<PlayModel, RepoError> {
+    sqlx::query_as::<_, PlayModel>(
+        r#"INSERT INTO plays
+        (id, game_id, session_id, location, length_minutes, comments, created_at)
+        SELECT ?1, id, ?4, ?5, ?6, ?7, ?2 FROM games WHERE id = ?3 AND deleted_at IS NULL
+        RETURNING *"#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(Utc::now())
+    .bind(body.game_id)
+    .bind(body.session_id)
+    .bind(&body.location)
+    .bind(body.length_minutes)
+    .bind(&body.comments)
+    .fetch_one(conn)
+    .await
+    .map_err(play_error)
+}
+
+/// A session with its attendees
+async fn select_session(conn: &mut SqliteConnection, id: Uuid) -> Result<SessionModel, RepoError> {
+    let mut session = sqlx::query_as::<_, SessionModel>(r#"SELECT * FROM sessions WHERE id = ?1"#)
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(not_found)?;
+    session.attendee_ids = sqlx::query_scalar::<_, Uuid>(
+        r#"SELECT player_id FROM session_attendees WHERE session_id = ?1"#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    session.attendee_ids.sort();
+    Ok(session)
+}
+
+/// Every session with its attendees
+async fn select_sessions(conn: &mut SqliteConnection) -> Result<Vec<SessionModel>, RepoError> {
+    let mut sessions =
+        sqlx::query_as::<_, SessionModel>(r#"SELECT * FROM sessions ORDER BY created_at, id"#)
+            .fetch_all(&mut *conn)
+            .await?;
+    let attendance = sqlx::query_as::<_, (Uuid, Uuid)>(
+        r#"SELECT session_id, player_id FROM session_attendees ORDER BY player_id"#,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for session in &mut sessions {
+        session.attendee_ids = attendance
+            .iter()
+            .filter(|(session_id, _)| *session_id == session.id)
+            .map(|(_, player_id)| *player_id)
+            .collect();
+    }
+    Ok(sessions)
+}
+
+/// Checks that the host of a session or event and the given players are
+/// live players, listing each field that fails; `field` names the players
+async fn check_players(
+    conn: &mut SqliteConnection,
+    host_id: Option<Uuid>,
+    player_ids: &[Uuid],
+    field: &'static str,
+) -> Result<(), RepoError> {
+    let mut missing = Vec::new();
+    for (field, ids) in [("host_id", host_id.as_slice()), (field, player_ids)] {
+        for &id in ids {
+            let live: bool = sqlx::query_scalar(
+                r#"SELECT EXISTS (SELECT 1 FROM players WHERE id = ?1 AND deleted_at IS NULL)"#,
+            )
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+            if !live {
+                missing.push(field);
+                break;
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(RepoError::MissingReferences(missing))
+    }
+}
+
+/// Makes `attendee_ids` the attendees of a session
+async fn set_attendees(
+    conn: &mut SqliteConnection,
+    session_id: Uuid,
+    attendee_ids: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM session_attendees WHERE session_id = ?1"#)
+        .bind(session_id)
+        .execute(&mut *conn)
+        .await?;
+    for player_id in attendee_ids {
+        sqlx::query(r#"INSERT INTO session_attendees (session_id, player_id) VALUES (?1, ?2)"#)
+            .bind(session_id)
+            .bind(player_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Events with their invitations, soonest first; only the one with `id`
+/// when given
+async fn select_events(
+    conn: &mut SqliteConnection,
+    id: Option<Uuid>,
+) -> Result<Vec<EventModel>, sqlx::Error> {
+    let mut events = sqlx::query_as::<_, EventModel>(
+        r#"SELECT * FROM events WHERE ?1 IS NULL OR id = ?1
+        ORDER BY scheduled_on, created_at, id"#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let invitations = sqlx::query_as::<_, (Uuid, Uuid, Option<Rsvp>, Option<DateTime<Utc>>)>(
+        r#"SELECT event_id, player_id, response, responded_at FROM event_invitations
+        WHERE ?1 IS NULL OR event_id = ?1 ORDER BY player_id"#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let games = sqlx::query_as::<_, (Uuid, Uuid, Uuid)>(
+        r#"SELECT event_id, player_id, game_id FROM event_games
+        WHERE ?1 IS NULL OR event_id = ?1 ORDER BY game_id"#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for event in &mut events {
+        event.invitations = invitations
+            .iter()
+            .filter(|(event_id, ..)| *event_id == event.id)
+            .map(
+                |&(event_id, player_id, response, responded_at)| InvitationModel {
+                    player_id,
+                    response,
+                    responded_at,
+                    game_ids: games
+                        .iter()
+                        .filter(|game| (game.0, game.1) == (event_id, player_id))
+                        .map(|game| game.2)
+                        .collect(),
+                },
+            )
+            .collect();
+    }
+    Ok(events)
+}
+
+/// An event with its invitations
+async fn select_event(conn: &mut SqliteConnection, id: Uuid) -> Result<EventModel, RepoError> {
+    select_events(conn, Some(id))
+        .await?
+        .pop()
+        .ok_or(RepoError::NotFound)
+}
+
+/// Makes `invitations` the invitations of an event, with the games they bring
+async fn set_invitations(
+    conn: &mut SqliteConnection,
+    event_id: Uuid,
+    invitations: &[InvitationModel],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM event_invitations WHERE event_id = ?1"#)
+        .bind(event_id)
+        .execute(&mut *conn)
+        .await?;
+    for invitation in invitations {
+        sqlx::query(
+            r#"INSERT INTO event_invitations (event_id, player_id, response, responded_at)
+            VALUES (?1, ?2, ?3, ?4)"#,
+        )
+        .bind(event_id)
+        .bind(invitation.player_id)
+        .bind(invitation.response)
+        .bind(invitation.responded_at)
+        .execute(&mut *conn)
+        .await?;
+        for game_id in &invitation.game_ids {
+            sqlx::query(
+                r#"INSERT INTO event_games (event_id, player_id, game_id) VALUES (?1, ?2, ?3)"#,
+            )
+            .bind(event_id)
+            .bind(invitation.player_id)
+            .bind(game_id)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves a live play to the trash
+async fn trash_play(conn: &mut SqliteConnection, id: Uuid) -> Result<PlayModel, RepoError> {
+    sqlx::query_as::<_, PlayModel>(
+        r#"UPDATE plays SET deleted_at = ?1, version = version + 1
+        WHERE id = ?2 AND deleted_at IS NULL RETURNING *"#,
+    )
+    .bind(Utc::now())
+    .bind(id)
+    .fetch_one(conn)
+    .await
+    .map_err(not_found)
+}
+
+/// Checks that the play and player a participation points at exist and are
+/// not in the trash, listing each one that fails
+async fn check_references(
+    conn: &mut SqliteConnection,
+    play_id: Uuid,
+    player_id: Uuid,
+) -> Result<(), RepoError> {
+    let (play, player): (bool, bool) = sqlx::query_as(
+        r#"SELECT
+            EXISTS (SELECT 1 FROM plays WHERE id = ?1 AND deleted_at IS NULL),
+            EXISTS (SELECT 1 FROM players WHERE id = ?2 AND deleted_at IS NULL)"#,
+    )
+    .bind(play_id)
+    .bind(player_id)
+    .fetch_one(conn)
+    .await?;
+
+    let mut missing = Vec::new();
+    if !play {
+        missing.push("play_id");
+    }
+    if !player {
+        missing.push("player_id");
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(RepoError::MissingReferences(missing))
+    }
+}
+
+fn participant_error(e: sqlx::Error) -> RepoError {
+    if is_unique_violation(&e) {
+        RepoError::Duplicate { existing_id: None }
+    } else if is_foreign_key_violation(&e) {
+        RepoError::MissingReferences(vec!["play_id", "player_id"])
+    } else {
+        e.into()
+    }
+}
+
+/// Finishes a bulk write: the transaction is only committed when `commit`
+/// allows it for these results. A failed statement does not abort a SQLite
+/// transaction, so the items need no savepoints of their own.
+async fn finish_bulk<T>(
+    tx: sqlx::Transaction<'_, sqlx::Sqlite>,
+    results: Vec<Result<T, RepoError>>,
+    commit: BulkCommit,
+) -> Result<Vec<Result<T, RepoError>>, RepoError> {
+    let keep = match commit {
+        BulkCommit::Successful => true,
+        BulkCommit::AllOrNothing => results.iter().all(Result::is_ok),
+        BulkCommit::Nothing => false,
+    };
+
+    if keep {
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+    Ok(results)
+}
+
+#[async_trait]
+impl GameRepository for SqliteRepository {
+    async fn create(&self, game: &GameSchema) -> Result<GameModel, RepoError> {
+        check_game_ranges(
+            game.min_players,
+            game.max_players,
+            game.min_playtime_minutes,
+            game.max_playtime_minutes,
+        )?;
+
+        let mut conn = self.db.acquire().await?;
+        let id = Uuid::new_v4();
+        let created = sqlx::query_as::<_, GameModel>(
+            r#"INSERT INTO games (
+                id, name, normalized_name, creator_name, normalized_creator_name, created_at,
+                bgg_id, year_published, min_players, max_players, min_playtime_minutes,
+                max_playtime_minutes, weight
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13) RETURNING *"#,
+        )
+        .bind(id)
+        .bind(&game.name)
+        .bind(normalize_name(&game.name))
+        .bind(&game.creator_name)
+        .bind(normalize_name(&game.creator_name))
+        .bind(Utc::now())
+        .bind(game.bgg_id)
+        .bind(game.year_published)
+        .bind(game.min_players)
+        .bind(game.max_players)
+        .bind(game.min_playtime_minutes)
+        .bind(game.max_playtime_minutes)
+        .bind(game.weight)
+        .fetch_one(&mut *conn)
+        .await;
+
+        match created {
+            Ok(created) => Ok(created),
+            Err(e) if is_unique_violation(&e) => {
+                Err(game_conflict(&mut conn, id, &game.name, &game.creator_name, game.bgg_id).await)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<GameModel>, RepoError> {
+        let games = sqlx::query_as::<_, GameModel>(
+            r#"SELECT * FROM games WHERE deleted_at IS NULL ORDER BY name"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(games)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<GameModel, RepoError> {
+        sqlx::query_as::<_, GameModel>(
+            r#"SELECT * FROM games WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateGameSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let game = sqlx::query_as::<_, GameModel>(
+            r#"SELECT * FROM games WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, game.version)?;
+
+        let new_name = changes.name.as_ref().unwrap_or(&game.name);
+        let new_creator = changes.creator_name.as_ref().unwrap_or(&game.creator_name);
+        let new_bgg_id = changes.bgg_id.unwrap_or(game.bgg_id);
+        let new_min_players = changes.min_players.unwrap_or(game.min_players);
+        let new_max_players = changes.max_players.unwrap_or(game.max_players);
+        let new_min_playtime = changes
+            .min_playtime_minutes
+            .unwrap_or(game.min_playtime_minutes);
+        let new_max_playtime = changes
+            .max_playtime_minutes
+            .unwrap_or(game.max_playtime_minutes);
+        check_game_ranges(
+            new_min_players,
+            new_max_players,
+            new_min_playtime,
+            new_max_playtime,
+        )?;
+
+        let updated_game = sqlx::query_as::<_, GameModel>(
+            r#"UPDATE games SET
+                name = ?1,
+                normalized_name = ?2,
+                creator_name = ?3,
+                normalized_creator_name = ?4,
+                bgg_id = ?6,
+                year_published = ?7,
+                min_players = ?8,
+                max_players = ?9,
+                min_playtime_minutes = ?10,
+                max_playtime_minutes = ?11,
+                weight = ?12,
+                version = version + (
+                    name IS NOT ?1 OR creator_name IS NOT ?3 OR bgg_id IS NOT ?6
+                    OR year_published IS NOT ?7 OR min_players IS NOT ?8
+                    OR max_players IS NOT ?9 OR min_playtime_minutes IS NOT ?10
+                    OR max_playtime_minutes IS NOT ?11 OR weight IS NOT ?12
+                )
+            WHERE id = ?5 RETURNING *"#,
+        )
+        .bind(new_name)
+        .bind(normalize_name(new_name))
+        .bind(new_creator)
+        .bind(normalize_name(new_creator))
+        .bind(id)
+        .bind(new_bgg_id)
+        .bind(changes.year_published.unwrap_or(game.year_published))
+        .bind(new_min_players)
+        .bind(new_max_players)
+        .bind(new_min_playtime)
+        .bind(new_max_playtime)
+        .bind(changes.weight.unwrap_or(game.weight))
+        .fetch_one(&mut *tx)
+        .await;
+
+        let updated_game = match updated_game {
+            Ok(game) => game,
+            Err(e) if is_unique_violation(&e) => {
+                return Err(game_conflict(&mut tx, id, new_name, new_creator, new_bgg_id).await);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.commit().await?;
+        Ok(updated_game)
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<GameModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let game = sqlx::query_as::<_, GameModel>(
+            r#"SELECT * FROM games WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, game.version)?;
+
+        // Mirrors the ON DELETE RESTRICT on plays.game_id for live plays
+        let has_plays: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS (SELECT 1 FROM plays WHERE game_id = ?1 AND deleted_at IS NULL)"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if has_plays {
+            return Err(RepoError::InUse);
+        }
+
+        let game = sqlx::query_as::<_, GameModel>(
+            r#"UPDATE games SET deleted_at = ?1, version = version + 1 WHERE id = ?2 RETURNING *"#,
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(game)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<GameModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        let restored = sqlx::query_as::<_, GameModel>(
+            r#"UPDATE games SET deleted_at = NULL, version = version + 1
+            WHERE id = ?1 AND deleted_at IS NOT NULL RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await;
+
+        match restored {
+            Ok(game) => Ok(game),
+            Err(e) if is_unique_violation(&e) => {
+                let game = sqlx::query_as::<_, GameModel>(r#"SELECT * FROM games WHERE id = ?1"#)
+                    .bind(id)
+                    .fetch_one(&mut *conn)
+                    .await?;
+                Err(game_conflict(&mut conn, id, &game.name, &game.creator_name, game.bgg_id).await)
+            }
+            Err(e) => Err(not_found(e)),
+        }
+    }
+
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedGameData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM games WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM games WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(target_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepoError::MissingReferences(vec!["target_id"]),
+            _ => e.into(),
+        })?;
+
+        let moved_plays = sqlx::query(
+            r#"UPDATE plays SET game_id = ?1, version = version + 1 WHERE game_id = ?2"#,
+        )
+        .bind(target_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Games brought to an event change the event; a player bringing both
+        // brings the target once
+        sqlx::query(
+            r#"UPDATE events SET version = version + 1
+            WHERE id IN (SELECT event_id FROM event_games WHERE game_id = ?1)"#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"INSERT OR IGNORE INTO event_games (event_id, player_id, game_id)
+            SELECT event_id, player_id, ?1 FROM event_games WHERE game_id = ?2"#,
+        )
+        .bind(target_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        let merged_game =
+            sqlx::query_as::<_, GameModel>(r#"DELETE FROM games WHERE id = ?1 RETURNING *"#)
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let game = sqlx::query_as::<_, GameModel>(r#"SELECT * FROM games WHERE id = ?1"#)
+            .bind(target_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(MergedGameData {
+            game,
+            merged_game,
+            moved_plays,
+        })
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<GameModel>, RepoError> {
+        let games = sqlx::query_as::<_, GameModel>(
+            r#"SELECT * FROM games WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(games)
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for SqliteRepository {
+    async fn create(&self, player: &PlayerSchema) -> Result<PlayerModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        insert_player(&mut conn, player).await
+    }
+
+    async fn create_many(
+        &self,
+        players: &[PlayerSchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayerModel, RepoError>>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut results = Vec::with_capacity(players.len());
+        for player in players {
+            results.push(insert_player(&mut tx, player).await);
+        }
+
+        finish_bulk(tx, results, commit).await
+    }
+
+    async fn list(&self) -> Result<Vec<PlayerModel>, RepoError> {
+        let players = sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players WHERE deleted_at IS NULL ORDER BY name"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(players)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<PlayerModel, RepoError> {
+        sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlayerSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let player = sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, player.version)?;
+
+        let new_name = changes.name.as_ref().unwrap_or(&player.name);
+        let new_is_owner = changes.is_owner.unwrap_or(player.is_owner);
+
+        let updated_player = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET
+                name = ?1,
+                normalized_name = ?2,
+                is_owner = ?3,
+                version = version + (name IS NOT ?1 OR is_owner IS NOT ?3)
+            WHERE id = ?4 RETURNING *"#,
+        )
+        .bind(new_name)
+        .bind(normalize_name(new_name))
+        .bind(new_is_owner)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await;
+
+        let updated_player = match updated_player {
+            Ok(player) => player,
+            Err(e) if is_unique_violation(&e) => {
+                return Err(player_conflict(&mut tx, id, new_name).await);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.commit().await?;
+        Ok(updated_player)
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayerModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let player = sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, player.version)?;
+
+        let player = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET deleted_at = ?1, version = version + 1 WHERE id = ?2 RETURNING *"#,
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(player)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<PlayerModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        let restored = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET deleted_at = NULL, version = version + 1
+            WHERE id = ?1 AND deleted_at IS NOT NULL RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await;
+
+        match restored {
+            Ok(player) => Ok(player),
+            Err(e) if is_unique_violation(&e) => {
+                let player =
+                    sqlx::query_as::<_, PlayerModel>(r#"SELECT * FROM players WHERE id = ?1"#)
+                        .bind(id)
+                        .fetch_one(&mut *conn)
+                        .await?;
+                Err(player_conflict(&mut conn, id, &player.name).await)
+            }
+            Err(e) => Err(not_found(e)),
+        }
+    }
+
+    async fn merge(&self, id: Uuid, target_id: Uuid) -> Result<MergedPlayerData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM players WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM players WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(target_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepoError::MissingReferences(vec!["target_id"]),
+            _ => e.into(),
+        })?;
+
+        // Plays both players took part in would collide on (play_id, player_id)
+        let dropped_participations = sqlx::query(
+            r#"DELETE FROM play_participants
+            WHERE player_id = ?1
+            AND play_id IN (SELECT play_id FROM play_participants WHERE player_id = ?2)"#,
+        )
+        .bind(id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let moved_participations = sqlx::query(
+            r#"UPDATE play_participants SET player_id = ?1, version = version + 1
+            WHERE player_id = ?2"#,
+        )
+        .bind(target_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // The sessions change along with their host or attendance
+        sqlx::query(
+            r#"UPDATE sessions SET
+                host_id = CASE WHEN host_id = ?2 THEN ?1 ELSE host_id END,
+                version = version + 1
+            WHERE host_id = ?2
+            OR id IN (SELECT session_id FROM session_attendees WHERE player_id = ?2)"#,
+        )
+        .bind(target_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"INSERT OR IGNORE INTO session_attendees (session_id, player_id)
+            SELECT session_id, ?1 FROM session_attendees WHERE player_id = ?2"#,
+        )
+        .bind(target_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Events likewise; an invitation both players had keeps the target's
+        // answer, and the games brought follow a moved invitation
+        sqlx::query(
+            r#"UPDATE events SET
+                host_id = CASE WHEN host_id = ?2 THEN ?1 ELSE host_id END,
+                version = version + 1
+            WHERE host_id = ?2
+            OR id IN (SELECT event_id FROM event_invitations WHERE player_id = ?2)"#,
+        )
+        .bind(target_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"DELETE FROM event_invitations
+            WHERE player_id = ?1
+            AND event_id IN (SELECT event_id FROM event_invitations WHERE player_id = ?2)"#,
+        )
+        .bind(id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(r#"UPDATE event_invitations SET player_id = ?1 WHERE player_id = ?2"#)
+            .bind(target_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        let merged_player =
+            sqlx::query_as::<_, PlayerModel>(r#"DELETE FROM players WHERE id = ?1 RETURNING *"#)
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let player = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET
+                is_owner = is_owner OR ?1,
+                version = version + (NOT is_owner AND ?1)
+            WHERE id = ?2 RETURNING *"#,
+        )
+        .bind(merged_player.is_owner)
+        .bind(target_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(MergedPlayerData {
+            player,
+            merged_player,
+            moved_participations,
+            dropped_participations,
+        })
+    }
+
+    async fn make_owner(&self, id: Uuid) -> Result<OwnerData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        // Also clears the flag on a trashed former owner so restoring it cannot clash
+        let previous_owner = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET is_owner = FALSE, version = version + 1
+            WHERE is_owner AND id <> ?1 RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .find(|player| player.deleted_at.is_none());
+
+        let player = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET is_owner = TRUE, version = version + (NOT is_owner)
+            WHERE id = ?1 AND deleted_at IS NULL RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        tx.commit().await?;
+
+        Ok(OwnerData {
+            player,
+            previous_owner,
+        })
+    }
+
+    async fn owner(&self) -> Result<Option<PlayerModel>, RepoError> {
+        let owner = sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players WHERE is_owner AND deleted_at IS NULL"#,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(owner)
+    }
+
+    async fn stats(&self, id: Uuid) -> Result<PlayerStatsModel, RepoError> {
+        let stats = sqlx::query_as::<_, PlayerStatsModel>(
+            r#"SELECT
+                COUNT(*) AS plays,
+                COUNT(*) FILTER (WHERE pp.is_winner) AS wins,
+                COUNT(DISTINCT p.game_id) AS games
+            FROM play_participants pp
+            JOIN plays p ON p.id = pp.play_id AND p.deleted_at IS NULL
+            WHERE pp.player_id = ?1"#,
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(stats)
+    }
+
+    async fn plays(&self, id: Uuid) -> Result<Vec<PlayerPlayModel>, RepoError> {
+        let plays = sqlx::query_as::<_, PlayerPlayModel>(
+            r#"SELECT p.id, p.game_id, p.created_at, pp.score, pp.is_winner
+            FROM plays p
+            JOIN play_participants pp ON pp.play_id = p.id
+            WHERE pp.player_id = ?1 AND p.deleted_at IS NULL
+            ORDER BY p.created_at DESC"#,
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(plays)
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<PlayerModel>, RepoError> {
+        let players = sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(players)
+    }
+}
+
+#[async_trait]
+impl PlayRepository for SqliteRepository {
+    async fn create(&self, play: &PlaySchema) -> Result<PlayModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        insert_play(&mut conn, play).await
+    }
+
+    async fn create_many(
+        &self,
+        plays: &[PlaySchema],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut results = Vec::with_capacity(plays.len());
+        for play in plays {
+            results.push(insert_play(&mut tx, play).await);
+        }
+
+        finish_bulk(tx, results, commit).await
+    }
+
+    async fn list(&self) -> Result<Vec<PlayModel>, RepoError> {
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays WHERE deleted_at IS NULL ORDER BY game_id"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(plays)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<PlayModel, RepoError> {
+        sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdatePlaySchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let play = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, play.version)?;
+
+        let new_game_id = changes.game_id.unwrap_or(play.game_id);
+        let new_session_id = changes.session_id.unwrap_or(play.session_id);
+        let new_location = changes.location.clone().unwrap_or(play.location);
+        let new_length = changes.length_minutes.unwrap_or(play.length_minutes);
+        let new_comments = changes.comments.clone().unwrap_or(play.comments);
+
+        let updated_play = sqlx::query_as::<_, PlayModel>(
+            r#"UPDATE plays SET
+                game_id = ?1,
+                session_id = ?3,
+                location = ?4,
+                length_minutes = ?5,
+                comments = ?6,
+                version = version + (
+                    game_id IS NOT ?1 OR session_id IS NOT ?3 OR location IS NOT ?4
+                    OR length_minutes IS NOT ?5 OR comments IS NOT ?6
+                )
+            WHERE id = ?2
+            AND EXISTS (SELECT 1 FROM games WHERE id = ?1 AND deleted_at IS NULL)
+            RETURNING *"#,
+        )
+        .bind(new_game_id)
+        .bind(id)
+        .bind(new_session_id)
+        .bind(new_location)
+        .bind(new_length)
+        .bind(new_comments)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(play_error)?;
+
+        tx.commit().await?;
+        Ok(updated_play)
+    }
+
+    async fn trash(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let play = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays WHERE id = ?1 AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, play.version)?;
+
+        let play = trash_play(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(play)
+    }
+
+    async fn trash_many(
+        &self,
+        ids: &[Uuid],
+        commit: BulkCommit,
+    ) -> Result<Vec<Result<PlayModel, RepoError>>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut results = Vec::with_capacity(ids.len());
+        for id in ids {
+            results.push(trash_play(&mut tx, *id).await);
+        }
+
+        finish_bulk(tx, results, commit).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<PlayModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        let play = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays WHERE id = ?1 AND deleted_at IS NOT NULL"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(not_found)?;
+
+        sqlx::query_as::<_, PlayModel>(
+            r#"UPDATE plays SET deleted_at = NULL, version = version + 1
+            WHERE id = ?1
+            AND EXISTS (SELECT 1 FROM games WHERE id = plays.game_id AND deleted_at IS NULL)
+            RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepoError::ReferenceTrashed { id: play.game_id },
+            _ => e.into(),
+        })
+    }
+
+    async fn list_trashed(&self) -> Result<Vec<PlayModel>, RepoError> {
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(plays)
+    }
+}
+
+#[async_trait]
+impl ParticipantRepository for SqliteRepository {
+    async fn create(
+        &self,
+        participant: &PlayParticipantSchema,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        check_references(&mut conn, participant.play_id, participant.player_id).await?;
+
+        sqlx::query_as::<_, PlayParticipantModel>(
+            r#"INSERT INTO play_participants (play_id, player_id, score, is_winner)
+            VALUES (?1, ?2, ?3, ?4) RETURNING *"#,
+        )
+        .bind(participant.play_id)
+        .bind(participant.player_id)
+        .bind(participant.score)
+        .bind(participant.is_winner.unwrap_or(false))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(participant_error)
+    }
+
+    async fn list(&self) -> Result<Vec<PlayParticipantModel>, RepoError> {
+        let participants = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"SELECT pp.* FROM play_participants pp
+            JOIN plays p ON p.id = pp.play_id AND p.deleted_at IS NULL
+            JOIN players pl ON pl.id = pp.player_id AND pl.deleted_at IS NULL"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(participants)
+    }
+
+    async fn list_for_play(&self, play_id: Uuid) -> Result<Vec<PlayParticipantModel>, RepoError> {
+        let participants = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"SELECT pp.* FROM play_participants pp
+            JOIN plays p ON p.id = pp.play_id AND p.deleted_at IS NULL
+            JOIN players pl ON pl.id = pp.player_id AND pl.deleted_at IS NULL
+            WHERE pp.play_id = ?1"#,
+        )
+        .bind(play_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(participants)
+    }
+
+    async fn update(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        changes: &UpdatePlayParticipantSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let participant = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"SELECT * FROM play_participants WHERE play_id = ?1 AND player_id = ?2"#,
+        )
+        .bind(play_id)
+        .bind(player_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, participant.version)?;
+
+        let new_play_id = changes.play_id.unwrap_or(participant.play_id);
+        let new_player_id = changes.player_id.unwrap_or(participant.player_id);
+        let new_score = changes.score.unwrap_or(participant.score);
+        let new_is_winner = changes.is_winner.unwrap_or(participant.is_winner);
+
+        check_references(&mut tx, new_play_id, new_player_id).await?;
+
+        let updated_participant = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"UPDATE play_participants SET
+                play_id = ?1,
+                player_id = ?2,
+                score = ?3,
+                is_winner = ?4,
+                version = version + (
+                    play_id IS NOT ?1 OR player_id IS NOT ?2
+                    OR score IS NOT ?3 OR is_winner IS NOT ?4
+                )
+            WHERE play_id = ?5 AND player_id = ?6 RETURNING *"#,
+        )
+        .bind(new_play_id)
+        .bind(new_player_id)
+        .bind(new_score)
+        .bind(new_is_winner)
+        .bind(play_id)
+        .bind(player_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(participant_error)?;
+
+        tx.commit().await?;
+        Ok(updated_participant)
+    }
+
+    async fn delete(
+        &self,
+        play_id: Uuid,
+        player_id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let participant = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"SELECT * FROM play_participants WHERE play_id = ?1 AND player_id = ?2"#,
+        )
+        .bind(play_id)
+        .bind(player_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(not_found)?;
+
+        check(precondition, participant.version)?;
+
+        let deleted_participant = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"DELETE FROM play_participants WHERE play_id = ?1 AND player_id = ?2 RETURNING *"#,
+        )
+        .bind(play_id)
+        .bind(player_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(deleted_participant)
+    }
+}
+
+/// Reverts update the columns a revert may change and bump the version when
+/// that changes anything, or re-insert the row if it has been purged, like the
+/// `ON CONFLICT` upserts of the Postgres implementation
+#[async_trait]
+impl SessionRepository for SqliteRepository {
+    async fn create(&self, session: &SessionSchema) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let attendee_ids = attendees(session.host_id, &session.attendee_ids);
+        check_players(&mut tx, session.host_id, &attendee_ids, "attendee_ids").await?;
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"INSERT INTO sessions (id, played_on, location, host_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        )
+        .bind(id)
+        .bind(session.played_on)
+        .bind(&session.location)
+        .bind(session.host_id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        set_attendees(&mut tx, id, &attendee_ids).await?;
+        let session = select_session(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(session)
+    }
+
+    async fn list(&self) -> Result<Vec<SessionModel>, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM sessions ORDER BY played_on DESC, created_at DESC"#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut sessions = Vec::with_capacity(ids.len());
+        for id in ids {
+            sessions.push(select_session(&mut tx, id).await?);
+        }
+
+        tx.commit().await?;
+        Ok(sessions)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<SessionModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        select_session(&mut conn, id).await
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateSessionSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let session = select_session(&mut tx, id).await?;
+
+        check(precondition, session.version)?;
+
+        let new_played_on = changes.played_on.unwrap_or(session.played_on);
+        let new_location = changes.location.clone().unwrap_or(session.location);
+        let new_host_id = changes.host_id.unwrap_or(session.host_id);
+        let listed = changes
+            .attendee_ids
+            .as_deref()
+            .unwrap_or(&session.attendee_ids);
+        let new_attendee_ids = attendees(new_host_id, listed);
+
+        // Players who already attend may have been trashed since
+        let added: Vec<_> = new_attendee_ids
+            .iter()
+            .copied()
+            .filter(|player_id| !session.attendee_ids.contains(player_id))
+            .collect();
+        let new_host = new_host_id.filter(|_| new_host_id != session.host_id);
+        check_players(&mut tx, new_host, &added, "attendee_ids").await?;
+
+        let attendance_changed = new_attendee_ids != session.attendee_ids;
+        sqlx::query(
+            r#"UPDATE sessions SET
+                played_on = ?1,
+                location = ?2,
+                host_id = ?3,
+                version = version + (
+                    ?4 OR played_on IS NOT ?1 OR location IS NOT ?2 OR host_id IS NOT ?3
+                )
+            WHERE id = ?5"#,
+        )
+        .bind(new_played_on)
+        .bind(&new_location)
+        .bind(new_host_id)
+        .bind(attendance_changed)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        set_attendees(&mut tx, id, &new_attendee_ids).await?;
+        let updated_session = select_session(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(updated_session)
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<SessionModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let session = select_session(&mut tx, id).await?;
+
+        check(precondition, session.version)?;
+
+        // Attendance goes through ON DELETE CASCADE
+        sqlx::query(
+            r#"UPDATE plays SET session_id = NULL, version = version + 1 WHERE session_id = ?1"#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"UPDATE events SET session_id = NULL, version = version + 1 WHERE session_id = ?1"#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(r#"DELETE FROM sessions WHERE id = ?1"#)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl EventRepository for SqliteRepository {
+    async fn create(&self, event: &EventSchema) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let invitations = invitations(&[], event.host_id, &event.invitee_ids);
+        check_players(&mut tx, event.host_id, &event.invitee_ids, "invitee_ids").await?;
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"INSERT INTO events (id, scheduled_on, location, host_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        )
+        .bind(id)
+        .bind(event.scheduled_on)
+        .bind(&event.location)
+        .bind(event.host_id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        set_invitations(&mut tx, id, &invitations).await?;
+        let event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(event)
+    }
+
+    async fn list(&self) -> Result<Vec<EventModel>, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        Ok(select_events(&mut conn, None).await?)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<EventModel, RepoError> {
+        let mut conn = self.db.acquire().await?;
+        select_event(&mut conn, id).await
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        changes: &UpdateEventSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let event = select_event(&mut tx, id).await?;
+
+        check(precondition, event.version)?;
+
+        let new_scheduled_on = changes.scheduled_on.unwrap_or(event.scheduled_on);
+        let new_location = changes.location.clone().unwrap_or(event.location);
+        let new_host_id = changes.host_id.unwrap_or(event.host_id);
+        let current_ids: Vec<_> = event.invitations.iter().map(|i| i.player_id).collect();
+        let listed = changes.invitee_ids.as_deref().unwrap_or(&current_ids);
+        let new_invitations = invitations(&event.invitations, new_host_id, listed);
+
+        // Players who are invited already may have been trashed since
+        let added: Vec<_> = new_invitations
+            .iter()
+            .map(|invitation| invitation.player_id)
+            .filter(|player_id| !current_ids.contains(player_id))
+            .collect();
+        let new_host = new_host_id.filter(|_| new_host_id != event.host_id);
+        check_players(&mut tx, new_host, &added, "invitee_ids").await?;
+
+        let invitations_changed = new_invitations != event.invitations;
+        sqlx::query(
+            r#"UPDATE events SET
+                scheduled_on = ?1,
+                location = ?2,
+                host_id = ?3,
+                version = version + (
+                    ?4 OR scheduled_on IS NOT ?1 OR location IS NOT ?2 OR host_id IS NOT ?3
+                )
+            WHERE id = ?5"#,
+        )
+        .bind(new_scheduled_on)
+        .bind(&new_location)
+        .bind(new_host_id)
+        .bind(invitations_changed)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if invitations_changed {
+            set_invitations(&mut tx, id, &new_invitations).await?;
+        }
+        let updated_event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(updated_event)
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let event = select_event(&mut tx, id).await?;
+
+        check(precondition, event.version)?;
+
+        // Invitations go through ON DELETE CASCADE, the session stays
+        sqlx::query(r#"DELETE FROM events WHERE id = ?1"#)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(event)
+    }
+
+    async fn respond(
+        &self,
+        id: Uuid,
+        player_id: Uuid,
+        rsvp: &RsvpSchema,
+        precondition: Precondition<'_>,
+    ) -> Result<EventModel, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let event = select_event(&mut tx, id).await?;
+
+        check(precondition, event.version)?;
+
+        let mut game_ids = rsvp.game_ids.clone();
+        game_ids.sort();
+        game_ids.dedup();
+
+        let mut missing = Vec::new();
+        let live: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS (SELECT 1 FROM players WHERE id = ?1 AND deleted_at IS NULL)"#,
+        )
+        .bind(player_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !live {
+            missing.push("player_id");
+        }
+        for &game_id in &game_ids {
+            let live: bool = sqlx::query_scalar(
+                r#"SELECT EXISTS (SELECT 1 FROM games WHERE id = ?1 AND deleted_at IS NULL)"#,
+            )
+            .bind(game_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if !live {
+                missing.push("game_ids");
+                break;
+            }
+        }
+        if !missing.is_empty() {
+            return Err(RepoError::MissingReferences(missing));
+        }
+
+        let mut new_invitations = event.invitations;
+        new_invitations.retain(|invitation| invitation.player_id != player_id);
+        new_invitations.push(InvitationModel {
+            player_id,
+            response: Some(rsvp.response),
+            responded_at: Some(Utc::now()),
+            game_ids,
+        });
+        new_invitations.sort_by_key(|invitation| invitation.player_id);
+
+        sqlx::query(r#"UPDATE events SET version = version + 1 WHERE id = ?1"#)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        set_invitations(&mut tx, id, &new_invitations).await?;
+        let updated_event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(updated_event)
+    }
+
+    async fn start_session(
+        &self,
+        id: Uuid,
+        precondition: Precondition<'_>,
+    ) -> Result<EventSessionData, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let event = select_event(&mut tx, id).await?;
+
+        check(precondition, event.version)?;
+        if let Some(session_id) = event.session_id {
+            return Err(RepoError::Duplicate {
+                existing_id: Some(session_id),
+            });
+        }
+
+        // Players who answered yes and the host, as far as they are still live
+        let candidate_ids = event
+            .invitations
+            .iter()
+            .filter(|invitation| invitation.response == Some(Rsvp::Yes))
+            .map(|invitation| invitation.player_id)
+            .chain(event.host_id);
+        let mut live = Vec::new();
+        for player_id in candidate_ids {
+            let is_live: bool = sqlx::query_scalar(
+                r#"SELECT EXISTS (SELECT 1 FROM players WHERE id = ?1 AND deleted_at IS NULL)"#,
+            )
+            .bind(player_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if is_live {
+                live.push(player_id);
+            }
+        }
+        let host_id = event.host_id.filter(|id| live.contains(id));
+        let attendee_ids = attendees(host_id, &live);
+
+        let session_id = Uuid::new_v4();
+        sqlx::query(
+            r#"INSERT INTO sessions (id, played_on, location, host_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        )
+        .bind(session_id)
+        .bind(event.scheduled_on)
+        .bind(&event.location)
+        .bind(host_id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        set_attendees(&mut tx, session_id, &attendee_ids).await?;
+
+        sqlx::query(r#"UPDATE events SET session_id = ?1, version = version + 1 WHERE id = ?2"#)
+            .bind(session_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let session = select_session(&mut tx, session_id).await?;
+        let event = select_event(&mut tx, id).await?;
+
+        tx.commit().await?;
+        Ok(EventSessionData { event, session })
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for SqliteRepository {
+    async fn list(&self, entity: &str, entity_id: &str) -> Result<Vec<AuditLogModel>, RepoError> {
+        let history = sqlx::query_as::<_, AuditLogModel>(
+            r#"SELECT * FROM audit_log WHERE entity = ?1 AND entity_id = ?2 ORDER BY id DESC"#,
+        )
+        .bind(entity)
+        .bind(entity_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(history)
+    }
+
+    async fn get(
+        &self,
+        entity: &str,
+        entity_id: &str,
+        audit_id: i64,
+    ) -> Result<AuditLogModel, RepoError> {
+        sqlx::query_as::<_, AuditLogModel>(
+            r#"SELECT * FROM audit_log WHERE id = ?1 AND entity = ?2 AND entity_id = ?3"#,
+        )
+        .bind(audit_id)
+        .bind(entity)
+        .bind(entity_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn revert_game(&self, snapshot: &serde_json::Value) -> Result<GameModel, RepoError> {
+        let game: GameModel = from_snapshot(snapshot)?;
+        let mut tx = self.db.begin().await?;
+
+        let updated = sqlx::query_as::<_, GameModel>(
+            r#"UPDATE games SET
+                name = ?1,
+                normalized_name = ?2,
+                creator_name = ?3,
+                normalized_creator_name = ?4,
+                deleted_at = ?5,
+                bgg_id = ?7,
+                year_published = ?8,
+                min_players = ?9,
+                max_players = ?10,
+                min_playtime_minutes = ?11,
+                max_playtime_minutes = ?12,
+                weight = ?13,
+                version = version + (
+                    name IS NOT ?1 OR creator_name IS NOT ?3 OR deleted_at IS NOT ?5
+                    OR bgg_id IS NOT ?7 OR year_published IS NOT ?8 OR min_players IS NOT ?9
+                    OR max_players IS NOT ?10 OR min_playtime_minutes IS NOT ?11
+                    OR max_playtime_minutes IS NOT ?12 OR weight IS NOT ?13
+                )
+            WHERE id = ?6 RETURNING *"#,
+        )
+        .bind(&game.name)
+        .bind(normalize_name(&game.name))
+        .bind(&game.creator_name)
+        .bind(normalize_name(&game.creator_name))
+        .bind(game.deleted_at)
+        .bind(game.id)
+        .bind(game.bgg_id)
+        .bind(game.year_published)
+        .bind(game.min_players)
+        .bind(game.max_players)
+        .bind(game.min_playtime_minutes)
+        .bind(game.max_playtime_minutes)
+        .bind(game.weight)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| revert_error(e, &[]))?;
+
+        let reverted = match updated {
+            Some(reverted) => reverted,
+            None => sqlx::query_as::<_, GameModel>(
+                r#"INSERT INTO games (
+                    id, name, normalized_name, creator_name, normalized_creator_name,
+                    created_at, deleted_at, version, bgg_id, year_published, min_players,
+                    max_players, min_playtime_minutes, max_playtime_minutes, weight
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                RETURNING *"#,
+            )
+            .bind(game.id)
+            .bind(&game.name)
+            .bind(normalize_name(&game.name))
+            .bind(&game.creator_name)
+            .bind(normalize_name(&game.creator_name))
+            .bind(game.created_at)
+            .bind(game.deleted_at)
+            .bind(game.version)
+            .bind(game.bgg_id)
+            .bind(game.year_published)
+            .bind(game.min_players)
+            .bind(game.max_players)
+            .bind(game.min_playtime_minutes)
+            .bind(game.max_playtime_minutes)
+            .bind(game.weight)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &[]))?,
+        };
+
+        tx.commit().await?;
+        Ok(reverted)
+    }
+
+    async fn revert_player(&self, snapshot: &serde_json::Value) -> Result<PlayerModel, RepoError> {
+        let player: PlayerModel = from_snapshot(snapshot)?;
+        let mut tx = self.db.begin().await?;
+
+        let updated = sqlx::query_as::<_, PlayerModel>(
+            r#"UPDATE players SET
+                name = ?1,
+                normalized_name = ?2,
+                is_owner = ?3,
+                deleted_at = ?4,
+                bgg_username = ?6,
+                version = version + (
+                    name IS NOT ?1 OR is_owner IS NOT ?3 OR deleted_at IS NOT ?4
+                    OR bgg_username IS NOT ?6
+                )
+            WHERE id = ?5 RETURNING *"#,
+        )
+        .bind(&player.name)
+        .bind(normalize_name(&player.name))
+        .bind(player.is_owner)
+        .bind(player.deleted_at)
+        .bind(player.id)
+        .bind(&player.bgg_username)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| revert_error(e, &[]))?;
+
+        let reverted = match updated {
+            Some(reverted) => reverted,
+            None => sqlx::query_as::<_, PlayerModel>(
+                r#"INSERT INTO players (
+                    id, name, normalized_name, is_owner, created_at, deleted_at, version,
+                    bgg_username
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) RETURNING *"#,
+            )
+            .bind(player.id)
+            .bind(&player.name)
+            .bind(normalize_name(&player.name))
+            .bind(player.is_owner)
+            .bind(player.created_at)
+            .bind(player.deleted_at)
+            .bind(player.version)
+            .bind(&player.bgg_username)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &[]))?,
+        };
+
+        tx.commit().await?;
+        Ok(reverted)
+    }
+
+    async fn revert_play(&self, snapshot: &serde_json::Value) -> Result<PlayModel, RepoError> {
+        let play: PlayModel = from_snapshot(snapshot)?;
+        let mut tx = self.db.begin().await?;
+
+        let updated = sqlx::query_as::<_, PlayModel>(
+            r#"UPDATE plays SET
+                game_id = ?1,
+                deleted_at = ?2,
+                location = ?4,
+                length_minutes = ?5,
+                comments = ?6,
+                bgg_play_id = ?7,
+                session_id = ?8,
+                version = version + (
+                    game_id IS NOT ?1 OR deleted_at IS NOT ?2 OR location IS NOT ?4
+                    OR length_minutes IS NOT ?5 OR comments IS NOT ?6 OR bgg_play_id IS NOT ?7
+                    OR session_id IS NOT ?8
+                )
+            WHERE id = ?3 RETURNING *"#,
+        )
+        .bind(play.game_id)
+        .bind(play.deleted_at)
+        .bind(play.id)
+        .bind(&play.location)
+        .bind(play.length_minutes)
+        .bind(&play.comments)
+        .bind(play.bgg_play_id)
+        .bind(play.session_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| revert_error(e, &["game_id", "session_id"]))?;
+
+        let reverted = match updated {
+            Some(reverted) => reverted,
+            None => sqlx::query_as::<_, PlayModel>(
+                r#"INSERT INTO plays (
+                    id, game_id, created_at, deleted_at, version,
+                    location, length_minutes, comments, bgg_play_id, session_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) RETURNING *"#,
+            )
+            .bind(play.id)
+            .bind(play.game_id)
+            .bind(play.created_at)
+            .bind(play.deleted_at)
+            .bind(play.version)
+            .bind(&play.location)
+            .bind(play.length_minutes)
+            .bind(&play.comments)
+            .bind(play.bgg_play_id)
+            .bind(play.session_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["game_id", "session_id"]))?,
+        };
+
+        tx.commit().await?;
+        Ok(reverted)
+    }
+
+    async fn revert_participant(
+        &self,
+        snapshot: &serde_json::Value,
+    ) -> Result<PlayParticipantModel, RepoError> {
+        let participant: PlayParticipantModel = from_snapshot(snapshot)?;
+        let mut tx = self.db.begin().await?;
+
+        let updated = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"UPDATE play_participants SET
+                score = ?1,
+                is_winner = ?2,
+                version = version + (score IS NOT ?1 OR is_winner IS NOT ?2)
+            WHERE play_id = ?3 AND player_id = ?4 RETURNING *"#,
+        )
+        .bind(participant.score)
+        .bind(participant.is_winner)
+        .bind(participant.play_id)
+        .bind(participant.player_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let reverted = match updated {
+            Some(reverted) => reverted,
+            None => sqlx::query_as::<_, PlayParticipantModel>(
+                r#"INSERT INTO play_participants (play_id, player_id, score, is_winner, version)
+                VALUES (?1, ?2, ?3, ?4, ?5) RETURNING *"#,
+            )
+            .bind(participant.play_id)
+            .bind(participant.player_id)
+            .bind(participant.score)
+            .bind(participant.is_winner)
+            .bind(participant.version)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["play_id", "player_id"]))?,
+        };
+
+        tx.commit().await?;
+        Ok(reverted)
+    }
+
+    async fn revert_session(
+        &self,
+        snapshot: &serde_json::Value,
+    ) -> Result<SessionModel, RepoError> {
+        let session: SessionModel = from_snapshot(snapshot)?;
+        let mut tx = self.db.begin().await?;
+
+        let updated = sqlx::query_scalar::<_, Uuid>(
+            r#"UPDATE sessions SET
+                played_on = ?1,
+                location = ?2,
+                host_id = ?3,
+                version = version + (
+                    played_on IS NOT ?1 OR location IS NOT ?2 OR host_id IS NOT ?3
+                )
+            WHERE id = ?4 RETURNING id"#,
+        )
+        .bind(session.played_on)
+        .bind(&session.location)
+        .bind(session.host_id)
+        .bind(session.id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| revert_error(e, &["host_id"]))?;
+
+        if updated.is_none() {
+            sqlx::query(
+                r#"INSERT INTO sessions (id, played_on, location, host_id, created_at, version)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            )
+            .bind(session.id)
+            .bind(session.played_on)
+            .bind(&session.location)
+            .bind(session.host_id)
+            .bind(session.created_at)
+            .bind(session.version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["host_id"]))?;
+        }
+
+        // The host only changes along with the row, whose version is bumped
+        let mut reverted = select_session(&mut tx, session.id).await?;
+        let attendee_ids = attendees(reverted.host_id, &reverted.attendee_ids);
+        if attendee_ids != reverted.attendee_ids {
+            set_attendees(&mut tx, session.id, &attendee_ids).await?;
+            reverted.attendee_ids = attendee_ids;
+        }
+
+        tx.commit().await?;
+        Ok(reverted)
+    }
+
+    async fn revert_event(&self, snapshot: &serde_json::Value) -> Result<EventModel, RepoError> {
+        let event: EventModel = from_snapshot(snapshot)?;
+        let mut tx = self.db.begin().await?;
+
+        let updated = sqlx::query_scalar::<_, Uuid>(
+            r#"UPDATE events SET
+                scheduled_on = ?1,
+                location = ?2,
+                host_id = ?3,
+                session_id = ?4,
+                version = version + (
+                    scheduled_on IS NOT ?1 OR location IS NOT ?2 OR host_id IS NOT ?3
+                    OR session_id IS NOT ?4
+                )
+            WHERE id = ?5 RETURNING id"#,
+        )
+        .bind(event.scheduled_on)
+        .bind(&event.location)
+        .bind(event.host_id)
+        .bind(event.session_id)
+        .bind(event.id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| revert_error(e, &["host_id", "session_id"]))?;
+
+        if updated.is_none() {
+            sqlx::query(
+                r#"INSERT INTO events (
+                    id, scheduled_on, location, host_id, session_id, created_at, version
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            )
+            .bind(event.id)
+            .bind(event.scheduled_on)
+            .bind(&event.location)
+            .bind(event.host_id)
+            .bind(event.session_id)
+            .bind(event.created_at)
+            .bind(event.version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["host_id", "session_id"]))?;
+        }
+
+        // The host only changes along with the row, whose version is bumped
+        let mut reverted = select_event(&mut tx, event.id).await?;
+        let invitee_ids: Vec<_> = reverted.invitations.iter().map(|i| i.player_id).collect();
+        if reverted
+            .host_id
+            .is_some_and(|host_id| !invitee_ids.contains(&host_id))
+        {
+            let invitations = invitations(&reverted.invitations, reverted.host_id, &invitee_ids);
+            set_invitations(&mut tx, event.id, &invitations).await?;
+            reverted.invitations = invitations;
+        }
+
+        tx.commit().await?;
+        Ok(reverted)
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for SqliteRepository {
+    async fn claim(
+        &self,
+        key: &str,
+        path: &str,
+        request_hash: &[u8],
+        cutoff: DateTime<Utc>,
+    ) -> Result<bool, RepoError> {
+        let claimed = sqlx::query_scalar::<_, String>(
+            r#"INSERT INTO idempotency_keys (key, path, request_hash, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (key, path) DO UPDATE SET
+                request_hash = excluded.request_hash,
+                status_code = NULL,
+                content_type = NULL,
+                response_body = NULL,
+                created_at = excluded.created_at
+            WHERE idempotency_keys.created_at < ?5
+            RETURNING key"#,
+        )
+        .bind(key)
+        .bind(path)
+        .bind(request_hash)
+        .bind(Utc::now())
+        .bind(cutoff)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(claimed.is_some())
+    }
+
+    async fn get(&self, key: &str, path: &str) -> Result<IdempotencyKeyModel, RepoError> {
+        sqlx::query_as::<_, IdempotencyKeyModel>(
+            r#"SELECT request_hash, status_code, content_type, response_body
+            FROM idempotency_keys WHERE key = ?1 AND path = ?2"#,
+        )
+        .bind(key)
+        .bind(path)
+        .fetch_one(&self.db)
+        .await
+        .map_err(not_found)
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        path: &str,
+        status_code: i16,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> Result<(), RepoError> {
+        sqlx::query(
+            r#"UPDATE idempotency_keys
+            SET status_code = ?3, content_type = ?4, response_body = ?5
+            WHERE key = ?1 AND path = ?2"#,
+        )
+        .bind(key)
+        .bind(path)
+        .bind(status_code)
+        .bind(content_type)
+        .bind(response_body)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str, path: &str) -> Result<(), RepoError> {
+        sqlx::query(r#"DELETE FROM idempotency_keys WHERE key = ?1 AND path = ?2"#)
+            .bind(key)
+            .bind(path)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<u64, RepoError> {
+        let result = sqlx::query(r#"DELETE FROM idempotency_keys WHERE created_at < ?1"#)
+            .bind(cutoff)
+            .execute(&self.db)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl TrashRepository for SqliteRepository {
+    // Games and players keep their history: plays.game_id is ON DELETE
+    // RESTRICT, and players who took part in a play or attended a session stay
+    // in the trash instead of losing it through ON DELETE CASCADE
+    async fn purge(&self, cutoff: DateTime<Utc>) -> Result<PurgedCounts, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let plays = sqlx::query(r#"DELETE FROM plays WHERE deleted_at < ?1"#)
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        // A session or event lets go of its purged host like the Postgres
+        // trigger would
+        sqlx::query(
+            r#"UPDATE sessions SET host_id = NULL, version = version + 1
+            WHERE host_id IN (SELECT id FROM players WHERE deleted_at < ?1
+                AND NOT EXISTS (SELECT 1 FROM play_participants pp WHERE pp.player_id = players.id)
+                AND NOT EXISTS (SELECT 1 FROM session_attendees sa WHERE sa.player_id = players.id))"#,
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"UPDATE events SET host_id = NULL, version = version + 1
+            WHERE host_id IN (SELECT id FROM players WHERE deleted_at < ?1
+                AND NOT EXISTS (SELECT 1 FROM play_participants pp WHERE pp.player_id = players.id)
+                AND NOT EXISTS (SELECT 1 FROM session_attendees sa WHERE sa.player_id = players.id))"#,
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?;
+
+        let players = sqlx::query(
+            r#"DELETE FROM players
+            WHERE deleted_at < ?1
+            AND NOT EXISTS (SELECT 1 FROM play_participants pp WHERE pp.player_id = players.id)
+            AND NOT EXISTS (SELECT 1 FROM session_attendees sa WHERE sa.player_id = players.id)"#,
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let games = sqlx::query(
+            r#"DELETE FROM games
+            WHERE deleted_at < ?1
+            AND NOT EXISTS (SELECT 1 FROM plays WHERE plays.game_id = games.id)"#,
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(PurgedCounts {
+            games,
+            players,
+            plays,
+        })
+    }
+}
+
+/// Every row of every table, and the audit log when `audit_log` is set, in
+/// one transaction
+async fn export_tables(db: &SqlitePool, audit_log: bool) -> Result<BackupTables, RepoError> {
+    let mut tx = db.begin().await?;
+
+    let games =
+        sqlx::query_as::<_, GameModel>(r#"SELECT * FROM games ORDER BY created_at, id"#)
+            .fetch_all(&mut *tx)
+            .await?;
+    let players =
+        sqlx::query_as::<_, PlayerModel>(r#"SELECT * FROM players ORDER BY created_at, id"#)
+            .fetch_all(&mut *tx)
+            .await?;
+    let sessions = select_sessions(&mut tx).await?;
+    let events = select_events(&mut tx, None).await?;
+    let plays =
+        sqlx::query_as::<_, PlayModel>(r#"SELECT * FROM plays ORDER BY created_at, id"#)
+            .fetch_all(&mut *tx)
+            .await?;
+    let play_participants = sqlx::query_as::<_, PlayParticipantModel>(
+        r#"SELECT * FROM play_participants ORDER BY play_id, player_id"#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let audit_log = if audit_log {
+        sqlx::query_as::<_, AuditLogModel>(r#"SELECT * FROM audit_log ORDER BY id"#)
+            .fetch_all(&mut *tx)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    tx.commit().await?;
+
+    Ok(BackupTables {
+        games,
+        players,
+        sessions,
+        events,
+        plays,
+        play_participants,
+        audit_log,
+    })
+}
+
+#[async_trait]
+impl BackupRepository for SqliteRepository {
+    // A read transaction sees one snapshot of the database
+    async fn export(&self) -> Result<BackupTables, RepoError> {
+        export_tables(&self.db, false).await
+    }
+
+    async fn export_with_audit_log(&self) -> Result<BackupTables, RepoError> {
+        export_tables(&self.db, true).await
+    }
+
+    // Only changed rows are restored, so a replaced row always gets a new version
+    async fn restore(&self, rows: &BackupTables) -> Result<(), RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        // Archived entries keep the actor they were logged with rather than
+        // being stamped with the one importing them
+        if !rows.audit_log.is_empty() {
+            sqlx::query(r#"UPDATE audit_context SET actor = NULL"#)
+                .execute(&mut *tx)
+                .await?;
+            for entry in &rows.audit_log {
+                sqlx::query(
+                    r#"INSERT INTO audit_log (entity, entity_id, action, before, after, changed_at, actor)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+                )
+                .bind(&entry.entity)
+                .bind(&entry.entity_id)
+                .bind(&entry.action)
+                .bind(entry.before.as_ref().map(ToString::to_string))
+                .bind(entry.after.as_ref().map(ToString::to_string))
+                .bind(entry.changed_at)
+                .bind(&entry.actor)
+                .execute(&mut *tx)
+                .await?;
+            }
+            set_actor(&mut tx).await?;
+        }
+
+        for game in &rows.games {
+            sqlx::query(
+                r#"INSERT INTO games (
+                    id, name, normalized_name, creator_name, normalized_creator_name,
+                    created_at, deleted_at, version, bgg_id, year_published, min_players,
+                    max_players, min_playtime_minutes, max_playtime_minutes, weight
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                ON CONFLICT (id) DO UPDATE SET
+                    name = excluded.name,
+                    normalized_name = excluded.normalized_name,
+                    creator_name = excluded.creator_name,
+                    normalized_creator_name = excluded.normalized_creator_name,
+                    bgg_id = excluded.bgg_id,
+                    year_published = excluded.year_published,
+                    min_players = excluded.min_players,
+                    max_players = excluded.max_players,
+                    min_playtime_minutes = excluded.min_playtime_minutes,
+                    max_playtime_minutes = excluded.max_playtime_minutes,
+                    weight = excluded.weight,
+                    created_at = excluded.created_at,
+                    deleted_at = excluded.deleted_at,
+                    version = version + 1"#,
+            )
+            .bind(game.id)
+            .bind(&game.name)
+            .bind(normalize_name(&game.name))
+            .bind(&game.creator_name)
+            .bind(normalize_name(&game.creator_name))
+            .bind(game.created_at)
+            .bind(game.deleted_at)
+            .bind(game.version)
+            .bind(game.bgg_id)
+            .bind(game.year_published)
+            .bind(game.min_players)
+            .bind(game.max_players)
+            .bind(game.min_playtime_minutes)
+            .bind(game.max_playtime_minutes)
+            .bind(game.weight)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &[]))?;
+        }
+
+        for player in &rows.players {
+            sqlx::query(
+                r#"INSERT INTO players (
+                    id, name, normalized_name, is_owner, created_at, deleted_at, version,
+                    bgg_username
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                ON CONFLICT (id) DO UPDATE SET
+                    name = excluded.name,
+                    normalized_name = excluded.normalized_name,
+                    is_owner = excluded.is_owner,
+                    bgg_username = excluded.bgg_username,
+                    created_at = excluded.created_at,
+                    deleted_at = excluded.deleted_at,
+                    version = version + 1"#,
+            )
+            .bind(player.id)
+            .bind(&player.name)
+            .bind(normalize_name(&player.name))
+            .bind(player.is_owner)
+            .bind(player.created_at)
+            .bind(player.deleted_at)
+            .bind(player.version)
+            .bind(&player.bgg_username)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &[]))?;
+        }
+
+        // Attendance is part of the session, so a replaced session is bumped
+        // whatever changed
+        for session in &rows.sessions {
+            sqlx::query(
+                r#"INSERT INTO sessions (id, played_on, location, host_id, created_at, version)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT (id) DO UPDATE SET
+                    played_on = excluded.played_on,
+                    location = excluded.location,
+                    host_id = excluded.host_id,
+                    created_at = excluded.created_at,
+                    version = version + 1"#,
+            )
+            .bind(session.id)
+            .bind(session.played_on)
+            .bind(&session.location)
+            .bind(session.host_id)
+            .bind(session.created_at)
+            .bind(session.version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["host_id"]))?;
+            set_attendees(&mut tx, session.id, &session.attendee_ids)
+                .await
+                .map_err(|e| revert_error(e, &["attendee_ids"]))?;
+        }
+
+        // Invitations are part of the event in the same way
+        for event in &rows.events {
+            sqlx::query(
+                r#"INSERT INTO events (
+                    id, scheduled_on, location, host_id, session_id, created_at, version
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT (id) DO UPDATE SET
+                    scheduled_on = excluded.scheduled_on,
+                    location = excluded.location,
+                    host_id = excluded.host_id,
+                    session_id = excluded.session_id,
+                    created_at = excluded.created_at,
+                    version = version + 1"#,
+            )
+            .bind(event.id)
+            .bind(event.scheduled_on)
+            .bind(&event.location)
+            .bind(event.host_id)
+            .bind(event.session_id)
+            .bind(event.created_at)
+            .bind(event.version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["host_id", "session_id"]))?;
+            set_invitations(&mut tx, event.id, &event.invitations)
+                .await
+                .map_err(|e| revert_error(e, &["player_id", "game_ids"]))?;
+        }
+
+        for play in &rows.plays {
+            sqlx::query(
+                r#"INSERT INTO plays (
+                    id, game_id, created_at, deleted_at, version,
+                    location, length_minutes, comments, bgg_play_id, session_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                ON CONFLICT (id) DO UPDATE SET
+                    game_id = excluded.game_id,
+                    session_id = excluded.session_id,
+                    location = excluded.location,
+                    length_minutes = excluded.length_minutes,
+                    comments = excluded.comments,
+                    bgg_play_id = excluded.bgg_play_id,
+                    created_at = excluded.created_at,
+                    deleted_at = excluded.deleted_at,
+                    version = version + 1"#,
+            )
+            .bind(play.id)
+            .bind(play.game_id)
+            .bind(play.created_at)
+            .bind(play.deleted_at)
+            .bind(play.version)
+            .bind(&play.location)
+            .bind(play.length_minutes)
+            .bind(&play.comments)
+            .bind(play.bgg_play_id)
+            .bind(play.session_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["game_id", "session_id"]))?;
+        }
+
+        for participant in &rows.play_participants {
+            sqlx::query(
+                r#"INSERT INTO play_participants (play_id, player_id, score, is_winner, version)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (play_id, player_id) DO UPDATE SET
+                    score = excluded.score,
+                    is_winner = excluded.is_winner,
+                    version = version + 1"#,
+            )
+            .bind(participant.play_id)
+            .bind(participant.player_id)
+            .bind(participant.score)
+            .bind(participant.is_winner)
+            .bind(participant.version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| revert_error(e, &["play_id", "player_id"]))?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// `ids` as a JSON array of hex strings, for `json_each` to turn back into
+/// IDs with `unhex`: SQLite has no array parameters
+fn id_array(ids: impl IntoIterator<Item = Uuid>) -> String {
+    let ids: Vec<_> = ids.into_iter().map(|id| id.simple().to_string()).collect();
+    serde_json::json!(ids).to_string()
+}
+
+/// `sessions`, `events` and `plays`, with the participants of the plays, the
+/// games played or brought and the players who took part, attended or were
+/// invited
+async fn play_tables(
+    conn: &mut SqliteConnection,
+    sessions: Vec<SessionModel>,
+    events: Vec<EventModel>,
+    plays: Vec<PlayModel>,
+) -> Result<BackupTables, RepoError> {
+    let play_ids = id_array(plays.iter().map(|play| play.id));
+    let player_ids = id_array(
+        sessions
+            .iter()
+            .flat_map(|session| session.attendee_ids.iter().copied())
+            .chain(
+                events
+                    .iter()
+                    .flat_map(|event| event.invitations.iter().map(|i| i.player_id)),
+            ),
+    );
+    let brought_ids = id_array(
+        events
+            .iter()
+            .flat_map(|event| event.invitations.iter())
+            .flat_map(|invitation| invitation.game_ids.iter().copied()),
+    );
+    let play_participants = sqlx::query_as::<_, PlayParticipantModel>(
+        r#"SELECT * FROM play_participants
+        WHERE play_id IN (SELECT unhex(value) FROM json_each(?1))
+        ORDER BY play_id, player_id"#,
+    )
+    .bind(&play_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    let games = sqlx::query_as::<_, GameModel>(
+        r#"SELECT * FROM games
+        WHERE id IN (
+            SELECT game_id FROM plays
+            WHERE id IN (SELECT unhex(value) FROM json_each(?1))
+        )
+            OR id IN (SELECT unhex(value) FROM json_each(?2))
+        ORDER BY created_at, id"#,
+    )
+    .bind(&play_ids)
+    .bind(&brought_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    let players = sqlx::query_as::<_, PlayerModel>(
+        r#"SELECT * FROM players
+        WHERE id IN (
+            SELECT player_id FROM play_participants
+            WHERE play_id IN (SELECT unhex(value) FROM json_each(?1))
+        )
+            OR id IN (SELECT unhex(value) FROM json_each(?2))
+        ORDER BY created_at, id"#,
+    )
+    .bind(&play_ids)
+    .bind(&player_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(BackupTables {
+        games,
+        players,
+        sessions,
+        events,
+        plays,
+        play_participants,
+        ..BackupTables::default()
+    })
+}
+
+#[async_trait]
+impl StatsRepository for SqliteRepository {
+    async fn calendar_plays(
+        &self,
+        player_id: Option<Uuid>,
+        game_id: Option<Uuid>,
+    ) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays p
+            WHERE deleted_at IS NULL
+                AND (?1 IS NULL OR game_id = ?1)
+                AND (?2 IS NULL OR EXISTS (
+                    SELECT 1 FROM play_participants
+                    WHERE play_id = p.id AND player_id = ?2
+                ))
+            ORDER BY created_at, id"#,
+        )
+        .bind(game_id)
+        .bind(player_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut sessions = select_sessions(&mut tx).await?;
+        sessions.retain(|session| calendar_session(session, &plays, player_id, game_id));
+        let mut events = select_events(&mut tx, None).await?;
+        events.retain(|event| calendar_event(event, player_id, game_id));
+        let tables = play_tables(&mut tx, sessions, events, plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn year_plays(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let mut plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays p
+            WHERE deleted_at IS NULL AND created_at < ?1
+                AND NOT EXISTS (
+                    SELECT 1 FROM plays earlier
+                    WHERE earlier.game_id = p.game_id AND earlier.deleted_at IS NULL
+                        AND (earlier.created_at, earlier.id) < (p.created_at, p.id)
+                )
+            ORDER BY created_at, id"#,
+        )
+        .bind(from)
+        .fetch_all(&mut *tx)
+        .await?;
+        plays.extend(
+            sqlx::query_as::<_, PlayModel>(
+                r#"SELECT * FROM plays
+                WHERE deleted_at IS NULL AND created_at >= ?1 AND created_at < ?2
+                ORDER BY created_at, id"#,
+            )
+            .bind(from)
+            .bind(until)
+            .fetch_all(&mut *tx)
+            .await?,
+        );
+        let tables = play_tables(&mut tx, Vec::new(), Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn plays_until(&self, until: DateTime<Utc>) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays
+            WHERE deleted_at IS NULL AND created_at < ?1
+            ORDER BY created_at, id"#,
+        )
+        .bind(until)
+        .fetch_all(&mut *tx)
+        .await?;
+        let tables = play_tables(&mut tx, Vec::new(), Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn player_history(&self, player_ids: &[Uuid]) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let player_ids = id_array(player_ids.iter().copied());
+        let games = sqlx::query_as::<_, GameModel>(
+            r#"SELECT * FROM games WHERE deleted_at IS NULL ORDER BY created_at, id"#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let players = sqlx::query_as::<_, PlayerModel>(
+            r#"SELECT * FROM players
+            WHERE id IN (SELECT unhex(value) FROM json_each(?1))
+            ORDER BY created_at, id"#,
+        )
+        .bind(&player_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays p
+            WHERE deleted_at IS NULL AND EXISTS (
+                SELECT 1 FROM play_participants
+                WHERE play_id = p.id
+                    AND player_id IN (SELECT unhex(value) FROM json_each(?1))
+            )
+            ORDER BY created_at, id"#,
+        )
+        .bind(&player_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        let play_participants = sqlx::query_as::<_, PlayParticipantModel>(
+            r#"SELECT * FROM play_participants
+            WHERE play_id IN (SELECT unhex(value) FROM json_each(?1))
+            ORDER BY play_id, player_id"#,
+        )
+        .bind(id_array(plays.iter().map(|play| play.id)))
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(BackupTables {
+            games,
+            players,
+            plays,
+            play_participants,
+            ..BackupTables::default()
+        })
+    }
+
+    async fn session_plays(&self, id: Uuid) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let session = select_session(&mut tx, id).await?;
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays
+            WHERE session_id = ?1 AND deleted_at IS NULL
+            ORDER BY created_at, id"#,
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let tables = play_tables(&mut tx, vec![session], Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    async fn session_attendance(&self) -> Result<BackupTables, RepoError> {
+        let mut tx = self.db.begin().await?;
+
+        let sessions = select_sessions(&mut tx).await?;
+        let plays = sqlx::query_as::<_, PlayModel>(
+            r#"SELECT * FROM plays
+            WHERE session_id IS NOT NULL AND deleted_at IS NULL
+            ORDER BY created_at, id"#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let tables = play_tables(&mut tx, sessions, Vec::new(), plays).await?;
+
+        tx.commit().await?;
+        Ok(tables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn repository() -> SqliteRepository {
+        SqliteRepository::connect("sqlite::memory:").await.unwrap()
+    }
+
+    fn player(name: &str, is_owner: bool) -> PlayerSchema {
+        PlayerSchema {
+            name: name.to_string(),
+            is_owner: Some(is_owner),
+        }
+    }
+
+    #[tokio::test]
+    async fn clashes_are_told_apart_without_index_names() {
+        let repository = repository().await;
+        let owner = PlayerRepository::create(&repository, &player("Ann", true))
+            .await
+            .unwrap();
+
+        let same_name = PlayerRepository::create(&repository, &player(" ann ", false)).await;
+        assert!(matches!(
+            same_name,
+            Err(RepoError::Duplicate { existing_id: Some(id) }) if id == owner.id
+        ));
+
+        let second_owner = PlayerRepository::create(&repository, &player("Bob", true)).await;
+        assert!(matches!(
+            second_owner,
+            Err(RepoError::OwnerExists { owner_id: Some(id) }) if id == owner.id
+        ));
+    }
+
+    #[tokio::test]
+    async fn changes_are_recorded_and_can_be_reverted() {
+        let repository = repository().await;
+        let game = GameRepository::create(
+            &repository,
+            &GameSchema {
+                name: "Catan".to_string(),
+                creator_name: "Klaus Teuber".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let changes = UpdateGameSchema {
+            name: Some("Settlers".to_string()),
+            creator_name: None,
+            ..Default::default()
+        };
+        let renamed = GameRepository::update(&repository, game.id, &changes, &|_| true)
+            .await
+            .unwrap();
+        assert_eq!(renamed.version, 2);
+
+        let history = HistoryRepository::list(&repository, "games", &game.id.to_string())
+            .await
+            .unwrap();
+        let actions: Vec<_> = history.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, ["update", "create"]);
+
+        let created = history[1].after.as_ref().unwrap();
+        let reverted = repository.revert_game(created).await.unwrap();
+        assert_eq!(reverted.name, "Catan");
+        assert_eq!(reverted.version, 3);
+    }
+
+    #[tokio::test]
+    async fn changes_record_their_actor() {
+        let repository = repository().await;
+        let game = GameSchema {
+            name: "Catan".to_string(),
+            creator_name: "Klaus Teuber".to_string(),
+            ..Default::default()
+        };
+        let game = actor::scope(
+            Some("ann".to_string()),
+            GameRepository::create(&repository, &game),
+        )
+        .await
+        .unwrap();
+        GameRepository::trash(&repository, game.id, &|_| true)
+            .await
+            .unwrap();
+
+        let history = HistoryRepository::list(&repository, "games", &game.id.to_string())
+            .await
+            .unwrap();
+        let actors: Vec<_> = history.iter().map(|entry| entry.actor.as_deref()).collect();
+        assert_eq!(actors, [None, Some("ann")]);
+    }
+
+    #[tokio::test]
+    async fn reverting_a_deleted_session_brings_back_its_host() {
+        let repository = repository().await;
+        let ann = PlayerRepository::create(&repository, &player("Ann", false))
+            .await
+            .unwrap();
+        let session = SessionRepository::create(
+            &repository,
+            &SessionSchema {
+                played_on: chrono::NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+                location: Some("Game café".to_string()),
+                host_id: Some(ann.id),
+                attendee_ids: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+        SessionRepository::delete(&repository, session.id, &|_| true)
+            .await
+            .unwrap();
+
+        let history = HistoryRepository::list(&repository, "sessions", &session.id.to_string())
+            .await
+            .unwrap();
+        let actions: Vec<_> = history.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, ["delete", "create"]);
+
+        let created = history[1].after.as_ref().unwrap();
+        let reverted = repository.revert_session(created).await.unwrap();
+        assert_eq!(reverted.location.as_deref(), Some("Game café"));
+        assert_eq!(reverted.host_id, Some(ann.id));
+        assert_eq!(reverted.attendee_ids, [ann.id]);
+        assert_eq!(
+            SessionRepository::get(&repository, session.id)
+                .await
+                .unwrap()
+                .attendee_ids,
+            [ann.id]
+        );
+    }
+
+    #[tokio::test]
+    async fn purge_keeps_games_with_plays_left() {
+        let repository = repository().await;
+        let game = GameRepository::create(
+            &repository,
+            &GameSchema {
+                name: "Azul".to_string(),
+                creator_name: "Michael Kiesling".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let play = PlayRepository::create(
+            &repository,
+            &PlaySchema {
+                game_id: game.id,
+                session_id: None,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        PlayRepository::trash(&repository, play.id, &|_| true)
+            .await
+            .unwrap();
+        GameRepository::trash(&repository, game.id, &|_| true)
+            .await
+            .unwrap();
+
+        let purged = TrashRepository::purge(&repository, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!((purged.games, purged.players, purged.plays), (1, 0, 1));
+        assert!(
+            GameRepository::list_trashed(&repository)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn purge_keeps_players_with_plays() {
+        let repository = repository().await;
+        let game = GameRepository::create(
+            &repository,
+            &GameSchema {
+                name: "Azul".to_string(),
+                creator_name: "Michael Kiesling".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let play = PlayRepository::create(
+            &repository,
+            &PlaySchema {
+                game_id: game.id,
+                session_id: None,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let ann = PlayerRepository::create(&repository, &player("Ann", false))
+            .await
+            .unwrap();
+        let bob = PlayerRepository::create(&repository, &player("Bob", false))
+            .await
+            .unwrap();
+        ParticipantRepository::create(
+            &repository,
+            &PlayParticipantSchema {
+                play_id: play.id,
+                player_id: ann.id,
+                score: None,
+                is_winner: None,
+            },
+        )
+        .await
+        .unwrap();
+        for id in [ann.id, bob.id] {
+            PlayerRepository::trash(&repository, id, &|_| true)
+                .await
+                .unwrap();
+        }
+
+        let purged = TrashRepository::purge(&repository, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!((purged.games, purged.players, purged.plays), (0, 1, 0));
+        let trashed = PlayerRepository::list_trashed(&repository).await.unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].id, ann.id);
+    }
+
+    #[tokio::test]
+    async fn merged_players_take_over_their_sessions() {
+        let repository = repository().await;
+        let ann = PlayerRepository::create(&repository, &player("Ann", false))
+            .await
+            .unwrap();
+        let bob = PlayerRepository::create(&repository, &player("Bob", false))
+            .await
+            .unwrap();
+        let session = SessionRepository::create(
+            &repository,
+            &SessionSchema {
+                played_on: "2024-03-01".parse().unwrap(),
+                location: None,
+                host_id: Some(bob.id),
+                attendee_ids: vec![ann.id],
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(session.attendee_ids, attendees(None, &[ann.id, bob.id]));
+
+        PlayerRepository::merge(&repository, bob.id, ann.id)
+            .await
+            .unwrap();
+
+        let session = SessionRepository::get(&repository, session.id)
+            .await
+            .unwrap();
+        assert_eq!(session.host_id, Some(ann.id));
+        assert_eq!(session.attendee_ids, [ann.id]);
+        assert_eq!(session.version, 2);
+    }
+
+    #[tokio::test]
+    async fn merged_players_keep_the_answer_of_the_target() {
+        let repository = repository().await;
+        let ann = PlayerRepository::create(&repository, &player("Ann", false))
+            .await
+            .unwrap();
+        let bob = PlayerRepository::create(&repository, &player("Bob", false))
+            .await
+            .unwrap();
+        let game = GameSchema {
+            name: "Azul".to_string(),
+            creator_name: "Michael Kiesling".to_string(),
+            ..Default::default()
+        };
+        let game = GameRepository::create(&repository, &game).await.unwrap();
+        let event = EventRepository::create(
+            &repository,
+            &EventSchema {
+                scheduled_on: "2026-11-06".parse().unwrap(),
+                location: None,
+                host_id: Some(ann.id),
+                invitee_ids: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+        let rsvp = RsvpSchema {
+            response: Rsvp::Yes,
+            game_ids: vec![game.id, game.id],
+        };
+        let event = EventRepository::respond(&repository, event.id, bob.id, &rsvp, &|_| true)
+            .await
+            .unwrap();
+        assert_eq!(event.invitations.len(), 2);
+        assert_eq!(event.version, 2);
+
+        PlayerRepository::merge(&repository, ann.id, bob.id)
+            .await
+            .unwrap();
+
+        let event = EventRepository::get(&repository, event.id).await.unwrap();
+        assert_eq!(event.host_id, Some(bob.id));
+        assert_eq!(event.invitations.len(), 1);
+        assert_eq!(event.invitations[0].response, Some(Rsvp::Yes));
+        assert_eq!(event.invitations[0].game_ids, [game.id]);
+        assert_eq!(event.version, 3);
+
+        let started = EventRepository::start_session(&repository, event.id, &|_| true)
+            .await
+            .unwrap();
+        assert_eq!(started.session.attendee_ids, [bob.id]);
+        assert_eq!(started.event.session_id, Some(started.session.id));
+    }
+
+    #[tokio::test]
+    async fn calendar_plays_come_with_their_rows() {
+        let repository = repository().await;
+        let game = GameRepository::create(
+            &repository,
+            &GameSchema {
+                name: "Azul".to_string(),
+                creator_name: "Michael Kiesling".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let ann = PlayerRepository::create(&repository, &player("Ann", false))
+            .await
+            .unwrap();
+        let bob = PlayerRepository::create(&repository, &player("Bob", false))
+            .await
+            .unwrap();
+        EventRepository::create(
+            &repository,
+            &EventSchema {
+                scheduled_on: "2026-11-06".parse().unwrap(),
+                location: None,
+                host_id: Some(bob.id),
+                invitee_ids: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+        let mut plays = Vec::new();
+        for _ in 0..2 {
+            let play = PlayRepository::create(
+                &repository,
+                &PlaySchema {
+                    game_id: game.id,
+                    session_id: None,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            plays.push(play.id);
+        }
+        ParticipantRepository::create(
+            &repository,
+            &PlayParticipantSchema {
+                play_id: plays[0],
+                player_id: ann.id,
+                score: Some(12),
+                is_winner: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+
+        let all = repository
+            .calendar_plays(None, Some(game.id))
+            .await
+            .unwrap();
+        assert_eq!(all.plays.len(), 2);
+        assert!(all.events.is_empty());
+        let ann_played = repository.calendar_plays(Some(ann.id), None).await.unwrap();
+        let ids: Vec<_> = ann_played.plays.iter().map(|play| play.id).collect();
+        assert_eq!(ids, [plays[0]]);
+        assert_eq!(ann_played.play_participants.len(), 1);
+        assert_eq!(ann_played.games.len(), 1);
+        let names: Vec<_> = ann_played.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Ann"]);
+        assert!(ann_played.events.is_empty());
+        let everything = repository.calendar_plays(None, None).await.unwrap();
+        assert_eq!(everything.events.len(), 1);
+        let names: Vec<_> = everything.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Ann", "Bob"]);
+    }
+
+    #[tokio::test]
+    async fn year_plays_bring_the_first_earlier_play_of_each_game() {
+        let repository = repository().await;
+        let game = GameRepository::create(
+            &repository,
+            &GameSchema {
+                name: "Azul".to_string(),
+                creator_name: "Michael Kiesling".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let mut plays = Vec::new();
+        for played_at in ["2023-03-01", "2023-06-01", "2024-02-01", "2025-01-01"] {
+            let play = PlayRepository::create(
+                &repository,
+                &PlaySchema {
+                    game_id: game.id,
+                    session_id: None,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            let played_at = format!("{}T20:00:00.000+00:00", played_at);
+            sqlx::query(r#"UPDATE plays SET created_at = ?1 WHERE id = ?2"#)
+                .bind(played_at)
+                .bind(play.id)
+                .execute(&repository.db)
+                .await
+                .unwrap();
+            plays.push(play.id);
+        }
+
+        let from = "2024-01-01T00:00:00Z".parse().unwrap();
+        let until = "2025-01-01T00:00:00Z".parse().unwrap();
+        let tables = repository.year_plays(from, until).await.unwrap();
+        let ids: Vec<_> = tables.plays.iter().map(|play| play.id).collect();
+        assert_eq!(ids, [plays[0], plays[2]]);
+        assert_eq!(tables.games.len(), 1);
+    }
+}
@@ -0,0 +1,667 @@
+//! Response bodies. Successful ones are wrapped in `ApiResponse`, so clients
+//! always find the payload under `data`; lists carry their `count` next to the
+//! items. Every error is answered with an `ApiError`.
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    etag::entity_tag,
+    model::{
+        AuditLogModel, EventModel, GameModel, PlayModel, PlayParticipantModel, PlayerModel,
+        PlayerPlayModel, SessionModel,
+    },
+    play_csv::RowError,
+    schema::{ConflictStrategy, NightWinnerBy},
+};
+
+/// `{"status": "success", "data": {...}}`, with a `message` on some endpoints
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiResponse<T> {
+    #[schema(value_type = String, example = "success")]
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(nullable = false)]
+    pub message: Option<String>,
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn new(data: T) -> Self {
+        ApiResponse {
+            status: "success",
+            message: None,
+            data,
+        }
+    }
+
+    pub fn with_message(message: impl Into<String>, data: T) -> Self {
+        ApiResponse {
+            status: "success",
+            message: Some(message.into()),
+            data,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// Error answer of every handler, answered with an `ErrorResponse` body
+#[derive(Debug)]
+pub struct ApiError(Box<ErrorResponse>);
+
+/// Body of every `4xx` and `5xx` response. `status` follows the HTTP status:
+/// `error` for conflicts and server errors, `fail` for other client errors.
+/// The optional members only appear on the responses they belong to.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    #[serde(skip)]
+    code: StatusCode,
+    #[schema(value_type = String, example = "fail")]
+    status: &'static str,
+    message: String,
+    /// Every failing field, on `422` validation errors
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(nullable = false)]
+    errors: Vec<FieldError>,
+    /// The record a `409` clashed with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(nullable = false)]
+    existing_id: Option<Uuid>,
+    /// The current ETag, on `412` responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(nullable = false)]
+    etag: Option<String>,
+    /// Archived rows clashing with existing ones, on imports with
+    /// `strategy=fail`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(nullable = false)]
+    conflicts: Vec<ImportConflict>,
+    /// The failed items of an atomic bulk request
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<crate::openapi::BulkItemResult>, nullable = false)]
+    results: Vec<serde_json::Value>,
+    /// The rows of a CSV import that cannot be imported
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<crate::openapi::CsvRowError>, nullable = false)]
+    rows: Vec<RowError>,
+}
+
+impl ApiError {
+    pub fn new(code: StatusCode, message: impl Into<String>) -> Self {
+        ApiError(Box::new(ErrorResponse {
+            code,
+            status: if code == StatusCode::CONFLICT || code.is_server_error() {
+                "error"
+            } else {
+                "fail"
+            },
+            message: message.into(),
+            errors: Vec::new(),
+            existing_id: None,
+            etag: None,
+            conflicts: Vec::new(),
+            results: Vec::new(),
+            rows: Vec::new(),
+        }))
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::CONFLICT, message)
+    }
+
+    /// The 422 answer listing every failing field
+    pub fn validation(errors: Vec<FieldError>) -> Self {
+        let mut error = ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "Validation failed");
+        error.0.errors = errors;
+        error
+    }
+
+    pub fn with_existing_id(mut self, existing_id: Option<Uuid>) -> Self {
+        self.0.existing_id = existing_id;
+        self
+    }
+
+    pub fn with_etag(mut self, version: i32) -> Self {
+        self.0.etag = Some(entity_tag(version));
+        self
+    }
+
+    pub fn with_conflicts(mut self, conflicts: Vec<ImportConflict>) -> Self {
+        self.0.conflicts = conflicts;
+        self
+    }
+
+    pub fn with_results(mut self, results: Vec<serde_json::Value>) -> Self {
+        self.0.results = results;
+        self
+    }
+
+    pub fn with_rows(mut self, rows: Vec<RowError>) -> Self {
+        self.0.rows = rows;
+        self
+    }
+
+    /// The HTTP status the error is answered with
+    pub fn code(&self) -> StatusCode {
+        self.0.code
+    }
+
+    pub fn body(&self) -> &ErrorResponse {
+        &self.0
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0.code, Json(self.0)).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GameData {
+    pub game: GameModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedGameData {
+    pub deleted_game: GameModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergedGameData {
+    pub game: GameModel,
+    pub merged_game: GameModel,
+    pub moved_plays: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GameList {
+    pub count: usize,
+    pub games: Vec<GameModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayerData {
+    pub player: PlayerModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedPlayerData {
+    pub deleted_player: PlayerModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergedPlayerData {
+    pub player: PlayerModel,
+    pub merged_player: PlayerModel,
+    pub moved_participations: u64,
+    /// Participations dropped because both players took part in the play
+    pub dropped_participations: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OwnerData {
+    pub player: PlayerModel,
+    pub previous_owner: Option<PlayerModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayerList {
+    pub count: usize,
+    pub players: Vec<PlayerModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MeStats {
+    pub plays: i64,
+    pub wins: i64,
+    pub games: i64,
+    pub win_rate: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MeData {
+    pub player: PlayerModel,
+    pub stats: MeStats,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayerPlayList {
+    pub count: usize,
+    pub plays: Vec<PlayerPlayModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayData {
+    pub play: PlayModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedPlayData {
+    pub deleted_play: PlayModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayList {
+    pub count: usize,
+    pub plays: Vec<PlayModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayParticipantData {
+    pub play_participant: PlayParticipantModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedPlayParticipantData {
+    pub deleted_participant: PlayParticipantModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayParticipantList {
+    pub count: usize,
+    pub play_participants: Vec<PlayParticipantModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionData {
+    pub session: SessionModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedSessionData {
+    pub deleted_session: SessionModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionList {
+    pub count: usize,
+    pub sessions: Vec<SessionModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventData {
+    pub event: EventModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedEventData {
+    pub deleted_event: EventModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventList {
+    pub count: usize,
+    pub events: Vec<EventModel>,
+}
+
+/// An event and the session it turned into
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventSessionData {
+    pub event: EventModel,
+    pub session: SessionModel,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashList {
+    pub count: usize,
+    pub retention_days: i64,
+    pub games: Vec<GameModel>,
+    pub players: Vec<PlayerModel>,
+    pub plays: Vec<PlayModel>,
+}
+
+/// Number of rows removed for good by a purge
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PurgedCounts {
+    pub games: u64,
+    pub players: u64,
+    pub plays: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PurgedData {
+    pub purged: PurgedCounts,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryList {
+    pub count: usize,
+    pub history: Vec<AuditLogModel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkData {
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Outcome of every item, in request order
+    #[schema(value_type = Vec<crate::openapi::BulkItemResult>)]
+    pub results: Vec<serde_json::Value>,
+}
+
+/// What an import did, or would do, with the archived rows of one table
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportCounts {
+    pub created: usize,
+    /// Existing rows replaced by the archived ones
+    pub updated: usize,
+    /// Archived rows identical to the existing ones
+    pub unchanged: usize,
+    /// Archived rows left out because they clash with an existing row
+    pub skipped: usize,
+}
+
+/// Why an archived row clashes with an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictReason {
+    /// A row with the same ID but different contents exists
+    Id,
+    /// A live row with the same name exists. Rows pointing at the archived
+    /// row are pointed at the existing one instead.
+    Name,
+    /// Another live player is the owner
+    Owner,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportConflict {
+    #[schema(example = "games")]
+    pub table: &'static str,
+    /// ID of the archived row, `play_id/player_id` for play participants
+    pub id: String,
+    /// ID of the existing row it clashes with
+    pub existing_id: String,
+    pub reason: ConflictReason,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub strategy: ConflictStrategy,
+    pub dry_run: bool,
+    pub games: ImportCounts,
+    pub players: ImportCounts,
+    pub sessions: ImportCounts,
+    pub events: ImportCounts,
+    pub plays: ImportCounts,
+    pub play_participants: ImportCounts,
+    /// Entries are only ever created or found logged already
+    pub audit_log: ImportCounts,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportData {
+    pub report: ImportReport,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct PlayCsvImportData {
+    /// Rows read, not counting the header and empty rows
+    pub rows: usize,
+    pub plays_created: usize,
+    pub participants_created: usize,
+    /// Games and players the import created because no live one had the name
+    pub games_created: Vec<GameModel>,
+    pub players_created: Vec<PlayerModel>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BggPlaysImportData {
+    pub plays_imported: usize,
+    /// Plays whose BGG play ID was imported before
+    pub plays_skipped: usize,
+    pub games_created: Vec<GameModel>,
+    /// Stored games and players the imported plays use
+    pub games_matched: usize,
+    pub players_created: Vec<PlayerModel>,
+    pub players_matched: usize,
+    /// What was imported differently from the file or left out
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BgStatsImportData {
+    pub plays_imported: usize,
+    /// Plays stored already, with the same game, time and players or the same
+    /// BGG play ID
+    pub plays_skipped: usize,
+    pub participants_imported: usize,
+    pub games_created: Vec<GameModel>,
+    /// Stored games and players the file's records were matched to
+    pub games_matched: usize,
+    pub players_created: Vec<PlayerModel>,
+    pub players_matched: usize,
+    /// Records left out or imported differently from the file
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BggGamesImportData {
+    pub games_created: Vec<GameModel>,
+    /// Stored games the file's items were matched to and changed
+    pub games_updated: Vec<GameModel>,
+    /// Stored games the file's items were matched to and did not change
+    pub games_unchanged: usize,
+    /// Collection items left out as the user does not own them
+    pub items_not_owned: usize,
+    /// Details of the file that were not imported
+    pub warnings: Vec<String>,
+}
+
+/// A game's plays in a report, with its share of all plays in percent
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReportGame {
+    pub game_id: Uuid,
+    pub name: String,
+    pub plays: usize,
+    pub share: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportPlayer {
+    pub player_id: Uuid,
+    pub name: String,
+    pub plays: usize,
+    pub wins: usize,
+}
+
+/// A player's longest run of plays won in a row
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WinningStreak {
+    pub player_id: Uuid,
+    pub name: String,
+    pub wins: usize,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BusiestMonth {
+    /// 1 for January
+    pub month: u32,
+    pub plays: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct YearReport {
+    pub year: i32,
+    pub plays: usize,
+    /// Of the plays with a length
+    pub hours: f64,
+    pub plays_without_length: usize,
+    /// Games first played this year
+    pub new_games: Vec<ReportGame>,
+    /// Every game played, most played first
+    pub games: Vec<ReportGame>,
+    /// Every player who took part, most plays first
+    pub players: Vec<ReportPlayer>,
+    /// Each player's best streak, longest first
+    pub winning_streaks: Vec<WinningStreak>,
+    /// The earliest of the months with the most plays
+    pub busiest_month: Option<BusiestMonth>,
+    /// The largest `h` such that `h` games were played at least `h` times
+    pub h_index: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct YearReportData {
+    pub report: YearReport,
+}
+
+/// A game that has been played at least as often as a milestone asks
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MilestoneGame {
+    pub game_id: Uuid,
+    pub name: String,
+    pub plays: usize,
+    /// When the play reaching the milestone was logged
+    pub reached_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Milestone {
+    pub plays: usize,
+    /// `nickel`, `dime` or `quarter`
+    #[schema(example = "dime")]
+    pub name: &'static str,
+    /// Most played first
+    pub games: Vec<MilestoneGame>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NewMilestone {
+    pub game_id: Uuid,
+    pub name: String,
+    pub milestone: &'static str,
+    pub reached_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MilestonesData {
+    pub as_of: chrono::NaiveDate,
+    /// The largest `h` such that `h` games were played at least `h` times
+    pub game_h_index: usize,
+    /// The largest `h` such that `h` players took part in at least `h` plays
+    pub player_h_index: usize,
+    pub milestones: Vec<Milestone>,
+    /// Milestones reached from the start of the month of `as_of` on, oldest
+    /// first
+    pub new_this_month: Vec<NewMilestone>,
+}
+
+/// A game to play and why, each reason scored from 0 to 1
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Suggestion {
+    pub game: GameModel,
+    /// The weighted sum of the reasons below; higher is better
+    pub score: f64,
+    /// When any of the players last played the game
+    pub last_played_at: Option<DateTime<Utc>>,
+    /// 1 when none of the players has played the game for a year or ever
+    pub recency: f64,
+    /// The players who have never played the game
+    pub new_to: Vec<Uuid>,
+    /// Share of the players who have never played the game
+    pub novelty: f64,
+    /// 1 when the players have won the game equally often, 0 when one of
+    /// them won every time
+    pub win_balance: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SuggestionList {
+    pub count: usize,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// A game played at a game night
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionGame {
+    pub game_id: Uuid,
+    pub name: String,
+    pub plays: usize,
+}
+
+/// How a player did over a game night
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionStanding {
+    pub player_id: Uuid,
+    pub name: String,
+    pub plays: usize,
+    pub wins: usize,
+    /// Sum of the recorded scores; missing when none was recorded
+    pub points: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionSummary {
+    pub session: SessionModel,
+    pub by: NightWinnerBy,
+    pub plays: usize,
+    /// Most played first
+    pub games: Vec<SessionGame>,
+    /// Every attendee and participant, best first
+    pub standings: Vec<SessionStanding>,
+    /// Players sharing the best result; empty when nobody won or scored
+    pub night_winner_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionSummaryData {
+    pub summary: SessionSummary,
+}
+
+/// How often a player came to game nights
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayerAttendance {
+    pub player_id: Uuid,
+    pub name: String,
+    pub attended: usize,
+    pub hosted: usize,
+    /// Share of all sessions attended, in percent
+    pub share: f64,
+    pub last_attended_on: Option<chrono::NaiveDate>,
+    /// Plays taken part in at the sessions attended
+    pub plays: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttendanceList {
+    /// Sessions held
+    pub sessions: usize,
+    pub count: usize,
+    /// Players who attended a session, most sessions first
+    pub players: Vec<PlayerAttendance>,
+}
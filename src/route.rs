@@ -2,63 +2,294 @@ use std::sync::Arc;
 
 use axum::{
     Router,
-    routing::{delete, get, patch, post},
+    extract::{DefaultBodyLimit, OriginalUri, Request},
+    http::{HeaderName, HeaderValue, header::LINK},
+    middleware::{self, Next},
+    response::Response,
 };
+use utoipa::OpenApi;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
     AppState,
-    handlers::{
-        create_game_handler, create_play_handler, create_play_participant_handler,
-        create_player_handler, delete_game_handler, delete_play_handler,
-        delete_play_participant_handler, delete_player_handler, game_list_handler,
-        get_game_handler, get_play_handler, get_play_participants_handler, get_player_handler,
-        play_list_handler, play_participant_list_handler, player_list_handler, update_game_handler,
-        update_play_handler, update_play_participant_handler, update_player_handler,
-    },
+    actor::actor,
+    // `routes!` needs the `#[utoipa::path]` items next to each handler too
+    handlers::*,
+    idempotency::idempotency,
+    openapi::ApiDoc,
 };
 
+/// Set on responses of deprecated routes (RFC 9745)
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+/// When the unversioned routes were deprecated, 2026-10-19 as a structured
+/// field date
+const UNVERSIONED_DEPRECATED_AT: &str = "@1792368000";
+
+/// Largest file the import endpoints accept, archives aside: as exports have no
+/// size cap, neither do archive imports. Other bodies keep axum's default
+/// limit of 2 MB.
+const IMPORT_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
 pub fn create_router(app_state: Arc<AppState>) -> Router {
+    let (api, paths) = api_routes(&app_state).split_for_parts();
+    let api = api.layer(middleware::from_fn(actor));
+
     Router::new()
+        .nest("/api/v1", api.clone())
+        // The unversioned routes predate /api/v1 and are kept as aliases
+        .nest("/api", api.layer(middleware::from_fn(deprecated_alias)))
+        // API documentation
+        .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", api_doc(paths)))
+        .with_state(app_state)
+}
+
+/// The OpenAPI document, with the paths the router collected from the
+/// handlers' `#[utoipa::path]` attributes under `/api/v1`
+fn api_doc(paths: utoipa::openapi::OpenApi) -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi().nest("/api/v1", paths)
+}
+
+/// Marks a response of an unversioned route as deprecated, linking to the same
+/// route under `/api/v1`
+async fn deprecated_alias(OriginalUri(uri): OriginalUri, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+
+    let successor = uri.path().replacen("/api", "/api/v1", 1);
+    let headers = response.headers_mut();
+    headers.insert(
+        DEPRECATION,
+        HeaderValue::from_static(UNVERSIONED_DEPRECATED_AT),
+    );
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+    {
+        headers.insert(LINK, link);
+    }
+
+    response
+}
+
+/// Every API route, relative to the prefix it is mounted under. Routes are
+/// added through `routes!`, which also documents them, so none of them can be
+/// left out of the OpenAPI document.
+fn api_routes(app_state: &Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    // Create and import endpoints honour the Idempotency-Key header. Imports
+    // have it read their body within their own, larger limit.
+    let idempotent = || middleware::from_fn_with_state(app_state.clone(), idempotency);
+    let import = || (DefaultBodyLimit::max(IMPORT_BODY_LIMIT), idempotent());
+    let archive_import = || (DefaultBodyLimit::disable(), idempotent());
+
+    OpenApiRouter::new()
         // Game routes
-        .route("/api/games", post(create_game_handler))
-        .route("/api/games", get(game_list_handler))
-        .route(
-            "/api/games/{id}",
-            get(get_game_handler)
-                .delete(delete_game_handler)
-                .patch(update_game_handler),
-        )
+        .routes(routes!(create_game_handler).layer(idempotent()))
+        .routes(routes!(game_list_handler))
+        .routes(routes!(
+            get_game_handler,
+            delete_game_handler,
+            update_game_handler
+        ))
+        .routes(routes!(merge_game_handler))
+        .routes(routes!(restore_game_handler))
+        .routes(routes!(game_history_handler))
+        .routes(routes!(revert_game_handler))
+        .routes(routes!(suggest_handler))
         // Player routes
-        .route("/api/players", post(create_player_handler))
-        .route("/api/players", get(player_list_handler))
-        .route(
-            "/api/players/{id}",
-            get(get_player_handler)
-                .delete(delete_player_handler)
-                .patch(update_player_handler),
-        )
+        .routes(routes!(create_player_handler).layer(idempotent()))
+        .routes(routes!(player_list_handler))
+        .routes(routes!(bulk_create_player_handler).layer(idempotent()))
+        .routes(routes!(
+            get_player_handler,
+            delete_player_handler,
+            update_player_handler
+        ))
+        .routes(routes!(merge_player_handler))
+        .routes(routes!(make_owner_handler))
+        .routes(routes!(restore_player_handler))
+        .routes(routes!(player_history_handler))
+        .routes(routes!(revert_player_handler))
+        // Owner routes
+        .routes(routes!(get_me_handler))
+        .routes(routes!(my_play_list_handler))
         // Play routes
-        .route("/api/plays", post(create_play_handler))
-        .route("/api/plays", get(play_list_handler))
-        .route(
-            "/api/plays/{id}",
-            get(get_play_handler)
-                .delete(delete_play_handler)
-                .patch(update_play_handler),
-        )
+        .routes(routes!(create_play_handler).layer(idempotent()))
+        .routes(routes!(play_list_handler, bulk_delete_play_handler))
+        .routes(routes!(plays_calendar_handler))
+        .routes(routes!(export_plays_csv_handler))
+        .routes(routes!(import_plays_csv_handler).layer(import()))
+        .routes(routes!(bulk_create_play_handler).layer(idempotent()))
+        .routes(routes!(
+            get_play_handler,
+            delete_play_handler,
+            update_play_handler
+        ))
+        .routes(routes!(restore_play_handler))
+        .routes(routes!(play_history_handler))
+        .routes(routes!(revert_play_handler))
         // Play Participant routes
-        .route(
-            "/api/playparticipants",
-            post(create_play_participant_handler),
-        )
-        .route("/api/playparticipants", get(play_participant_list_handler))
-        .route(
-            "/api/plays/{play_id}/participants",
-            get(get_play_participants_handler),
-        )
-        .route(
-            "/api/plays/{play_id}/participants/{player_id}",
-            delete(delete_play_participant_handler).patch(update_play_participant_handler),
-        )
-        .with_state(app_state)
+        .routes(routes!(create_play_participant_handler).layer(idempotent()))
+        .routes(routes!(play_participant_list_handler))
+        .routes(routes!(get_play_participants_handler))
+        .routes(routes!(
+            delete_play_participant_handler,
+            update_play_participant_handler
+        ))
+        .routes(routes!(play_participant_history_handler))
+        .routes(routes!(revert_play_participant_handler))
+        // Session routes
+        .routes(routes!(create_session_handler).layer(idempotent()))
+        .routes(routes!(session_list_handler))
+        .routes(routes!(
+            get_session_handler,
+            delete_session_handler,
+            update_session_handler
+        ))
+        .routes(routes!(session_summary_handler))
+        .routes(routes!(session_history_handler))
+        .routes(routes!(revert_session_handler))
+        .routes(routes!(attendance_handler))
+        // Event routes
+        .routes(routes!(create_event_handler).layer(idempotent()))
+        .routes(routes!(event_list_handler))
+        .routes(routes!(
+            get_event_handler,
+            delete_event_handler,
+            update_event_handler
+        ))
+        .routes(routes!(rsvp_handler))
+        .routes(routes!(start_session_handler))
+        .routes(routes!(event_history_handler))
+        .routes(routes!(revert_event_handler))
+        // Trash routes
+        .routes(routes!(trash_list_handler, purge_trash_handler))
+        // Report routes
+        .routes(routes!(year_report_handler))
+        .routes(routes!(milestones_handler))
+        // Backup routes
+        .routes(routes!(export_handler))
+        .routes(routes!(import_handler).layer(archive_import()))
+        .routes(routes!(export_bgstats_handler))
+        .routes(routes!(import_bgstats_handler).layer(import()))
+        .routes(routes!(import_bgg_games_handler).layer(import()))
+        .routes(routes!(import_bgg_plays_handler).layer(import()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::repository::memory::MemoryRepository;
+
+    #[tokio::test]
+    async fn every_documented_operation_is_routed() {
+        let app_state = MemoryRepository::app_state();
+        let (_, paths) = api_routes(&app_state).split_for_parts();
+        let router = create_router(app_state);
+
+        let spec = api_doc(paths);
+        assert!(!spec.paths.paths.is_empty(), "no paths documented");
+        for (path, item) in &spec.paths.paths {
+            let uri = path
+                .replace("{year}", "2026")
+                .split(['{', '}'])
+                .enumerate()
+                .map(|(i, part)| {
+                    if i % 2 == 1 {
+                        "00000000-0000-0000-0000-000000000000"
+                    } else {
+                        part
+                    }
+                })
+                .collect::<String>();
+            let operations = [
+                (Method::GET, &item.get),
+                (Method::POST, &item.post),
+                (Method::PUT, &item.put),
+                (Method::PATCH, &item.patch),
+                (Method::DELETE, &item.delete),
+            ];
+            for (method, operation) in operations {
+                if operation.is_none() {
+                    continue;
+                }
+                let request = Request::builder()
+                    .method(method.clone())
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let response = router.clone().oneshot(request).await.unwrap();
+
+                // Only the router answers without a body, when nothing matches
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                assert!(
+                    status != StatusCode::METHOD_NOT_ALLOWED
+                        && !(status == StatusCode::NOT_FOUND && body.is_empty()),
+                    "{} {} is documented but not routed",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_path_and_query_values_are_field_errors() {
+        let router = create_router(MemoryRepository::app_state());
+
+        for (uri, field) in [
+            ("/api/v1/games/notauuid", "id"),
+            ("/api/v1/plays.ics?player_id=bad", "player_id"),
+            ("/api/v1/stats/milestones?as_of=xx", "as_of"),
+        ] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+
+            assert_eq!(
+                response.status(),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "{}",
+                uri
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["errors"][0]["field"], field, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn type_and_validation_errors_are_listed_together() {
+        let router = create_router(MemoryRepository::app_state());
+
+        let request = Request::post("/api/v1/players")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name": "", "is_owner": 1}"#))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let fields: Vec<_> = body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, ["is_owner", "name"]);
+    }
 }
@@ -1,39 +1,536 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de::Error};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
+use validator::Validate;
 
-/// Schema for creating or updating a player
-#[derive(Serialize, Deserialize, Debug)]
+use crate::{
+    model::{BackupTables, Rsvp},
+    validation::{
+        BULK_MAX_ITEMS, COMMENTS_MAX_CHARS, NAME_MAX_CHARS, PLAY_MAX_MINUTES, PLAYERS_MAX,
+        RETENTION_MAX_DAYS, SCORE_MAX, SCORE_MIN, YEAR_MAX, YEAR_MIN, no_control_chars,
+        normalize_text,
+    },
+};
+
+/// Deserializes a merge patch member that may not be cleared: an absent member
+/// becomes `None` and an explicit `null` is rejected.
+fn non_null<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer)?
+        .map(Some)
+        .ok_or_else(|| D::Error::custom("field cannot be null"))
+}
+
+/// Deserializes a merge patch member for a nullable column: an absent member
+/// becomes `None`, `null` becomes `Some(None)` and a value `Some(Some(v))`.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Deserializes free text, trimmed and NFC normalized before validation runs
+fn normalized<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer).map(|text| normalize_text(&text))
+}
+
+/// Like `non_null`, normalizing the text the same way as `normalized`
+fn normalized_non_null<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    non_null::<D, String>(deserializer).map(|text| text.map(|text| normalize_text(&text)))
+}
+
+/// Like `normalized`, for optional text
+fn normalized_option<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(|text| text.map(|text| normalize_text(&text)))
+}
+
+/// Like `nullable`, normalizing the text the same way as `normalized`
+fn normalized_nullable<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    nullable::<D, String>(deserializer)
+        .map(|text| text.map(|text| text.map(|text| normalize_text(&text))))
+}
+
+/// Schema for creating a game
+#[derive(Serialize, Deserialize, Debug, Default, Validate, ToSchema)]
 pub struct GameSchema {
+    #[serde(deserialize_with = "normalized")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
     pub name: String,
+    #[serde(deserialize_with = "normalized")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
     pub creator_name: String,
+    /// BoardGameGeek object ID
+    #[validate(range(min = 1))]
+    pub bgg_id: Option<i32>,
+    #[validate(range(min = YEAR_MIN, max = YEAR_MAX))]
+    pub year_published: Option<i32>,
+    #[validate(range(min = 1, max = PLAYERS_MAX))]
+    pub min_players: Option<i32>,
+    /// At least `min_players`
+    #[validate(range(min = 1, max = PLAYERS_MAX))]
+    pub max_players: Option<i32>,
+    #[validate(range(min = 1))]
+    pub min_playtime_minutes: Option<i32>,
+    /// At least `min_playtime_minutes`
+    #[validate(range(min = 1))]
+    pub max_playtime_minutes: Option<i32>,
+    /// Complexity, 1 to 5 as BoardGameGeek rates it
+    #[validate(range(min = 1.0, max = 5.0))]
+    pub weight: Option<f64>,
 }
 
-/// Schema for updating an existing note
-#[derive(Serialize, Deserialize, Debug)]
+/// Merge patch for an existing game; `null` clears a detail
+#[derive(Serialize, Deserialize, Debug, Default, Validate, ToSchema)]
 pub struct UpdateGameSchema {
+    #[serde(default, deserialize_with = "normalized_non_null")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
     pub name: Option<String>,
+    #[serde(default, deserialize_with = "normalized_non_null")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
     pub creator_name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1))]
+    pub bgg_id: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = YEAR_MIN, max = YEAR_MAX))]
+    pub year_published: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1, max = PLAYERS_MAX))]
+    pub min_players: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1, max = PLAYERS_MAX))]
+    pub max_players: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1))]
+    pub min_playtime_minutes: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1))]
+    pub max_playtime_minutes: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1.0, max = 5.0))]
+    pub weight: Option<Option<f64>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct PlayerSchema {
+    #[serde(deserialize_with = "normalized")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
     pub name: String,
     pub is_owner: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Merge patch for an existing player
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct UpdatePlayerSchema {
+    #[serde(default, deserialize_with = "normalized_non_null")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    pub is_owner: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Validate, ToSchema)]
 pub struct PlaySchema {
     pub game_id: Uuid,
+    /// Game night the play belongs to
+    pub session_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "normalized_option")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub location: Option<String>,
+    #[validate(range(min = 1, max = PLAY_MAX_MINUTES))]
+    pub length_minutes: Option<i32>,
+    #[serde(default, deserialize_with = "normalized_option")]
+    #[validate(length(min = 1, max = COMMENTS_MAX_CHARS))]
+    pub comments: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Merge patch for an existing play; `"session_id": null` takes it out of its
+/// session and `null` clears the location, length or comments
+#[derive(Serialize, Deserialize, Debug, Default, Validate, ToSchema)]
+pub struct UpdatePlaySchema {
+    #[serde(default, deserialize_with = "non_null")]
+    pub game_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub session_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "normalized_nullable")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub location: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1, max = PLAY_MAX_MINUTES))]
+    pub length_minutes: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "normalized_nullable")]
+    #[validate(length(min = 1, max = COMMENTS_MAX_CHARS))]
+    pub comments: Option<Option<String>>,
+}
+
+/// Schema for creating a game night. The host is counted among the attendees
+/// whether listed or not.
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct SessionSchema {
+    pub played_on: chrono::NaiveDate,
+    #[serde(default, deserialize_with = "normalized_option")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub location: Option<String>,
+    pub host_id: Option<Uuid>,
+    #[serde(default)]
+    #[validate(length(max = 100, message = "must list at most 100 players"))]
+    pub attendee_ids: Vec<Uuid>,
+}
+
+/// Merge patch for an existing game night; `attendee_ids` replaces the
+/// attendees
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct UpdateSessionSchema {
+    #[serde(default, deserialize_with = "non_null")]
+    pub played_on: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "normalized_nullable")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub location: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub host_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(max = 100, message = "must list at most 100 players"))]
+    pub attendee_ids: Option<Vec<Uuid>>,
+}
+
+/// Schema for planning a game night. The host is invited whether listed or
+/// not.
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct EventSchema {
+    pub scheduled_on: chrono::NaiveDate,
+    #[serde(default, deserialize_with = "normalized_option")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub location: Option<String>,
+    pub host_id: Option<Uuid>,
+    #[serde(default)]
+    #[validate(length(max = 100, message = "must list at most 100 players"))]
+    pub invitee_ids: Vec<Uuid>,
+}
+
+/// Merge patch for a planned game night; `invitee_ids` replaces the
+/// invitations, keeping the answers of players still invited
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct UpdateEventSchema {
+    #[serde(default, deserialize_with = "non_null")]
+    pub scheduled_on: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "normalized_nullable")]
+    #[validate(
+        length(min = 1, max = NAME_MAX_CHARS),
+        custom(function = "no_control_chars")
+    )]
+    pub location: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub host_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(max = 100, message = "must list at most 100 players"))]
+    pub invitee_ids: Option<Vec<Uuid>>,
+}
+
+/// A player's answer to an event; `game_ids` replaces the games they bring
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct RsvpSchema {
+    pub response: Rsvp,
+    #[serde(default)]
+    #[validate(length(max = 20, message = "must list at most 20 games"))]
+    pub game_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct PlayParticipantSchema {
     pub play_id: Uuid,
     pub player_id: Uuid,
+    #[validate(range(min = SCORE_MIN, max = SCORE_MAX))]
+    pub score: Option<i32>,
+    pub is_winner: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Merge patch for an existing play participant; `"score": null` clears the score
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct UpdatePlayParticipantSchema {
+    #[serde(default, deserialize_with = "non_null")]
     pub play_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "non_null")]
+    pub player_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = SCORE_MIN, max = SCORE_MAX))]
+    pub score: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "non_null")]
+    pub is_winner: Option<bool>,
+}
+
+/// Schema for merging a duplicate game or player into another one
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct MergeSchema {
+    pub target_id: Uuid,
+}
+
+/// Query parameters for purging the trash
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PurgeTrashQuery {
+    /// Only purge rows trashed at least this many days ago; defaults to the
+    /// configured retention
+    #[validate(range(min = 0, max = RETENTION_MAX_DAYS))]
+    pub older_than_days: Option<i64>,
+}
+
+/// How a bulk request treats failing items
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    /// Nothing is written unless every item succeeds
+    #[default]
+    Atomic,
+    /// Items that succeed are written, failures are reported next to them
+    PerItem,
+}
+
+/// Query parameters for bulk create requests
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BulkQuery {
+    #[serde(default)]
+    pub mode: BulkMode,
+}
+
+/// Body of a bulk create request; each item is validated like the body of the
+/// matching single-item request
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct BulkSchema {
+    #[validate(length(
+        min = 1,
+        max = BULK_MAX_ITEMS,
+        message = "must hold between 1 and 500 items"
+    ))]
+    #[schema(value_type = Vec<Object>)]
+    pub items: Vec<serde_json::Value>,
+}
+
+/// Query parameters for bulk deletes, `ids` being a comma separated list
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BulkDeleteQuery {
+    #[validate(length(min = 1, message = "must list at least one ID"))]
+    pub ids: String,
+    #[serde(default)]
+    pub mode: BulkMode,
+}
+
+/// Identifies the backup archives written by `GET /api/v1/export`
+pub const ARCHIVE_FORMAT: &str = "bg-logger";
+
+/// Version of the archive layout, raised when an archive could no longer be
+/// read by an older importer. Version 2 added the audit log.
+pub const ARCHIVE_VERSION: u32 = 2;
+
+/// A backup archive, as exported and as imported
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct ArchiveSchema {
+    #[schema(example = "bg-logger")]
+    pub format: String,
+    #[schema(example = 2)]
+    pub version: u32,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub tables: BackupTables,
+}
+
+/// What an import does with an archived row that clashes with an existing one
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Keep the existing row
+    Skip,
+    /// Replace the existing row with the archived one
+    Overwrite,
+    /// Import nothing if any row clashes
+    #[default]
+    Fail,
+}
+
+/// Query parameters for importing a backup archive
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+    /// Only report what the import would do
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Query parameters for importing plays from CSV. The `*_column` parameters
+/// name the header of the column holding each value and default to the
+/// headers `GET /api/v1/plays/export.csv` writes.
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PlayCsvImportQuery {
+    /// Create games and players that do not exist yet instead of failing the
+    /// rows naming them
+    #[serde(default)]
+    pub create_missing: bool,
+    /// Rows with the same value in this column belong to one play. Without
+    /// the column, rows with the same date and game do.
+    pub play_column: Option<String>,
+    /// When the play took place: RFC 3339, `YYYY-MM-DD HH:MM[:SS]` or
+    /// `YYYY-MM-DD`, in UTC; defaults to the time of the import
+    pub played_at_column: Option<String>,
+    pub game_column: Option<String>,
+    /// Needed to create a game, and to tell games of the same name apart
+    pub creator_column: Option<String>,
+    /// Empty for a play without participants
+    pub player_column: Option<String>,
+    pub score_column: Option<String>,
+    /// `true`/`false`, `yes`/`no`, `1`/`0` or `x`/empty
+    pub winner_column: Option<String>,
+}
+
+/// How an import finds the stored game or player a record of the file stands
+/// for. Records matching none are created.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStrategy {
+    /// By BGG ID or username, then by name among the rows without one
+    #[default]
+    Auto,
+    /// By BGG ID or username only
+    Bgg,
+    /// By name only
+    Name,
+    /// Never, every record is created
+    None,
+}
+
+/// Query parameters for importing a BG Stats backup
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BgStatsImportQuery {
+    /// How games are matched
+    #[serde(default)]
+    pub games: MatchStrategy,
+    /// How players are matched
+    #[serde(default)]
+    pub players: MatchStrategy,
+    /// Only report what the import would do
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Query parameters for the calendar of plays
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PlaysCalendarQuery {
+    /// Only plays this player took part in
     pub player_id: Option<Uuid>,
+    /// Only plays of this game
+    pub game_id: Option<Uuid>,
+}
+
+/// How a report is rendered
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    /// In the response envelope
+    #[default]
+    Json,
+    /// A page to share
+    Html,
+    Markdown,
+}
+
+/// Query parameters for reports
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportQuery {
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// Query parameters for play milestones
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MilestonesQuery {
+    /// Count the plays up to the end of this day, in UTC; defaults to today
+    pub as_of: Option<chrono::NaiveDate>,
+}
+
+/// Who is playing tonight and for how long
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct SuggestSchema {
+    #[validate(length(min = 1, max = 100, message = "must list between 1 and 100 players"))]
+    pub player_ids: Vec<Uuid>,
+    /// Time budget; games known to take longer are left out
+    #[validate(range(min = 1, max = 1440))]
+    pub minutes: Option<i32>,
+    /// How many games to suggest, 10 by default
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<usize>,
+}
+
+/// How the winner of a game night is decided
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NightWinnerBy {
+    /// Most plays won
+    #[default]
+    Wins,
+    /// Highest total score
+    Points,
+}
+
+/// Query parameters for the summary of a game night
+#[derive(Serialize, Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SessionSummaryQuery {
+    #[serde(default)]
+    pub by: NightWinnerBy,
 }
@@ -0,0 +1,302 @@
+//! Game nights: what was played at a session and who came out on top, and
+//! how often each player comes to them.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::{
+    model::{BackupTables, SessionModel},
+    response::{AttendanceList, PlayerAttendance, SessionGame, SessionStanding, SessionSummary},
+    schema::NightWinnerBy,
+};
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// The live plays of `session`, with standings for its attendees and for
+/// everyone who took part in one of them
+pub fn session_summary(
+    tables: &BackupTables,
+    session: SessionModel,
+    by: NightWinnerBy,
+) -> SessionSummary {
+    let game_names: HashMap<_, _> = tables
+        .games
+        .iter()
+        .map(|game| (game.id, game.name.as_str()))
+        .collect();
+    let player_names: HashMap<_, _> = tables
+        .players
+        .iter()
+        .map(|player| (player.id, player.name.as_str()))
+        .collect();
+
+    let plays: Vec<_> = tables
+        .plays
+        .iter()
+        .filter(|play| play.session_id == Some(session.id) && play.deleted_at.is_none())
+        .collect();
+    let play_ids: HashSet<_> = plays.iter().map(|play| play.id).collect();
+
+    let mut games: HashMap<_, usize> = HashMap::new();
+    for play in &plays {
+        *games.entry(play.game_id).or_default() += 1;
+    }
+    let mut games: Vec<_> = games
+        .into_iter()
+        .map(|(game_id, plays)| SessionGame {
+            game_id,
+            name: game_names
+                .get(&game_id)
+                .copied()
+                .unwrap_or_default()
+                .to_string(),
+            plays,
+        })
+        .collect();
+    games.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.name.cmp(&b.name)));
+
+    let mut standings: HashMap<_, SessionStanding> = HashMap::new();
+    let standing = |player_id: Uuid| SessionStanding {
+        player_id,
+        name: player_names
+            .get(&player_id)
+            .copied()
+            .unwrap_or_default()
+            .to_string(),
+        plays: 0,
+        wins: 0,
+        points: None,
+    };
+    for &player_id in &session.attendee_ids {
+        standings.insert(player_id, standing(player_id));
+    }
+    for participant in &tables.play_participants {
+        if !play_ids.contains(&participant.play_id) {
+            continue;
+        }
+        let standing = standings
+            .entry(participant.player_id)
+            .or_insert_with(|| standing(participant.player_id));
+        standing.plays += 1;
+        if participant.is_winner {
+            standing.wins += 1;
+        }
+        if let Some(score) = participant.score {
+            *standing.points.get_or_insert(0) += i64::from(score);
+        }
+    }
+
+    let result = |standing: &SessionStanding| match by {
+        NightWinnerBy::Wins => Some(standing.wins as i64).filter(|&wins| wins > 0),
+        NightWinnerBy::Points => standing.points,
+    };
+    let mut standings: Vec<_> = standings.into_values().collect();
+    standings.sort_by(|a, b| {
+        result(b)
+            .cmp(&result(a))
+            .then_with(|| b.plays.cmp(&a.plays))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let best = standings.first().and_then(result);
+    let night_winner_ids = match best {
+        Some(best) => standings
+            .iter()
+            .filter(|standing| result(standing) == Some(best))
+            .map(|standing| standing.player_id)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    SessionSummary {
+        session,
+        by,
+        plays: plays.len(),
+        games,
+        standings,
+        night_winner_ids,
+    }
+}
+
+/// How many sessions each player attended and hosted, and how many plays they
+/// took part in at them
+pub fn attendance(tables: &BackupTables) -> AttendanceList {
+    let names: HashMap<_, _> = tables
+        .players
+        .iter()
+        .map(|player| (player.id, player.name.as_str()))
+        .collect();
+    let sessions: HashMap<_, _> = tables
+        .sessions
+        .iter()
+        .map(|session| (session.id, session))
+        .collect();
+    let plays: HashMap<_, _> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .filter_map(|play| Some((play.id, sessions.get(&play.session_id?)?)))
+        .collect();
+
+    let new = |player_id: Uuid| PlayerAttendance {
+        player_id,
+        name: names
+            .get(&player_id)
+            .copied()
+            .unwrap_or_default()
+            .to_string(),
+        attended: 0,
+        hosted: 0,
+        share: 0.0,
+        last_attended_on: None,
+        plays: 0,
+    };
+    let mut players: HashMap<Uuid, PlayerAttendance> = HashMap::new();
+    for session in &tables.sessions {
+        for &player_id in &session.attendee_ids {
+            let player = players.entry(player_id).or_insert_with(|| new(player_id));
+            player.attended += 1;
+            if session.host_id == Some(player_id) {
+                player.hosted += 1;
+            }
+            if player
+                .last_attended_on
+                .is_none_or(|on| on < session.played_on)
+            {
+                player.last_attended_on = Some(session.played_on);
+            }
+        }
+    }
+    for participant in &tables.play_participants {
+        if let Some(session) = plays.get(&participant.play_id)
+            && session.attendee_ids.contains(&participant.player_id)
+            && let Some(player) = players.get_mut(&participant.player_id)
+        {
+            player.plays += 1;
+        }
+    }
+
+    let mut players: Vec<_> = players
+        .into_values()
+        .map(|mut player| {
+            player.share = round(100.0 * player.attended as f64 / tables.sessions.len() as f64);
+            player
+        })
+        .collect();
+    players.sort_by(|a, b| {
+        b.attended
+            .cmp(&a.attended)
+            .then_with(|| b.last_attended_on.cmp(&a.last_attended_on))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    AttendanceList {
+        sessions: tables.sessions.len(),
+        count: players.len(),
+        players,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+
+    use super::*;
+    use crate::model::{PlayModel, PlayParticipantModel};
+
+    fn session(played_on: u32, host_id: Uuid, attendee_ids: &[Uuid]) -> SessionModel {
+        SessionModel {
+            id: Uuid::new_v4(),
+            played_on: NaiveDate::from_ymd_opt(2024, 3, played_on).unwrap(),
+            location: None,
+            host_id: Some(host_id),
+            attendee_ids: attendee_ids.to_vec(),
+            created_at: Utc::now(),
+            version: 1,
+        }
+    }
+
+    fn play(session_id: Uuid) -> PlayModel {
+        PlayModel {
+            id: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            session_id: Some(session_id),
+            location: None,
+            length_minutes: None,
+            comments: None,
+            bgg_play_id: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    fn seat(
+        play: &PlayModel,
+        player_id: Uuid,
+        score: i32,
+        is_winner: bool,
+    ) -> PlayParticipantModel {
+        PlayParticipantModel {
+            play_id: play.id,
+            player_id,
+            score: Some(score),
+            is_winner,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn the_night_goes_to_the_most_wins_or_points() {
+        let (ann, bob, cid) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let night = session(1, ann, &[ann, bob, cid]);
+        let (first, second) = (play(night.id), play(night.id));
+        let tables = BackupTables {
+            play_participants: vec![
+                seat(&first, ann, 10, true),
+                seat(&first, bob, 8, false),
+                seat(&second, ann, 3, false),
+                seat(&second, bob, 20, true),
+            ],
+            plays: vec![first, second],
+            ..Default::default()
+        };
+
+        let by_wins = session_summary(&tables, night.clone(), NightWinnerBy::Wins);
+        assert_eq!(by_wins.plays, 2);
+        assert_eq!(by_wins.games.len(), 2);
+        assert_eq!(by_wins.standings.len(), 3);
+        assert_eq!(by_wins.night_winner_ids.len(), 2);
+        assert_eq!(by_wins.standings[2].player_id, cid);
+        assert_eq!(by_wins.standings[2].points, None);
+
+        let by_points = session_summary(&tables, night, NightWinnerBy::Points);
+        assert_eq!(by_points.night_winner_ids, [bob]);
+        assert_eq!(by_points.standings[0].points, Some(28));
+    }
+
+    #[test]
+    fn attendance_counts_sessions_hosted_and_plays() {
+        let (ann, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let first = session(1, ann, &[ann, bob]);
+        let second = session(8, ann, &[ann]);
+        let played = play(first.id);
+        let tables = BackupTables {
+            play_participants: vec![seat(&played, bob, 5, true)],
+            plays: vec![played],
+            sessions: vec![first, second],
+            ..Default::default()
+        };
+
+        let list = attendance(&tables);
+        assert_eq!((list.sessions, list.count), (2, 2));
+        let ann = &list.players[0];
+        assert_eq!((ann.attended, ann.hosted, ann.share), (2, 2, 100.0));
+        assert_eq!(ann.last_attended_on, NaiveDate::from_ymd_opt(2024, 3, 8));
+        let bob = &list.players[1];
+        assert_eq!((bob.attended, bob.hosted, bob.plays), (1, 0, 1));
+        assert_eq!(bob.share, 50.0);
+    }
+}
@@ -0,0 +1,202 @@
+//! What to play tonight: the games of the collection that suit the players
+//! present and the time they have, ranked by how long it has been since they
+//! played them, how many of them would learn something new and how evenly
+//! the games have been won among them.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{model::BackupTables, response::Suggestion};
+
+/// How much each reason counts towards the score
+const RECENCY_WEIGHT: f64 = 0.4;
+const NOVELTY_WEIGHT: f64 = 0.35;
+const WIN_BALANCE_WEIGHT: f64 = 0.25;
+
+/// A game unplayed for this long counts as not played recently at all
+const RECENCY_DAYS: f64 = 365.0;
+
+/// How often the players present took part in and won a game, and when any
+/// of them last played it
+#[derive(Default)]
+struct History {
+    last_played_at: Option<DateTime<Utc>>,
+    played: HashSet<Uuid>,
+    wins: HashMap<Uuid, usize>,
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+/// Live games for `players`, who are live players without repeats, best
+/// first. A game whose player counts or playtime are unknown is assumed to
+/// fit.
+pub fn suggest(
+    tables: &BackupTables,
+    players: &[Uuid],
+    minutes: Option<i32>,
+    limit: usize,
+    now: DateTime<Utc>,
+) -> Vec<Suggestion> {
+    let present: HashSet<_> = players.iter().copied().collect();
+    let count = players.len() as i32;
+
+    let plays: HashMap<_, _> = tables
+        .plays
+        .iter()
+        .filter(|play| play.deleted_at.is_none())
+        .map(|play| (play.id, play))
+        .collect();
+    let mut histories: HashMap<Uuid, History> = HashMap::new();
+    for participant in &tables.play_participants {
+        let Some(play) = plays.get(&participant.play_id) else {
+            continue;
+        };
+        if !present.contains(&participant.player_id) {
+            continue;
+        }
+        let history = histories.entry(play.game_id).or_default();
+        if history.last_played_at.is_none_or(|at| at < play.created_at) {
+            history.last_played_at = Some(play.created_at);
+        }
+        history.played.insert(participant.player_id);
+        if participant.is_winner {
+            *history.wins.entry(participant.player_id).or_default() += 1;
+        }
+    }
+
+    let no_history = History::default();
+    let mut suggestions: Vec<_> = tables
+        .games
+        .iter()
+        .filter(|game| game.deleted_at.is_none())
+        .filter(|game| {
+            game.min_players.is_none_or(|min| count >= min)
+                && game.max_players.is_none_or(|max| count <= max)
+        })
+        .filter(|game| {
+            let needed = game.max_playtime_minutes.or(game.min_playtime_minutes);
+            minutes.is_none_or(|budget| needed.is_none_or(|needed| needed <= budget))
+        })
+        .map(|game| {
+            let history = histories.get(&game.id).unwrap_or(&no_history);
+
+            let recency = history.last_played_at.map_or(1.0, |at| {
+                let days = (now - at).num_hours() as f64 / 24.0;
+                (days / RECENCY_DAYS).clamp(0.0, 1.0)
+            });
+            let new_to: Vec<_> = players
+                .iter()
+                .filter(|player| !history.played.contains(player))
+                .copied()
+                .collect();
+            let novelty = new_to.len() as f64 / players.len() as f64;
+            let total_wins: usize = history.wins.values().sum();
+            let win_balance = match history.wins.values().max() {
+                Some(&most) if players.len() > 1 && total_wins > 0 => {
+                    let even = 1.0 / players.len() as f64;
+                    let share = most as f64 / total_wins as f64;
+                    ((1.0 - share) / (1.0 - even)).clamp(0.0, 1.0)
+                }
+                _ => 1.0,
+            };
+
+            Suggestion {
+                game: game.clone(),
+                score: round(
+                    RECENCY_WEIGHT * recency
+                        + NOVELTY_WEIGHT * novelty
+                        + WIN_BALANCE_WEIGHT * win_balance,
+                ),
+                last_played_at: history.last_played_at,
+                recency: round(recency),
+                new_to,
+                novelty: round(novelty),
+                win_balance: round(win_balance),
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.game.name.cmp(&b.game.name))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::model::{GameModel, PlayModel, PlayParticipantModel};
+
+    fn game(name: &str, players: (i32, i32), minutes: i32) -> GameModel {
+        GameModel {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            creator_name: "Someone".to_string(),
+            bgg_id: None,
+            year_published: None,
+            min_players: Some(players.0),
+            max_players: Some(players.1),
+            min_playtime_minutes: None,
+            max_playtime_minutes: Some(minutes),
+            weight: None,
+            created_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn unplayed_fitting_games_come_first() {
+        let now = Utc::now();
+        let (ann, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let catan = game("Catan", (3, 4), 90);
+        let azul = game("Azul", (2, 4), 45);
+        let patchwork = game("Patchwork", (2, 2), 30);
+        let twilight = game("Twilight Imperium", (3, 6), 480);
+        // Ann beat Bob at Azul yesterday
+        let play = PlayModel {
+            id: Uuid::new_v4(),
+            game_id: azul.id,
+            session_id: None,
+            location: None,
+            length_minutes: None,
+            comments: None,
+            bgg_play_id: None,
+            created_at: now - TimeDelta::days(1),
+            deleted_at: None,
+            version: 1,
+        };
+        let participant = |player_id, is_winner| PlayParticipantModel {
+            play_id: play.id,
+            player_id,
+            score: None,
+            is_winner,
+            version: 1,
+        };
+        let tables = BackupTables {
+            play_participants: vec![participant(ann, true), participant(bob, false)],
+            games: vec![catan, azul, patchwork, twilight],
+            plays: vec![play],
+            ..Default::default()
+        };
+
+        let suggestions = suggest(&tables, &[ann, bob], Some(60), 10, now);
+
+        let names: Vec<_> = suggestions.iter().map(|s| s.game.name.as_str()).collect();
+        assert_eq!(names, ["Patchwork", "Azul"]);
+        assert_eq!(suggestions[0].score, 1.0);
+        assert_eq!(suggestions[0].new_to.len(), 2);
+        let azul = &suggestions[1];
+        assert!(azul.new_to.is_empty());
+        assert_eq!(azul.win_balance, 0.0);
+    }
+}
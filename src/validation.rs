@@ -0,0 +1,105 @@
+use unicode_normalization::UnicodeNormalization;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::response::{ApiError, FieldError};
+
+/// Longest game, creator or player name we accept, in characters
+pub const NAME_MAX_CHARS: u64 = 100;
+
+/// Longest comments on a play we accept, in characters
+pub const COMMENTS_MAX_CHARS: u64 = 10_000;
+
+/// Longest play we accept, in minutes
+pub const PLAY_MAX_MINUTES: i32 = 1440;
+
+/// Most players a game can be for
+pub const PLAYERS_MAX: i32 = 100;
+
+/// Bounds for the year a game was published; BoardGameGeek dates ancient
+/// games before the common era
+pub const YEAR_MIN: i32 = -5000;
+pub const YEAR_MAX: i32 = 9999;
+
+/// Bounds for a participant's score
+pub const SCORE_MIN: i32 = -1_000_000;
+pub const SCORE_MAX: i32 = 1_000_000;
+
+/// Most items a single bulk request may carry
+pub const BULK_MAX_ITEMS: u64 = 500;
+
+/// Longest retention in days, for the trash and idempotency keys alike. Keeps
+/// the purge cutoff well within the dates `chrono` can represent.
+pub const RETENTION_MAX_DAYS: i64 = 36_500;
+
+/// Normalizes user supplied text to Unicode NFC and trims surrounding whitespace
+pub fn normalize_text(text: &str) -> String {
+    text.nfc().collect::<String>().trim().to_string()
+}
+
+/// Rejects names containing control characters such as newlines or tabs
+pub fn no_control_chars(name: &str) -> Result<(), ValidationError> {
+    if name.chars().any(char::is_control) {
+        let mut error = ValidationError::new("control_chars");
+        error.message = Some("must not contain control characters".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Builds the 422 response listing every failing field
+pub fn validation_error_response(errors: &ValidationErrors) -> ApiError {
+    ApiError::validation(validation_field_errors(errors))
+}
+
+/// Every failing field with what is wrong with it, ordered by field
+pub fn validation_field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields: Vec<_> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors
+                .iter()
+                .map(move |error| FieldError::new(field.as_ref(), describe(error)))
+        })
+        .collect();
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    match error.code.as_ref() {
+        "length" => match (error.params.get("min"), error.params.get("max")) {
+            (Some(min), Some(max)) => format!("must be between {} and {} characters", min, max),
+            (Some(min), None) => format!("must be at least {} characters", min),
+            (None, Some(max)) => format!("must be at most {} characters", max),
+            (None, None) => "has an invalid length".to_string(),
+        },
+        "range" => match (error.params.get("min"), error.params.get("max")) {
+            (Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+            (Some(min), None) => format!("must be at least {}", min),
+            (None, Some(max)) => format!("must be at most {}", max),
+            (None, None) => "is out of range".to_string(),
+        },
+        code => format!("is invalid ({})", code),
+    }
+}
+
+/// Builds the 422 response for a single failing field, e.g. a reference to a
+/// row that does not exist
+pub fn field_error_response(field: &str, message: &str) -> ApiError {
+    field_errors_response(&[(field, message)])
+}
+
+/// Builds the 422 response for several `(field, message)` failures
+pub fn field_errors_response(errors: &[(&str, &str)]) -> ApiError {
+    let fields = errors
+        .iter()
+        .map(|(field, message)| FieldError::new(*field, *message))
+        .collect();
+
+    ApiError::validation(fields)
+}